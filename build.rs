@@ -0,0 +1,4 @@
+// Re-embed migrations whenever the migrations directory changes.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
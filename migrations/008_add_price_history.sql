@@ -0,0 +1,14 @@
+-- Price samples for every configured asset/USD pair
+-- Written by the price feed on each aggregation; used for history and TWAP queries
+
+CREATE TABLE IF NOT EXISTS price_history (
+    id BIGSERIAL PRIMARY KEY,
+    pair TEXT NOT NULL,
+    price DOUBLE PRECISION NOT NULL,
+    "sourceCount" INTEGER NOT NULL,
+    sources JSONB NOT NULL DEFAULT '{}'::jsonb,
+    "recordedAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_history_pair_recorded_at
+    ON price_history(pair, "recordedAt" DESC);
@@ -0,0 +1,4 @@
+-- Flag price samples aggregated from fewer sources than price_feed.min_sources
+-- Degraded samples are kept for history but excluded from TWAP and cache reads
+
+ALTER TABLE price_history ADD COLUMN IF NOT EXISTS degraded BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,21 @@
+-- Append-only APY readings per protocol
+-- "source" is 'contract' when read from the adapter view function, 'default' when the
+-- contract call failed and the stored baseApy was kept
+
+CREATE TABLE IF NOT EXISTS apy_history (
+    id TEXT PRIMARY KEY,
+    "protocolId" TEXT NOT NULL REFERENCES protocols(id) ON DELETE CASCADE,
+    apy NUMERIC(10, 6) NOT NULL,
+    source TEXT NOT NULL CHECK (source IN ('contract', 'default')),
+    error TEXT,
+    "recordedAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_apy_history_protocol_recorded_at
+    ON apy_history("protocolId", "recordedAt" DESC);
+
+-- Seed the series with the current APY so integrations have a starting point
+INSERT INTO apy_history (id, "protocolId", apy, source, "recordedAt")
+SELECT gen_random_uuid()::text, p.id, p."baseApy", 'default', p."updatedAt"
+FROM protocols p
+WHERE NOT EXISTS (SELECT 1 FROM apy_history h WHERE h."protocolId" = p.id);
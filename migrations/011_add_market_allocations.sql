@@ -0,0 +1,17 @@
+-- Per-market yield allocation across protocols
+-- One row per (market, protocol); the share not allocated is held idle as a liquidity reserve
+
+CREATE TABLE IF NOT EXISTS market_allocations (
+    id TEXT PRIMARY KEY,
+    "marketId" TEXT NOT NULL REFERENCES markets_extended(id) ON DELETE CASCADE,
+    "protocolId" TEXT NOT NULL REFERENCES protocols(id) ON DELETE CASCADE,
+    strategy TEXT NOT NULL CHECK (strategy IN ('equal_weight', 'max_apy', 'risk_weighted')),
+    weight NUMERIC(10, 8) NOT NULL,
+    amount NUMERIC(78, 18) NOT NULL DEFAULT 0,
+    apy NUMERIC(10, 6) NOT NULL,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE ("marketId", "protocolId")
+);
+
+CREATE INDEX IF NOT EXISTS idx_market_allocations_protocol ON market_allocations("protocolId");
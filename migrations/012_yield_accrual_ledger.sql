@@ -0,0 +1,15 @@
+-- Daily yield accrual ledger
+-- yield_records holds one row per (market, protocol, day); "yieldAccruedUntil" marks the end
+-- of the last day accrued for a market so missed days can be backfilled
+
+DELETE FROM yield_records a
+USING yield_records b
+WHERE a."marketId" = b."marketId"
+  AND a."protocolId" = b."protocolId"
+  AND a.period = b.period
+  AND (a."createdAt", a.id) > (b."createdAt", b.id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_yield_records_market_protocol_period
+    ON yield_records("marketId", "protocolId", period);
+
+ALTER TABLE markets_extended ADD COLUMN IF NOT EXISTS "yieldAccruedUntil" TIMESTAMP;
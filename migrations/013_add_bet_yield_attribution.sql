@@ -0,0 +1,6 @@
+-- Per-bet yield attribution
+-- "yieldEarned" is each bet's share of its market's yield_records ledger, split by amount and
+-- time in pool; per market the bets sum to "totalYieldEarned"
+
+ALTER TABLE bets_extended ADD COLUMN IF NOT EXISTS "yieldEarned" NUMERIC(78, 18) NOT NULL DEFAULT 0;
+ALTER TABLE bets_extended ADD COLUMN IF NOT EXISTS "yieldAttributedAt" TIMESTAMP;
@@ -0,0 +1,33 @@
+-- Categorical (multi-outcome) markets
+-- Binary markets keep using position/yes/no columns and have no market_outcomes rows;
+-- categorical markets list their outcomes here and bets reference one by "outcomeIndex"
+
+ALTER TABLE markets_extended ADD COLUMN IF NOT EXISTS "marketType" TEXT NOT NULL DEFAULT 'binary';
+ALTER TABLE markets_extended ADD COLUMN IF NOT EXISTS "winningOutcome" INTEGER;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint WHERE conname = 'markets_extended_marketType_check'
+    ) THEN
+        ALTER TABLE markets_extended ADD CONSTRAINT "markets_extended_marketType_check"
+            CHECK ("marketType" IN ('binary', 'categorical'));
+    END IF;
+END $$;
+
+CREATE TABLE IF NOT EXISTS market_outcomes (
+    id TEXT PRIMARY KEY,
+    "marketId" TEXT NOT NULL REFERENCES markets_extended(id) ON DELETE CASCADE,
+    "outcomeIndex" INTEGER NOT NULL CHECK ("outcomeIndex" >= 0),
+    label TEXT NOT NULL,
+    "poolSize" NUMERIC(78, 18) NOT NULL DEFAULT 0,
+    "betCount" INTEGER NOT NULL DEFAULT 0,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE ("marketId", "outcomeIndex")
+);
+
+ALTER TABLE bets_extended ADD COLUMN IF NOT EXISTS "outcomeIndex" INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_bets_extended_market_outcome
+    ON bets_extended("marketId", "outcomeIndex") WHERE "outcomeIndex" IS NOT NULL;
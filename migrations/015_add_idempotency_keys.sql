@@ -0,0 +1,18 @@
+-- Idempotency keys for bet placement and claims
+-- One row per Idempotency-Key header: the hash of the request it was first used with and, once
+-- the request finished, the response to replay. Rows are purged after "expiresAt"
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    key TEXT PRIMARY KEY,
+    "requestHash" TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'in_progress',
+    "responseStatus" INTEGER,
+    "responseBody" TEXT,
+    "responseContentType" TEXT,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "completedAt" TIMESTAMP,
+    "expiresAt" TIMESTAMP NOT NULL,
+    CONSTRAINT idempotency_keys_status_check CHECK (status IN ('in_progress', 'completed'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_expires_at ON idempotency_keys("expiresAt");
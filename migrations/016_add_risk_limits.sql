@@ -0,0 +1,21 @@
+-- Betting risk limits
+-- One 'global' row for platform-wide limits plus optional per-market rows keyed by market id.
+-- NULL limits on a market row fall back to the global row; NULL on the global row means no limit.
+-- Betting is paused for a market when either its own row or the global row is paused
+
+CREATE TABLE IF NOT EXISTS risk_limits (
+    scope TEXT PRIMARY KEY,
+    "minBet" NUMERIC(78,18),
+    "maxBet" NUMERIC(78,18),
+    "maxUserMarketExposure" NUMERIC(78,18),
+    "maxMarketExposure" NUMERIC(78,18),
+    "closeCooldownSecs" INTEGER,
+    "bettingPaused" BOOLEAN NOT NULL DEFAULT false,
+    "pauseReason" TEXT,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO risk_limits (scope) VALUES ('global') ON CONFLICT (scope) DO NOTHING;
+
+CREATE INDEX IF NOT EXISTS idx_bets_extended_market_user ON bets_extended("marketId", "userId");
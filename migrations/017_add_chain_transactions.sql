@@ -0,0 +1,53 @@
+-- Submitted chain transactions and their confirmation state
+-- Every transaction the API submits (bets, claims, market creation) is recorded as 'pending'
+-- and polled against the node until it is committed, fails, or passes its expiry.
+-- Extended rows written by those requests carry the transaction's hash and a "txStatus" that
+-- only becomes 'confirmed' once the transaction is committed; NULL means the row was not
+-- written by a tracked transaction (e.g. it came from the indexer)
+
+CREATE TABLE IF NOT EXISTS chain_transactions (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('place_bet', 'claim_winnings', 'create_market')),
+    sender TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    hash TEXT NOT NULL UNIQUE,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'committed', 'failed', 'expired')),
+    "vmStatus" TEXT,
+    "gasUsed" BIGINT,
+    version BIGINT,
+    "marketId" TEXT,
+    "betId" TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    "lastError" TEXT,
+    "submittedAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "expiresAt" TIMESTAMP NOT NULL,
+    "confirmedAt" TIMESTAMP,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_chain_transactions_pending
+    ON chain_transactions ("submittedAt") WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_chain_transactions_market ON chain_transactions ("marketId");
+CREATE INDEX IF NOT EXISTS idx_chain_transactions_bet ON chain_transactions ("betId");
+
+ALTER TABLE bets_extended ADD COLUMN IF NOT EXISTS "txHash" TEXT;
+ALTER TABLE bets_extended ADD COLUMN IF NOT EXISTS "txStatus" TEXT;
+ALTER TABLE markets_extended ADD COLUMN IF NOT EXISTS "txHash" TEXT;
+ALTER TABLE markets_extended ADD COLUMN IF NOT EXISTS "txStatus" TEXT;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint WHERE conname = 'bets_extended_txStatus_check'
+    ) THEN
+        ALTER TABLE bets_extended ADD CONSTRAINT "bets_extended_txStatus_check"
+            CHECK ("txStatus" IN ('pending', 'confirmed', 'failed'));
+    END IF;
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint WHERE conname = 'markets_extended_txStatus_check'
+    ) THEN
+        ALTER TABLE markets_extended ADD CONSTRAINT "markets_extended_txStatus_check"
+            CHECK ("txStatus" IN ('pending', 'confirmed', 'failed'));
+    END IF;
+END $$;
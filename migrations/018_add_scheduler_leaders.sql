@@ -0,0 +1,12 @@
+-- Current leader of each background job
+-- Replicas compete for a Postgres advisory lock per job; the holder runs the job and
+-- records itself here. The lock, not this row, decides leadership: a row whose backend
+-- no longer holds the lock belongs to a dead leader and is replaced on the next takeover
+
+CREATE TABLE IF NOT EXISTS scheduler_leaders (
+    job TEXT PRIMARY KEY,
+    "instanceId" TEXT NOT NULL,
+    "backendPid" INTEGER NOT NULL,
+    "acquiredAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "heartbeatAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
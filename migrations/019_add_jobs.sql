@@ -0,0 +1,40 @@
+-- Persistent background job queue
+-- Every run of a background job is a row in jobs: workers on any replica claim queued rows with
+-- FOR UPDATE SKIP LOCKED, failures are retried with exponential backoff until "maxAttempts",
+-- and finished rows stay as run history until they are pruned.
+-- job_schedules holds one recurring schedule per job kind; the leading replica enqueues a job
+-- when "nextRunAt" passes. A paused schedule enqueues nothing and its queued jobs are not claimed
+
+CREATE TABLE IF NOT EXISTS jobs (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}'::jsonb,
+    state TEXT NOT NULL DEFAULT 'queued'
+        CHECK (state IN ('queued', 'running', 'succeeded', 'failed', 'cancelled')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    "maxAttempts" INTEGER NOT NULL DEFAULT 5,
+    "nextRunAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "lastError" TEXT,
+    result JSONB,
+    "lockedBy" TEXT,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "startedAt" TIMESTAMP,
+    "finishedAt" TIMESTAMP,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_queued ON jobs ("nextRunAt") WHERE state = 'queued';
+CREATE INDEX IF NOT EXISTS idx_jobs_kind_created ON jobs (kind, "createdAt" DESC);
+CREATE INDEX IF NOT EXISTS idx_jobs_state ON jobs (state);
+
+CREATE TABLE IF NOT EXISTS job_schedules (
+    kind TEXT PRIMARY KEY,
+    schedule TEXT NOT NULL,
+    paused BOOLEAN NOT NULL DEFAULT FALSE,
+    "nextRunAt" TIMESTAMP NOT NULL,
+    "lastEnqueuedAt" TIMESTAMP,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- The sync and yield loops now run from the queue and no longer elect a leader
+DELETE FROM scheduler_leaders WHERE job IN ('indexer_sync', 'yield_calc', 'yield_accrual');
@@ -0,0 +1,28 @@
+-- Health and catch-up cursors of the database event listener
+-- The listener records its connection state here so any replica can report it, and keeps
+-- the highest transaction version it has applied per indexer stream. After a reconnect it
+-- replays indexer rows above these versions so notifications sent while it was
+-- disconnected are not lost
+
+CREATE TABLE IF NOT EXISTS event_listener_state (
+    id TEXT PRIMARY KEY,
+    connected BOOLEAN NOT NULL DEFAULT FALSE,
+    "connectedAt" TIMESTAMP,
+    "disconnectedAt" TIMESTAMP,
+    "lastNotificationAt" TIMESTAMP,
+    "lastError" TEXT,
+    "lastErrorAt" TIMESTAMP,
+    reconnects INTEGER NOT NULL DEFAULT 0,
+    "betVersion" BIGINT NOT NULL DEFAULT 0,
+    "claimVersion" BIGINT NOT NULL DEFAULT 0,
+    "marketVersion" BIGINT NOT NULL DEFAULT 0,
+    "resolutionVersion" BIGINT NOT NULL DEFAULT 0,
+    "lastCatchUpAt" TIMESTAMP,
+    "lastCatchUpEvents" INTEGER,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_bets_transaction_version ON bets(transaction_version);
+CREATE INDEX IF NOT EXISTS idx_bets_claim_transaction_version ON bets(claim_transaction_version);
+CREATE INDEX IF NOT EXISTS idx_markets_transaction_version ON markets(transaction_version);
+CREATE INDEX IF NOT EXISTS idx_markets_resolution_transaction_version ON markets(resolution_transaction_version);
@@ -0,0 +1,19 @@
+-- Reports of the reconciliation job
+-- Each run compares bet counts, pool sums and resolution status of every on-chain market in
+-- the indexer tables (markets, bets) with markets_extended/bets_extended and stores what
+-- differed. With repair enabled the extended side is rebuilt from the indexer, and each
+-- discrepancy records whether the repair fixed it
+
+CREATE TABLE IF NOT EXISTS reconciliation_reports (
+    id TEXT PRIMARY KEY,
+    "jobId" TEXT,
+    repair BOOLEAN NOT NULL DEFAULT FALSE,
+    "marketsChecked" INTEGER NOT NULL DEFAULT 0,
+    "discrepancyCount" INTEGER NOT NULL DEFAULT 0,
+    "repairedCount" INTEGER NOT NULL DEFAULT 0,
+    discrepancies JSONB NOT NULL DEFAULT '[]'::jsonb,
+    "startedAt" TIMESTAMP NOT NULL,
+    "finishedAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_reconciliation_reports_finished_at ON reconciliation_reports("finishedAt" DESC);
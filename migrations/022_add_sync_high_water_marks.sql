@@ -0,0 +1,33 @@
+-- High-water marks for the indexer to extended table sync
+-- Market and bet sync read only indexer rows above the transaction version recorded here
+-- instead of scanning for rows missing from markets_extended/bets_extended, and keep the
+-- rows scanned and written by their last run and in total. Marks start just below the
+-- oldest row that has not been synced yet, so existing deployments do not rescan
+
+ALTER TABLE sync_status
+    ADD COLUMN IF NOT EXISTS "lastVersion" BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS "lastScanned" INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS "lastWritten" INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS "lastSkipped" INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS "totalScanned" BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS "totalWritten" BIGINT NOT NULL DEFAULT 0;
+
+INSERT INTO sync_status (id, "eventType", "lastVersion")
+SELECT 'MarketSync', 'MarketSync', COALESCE(
+    MIN(m.transaction_version) FILTER (WHERE me.id IS NULL) - 1,
+    MAX(m.transaction_version),
+    0
+)
+FROM markets m
+LEFT JOIN markets_extended me ON me."blockchainMarketId" = m.market_id
+ON CONFLICT ("eventType") DO NOTHING;
+
+INSERT INTO sync_status (id, "eventType", "lastVersion")
+SELECT 'BetSync', 'BetSync', COALESCE(
+    MIN(b.transaction_version) FILTER (WHERE be.id IS NULL) - 1,
+    MAX(b.transaction_version),
+    0
+)
+FROM bets b
+LEFT JOIN bets_extended be ON be."blockchainBetId" = b.bet_id
+ON CONFLICT ("eventType") DO NOTHING;
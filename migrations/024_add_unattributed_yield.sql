@@ -0,0 +1,6 @@
+-- Ledger yield that no bet could claim, per market
+-- Per-bet yields plus "unattributedYield" add up to "totalYieldEarned" once a market's
+-- ledger has been split across its bets, so reconciliation can tell attributed markets apart
+
+ALTER TABLE markets_extended
+    ADD COLUMN IF NOT EXISTS "unattributedYield" NUMERIC(78,18) NOT NULL DEFAULT 0;
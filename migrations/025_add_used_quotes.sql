@@ -0,0 +1,12 @@
+-- Single-use bet quotes
+-- Each quote ID carries a random nonce; placing a bet with the quote records the nonce here so
+-- the same quote cannot back a second bet. Rows are purged once the quote has expired
+
+CREATE TABLE IF NOT EXISTS used_quotes (
+    nonce TEXT PRIMARY KEY,
+    "userAddress" TEXT NOT NULL,
+    "usedAt" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "expiresAt" TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_used_quotes_expires_at ON used_quotes("expiresAt");
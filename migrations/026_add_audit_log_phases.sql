@@ -0,0 +1,10 @@
+-- Two-phase audit log entries
+-- A "started" row is written before a privileged action runs, and a "completed" row pointing
+-- back at it once the action has finished. A started row without a completion marks an
+-- action whose outcome was never recorded
+
+ALTER TABLE audit_log ADD COLUMN IF NOT EXISTS phase TEXT NOT NULL DEFAULT 'completed'
+    CHECK (phase IN ('started', 'completed'));
+ALTER TABLE audit_log ADD COLUMN IF NOT EXISTS "startedId" BIGINT REFERENCES audit_log(id);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_started_id ON audit_log("startedId");
@@ -0,0 +1,8 @@
+-- Resume point of the sync gap scan
+-- Each run retries a page of unsynced rows at or below the high-water mark, starting after
+-- the (transaction version, id) recorded here, so rows that keep failing do not hide the rows
+-- behind them. NULL starts again from the oldest row
+
+ALTER TABLE sync_status
+    ADD COLUMN IF NOT EXISTS "gapCursorVersion" BIGINT,
+    ADD COLUMN IF NOT EXISTS "gapCursorId" BIGINT;
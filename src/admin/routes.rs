@@ -1,30 +1,47 @@
 use axum::{
     extract::{Query, State},
+    middleware,
     response::Json,
     routing::post,
-    Router,
+    Extension, Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use tracing::{error, info};
+use tracing::info;
+
+use std::sync::Arc;
 
 use crate::{
+    config::Config,
     db::Database,
     error::AppError,
     services::{
-        aptos_contract::{AptosContractService, CreateMarketParams},
+        audit::AuditDetails,
+        market_admin::{MarketAdminService, SyncMarketsData},
         market_seeder::MarketSeeder,
+        signer::SignerPool,
     },
+    state::AppState,
 };
 
-pub fn create_admin_router(db: Database) -> Router {
+/// Market maintenance endpoints served at the root; they require the API key and are
+/// written to the audit log.
+pub fn create_admin_router(state: AppState) -> Router {
     Router::new()
         .route("/seed-markets", post(seed_markets))
         .route(
             "/sync-markets-to-blockchain",
             post(sync_markets_to_blockchain),
         )
-        .with_state(db)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::audit::audit,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::auth::require_api_key,
+        ))
+        .with_state(state)
 }
 
 #[derive(Debug, Deserialize)]
@@ -39,14 +56,12 @@ fn default_count() -> usize {
 
 async fn seed_markets(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
     Query(params): Query<SeedMarketsQuery>,
-) -> Result<Json<Value>, AppError> {
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
     info!("Admin: Seed markets requested, count: {}", params.count);
 
-    let api_key = std::env::var("ADJACENT_API_KEY")
-        .map_err(|_| AppError::Internal("ADJACENT_API_KEY not configured".to_string()))?;
-
-    let seeder = MarketSeeder::new(db.pool().clone(), api_key)
+    let seeder = MarketSeeder::new(db.pool().clone(), &config.adjacent, &config.images)
         .map_err(|e| AppError::Internal(format!("Failed to create seeder: {}", e)))?;
 
     let result = seeder
@@ -54,21 +69,26 @@ async fn seed_markets(
         .await
         .map_err(|e| AppError::Internal(format!("Seeding failed: {}", e)))?;
 
-    Ok(Json(json!({
-        "success": true,
-        "data": {
-            "total_requested": result.total_requested,
-            "fetched_from_api": result.fetched_from_api,
-            "created": result.created,
-            "updated": result.updated,
-            "skipped": result.skipped,
-            "errors": result.errors
-        },
-        "message": format!(
-            "Seeded {} markets: {} created, {} updated",
-            result.fetched_from_api, result.created, result.updated
-        )
-    })))
+    let data = json!({
+        "total_requested": result.total_requested,
+        "fetched_from_api": result.fetched_from_api,
+        "created": result.created,
+        "updated": result.updated,
+        "skipped": result.skipped,
+        "errors": result.errors
+    });
+
+    Ok((
+        Extension(AuditDetails::new("markets.seed").after(&data)),
+        Json(json!({
+            "success": true,
+            "data": data,
+            "message": format!(
+                "Seeded {} markets: {} created, {} updated",
+                result.fetched_from_api, result.created, result.updated
+            )
+        })),
+    ))
 }
 
 #[derive(Debug, Serialize)]
@@ -101,145 +121,29 @@ pub struct SyncMarketsResponse {
     pub message: String,
 }
 
-#[derive(Debug, Serialize)]
-pub struct SyncMarketsData {
-    pub total_found: usize,
-    pub synced: usize,
-    pub failed: usize,
-    pub markets: Vec<SyncedMarket>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct SyncedMarket {
-    pub market_id: String,
-    pub question: String,
-    pub blockchain_market_id: Option<i64>,
-    pub status: String,
-}
-
 async fn sync_markets_to_blockchain(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    State(signers): State<Arc<SignerPool>>,
     Query(params): Query<SyncMarketsQuery>,
-) -> Result<Json<Value>, AppError> {
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
     info!("Admin: Sync markets to blockchain requested");
 
-    let aptos_service = AptosContractService::new()
-        .map_err(|e| AppError::Internal(format!("Failed to initialize Aptos service: {}", e)))?;
-
-    let module_addr = std::env::var("APTOS_MODULE_ADDRESS")
-        .map_err(|_| AppError::Internal("APTOS_MODULE_ADDRESS not configured".to_string()))?;
-    let protocol_selector_addr =
-        std::env::var("APTOS_PROTOCOL_SELECTOR_ADDR").unwrap_or_else(|_| module_addr.clone());
-
-    let limit = params.limit.unwrap_or(10);
-    let markets = sqlx::query!(
-        r#"
-        SELECT id, "marketId", question, description, "endDate"
-        FROM markets_extended
-        WHERE "blockchainMarketId" IS NULL
-        AND status = 'active'
-        ORDER BY "createdAt" DESC
-        LIMIT $1
-        "#,
-        limit as i64
-    )
-    .fetch_all(db.pool())
-    .await?;
-
-    let total_found = markets.len();
-    info!("Found {} markets without blockchain ID", total_found);
-
-    let mut synced = 0;
-    let mut failed = 0;
-    let mut synced_markets = Vec::new();
-
-    for market in markets {
-        let market_id_str = market.marketId.clone().unwrap_or_default();
-        info!(
-            "Processing market: {} - {}",
-            market_id_str,
-            market.question.as_deref().unwrap_or("No question")
-        );
-
-        let now = chrono::Utc::now();
-        let end_utc = market.endDate.and_utc();
-        let duration = end_utc.signed_duration_since(now);
-        let duration_seconds = duration.num_seconds().max(0) as u64;
-
-        let params = CreateMarketParams {
-            question: market
-                .question
-                .clone()
-                .unwrap_or_else(|| "Untitled Market".to_string()),
-            description: market
-                .description
-                .clone()
-                .unwrap_or_else(|| "No description".to_string()),
-            duration_seconds,
-            token_type: "0x1::aptos_coin::AptosCoin".to_string(),
-            protocol_selector_addr: protocol_selector_addr.clone(),
-        };
-
-        match aptos_service.create_market(params).await {
-            Ok(result) => {
-                info!(
-                    "Created market on blockchain: ID {}, TX: {}",
-                    result.market_id, result.tx_hash
-                );
-
-                match sqlx::query!(
-                    r#"UPDATE markets_extended SET "blockchainMarketId" = $1 WHERE id = $2"#,
-                    result.market_id,
-                    market.id
-                )
-                .execute(db.pool())
-                .await
-                {
-                    Ok(_) => {
-                        synced += 1;
-                        synced_markets.push(SyncedMarket {
-                            market_id: market_id_str,
-                            question: market.question.unwrap_or_default(),
-                            blockchain_market_id: Some(result.market_id),
-                            status: "synced".to_string(),
-                        });
-                    }
-                    Err(e) => {
-                        error!("Failed to update database for market {}: {}", market.id, e);
-                        failed += 1;
-                        synced_markets.push(SyncedMarket {
-                            market_id: market_id_str,
-                            question: market.question.unwrap_or_default(),
-                            blockchain_market_id: Some(result.market_id),
-                            status: format!("blockchain_created_but_db_update_failed: {}", e),
-                        });
-                    }
-                }
-            }
-            Err(e) => {
-                error!("Failed to create market on blockchain: {}", e);
-                failed += 1;
-                synced_markets.push(SyncedMarket {
-                    market_id: market_id_str,
-                    question: market.question.unwrap_or_default(),
-                    blockchain_market_id: None,
-                    status: format!("failed: {}", e),
-                });
-            }
-        }
-    }
-
-    Ok(Json(json!({
-        "success": true,
-        "data": {
-            "total_found": total_found,
-            "synced": synced,
-            "failed": failed,
-            "markets": synced_markets
-        },
-        "message": format!(
-            "Sync complete: {}/{} markets synced to blockchain",
-            synced, total_found
-        )
-    })))
+    let service = MarketAdminService::new(db.pool().clone(), &config.chain).with_signers(signers);
+    let result = service
+        .push_pending_markets(params.limit.unwrap_or(10))
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to sync markets: {}", e)))?;
+
+    Ok((
+        Extension(AuditDetails::new("markets.sync_to_blockchain").after(&result)),
+        Json(json!({
+            "success": true,
+            "message": format!(
+                "Sync complete: {}/{} markets synced to blockchain",
+                result.synced, result.total_found
+            ),
+            "data": result
+        })),
+    ))
 }
@@ -0,0 +1,805 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::Instant;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use kizo_server::{
+    config::Config,
+    db::Database,
+    services::{
+        audit::{cli_actor, AuditDetails, AuditLog, NewAuditEntry},
+        blockchain_sync::BlockchainSyncService,
+        db_event_listener::DbEventListener,
+        job_queue::{JobFilter, JobKind, JobQueue, JobState, NewJob},
+        market_admin::MarketAdminService,
+        market_outcomes::MarketOutcomeService,
+        market_seeder::MarketSeeder,
+        reconciliation::ReconciliationService,
+        risk_limits::{RiskLimitService, RiskLimitsPatch, GLOBAL_SCOPE},
+        signer::{parse_private_key, KeystoreFile, SignerPool, KEYSTORE_ITERATIONS},
+        yield_accrual::YieldAccrualService,
+        YieldService,
+    },
+};
+
+#[derive(Parser)]
+#[command(
+    name = "kizo-admin",
+    version,
+    about = "Operational commands for the Kizo backend"
+)]
+struct Cli {
+    /// Path to a TOML config file (defaults to $KIZO_CONFIG or ./kizo.toml when present)
+    #[arg(short, long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Print results as JSON instead of human-readable text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Fetch markets from Adjacent and upsert them into markets_extended
+    SeedMarkets {
+        #[arg(long)]
+        count: Option<usize>,
+    },
+    /// Create on-chain markets for active markets without a blockchain ID
+    PushMarkets {
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Sync indexer tables into the extended tables
+    Sync {
+        #[arg(value_enum, default_value_t = SyncScope::Full)]
+        scope: SyncScope,
+    },
+    /// Recalculate current yields for active markets
+    RecalcYields,
+    /// Accrue completed days into the yield ledger, backfilling missed days
+    AccrueYields {
+        /// Only accrue this market (markets_extended id)
+        #[arg(long)]
+        market: Option<String>,
+    },
+    /// Refresh protocol APYs from the on-chain adapters
+    RefreshApy,
+    /// Compare the indexer tables with the extended tables and store a report
+    Reconcile {
+        /// Rebuild drifted extended rows from the indexer
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Resolve a market and settle its bets
+    ResolveMarket {
+        /// Market id, marketId, adjTicker or blockchain market id
+        market: String,
+        /// Winning side of a binary market
+        #[arg(long, value_enum, required_unless_present = "outcome_index")]
+        outcome: Option<Outcome>,
+        /// Winning outcome index of a categorical market
+        #[arg(long, conflicts_with = "outcome")]
+        outcome_index: Option<i32>,
+    },
+    /// Turn a market without bets into a categorical market with the given outcomes
+    SetOutcomes {
+        /// markets_extended id
+        market: String,
+        /// Outcome labels in index order, e.g. --label Red --label Blue --label Green
+        #[arg(long = "label", required = true)]
+        labels: Vec<String>,
+    },
+    /// Cancel an active market
+    CancelMarket {
+        /// Market id, marketId, adjTicker or blockchain market id
+        market: String,
+    },
+    /// Pause betting platform-wide, or on one market with --market
+    PauseBetting {
+        /// markets_extended id
+        #[arg(long)]
+        market: Option<String>,
+        /// Shown to bettors whose bets are refused
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Resume betting paused with pause-betting
+    ResumeBetting {
+        /// markets_extended id
+        #[arg(long)]
+        market: Option<String>,
+    },
+    /// Inspect and retry event processing
+    Events {
+        #[command(subcommand)]
+        action: EventsAction,
+    },
+    /// Inspect and control the background job queue
+    Jobs {
+        #[command(subcommand)]
+        action: JobsAction,
+    },
+    /// Manage signing keys
+    Keystore {
+        #[command(subcommand)]
+        action: KeystoreAction,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SyncScope {
+    Full,
+    Markets,
+    Bets,
+    Stats,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Outcome {
+    Yes,
+    No,
+}
+
+#[derive(Subcommand)]
+enum EventsAction {
+    /// List events that failed processing
+    Errors {
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+    /// Re-run a logged event by its event_processing_log id
+    Retry { id: i32 },
+}
+
+#[derive(Subcommand)]
+enum JobsAction {
+    /// List jobs, newest first
+    List {
+        /// queued, running, succeeded, failed or cancelled
+        #[arg(long)]
+        state: Option<String>,
+        /// indexer_sync, yield_calc, yield_accrual, apy_refresh or reconciliation
+        #[arg(long)]
+        kind: Option<String>,
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+    /// Queue a job to run as soon as a worker is free
+    Enqueue {
+        kind: String,
+        /// JSON payload, e.g. '{"marketId":"..."}' for yield_accrual
+        #[arg(long)]
+        payload: Option<String>,
+    },
+    /// Queue a failed or cancelled job again
+    Retry { id: String },
+    /// Cancel a queued or running job
+    Cancel { id: String },
+    /// List recurring schedules
+    Schedules,
+    /// Stop scheduling a kind and hold its queued jobs
+    Pause { kind: String },
+    /// Resume a paused kind
+    Resume { kind: String },
+}
+
+#[derive(Subcommand)]
+enum KeystoreAction {
+    /// Write a new encrypted keystore file, locked with KEYSTORE_PASSPHRASE
+    New {
+        /// File to create; an existing file is never overwritten
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+        /// Encrypt chain.private_key (APTOS_PRIVATE_KEY) instead of generating a new key
+        #[arg(long)]
+        import_env: bool,
+    },
+    /// Load the configured signer backend and list its accounts
+    Accounts,
+}
+
+/// Shortest passphrase accepted for new keystore files.
+const MIN_PASSPHRASE_LEN: usize = 12;
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    if let Err(e) = run(&cli).await {
+        if cli.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&json!({ "success": false, "error": e.to_string() }))
+                    .unwrap_or_default()
+            );
+        } else {
+            eprintln!("Error: {:#}", e);
+        }
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+
+    // Logs go to stderr so `--json` output stays machine-readable.
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_new(&config.server.log_level)
+                .unwrap_or_else(|_| "kizo_server=info".into()),
+        )
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .init();
+
+    // Key management works without a database.
+    if let Command::Keystore { action } = &cli.command {
+        let (message, data) = run_keystore(action, &config).await?;
+        print_output(cli.json, &message, data, true);
+        return Ok(());
+    }
+
+    let db = Database::new(&config.database).await?;
+    db.health_check().await?;
+
+    let Some(details) = audit_details(&cli.command) else {
+        let (message, data, success) = execute(cli, &config, &db).await?;
+        print_output(cli.json, &message, data, success);
+        if !success {
+            std::process::exit(1);
+        }
+        return Ok(());
+    };
+
+    // A command that changes state is refused when it cannot be audited.
+    let audit_log = AuditLog::new(db.pool().clone());
+    let mut entry = NewAuditEntry {
+        actor: cli_actor(),
+        details,
+        status: 0,
+        method: "CLI".to_string(),
+        path: std::env::args().skip(1).collect::<Vec<_>>().join(" "),
+        ip_address: None,
+        user_agent: Some(format!("kizo-admin/{}", env!("CARGO_PKG_VERSION"))),
+        request_id: None,
+        duration_ms: 0,
+    };
+    let started_id = audit_log
+        .start(&entry)
+        .await
+        .context("Refusing to run a command that cannot be audited")?;
+    let started = Instant::now();
+    let result = execute(cli, &config, &db).await;
+    entry.status = match &result {
+        Ok((_, _, true)) => 0,
+        _ => 1,
+    };
+    if let Ok((_, data, _)) = &result {
+        entry.details = std::mem::take(&mut entry.details).after(data);
+    }
+    entry.duration_ms = started.elapsed().as_millis() as i32;
+    // The command has already run; a missing outcome is reported but does not fail it.
+    if let Err(e) = audit_log.complete(started_id, &entry).await {
+        eprintln!(
+            "Warning: the command ran but its outcome was not recorded in the audit log (entry {}): {:#}",
+            started_id, e
+        );
+    }
+
+    let (message, data, success) = result?;
+    print_output(cli.json, &message, data, success);
+    if !success {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// What a state-changing command does, for its audit log entry; `None` for read-only ones.
+fn audit_details(command: &Command) -> Option<AuditDetails> {
+    let details = match command {
+        Command::SeedMarkets { .. } => AuditDetails::new("markets.seed"),
+        Command::PushMarkets { .. } => AuditDetails::new("markets.sync_to_blockchain"),
+        Command::Sync { .. } => AuditDetails::new("sync.trigger"),
+        Command::RecalcYields => AuditDetails::new("yields.recalculate"),
+        Command::AccrueYields { market: None } => AuditDetails::new("yields.accrue"),
+        Command::AccrueYields {
+            market: Some(market),
+        } => AuditDetails::new("yields.accrue").target("market", market),
+        Command::RefreshApy => AuditDetails::new("protocols.apy.update_all"),
+        Command::Reconcile { .. } => AuditDetails::new("reconciliation.run"),
+        Command::ResolveMarket { market, .. } => {
+            AuditDetails::new("market.resolve").target("market", market)
+        }
+        Command::SetOutcomes { market, .. } => {
+            AuditDetails::new("market.outcomes.set").target("market", market)
+        }
+        Command::CancelMarket { market } => {
+            AuditDetails::new("market.cancel").target("market", market)
+        }
+        Command::PauseBetting { market, .. } | Command::ResumeBetting { market } => {
+            AuditDetails::new("risk_limits.update")
+                .target("risk_limits", market.as_deref().unwrap_or(GLOBAL_SCOPE))
+        }
+        Command::Events {
+            action: EventsAction::Retry { id },
+        } => AuditDetails::new("event.retry").target("event", id),
+        Command::Jobs { action } => match action {
+            JobsAction::Enqueue { kind, .. } => {
+                AuditDetails::new("job.enqueue").target("job_kind", kind)
+            }
+            JobsAction::Retry { id } => AuditDetails::new("job.retry").target("job", id),
+            JobsAction::Cancel { id } => AuditDetails::new("job.cancel").target("job", id),
+            JobsAction::Pause { kind } => {
+                AuditDetails::new("job_schedule.pause").target("job_schedule", kind)
+            }
+            JobsAction::Resume { kind } => {
+                AuditDetails::new("job_schedule.resume").target("job_schedule", kind)
+            }
+            JobsAction::List { .. } | JobsAction::Schedules => return None,
+        },
+        Command::Events { .. } | Command::Keystore { .. } => return None,
+    };
+    Some(details)
+}
+
+/// Runs a database command: its message, data and whether it succeeded.
+async fn execute(cli: &Cli, config: &Config, db: &Database) -> Result<(String, Value, bool)> {
+    let pool = db.pool().clone();
+
+    let (message, data) = match &cli.command {
+        Command::SeedMarkets { count } => {
+            let seeder = MarketSeeder::new(pool, &config.adjacent, &config.images)?;
+            let result = seeder
+                .seed_markets(count.unwrap_or(config.seeding.market_count))
+                .await?;
+            (
+                format!(
+                    "Seeded markets: {} created, {} updated, {} skipped, {} errors",
+                    result.created, result.updated, result.skipped, result.errors
+                ),
+                serde_json::to_value(result)?,
+            )
+        }
+        Command::PushMarkets { limit } => {
+            let signers = std::sync::Arc::new(SignerPool::load(config).await?);
+            let service = MarketAdminService::new(pool, &config.chain).with_signers(signers);
+            let result = service.push_pending_markets(*limit).await?;
+            (
+                format!(
+                    "Pushed {}/{} markets to blockchain ({} failed)",
+                    result.synced, result.total_found, result.failed
+                ),
+                serde_json::to_value(result)?,
+            )
+        }
+        Command::Sync { scope } => {
+            let sync = BlockchainSyncService::new(pool);
+            match scope {
+                SyncScope::Full => {
+                    let summary = sync.run_full_sync().await?;
+                    (
+                        format!(
+                            "Full sync: {} processed, {} errors in {}ms",
+                            summary.total_processed, summary.total_errors, summary.duration_ms
+                        ),
+                        serde_json::to_value(summary)?,
+                    )
+                }
+                SyncScope::Markets | SyncScope::Bets => {
+                    let result = if matches!(scope, SyncScope::Markets) {
+                        sync.sync_markets().await?
+                    } else {
+                        sync.sync_bets().await?
+                    };
+                    (
+                        format!(
+                            "Synced {}: {} scanned, {} written, {} skipped, {} errors (high-water version {})",
+                            result.event_type,
+                            result.processed,
+                            result.new_events,
+                            result.skipped,
+                            result.errors,
+                            result.high_water_version
+                        ),
+                        serde_json::to_value(result)?,
+                    )
+                }
+                SyncScope::Stats => {
+                    sync.update_market_stats().await?;
+                    ("Market stats updated".to_string(), Value::Null)
+                }
+            }
+        }
+        Command::RecalcYields => {
+            let service = YieldService::new(pool, &config.chain, &config.yield_allocation);
+            let processed = service.calculate_all_market_yields().await?;
+            (
+                format!("Recalculated yields for {} markets", processed),
+                json!({ "marketsProcessed": processed }),
+            )
+        }
+        Command::AccrueYields { market } => {
+            let service = YieldAccrualService::new(pool, config);
+            match market {
+                Some(id) => {
+                    let result = service.accrue_market(id).await?;
+                    (
+                        format!(
+                            "Accrued {} periods for market {} (total yield earned: {})",
+                            result.periods_accrued, result.market_id, result.total_yield_earned
+                        ),
+                        serde_json::to_value(&result)?,
+                    )
+                }
+                None => {
+                    let summary = service.accrue_all().await?;
+                    (
+                        format!(
+                            "Accrued {} periods across {} markets ({} records written, {} failed)",
+                            summary.periods_accrued,
+                            summary.markets_processed,
+                            summary.records_written,
+                            summary.markets_failed
+                        ),
+                        serde_json::to_value(&summary)?,
+                    )
+                }
+            }
+        }
+        Command::RefreshApy => {
+            let service = YieldService::new(pool, &config.chain, &config.yield_allocation);
+            let updated = service.update_all_protocols_apy().await?;
+            let lines: Vec<String> = updated
+                .iter()
+                .map(|(name, apy)| format!("  {}: {}%", name, apy))
+                .collect();
+            (
+                format!(
+                    "Updated APY for {} protocols\n{}",
+                    updated.len(),
+                    lines.join("\n")
+                ),
+                json!(updated
+                    .iter()
+                    .map(|(name, apy)| json!({ "protocol": name, "apy": apy.to_string() }))
+                    .collect::<Vec<_>>()),
+            )
+        }
+        Command::Reconcile { repair } => {
+            let report = ReconciliationService::new(pool).run(*repair, None).await?;
+            let lines: Vec<String> = report
+                .discrepancies
+                .iter()
+                .map(|d| {
+                    format!(
+                        "  market {} {}: expected {}, found {}{}",
+                        d.blockchain_market_id,
+                        d.field,
+                        d.expected,
+                        d.actual,
+                        if d.repaired { " (repaired)" } else { "" }
+                    )
+                })
+                .collect();
+            (
+                format!(
+                    "Checked {} markets: {} discrepancies, {} repaired\n{}",
+                    report.markets_checked,
+                    report.discrepancy_count,
+                    report.repaired_count,
+                    lines.join("\n")
+                ),
+                serde_json::to_value(&report)?,
+            )
+        }
+        Command::ResolveMarket {
+            market,
+            outcome,
+            outcome_index,
+        } => {
+            let service = MarketAdminService::new(pool, &config.chain);
+            let result = match (outcome, outcome_index) {
+                (_, Some(index)) => service.resolve_outcome(market, *index).await?,
+                (outcome, None) => {
+                    service
+                        .resolve_market(market, matches!(outcome, Some(Outcome::Yes)))
+                        .await?
+                }
+            };
+            let resolved_as = match (result.result, result.winning_outcome) {
+                (Some(true), _) => "YES".to_string(),
+                (Some(false), _) => "NO".to_string(),
+                (None, Some(index)) => format!("outcome {}", index),
+                (None, None) => "unknown".to_string(),
+            };
+            (
+                format!(
+                    "Market {} resolved as {} ({} bets settled)",
+                    result.market_id, resolved_as, result.bets_updated
+                ),
+                serde_json::to_value(result)?,
+            )
+        }
+        Command::SetOutcomes { market, labels } => {
+            let outcomes = MarketOutcomeService::new(pool)
+                .define_outcomes(market, labels)
+                .await?;
+            (
+                format!(
+                    "Market {} is now categorical with {} outcomes",
+                    market,
+                    outcomes.len()
+                ),
+                serde_json::to_value(outcomes)?,
+            )
+        }
+        Command::CancelMarket { market } => {
+            let service = MarketAdminService::new(pool, &config.chain);
+            let result = service.cancel_market(market).await?;
+            (
+                format!(
+                    "Market {} cancelled ({} bets cancelled)",
+                    result.market_id, result.bets_updated
+                ),
+                serde_json::to_value(result)?,
+            )
+        }
+        Command::PauseBetting { market, reason } => {
+            let scope = market.as_deref().unwrap_or(GLOBAL_SCOPE);
+            let limits = RiskLimitService::new(pool)
+                .update(
+                    scope,
+                    RiskLimitsPatch {
+                        betting_paused: Some(true),
+                        pause_reason: Some(reason.clone()),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            (
+                format!("Betting paused ({})", scope),
+                serde_json::to_value(limits)?,
+            )
+        }
+        Command::ResumeBetting { market } => {
+            let scope = market.as_deref().unwrap_or(GLOBAL_SCOPE);
+            let limits = RiskLimitService::new(pool)
+                .update(
+                    scope,
+                    RiskLimitsPatch {
+                        betting_paused: Some(false),
+                        pause_reason: Some(None),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            (
+                format!("Betting resumed ({})", scope),
+                serde_json::to_value(limits)?,
+            )
+        }
+        Command::Events { action } => {
+            let listener = DbEventListener::new(pool);
+            match action {
+                EventsAction::Errors { limit } => {
+                    let entries = listener.list_failed_events(*limit).await?;
+                    let mut text = format!("{} failed events", entries.len());
+                    for entry in &entries {
+                        text.push_str(&format!(
+                            "\n  #{} {} {} tx={} {}",
+                            entry.id,
+                            entry.created_at.format("%Y-%m-%d %H:%M:%S"),
+                            entry.event_type,
+                            entry
+                                .transaction_version
+                                .map(|v| v.to_string())
+                                .unwrap_or_else(|| "-".to_string()),
+                            entry.error_message.as_deref().unwrap_or("")
+                        ));
+                    }
+                    (text, serde_json::to_value(entries)?)
+                }
+                EventsAction::Retry { id } => {
+                    let result = listener.retry_logged_event(*id).await?;
+                    let text = match &result.error {
+                        None => format!("Event {} reprocessed successfully", result.id),
+                        Some(e) => format!("Event {} failed again: {}", result.id, e),
+                    };
+                    let success = result.success;
+                    return Ok((text, serde_json::to_value(result)?, success));
+                }
+            }
+        }
+        Command::Jobs { action } => {
+            let queue = JobQueue::new(pool, &config.jobs);
+            match action {
+                JobsAction::List { state, kind, limit } => {
+                    let filter = JobFilter {
+                        state: state.as_deref().map(str::parse::<JobState>).transpose()?,
+                        kind: kind.as_deref().map(str::parse::<JobKind>).transpose()?,
+                        limit: *limit,
+                    };
+                    let jobs = queue.list(&filter).await?;
+                    let mut text = format!("{} jobs", jobs.len());
+                    for job in &jobs {
+                        text.push_str(&format!(
+                            "\n  {} {} {} {} attempts {}/{} {}",
+                            job.id,
+                            job.created_at.format("%Y-%m-%d %H:%M:%S"),
+                            job.kind,
+                            job.state,
+                            job.attempts,
+                            job.max_attempts,
+                            job.last_error.as_deref().unwrap_or("")
+                        ));
+                    }
+                    (text, serde_json::to_value(jobs)?)
+                }
+                JobsAction::Enqueue { kind, payload } => {
+                    let payload = payload
+                        .as_deref()
+                        .map(serde_json::from_str::<Value>)
+                        .transpose()
+                        .context("--payload must be JSON")?;
+                    let job = queue
+                        .enqueue(NewJob {
+                            kind: kind.parse()?,
+                            payload,
+                            delay_secs: None,
+                            max_attempts: None,
+                        })
+                        .await?;
+                    (
+                        format!("Queued {} job {}", job.kind, job.id),
+                        serde_json::to_value(job)?,
+                    )
+                }
+                JobsAction::Retry { id } => {
+                    let job = queue
+                        .retry(id)
+                        .await?
+                        .ok_or_else(|| anyhow!("Job {} not found or not failed/cancelled", id))?;
+                    (
+                        format!("Queued job {} again", job.id),
+                        serde_json::to_value(job)?,
+                    )
+                }
+                JobsAction::Cancel { id } => {
+                    let job = queue
+                        .cancel(id)
+                        .await?
+                        .ok_or_else(|| anyhow!("Job {} not found or already finished", id))?;
+                    (
+                        format!("Cancelled job {}", job.id),
+                        serde_json::to_value(job)?,
+                    )
+                }
+                JobsAction::Schedules => {
+                    let schedules = queue.schedules().await?;
+                    let mut text = format!("{} schedules", schedules.len());
+                    for schedule in &schedules {
+                        text.push_str(&format!(
+                            "\n  {} {} next {}{}",
+                            schedule.kind,
+                            schedule.schedule,
+                            schedule.next_run_at.format("%Y-%m-%d %H:%M:%S"),
+                            if schedule.paused { " (paused)" } else { "" }
+                        ));
+                    }
+                    (text, serde_json::to_value(schedules)?)
+                }
+                JobsAction::Pause { kind } | JobsAction::Resume { kind } => {
+                    let paused = matches!(action, JobsAction::Pause { .. });
+                    let schedule = queue
+                        .set_paused(kind.parse()?, paused)
+                        .await?
+                        .ok_or_else(|| anyhow!("{} has no schedule", kind))?;
+                    (
+                        format!(
+                            "{} {}",
+                            if paused { "Paused" } else { "Resumed" },
+                            schedule.kind
+                        ),
+                        serde_json::to_value(schedule)?,
+                    )
+                }
+            }
+        }
+        Command::Keystore { .. } => unreachable!("handled before connecting to the database"),
+    };
+
+    Ok((message, data, true))
+}
+
+async fn run_keystore(action: &KeystoreAction, config: &Config) -> Result<(String, Value)> {
+    match action {
+        KeystoreAction::New { out, import_env } => {
+            let passphrase = config
+                .signer
+                .keystore_passphrase
+                .as_deref()
+                .ok_or_else(|| anyhow!("Set KEYSTORE_PASSPHRASE to encrypt the keystore"))?;
+            if passphrase.len() < MIN_PASSPHRASE_LEN {
+                bail!(
+                    "KEYSTORE_PASSPHRASE must be at least {} characters",
+                    MIN_PASSPHRASE_LEN
+                );
+            }
+            if out.exists() {
+                bail!("{} already exists", out.display());
+            }
+
+            let key = if *import_env {
+                let private_key =
+                    config.chain.private_key.as_deref().ok_or_else(|| {
+                        anyhow!("chain.private_key (APTOS_PRIVATE_KEY) is not set")
+                    })?;
+                parse_private_key(private_key)?
+            } else {
+                parse_private_key(&hex::encode(rand::random::<[u8; 32]>()))?
+            };
+            let keystore = KeystoreFile::encrypt(&key, passphrase, KEYSTORE_ITERATIONS)?;
+            write_private_file(out, &serde_json::to_string_pretty(&keystore)?)?;
+
+            Ok((
+                format!(
+                    "Wrote keystore for {} to {}",
+                    keystore.address,
+                    out.display()
+                ),
+                json!({ "address": keystore.address, "publicKey": keystore.public_key, "path": out }),
+            ))
+        }
+        KeystoreAction::Accounts => {
+            let status = SignerPool::load(config).await?.status();
+            let mut text = format!(
+                "{} signing account(s) from {} backend",
+                status.accounts.len(),
+                status.backend
+            );
+            for account in &status.accounts {
+                let role = account.role.map_or("any", |r| r.as_str());
+                text.push_str(&format!(
+                    "\n  {} ({}, {} role)",
+                    account.account.address, account.account.source, role
+                ));
+            }
+            Ok((text, serde_json::to_value(status)?))
+        }
+    }
+}
+
+/// Creates `path` readable by the owner only.
+fn write_private_file(path: &std::path::Path, contents: &str) -> Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+fn print_output(as_json: bool, message: &str, data: Value, success: bool) {
+    if as_json {
+        let output = json!({ "success": success, "message": message, "data": data });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output).unwrap_or_default()
+        );
+    } else {
+        println!("{}", message);
+    }
+}
@@ -20,7 +20,7 @@ async fn main() -> Result<()> {
     info!("✅ Configuration loaded");
 
     info!("🔌 Connecting to database...");
-    let db = Database::new(&config.database_url).await?;
+    let db = Database::new(&config.database).await?;
     info!("✅ Database connection established");
 
     if db.health_check().await.is_err() {
@@ -30,7 +30,7 @@ async fn main() -> Result<()> {
     info!("✅ Database health check passed");
 
     info!("🌱 Starting market seeding process...");
-    match seed::run_all_seeds(db.pool()).await {
+    match seed::run_all_seeds(db.pool(), &config).await {
         Ok(_) => {
             info!("✅ Seeding completed successfully!");
         }
@@ -5,7 +5,8 @@ use std::collections::HashMap;
 use tracing::debug;
 
 use crate::error::AppError;
-use crate::models::{ChartDataPoint, MarketChartData};
+use crate::models::{ChartDataPoint, MarketChartData, OutcomeChartData, OutcomeChartSeries};
+use crate::services::market_outcomes::outcome_probabilities;
 
 pub struct ChartService {
     pool: PgPool,
@@ -195,6 +196,140 @@ impl ChartService {
         })
     }
 
+    /// Chart series per outcome of a categorical market, from its bets in `bets_extended`.
+    pub async fn get_outcome_chart_data(
+        &self,
+        market_id: &str,
+        interval: &str,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> Result<OutcomeChartData, AppError> {
+        let interval_seconds = Self::validate_interval(interval)?;
+
+        let market = sqlx::query!(
+            r#"SELECT EXTRACT(EPOCH FROM "createdAt")::BIGINT as created_at FROM markets_extended WHERE id = $1"#,
+            market_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(anyhow::anyhow!(e)))?
+        .ok_or_else(|| AppError::NotFound("Market not found".to_string()))?;
+
+        let to_timestamp = to.unwrap_or_else(|| chrono::Utc::now().timestamp());
+        let from_timestamp = from.unwrap_or_else(|| {
+            market
+                .created_at
+                .unwrap_or_else(|| chrono::Utc::now().timestamp() - 86400 * 7)
+        });
+
+        let outcomes = sqlx::query!(
+            r#"
+            SELECT "outcomeIndex" as outcome_index, label
+            FROM market_outcomes
+            WHERE "marketId" = $1
+            ORDER BY "outcomeIndex"
+            "#,
+            market_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(anyhow::anyhow!(e)))?;
+
+        // Bets before the window still count towards the running pools.
+        let bets = sqlx::query!(
+            r#"
+            SELECT
+                "outcomeIndex" as "outcome_index!",
+                COALESCE(amount, 0) as "amount!",
+                EXTRACT(EPOCH FROM "createdAt")::BIGINT as "timestamp!"
+            FROM bets_extended
+            WHERE "marketId" = $1
+                AND "outcomeIndex" IS NOT NULL
+                AND status != 'cancelled'
+                AND EXTRACT(EPOCH FROM "createdAt") <= $2
+            ORDER BY "createdAt" ASC
+            "#,
+            market_id,
+            BigDecimal::from(to_timestamp)
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(anyhow::anyhow!(e)))?;
+
+        debug!("Fetched {} outcome bets for chart data", bets.len());
+
+        let position: HashMap<i32, usize> = outcomes
+            .iter()
+            .enumerate()
+            .map(|(i, o)| (o.outcome_index, i))
+            .collect();
+
+        let mut series: Vec<OutcomeChartSeries> = outcomes
+            .into_iter()
+            .map(|o| OutcomeChartSeries {
+                index: o.outcome_index,
+                label: o.label,
+                probability: Vec::new(),
+                volume: Vec::new(),
+                odds: Vec::new(),
+            })
+            .collect();
+        let mut total_volume = Vec::new();
+        let mut bet_count = Vec::new();
+
+        let mut pools = vec![0.0; series.len()];
+        let mut count = 0usize;
+        let mut bets = bets.into_iter().peekable();
+
+        let mut time = (from_timestamp / interval_seconds) * interval_seconds;
+        let end_bucket = (to_timestamp / interval_seconds) * interval_seconds;
+        while time <= end_bucket {
+            while let Some(bet) = bets.next_if(|b| b.timestamp < time + interval_seconds) {
+                if let Some(&i) = position.get(&bet.outcome_index) {
+                    pools[i] += bet.amount.to_f64().unwrap_or(0.0);
+                    count += 1;
+                }
+            }
+
+            let probabilities = outcome_probabilities(&pools);
+            for (i, outcome) in series.iter_mut().enumerate() {
+                let probability = probabilities[i];
+                outcome.probability.push(ChartDataPoint {
+                    time,
+                    value: probability,
+                });
+                outcome.volume.push(ChartDataPoint {
+                    time,
+                    value: pools[i],
+                });
+                outcome.odds.push(ChartDataPoint {
+                    time,
+                    value: if probability > 0.0 {
+                        1.0 / probability
+                    } else {
+                        pools.len() as f64
+                    },
+                });
+            }
+            total_volume.push(ChartDataPoint {
+                time,
+                value: pools.iter().sum(),
+            });
+            bet_count.push(ChartDataPoint {
+                time,
+                value: count as f64,
+            });
+
+            time += interval_seconds;
+        }
+
+        Ok(OutcomeChartData {
+            outcomes: series,
+            total_volume,
+            bet_count,
+        })
+    }
+
     fn validate_interval(interval: &str) -> Result<i64, AppError> {
         let seconds = match interval {
             "1m" => 60,
@@ -1,33 +1,197 @@
 use anyhow::{Context, Result};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue};
+use serde::Serialize;
 use sqlx::{postgres::PgPoolOptions, PgPool, Row};
-use tracing::{debug, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
 
+use crate::config::{Config, DatabaseConfig};
 use crate::models::*;
 
 #[derive(Clone)]
 pub struct Database {
     pool: PgPool,
+    replica: Option<Arc<Replica>>,
+    /// Keeps this handle's reads on the primary, for a request that must see its own writes.
+    primary_reads: bool,
+}
+
+/// The read replica, with what its last lag check found.
+struct Replica {
+    pool: PgPool,
+    max_lag: Duration,
+    check_interval: Duration,
+    read_your_writes: Duration,
+    /// Reachable and within `max_lag` at the last check; false until the first one passes.
+    usable: AtomicBool,
+    status: Mutex<ReplicaStatus>,
+}
+
+/// Cookie set on a user's write responses, naming the user whose reads stay on the primary.
+pub const READ_PRIMARY_COOKIE: &str = "kizo_read_primary";
+
+/// Request header with the same meaning as [`READ_PRIMARY_COOKIE`], for clients without cookies.
+pub const READ_PRIMARY_HEADER: HeaderName = HeaderName::from_static("x-read-primary");
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicaStatus {
+    pub usable: bool,
+    /// Replay lag at the last check; `None` when it could not be measured.
+    pub lag_ms: Option<i64>,
+    pub max_lag_ms: i64,
+    pub last_checked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_error: Option<String>,
 }
 
 impl Database {
-    pub async fn new(database_url: &str) -> Result<Self> {
-        info!("Connecting to database: {}", database_url);
+    pub async fn new(config: &DatabaseConfig) -> Result<Self> {
+        info!(
+            "Connecting to database: {}",
+            Config::redact_database_url(&config.url)
+        );
 
         let pool = PgPoolOptions::new()
-            .max_connections(10)
-            .connect(database_url)
+            .max_connections(config.max_connections)
+            .connect(&config.url)
             .await
             .context("Failed to connect to database")?;
 
         info!("Database connection pool established");
 
-        Ok(Self { pool })
+        // Connected lazily: an unreachable replica only means reads stay on the primary.
+        let replica = match &config.replica_url {
+            Some(url) => {
+                info!("Using read replica: {}", Config::redact_database_url(url));
+                let pool = PgPoolOptions::new()
+                    .max_connections(config.replica_max_connections)
+                    .connect_lazy(url)
+                    .context("Invalid read replica URL")?;
+                let max_lag = Duration::from_secs(config.replica_max_lag_secs);
+                Some(Arc::new(Replica {
+                    pool,
+                    max_lag,
+                    check_interval: Duration::from_secs(config.replica_check_interval_secs),
+                    read_your_writes: Duration::from_secs(config.read_your_writes_secs),
+                    usable: AtomicBool::new(false),
+                    status: Mutex::new(ReplicaStatus {
+                        max_lag_ms: max_lag.as_millis() as i64,
+                        ..ReplicaStatus::default()
+                    }),
+                }))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            pool,
+            replica,
+            primary_reads: false,
+        })
     }
 
+    /// The primary. Writes, `LISTEN` and reads that must be current go here.
     pub fn pool(&self) -> &PgPool {
         &self.pool
     }
 
+    /// Pool for read-only queries that tolerate replication lag: the replica while it is
+    /// usable, the primary otherwise.
+    pub fn read_pool(&self) -> &PgPool {
+        match &self.replica {
+            Some(replica) if !self.primary_reads && replica.usable.load(Ordering::Relaxed) => {
+                &replica.pool
+            }
+            _ => &self.pool,
+        }
+    }
+
+    /// `Set-Cookie` for a response to a write by `user_addr`: for `database.read_your_writes_secs`
+    /// the browser sends it back and [`Self::for_user`] keeps that user's reads on the primary,
+    /// whichever instance serves them. `None` without a replica.
+    pub fn read_primary_cookie(&self, user_addr: &str) -> Option<HeaderValue> {
+        let replica = self.replica.as_ref()?;
+        let cookie = format!(
+            "{}={}; Max-Age={}; Path=/; HttpOnly; SameSite=Lax",
+            READ_PRIMARY_COOKIE,
+            user_addr.to_lowercase(),
+            replica.read_your_writes.as_secs()
+        );
+        HeaderValue::from_str(&cookie).ok()
+    }
+
+    /// Handle for serving a request on behalf of `user_addr`: reads go to the primary if the
+    /// request names the user in [`READ_PRIMARY_COOKIE`] or [`READ_PRIMARY_HEADER`].
+    pub fn for_user(&self, user_addr: &str, headers: &HeaderMap) -> Database {
+        let requested = self.replica.is_some()
+            && read_primary_users(headers).any(|user| user.eq_ignore_ascii_case(user_addr));
+        Database {
+            primary_reads: self.primary_reads || requested,
+            ..self.clone()
+        }
+    }
+
+    pub fn replica_status(&self) -> Option<ReplicaStatus> {
+        self.replica.as_ref().map(|replica| {
+            replica
+                .status
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone()
+        })
+    }
+
+    /// Measures the replica's lag every `database.replica_check_interval_secs` until
+    /// `shutdown` is cancelled, moving reads off it while it is unreachable or lags too far.
+    /// Returns at once without a replica.
+    pub async fn monitor_replica(self, shutdown: CancellationToken) {
+        let Some(replica) = self.replica else {
+            return;
+        };
+
+        let mut first_check = true;
+        let mut interval = tokio::time::interval(replica.check_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.cancelled() => break,
+            }
+
+            let lag = tokio::time::timeout(replica.check_interval, replica_lag(&replica.pool))
+                .await
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("lag check timed out")));
+            let (usable, lag_ms, error) = match lag {
+                Ok(Some(lag)) => (lag <= replica.max_lag, Some(lag.as_millis() as i64), None),
+                Ok(None) => (false, None, Some("replay lag is unknown".to_string())),
+                Err(e) => (false, None, Some(format!("{:#}", e))),
+            };
+
+            let was_usable = replica.usable.swap(usable, Ordering::Relaxed);
+            if usable && !was_usable {
+                info!("Read replica is usable (lag {}ms)", lag_ms.unwrap_or(0));
+            } else if !usable && (was_usable || first_check) {
+                warn!(
+                    "Read replica is unusable, reading from the primary: {}",
+                    error
+                        .as_deref()
+                        .map_or_else(|| format!("lag {}ms", lag_ms.unwrap_or(0)), str::to_string)
+                );
+            }
+
+            first_check = false;
+
+            let mut status = replica.status.lock().unwrap_or_else(|e| e.into_inner());
+            status.usable = usable;
+            status.lag_ms = lag_ms;
+            status.last_checked_at = Some(chrono::Utc::now());
+            status.last_error = error;
+        }
+    }
+
     pub async fn get_markets(&self, params: &MarketQueryParams) -> Result<Vec<MarketExtended>> {
         let status_filter = match params.status {
             MarketStatus::Active => " WHERE status = 'active'",
@@ -53,7 +217,7 @@ impl Database {
         let markets = sqlx::query_as::<_, MarketExtended>(&query)
             .bind(params.limit)
             .bind(params.offset)
-            .fetch_all(&self.pool)
+            .fetch_all(self.read_pool())
             .await
             .context("Failed to fetch markets")?;
 
@@ -104,7 +268,7 @@ impl Database {
         );
 
         let row = sqlx::query(&query)
-            .fetch_one(&self.pool)
+            .fetch_one(self.read_pool())
             .await
             .context("Failed to count markets")?;
 
@@ -136,7 +300,7 @@ impl Database {
             "#
         )
         .bind(market_id)
-        .fetch_one(&self.pool)
+        .fetch_one(self.read_pool())
         .await
         .context("Failed to fetch market stats")?;
 
@@ -190,7 +354,7 @@ impl Database {
             FROM markets
             "#,
         )
-        .fetch_one(&self.pool)
+        .fetch_one(self.read_pool())
         .await
         .context("Failed to fetch platform market stats")?;
 
@@ -207,7 +371,7 @@ impl Database {
             FROM bets
             "#,
         )
-        .fetch_one(&self.pool)
+        .fetch_one(self.read_pool())
         .await
         .context("Failed to fetch platform bet stats")?;
 
@@ -220,7 +384,7 @@ impl Database {
         let yield_row = sqlx::query(
             "SELECT COALESCE(SUM(total_yield_earned), 0) as total_yield FROM market_resolutions",
         )
-        .fetch_one(&self.pool)
+        .fetch_one(self.read_pool())
         .await
         .context("Failed to fetch yield stats")?;
 
@@ -245,7 +409,7 @@ impl Database {
         )
         .bind(params.limit)
         .bind(params.offset)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await
         .context("Failed to fetch bets")?;
 
@@ -273,7 +437,7 @@ impl Database {
         .bind(user_addr)
         .bind(params.limit)
         .bind(params.offset)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await
         .context("Failed to fetch user bets")?;
 
@@ -291,7 +455,7 @@ impl Database {
         .bind(market_id)
         .bind(params.limit)
         .bind(params.offset)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await
         .context("Failed to fetch market bets")?;
 
@@ -310,7 +474,7 @@ impl Database {
             "#,
         )
         .bind(user_addr)
-        .fetch_one(&self.pool)
+        .fetch_one(self.read_pool())
         .await
         .context("Failed to fetch user bet stats")?;
 
@@ -330,7 +494,7 @@ impl Database {
             "#,
         )
         .bind(user_addr)
-        .fetch_one(&self.pool)
+        .fetch_one(self.read_pool())
         .await
         .context("Failed to fetch user outcome stats")?;
 
@@ -348,7 +512,7 @@ impl Database {
             "#,
         )
         .bind(user_addr)
-        .fetch_one(&self.pool)
+        .fetch_one(self.read_pool())
         .await
         .context("Failed to fetch user winnings")?;
 
@@ -373,7 +537,7 @@ impl Database {
             "SELECT * FROM bets ORDER BY transaction_version DESC LIMIT $1",
         )
         .bind(limit)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await
         .context("Failed to fetch recent bets")?;
 
@@ -429,3 +593,121 @@ impl Database {
         Ok(true)
     }
 }
+
+/// Users a request asks to read from the primary, from [`READ_PRIMARY_HEADER`] and
+/// [`READ_PRIMARY_COOKIE`].
+fn read_primary_users(headers: &HeaderMap) -> impl Iterator<Item = &str> {
+    let from_header = headers
+        .get_all(READ_PRIMARY_HEADER)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .map(str::trim);
+    let from_cookie = headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .filter(|(name, _)| *name == READ_PRIMARY_COOKIE)
+        .map(|(_, value)| value);
+    from_header.chain(from_cookie)
+}
+
+/// How far the replica's replay is behind. A replica that has replayed everything it
+/// received counts as current, however long ago the last write was, but only while its WAL
+/// receiver is streaming: a disconnected one has received nothing new to compare against.
+/// `None` when it has not replayed anything yet.
+async fn replica_lag(pool: &PgPool) -> Result<Option<Duration>> {
+    // Without `pg_read_all_stats` the receiver's status reads as NULL; a running receiver is
+    // then taken to be streaming.
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            (NOT pg_is_in_recovery() OR EXISTS (
+                SELECT 1 FROM pg_stat_wal_receiver
+                WHERE COALESCE(status, 'streaming') = 'streaming'
+            )) AS "streaming!",
+            CASE
+                WHEN NOT pg_is_in_recovery() THEN 0
+                WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+                ELSE EXTRACT(EPOCH FROM NOW() - pg_last_xact_replay_timestamp())
+            END::float8 AS lag_secs
+        "#
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to measure read replica lag")?;
+
+    if !row.streaming {
+        anyhow::bail!("WAL receiver is not streaming from the primary");
+    }
+    Ok(row
+        .lag_secs
+        .map(|secs| Duration::from_secs_f64(secs.max(0.0))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lazy_pool() -> PgPool {
+        PgPoolOptions::new()
+            .connect_lazy("postgresql://kizo@localhost/kizo")
+            .unwrap()
+    }
+
+    fn with_replica(usable: bool) -> Database {
+        Database {
+            pool: lazy_pool(),
+            replica: Some(Arc::new(Replica {
+                pool: lazy_pool(),
+                max_lag: Duration::from_secs(10),
+                check_interval: Duration::from_secs(5),
+                read_your_writes: Duration::from_secs(30),
+                usable: AtomicBool::new(usable),
+                status: Mutex::new(ReplicaStatus::default()),
+            })),
+            primary_reads: false,
+        }
+    }
+
+    fn reads_primary(db: &Database) -> bool {
+        std::ptr::eq(db.read_pool(), db.pool())
+    }
+
+    #[tokio::test]
+    async fn test_reads_fall_back_to_primary_when_replica_is_unusable() {
+        assert!(!reads_primary(&with_replica(true)));
+        assert!(reads_primary(&with_replica(false)));
+    }
+
+    fn headers(name: HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_writer_reads_own_writes_from_primary() {
+        let db = with_replica(true);
+        let cookie = db.read_primary_cookie("0xABC").unwrap();
+        assert_eq!(
+            cookie,
+            "kizo_read_primary=0xabc; Max-Age=30; Path=/; HttpOnly; SameSite=Lax"
+        );
+
+        let sent = headers(header::COOKIE, "theme=dark; kizo_read_primary=0xabc");
+        assert!(reads_primary(&db.for_user("0xABC", &sent)));
+        assert!(!reads_primary(&db.for_user("0xdef", &sent)));
+        assert!(!reads_primary(&db.for_user("0xabc", &HeaderMap::new())));
+        assert!(!reads_primary(&db));
+
+        let sent = headers(READ_PRIMARY_HEADER, "0xabc");
+        assert!(reads_primary(&db.for_user("0xabc", &sent)));
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_replica_lag_is_zero_on_a_primary(pool: PgPool) {
+        assert_eq!(replica_lag(&pool).await.unwrap(), Some(Duration::ZERO));
+    }
+}
@@ -13,6 +13,11 @@ pub enum AppError {
     BadRequest(String),
     Internal(String),
     InternalError(String),
+    /// A request refused by a business rule, with a stable machine-readable code.
+    Rejected {
+        code: &'static str,
+        message: String,
+    },
 }
 
 impl fmt::Display for AppError {
@@ -23,6 +28,7 @@ impl fmt::Display for AppError {
             AppError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
             AppError::Internal(msg) => write!(f, "Internal error: {}", msg),
             AppError::InternalError(msg) => write!(f, "Internal error: {}", msg),
+            AppError::Rejected { code, message } => write!(f, "Rejected ({}): {}", code, message),
         }
     }
 }
@@ -43,6 +49,11 @@ impl From<sqlx::Error> for AppError {
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let code = match &self {
+            AppError::Rejected { code, .. } => Some(*code),
+            _ => None,
+        };
+
         let (status, error_message) = match self {
             AppError::Database(e) => {
                 tracing::error!("Database error: {:?}", e);
@@ -58,12 +69,17 @@ impl IntoResponse for AppError {
                 tracing::error!("Internal error: {}", msg);
                 (StatusCode::INTERNAL_SERVER_ERROR, msg.as_str())
             }
+            AppError::Rejected { ref message, .. } => (StatusCode::BAD_REQUEST, message.as_str()),
         };
 
-        let body = Json(json!({
+        let mut body = json!({
             "success": false,
             "error": error_message
-        }));
+        });
+        if let Some(code) = code {
+            body["code"] = json!(code);
+        }
+        let body = Json(body);
 
         (status, body).into_response()
     }
@@ -3,11 +3,13 @@ pub mod config;
 pub mod db;
 pub mod error;
 pub mod middleware;
+pub mod migrate;
 pub mod models;
 pub mod openapi;
 pub mod routes;
 pub mod seed;
 pub mod services;
+pub mod state;
 pub mod utils;
 
 pub mod admin {
@@ -1,14 +1,19 @@
-use anyhow::Result;
-use axum::Router;
+use anyhow::{Context, Result};
+use axum::{http::HeaderValue, Router};
+use clap::{Parser, Subcommand};
+use std::future::IntoFuture;
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
 use tokio::signal;
+use tokio_util::sync::CancellationToken;
 use tower::ServiceBuilder;
 use tower_http::{
     compression::CompressionLayer,
     cors::{Any, CorsLayer},
     trace::TraceLayer,
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
@@ -18,39 +23,91 @@ mod config;
 mod db;
 mod error;
 mod middleware;
+mod migrate;
 mod models;
 mod openapi;
 mod routes;
 mod seed;
 mod services;
+mod state;
 mod utils;
 
 pub mod admin {
     pub mod routes;
 }
 
-use config::Config;
+use config::{Config, CorsConfig};
 use db::Database;
+use state::AppState;
 use std::sync::Arc;
 
+#[derive(Parser)]
+#[command(
+    name = "kizo-server",
+    version,
+    about = "Kizo Prediction Market API Server"
+)]
+struct Cli {
+    /// Path to a TOML config file (defaults to $KIZO_CONFIG, then ./kizo.toml if present)
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// Print the effective configuration with secrets redacted and exit
+    #[arg(long)]
+    print_config: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage the embedded database migrations
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum MigrateAction {
+    /// Apply all pending migrations
+    Up,
+    /// List embedded migrations and whether they have been applied
+    Status,
+    /// Check that every table and column the server queries exists
+    Verify,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let config = Config::load(cli.config.as_deref())?;
+
+    if cli.print_config {
+        print!("{}", config.redacted().to_toml_string()?);
+        return Ok(());
+    }
+
     tracing_subscriber::registry()
         .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "kizo_server=info,tower_http=info".into()),
+            tracing_subscriber::EnvFilter::try_new(&config.server.log_level)
+                .context("server.log_level (RUST_LOG) is not a valid filter")?,
         )
         .with(tracing_subscriber::fmt::layer())
         .init();
 
     info!("Starting Kizo Prediction Market API Server (Rust)");
 
-    let config = Config::from_env()?;
     info!("Configuration loaded successfully");
-    info!("Server will listen on {}:{}", config.host, config.port);
+    info!(
+        "Server will listen on {}:{}",
+        config.server.host, config.server.port
+    );
 
     info!("Connecting to database...");
-    let db = Database::new(&config.database_url).await?;
+    let db = Database::new(&config.database).await?;
     info!("Database connection established");
 
     if db.health_check().await.is_ok() {
@@ -60,16 +117,29 @@ async fn main() -> Result<()> {
         return Err(anyhow::anyhow!("Database health check failed"));
     }
 
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    if let Some(Command::Migrate { action }) = cli.command {
+        return run_migrate_command(&db, action).await;
+    }
+
+    if config.database.run_migrations {
+        migrate::run_pending(db.pool()).await?;
+    }
 
-    if std::env::var("RUN_SEEDS").unwrap_or_default() == "true" {
+    if config.database.verify_schema {
+        migrate::ensure_schema(db.pool()).await?;
+    }
+
+    let cors = build_cors_layer(&config.cors)?;
+
+    if config.seeding.run_on_startup {
         info!("Running database seeds...");
-        seed::run_all_seeds(db.pool()).await?;
+        seed::run_all_seeds(db.pool(), &config).await?;
 
-        let yield_service = services::yield_service::YieldService::new(db.pool().clone());
+        let yield_service = services::yield_service::YieldService::new(
+            db.pool().clone(),
+            &config.chain,
+            &config.yield_allocation,
+        );
         yield_service.initialize_protocols().await?;
         info!("Seeds and protocols initialized");
     }
@@ -102,40 +172,72 @@ async fn main() -> Result<()> {
     }
 
     info!("🚀 Starting background scheduler and event listener...");
-    let scheduler = Arc::new(services::scheduler::Scheduler::new(db.pool().clone()));
-    scheduler.start().await;
+    let signers = services::signer::SignerPool::load(&config)
+        .await
+        .context("Failed to load signing accounts")?;
+    let state = AppState::new(db, config, signers);
+    let config = state.config.clone();
+    reload_signers_on_hangup(state.signers.clone());
+
+    let shutdown = CancellationToken::new();
+    let scheduler = Arc::new(services::scheduler::Scheduler::new(
+        state.db.pool().clone(),
+        config.clone(),
+        shutdown.clone(),
+    ));
+    Arc::clone(&scheduler).start().await;
+
+    tokio::spawn(state.db.clone().monitor_replica(shutdown.clone()));
+
+    if config.cache.enabled {
+        tokio::spawn(state.cache.clone().listen_for_invalidations(
+            state.db.pool().clone(),
+            config.scheduler.listener_reconnect_base_secs,
+            config.scheduler.listener_reconnect_max_secs,
+            shutdown.clone(),
+        ));
+    }
 
     let app = Router::new()
         .merge(
             SwaggerUi::new("/api-docs").url("/api-docs/openapi.json", openapi::ApiDoc::openapi()),
         )
-        .nest("/api", routes::create_router(db.clone()))
-        .merge(admin::routes::create_admin_router(db))
+        .nest("/api", routes::create_router(state.clone()))
+        .nest(
+            "/health",
+            routes::health::create_health_router(state.clone()),
+        )
+        .merge(admin::routes::create_admin_router(state))
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
                 .layer(CompressionLayer::new())
                 .layer(cors),
         )
-        .into_make_service();
-
-    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
-    info!("Server listening on {}", addr);
+        .into_make_service_with_connect_info::<SocketAddr>();
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let listener = tokio::net::TcpListener::bind((config.server.host.as_str(), config.server.port))
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to bind {}:{}",
+                config.server.host, config.server.port
+            )
+        })?;
+    info!("Server listening on {}", listener.local_addr()?);
 
     info!("🚀 Kizo Prediction Market API Server started successfully");
     info!(
         "📚 API Documentation: http://{}:{}/api-docs",
-        config.host, config.port
+        config.server.host, config.server.port
     );
     info!(
         "📖 OpenAPI Spec: http://{}:{}/api-docs/openapi.json",
-        config.host, config.port
+        config.server.host, config.server.port
     );
     info!(
         "💚 Health Check: http://{}:{}/api/health",
-        config.host, config.port
+        config.server.host, config.server.port
     );
     info!("");
     info!("Available endpoints:");
@@ -150,14 +252,113 @@ async fn main() -> Result<()> {
     info!("  - GET  /api/bets/market/:id      - Get market bets");
     info!("  - GET  /api/sync/status          - Get sync status");
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+        .into_future();
+    tokio::pin!(server);
+    tokio::select! {
+        result = &mut server => result?,
+        _ = shutdown_signal() => {}
+    }
+
+    // Stop accepting connections and tell every background task to wind down, then give
+    // in-flight requests and jobs until the deadline before exiting anyway.
+    shutdown.cancel();
+    let shutdown_timeout = Duration::from_secs(config.server.shutdown_timeout_secs);
+    info!(
+        "Waiting up to {}s for requests and background jobs to finish...",
+        shutdown_timeout.as_secs()
+    );
+    let drained = tokio::time::timeout(shutdown_timeout, async {
+        let (served, _) = tokio::join!(server, scheduler.stopped());
+        served
+    })
+    .await;
+    match drained {
+        Ok(served) => served?,
+        Err(_) => warn!("Shutdown deadline reached; exiting with work still in flight"),
+    }
 
     info!("Server shutdown complete");
     Ok(())
 }
 
+async fn run_migrate_command(db: &Database, action: MigrateAction) -> Result<()> {
+    match action {
+        MigrateAction::Up => migrate::run_pending(db.pool()).await,
+        MigrateAction::Status => {
+            for migration in migrate::status(db.pool()).await? {
+                let state = match (migration.applied, migration.checksum_matches) {
+                    (true, Some(false)) => "applied (checksum mismatch)".to_string(),
+                    (true, _) => format!(
+                        "applied {}",
+                        migration
+                            .installed_on
+                            .map(|t| t.to_rfc3339())
+                            .unwrap_or_default()
+                    ),
+                    (false, _) => "pending".to_string(),
+                };
+                println!(
+                    "{:03}  {:<45} {}",
+                    migration.version, migration.description, state
+                );
+            }
+            Ok(())
+        }
+        MigrateAction::Verify => {
+            migrate::ensure_schema(db.pool()).await?;
+            println!("Schema OK");
+            Ok(())
+        }
+    }
+}
+
+fn build_cors_layer(config: &CorsConfig) -> Result<CorsLayer> {
+    let cors = CorsLayer::new().allow_methods(Any).allow_headers(Any);
+
+    if config.allows_any_origin() {
+        return Ok(cors.allow_origin(Any));
+    }
+
+    let origins = config
+        .allowed_origins
+        .iter()
+        .map(|origin| {
+            origin
+                .parse::<HeaderValue>()
+                .with_context(|| format!("Invalid CORS origin '{}'", origin))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    info!("CORS restricted to origins: {:?}", config.allowed_origins);
+    Ok(cors.allow_origin(origins))
+}
+
+/// Re-reads signing keys on SIGHUP so keystore and signing-process keys can be rotated without
+/// a restart. Keys of the `env` backend are fixed at startup.
+#[cfg(unix)]
+fn reload_signers_on_hangup(signers: Arc<services::signer::SignerPool>) {
+    let mut hangup = match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            error!("Failed to install SIGHUP handler: {}", e);
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            info!("Received SIGHUP signal, reloading signing accounts...");
+            if let Err(e) = signers.reload().await {
+                error!("❌ Failed to reload signing accounts: {:#}", e);
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+fn reload_signers_on_hangup(_signers: Arc<services::signer::SignerPool>) {}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         signal::ctrl_c()
@@ -0,0 +1,177 @@
+use axum::{
+    extract::{ConnectInfo, MatchedPath, OriginalUri, Request, State},
+    http::{HeaderMap, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::error;
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::services::audit::{Actor, AuditDetails, AuditLog, NewAuditEntry, ANONYMOUS_ACTOR};
+
+/// Appends every state-changing request to the audit log, whatever its outcome.
+///
+/// Layer it inside `require_api_key`: only authenticated requests reach it, and the actor
+/// the key check set is known. Handlers describe what they did by attaching
+/// [`AuditDetails`] to their response; otherwise the entry is named after the method and
+/// route. GET requests are not recorded.
+///
+/// An action must not run unaudited: a `started` entry is written before the handler runs,
+/// and the request is refused when that fails. The outcome is appended as a `completed`
+/// entry afterwards; if that write fails the handler's response still goes out, since the
+/// action has already happened, and the started entry is left without a completion.
+pub async fn audit(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) {
+        return next.run(request).await;
+    }
+
+    let started = Instant::now();
+    let actor = request
+        .extensions()
+        .get::<Actor>()
+        .map(|actor| actor.0.clone())
+        .unwrap_or_else(|| ANONYMOUS_ACTOR.to_string());
+    let method = request.method().to_string();
+    let path = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.clone())
+        .unwrap_or_else(|| request.uri().clone())
+        .to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|route| route.as_str().to_string())
+        .unwrap_or_else(|| path.clone());
+    let headers = request.headers();
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    let mut entry = NewAuditEntry {
+        actor,
+        details: AuditDetails::new(format!("{} {}", method, route)),
+        status: 0,
+        method,
+        path,
+        ip_address: client_ip(headers, peer, &config.server.trusted_proxies),
+        user_agent: header(headers, "user-agent"),
+        request_id: header(headers, "x-request-id"),
+        duration_ms: 0,
+    };
+
+    let audit_log = AuditLog::new(db.pool().clone());
+    let started_id = match audit_log.start(&entry).await {
+        Ok(id) => id,
+        Err(e) => {
+            return AppError::Internal(format!("Refusing {} {}: {:#}", entry.method, entry.path, e))
+                .into_response()
+        }
+    };
+
+    let mut response = next.run(request).await;
+
+    if let Some(details) = response.extensions_mut().remove::<AuditDetails>() {
+        entry.details = details;
+    }
+    entry.status = response.status().as_u16();
+    entry.duration_ms = started.elapsed().as_millis() as i32;
+    if let Err(e) = audit_log.complete(started_id, &entry).await {
+        error!(
+            "Failed to record the outcome of {} {} by {} (audit entry {}): {:#}",
+            entry.method, entry.path, entry.actor, started_id, e
+        );
+    }
+
+    response
+}
+
+fn header(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+/// The address a request came from. Forwarding headers are only believed when the peer is
+/// one of `trusted_proxies`; then the client is the last `X-Forwarded-For` hop that is not a
+/// trusted proxy itself, else `X-Real-IP`.
+fn client_ip(
+    headers: &HeaderMap,
+    peer: Option<IpAddr>,
+    trusted_proxies: &[IpAddr],
+) -> Option<String> {
+    let peer = peer?;
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer.to_string());
+    }
+
+    let forwarded = header(headers, "x-forwarded-for").and_then(|list| {
+        list.rsplit(',')
+            .map(|hop| hop.trim().parse::<IpAddr>().ok())
+            .find(|hop| !hop.is_some_and(|ip| trusted_proxies.contains(&ip)))
+            .flatten()
+    });
+    let real_ip = || header(headers, "x-real-ip").and_then(|ip| ip.trim().parse::<IpAddr>().ok());
+    Some(forwarded.or_else(real_ip).unwrap_or(peer).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_forwarded_headers_are_ignored_from_untrusted_peers() {
+        let peer: IpAddr = "203.0.113.7".parse().unwrap();
+        let spoofed = headers(&[("x-forwarded-for", "1.2.3.4"), ("x-real-ip", "1.2.3.4")]);
+        assert_eq!(
+            client_ip(&spoofed, Some(peer), &[]).as_deref(),
+            Some("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn test_client_is_the_last_untrusted_forwarded_hop() {
+        let proxy: IpAddr = "10.0.0.2".parse().unwrap();
+        let inner: IpAddr = "10.0.0.3".parse().unwrap();
+        let trusted = [proxy, inner];
+
+        // The client prepended a fake hop; the proxies appended the real one.
+        let forwarded = headers(&[("x-forwarded-for", "1.2.3.4, 198.51.100.9, 10.0.0.3")]);
+        assert_eq!(
+            client_ip(&forwarded, Some(proxy), &trusted).as_deref(),
+            Some("198.51.100.9")
+        );
+
+        let real_ip = headers(&[("x-real-ip", "198.51.100.9")]);
+        assert_eq!(
+            client_ip(&real_ip, Some(proxy), &trusted).as_deref(),
+            Some("198.51.100.9")
+        );
+        assert_eq!(
+            client_ip(&HeaderMap::new(), Some(proxy), &trusted).as_deref(),
+            Some("10.0.0.2")
+        );
+    }
+}
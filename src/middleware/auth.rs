@@ -1,24 +1,37 @@
 use axum::{
-    extract::Request,
+    extract::{Request, State},
     http::{HeaderMap, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use serde_json::json;
+use std::sync::Arc;
 
+use crate::config::Config;
+use crate::services::audit::{api_key_actor, Actor, ANONYMOUS_ACTOR};
+
+/// Lets requests with the configured API key through, tagged with an [`Actor`] naming the
+/// key for the audit log.
 pub async fn require_api_key(
+    State(config): State<Arc<Config>>,
     headers: HeaderMap,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Result<Response, Response> {
-    let valid_api_key = std::env::var("API_KEY").unwrap_or_else(|_| "".to_string());
+    let valid_api_key = config.auth.api_key.as_deref().unwrap_or_default();
 
     if valid_api_key.is_empty() {
         tracing::warn!(
             "API_KEY not configured - allowing all write operations (NOT SECURE FOR PRODUCTION)"
         );
+        request
+            .extensions_mut()
+            .insert(Actor(ANONYMOUS_ACTOR.to_string()));
         return Ok(next.run(request).await);
     }
+    request
+        .extensions_mut()
+        .insert(Actor(api_key_actor(valid_api_key)));
 
     if let Some(auth_header) = headers.get("Authorization") {
         if let Ok(auth_value) = auth_header.to_str() {
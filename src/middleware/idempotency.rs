@@ -0,0 +1,189 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{OriginalUri, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::services::audit::{Actor, ANONYMOUS_ACTOR};
+use crate::services::idempotency::{
+    caller_key, request_hash, IdempotencyStore, KeyClaim, StoredResponse,
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+/// Set on responses replayed from a stored idempotency key.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+const MAX_KEY_LEN: usize = 255;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Makes a write endpoint safe to retry. A request carrying an `Idempotency-Key` header runs
+/// once; retries with the same key and body get the stored response back, a different body
+/// with the same key is rejected with 422, and a retry while the first request is still
+/// running gets 409. Once the handler has run its response is stored whatever the status:
+/// a server error may come after side effects such as a submitted bet, so retrying it under
+/// the same key must not run it again. Keys are scoped to the authenticated caller.
+/// Requests without the header are passed through unchanged.
+pub async fn idempotency(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let key = match key.to_str().map(str::trim) {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => key.to_string(),
+        _ => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "{} must be 1 to {} visible ASCII characters",
+                    IDEMPOTENCY_KEY_HEADER, MAX_KEY_LEN
+                ),
+            )
+        }
+    };
+
+    let caller = request
+        .extensions()
+        .get::<Actor>()
+        .map(|actor| actor.0.as_str())
+        .unwrap_or(ANONYMOUS_ACTOR);
+    let key = caller_key(caller, &key);
+
+    let path = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.path().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => {
+            return error_response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "Request body is too large".to_string(),
+            )
+        }
+    };
+    let hash = request_hash(parts.method.as_str(), &path, &body);
+
+    let store = IdempotencyStore::new(db.pool().clone());
+    match store
+        .claim(&key, &hash, config.idempotency.retention_secs)
+        .await
+    {
+        Ok(KeyClaim::New) => {}
+        Ok(KeyClaim::Replay(stored)) => {
+            info!("Replaying stored response for idempotency key {}", key);
+            return replay(stored);
+        }
+        Ok(KeyClaim::Mismatch) => {
+            return error_response(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "{} was already used for a different request",
+                    IDEMPOTENCY_KEY_HEADER
+                ),
+            )
+        }
+        Ok(KeyClaim::InProgress) => {
+            return error_response(
+                StatusCode::CONFLICT,
+                format!(
+                    "A request with this {} is still being processed",
+                    IDEMPOTENCY_KEY_HEADER
+                ),
+            )
+        }
+        Err(e) => {
+            error!("Failed to claim idempotency key {}: {}", key, e);
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            );
+        }
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Failed to read response for idempotency key {}: {}", key, e);
+            let failed = error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            );
+            let stored = StoredResponse {
+                status: failed.status().as_u16(),
+                body: json!({ "success": false, "error": "Internal server error" }).to_string(),
+                content_type: Some("application/json".to_string()),
+            };
+            if let Err(e) = store.complete(&key, &stored).await {
+                error!(
+                    "Failed to store response for idempotency key {}: {}",
+                    key, e
+                );
+            }
+            return failed;
+        }
+    };
+
+    let saved = store
+        .complete(
+            &key,
+            &StoredResponse {
+                status: parts.status.as_u16(),
+                body: String::from_utf8_lossy(&body).into_owned(),
+                content_type: parts
+                    .headers
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string),
+            },
+        )
+        .await;
+    if let Err(e) = saved {
+        error!(
+            "Failed to store response for idempotency key {}: {}",
+            key, e
+        );
+    }
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+    let headers = response.headers_mut();
+    if let Some(content_type) = stored
+        .content_type
+        .and_then(|v| HeaderValue::from_str(&v).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (
+        status,
+        axum::Json(json!({
+            "success": false,
+            "error": message
+        })),
+    )
+        .into_response()
+}
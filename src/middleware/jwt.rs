@@ -1,19 +1,23 @@
 use axum::{
-    extract::Request,
+    extract::{Request, State},
     http::{HeaderMap, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use serde_json::json;
 
+use std::sync::Arc;
+
+use crate::config::Config;
 use crate::utils::jwt::JwtService;
 
 pub async fn require_jwt(
+    State(config): State<Arc<Config>>,
     headers: HeaderMap,
     mut request: Request,
     next: Next,
 ) -> Result<Response, Response> {
-    let jwt_service = JwtService::new();
+    let jwt_service = JwtService::new(&config.auth);
 
     let auth_header = headers
         .get("Authorization")
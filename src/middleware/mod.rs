@@ -1,2 +1,5 @@
+pub mod audit;
 pub mod auth;
+pub mod idempotency;
 pub mod jwt;
+pub mod response_cache;
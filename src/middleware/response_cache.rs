@@ -0,0 +1,206 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{OriginalUri, Request, State},
+    http::{header, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::services::response_cache::{
+    CachedEndpoint, CachedMarket, CachedResponse, ResponseCache,
+};
+
+/// Set on cached endpoints' responses: `HIT`, `MISS` or `BYPASS` when caching is off.
+pub const CACHE_STATUS_HEADER: HeaderName = HeaderName::from_static("x-cache");
+
+/// Serves a GET endpoint through the [`ResponseCache`], keyed on its path and query.
+///
+/// Only 200 responses are stored, with the handler's headers, and not when the cache was
+/// invalidated while the handler ran. Every 200 carries an `ETag`,
+/// and a request whose `If-None-Match` matches it gets an empty 304 instead, whether or not
+/// the body came from the cache.
+pub async fn response_cache(
+    State((cache, endpoint)): State<(Arc<ResponseCache>, CachedEndpoint)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let key = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.clone())
+        .unwrap_or_else(|| request.uri().clone())
+        .to_string();
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+
+    let caches = cache.caches(endpoint);
+    if caches {
+        if let Some(cached) = cache.get(endpoint, &key).await {
+            return respond(cached, if_none_match.as_ref(), "HIT");
+        }
+    }
+
+    // An invalidation while the handler runs may postdate the data it read.
+    let generation = cache.generation();
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let market = response.extensions().get::<CachedMarket>().map(|m| m.0);
+    let (parts, body) = response.into_parts();
+    let mut headers = parts.headers;
+    headers.remove(header::CONTENT_LENGTH);
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Failed to read response for {}: {}", key, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let cached = CachedResponse::new(body, headers);
+    if caches {
+        cache
+            .insert(endpoint, key, market, cached.clone(), generation)
+            .await;
+    }
+    respond(
+        cached,
+        if_none_match.as_ref(),
+        if caches { "MISS" } else { "BYPASS" },
+    )
+}
+
+/// Serves `cached` with the handler's headers plus `ETag` and `X-Cache`; a 304 leaves out
+/// the content headers.
+fn respond(
+    cached: CachedResponse,
+    if_none_match: Option<&HeaderValue>,
+    status: &'static str,
+) -> Response {
+    let etag = HeaderValue::from_str(&cached.etag).expect("ETag is ASCII");
+    let not_modified = if_none_match.is_some_and(|v| matches_etag(v, &cached.etag));
+
+    let mut response = if not_modified {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        Body::from(cached.body).into_response()
+    };
+    let headers = response.headers_mut();
+    for (name, value) in &cached.headers {
+        if !(not_modified && (name == header::CONTENT_TYPE || name == header::CONTENT_ENCODING)) {
+            headers.append(name, value.clone());
+        }
+    }
+    headers.insert(header::ETAG, etag);
+    headers.insert(CACHE_STATUS_HEADER, HeaderValue::from_static(status));
+    response
+}
+
+/// `If-None-Match` is `*` or a list of entity tags, which match weakly.
+fn matches_etag(if_none_match: &HeaderValue, current: &str) -> bool {
+    let Ok(value) = if_none_match.to_str() else {
+        return false;
+    };
+    value
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CacheConfig;
+    use crate::services::response_cache::etag;
+    use axum::{middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_if_none_match_lists_and_weak_tags() {
+        let current = etag(b"{}");
+        let header = |v: &str| HeaderValue::from_str(v).unwrap();
+
+        assert!(matches_etag(&header(&current), &current));
+        assert!(matches_etag(
+            &header(&format!("\"x\", W/{}", current)),
+            &current
+        ));
+        assert!(matches_etag(&header("*"), &current));
+        assert!(!matches_etag(&header("\"x\""), &current));
+    }
+
+    #[tokio::test]
+    async fn test_handler_headers_survive_the_cache() {
+        let cache = Arc::new(ResponseCache::new(&CacheConfig::default()));
+        let app = Router::new().route(
+            "/markets",
+            get(|| async {
+                (
+                    [
+                        (header::CONTENT_TYPE, "application/json"),
+                        (header::CACHE_CONTROL, "public, max-age=5"),
+                    ],
+                    "{}",
+                )
+            })
+            .layer(middleware::from_fn_with_state(
+                (cache, CachedEndpoint::Markets),
+                response_cache,
+            )),
+        );
+        let get = || {
+            Request::builder()
+                .uri("/markets")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let miss = app.clone().oneshot(get()).await.unwrap();
+        let hit = app.oneshot(get()).await.unwrap();
+
+        assert_eq!(miss.headers()[CACHE_STATUS_HEADER], "MISS");
+        assert_eq!(hit.headers()[CACHE_STATUS_HEADER], "HIT");
+        for response in [&miss, &hit] {
+            assert_eq!(
+                response.headers()[header::CACHE_CONTROL],
+                "public, max-age=5"
+            );
+            assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_is_not_cached_across_an_invalidation() {
+        let cache = Arc::new(ResponseCache::new(&CacheConfig::default()));
+        let handler_cache = cache.clone();
+        let app = Router::new().route(
+            "/markets",
+            get(move || {
+                let cache = handler_cache.clone();
+                async move {
+                    // A bet lands while the handler is reading.
+                    cache.invalidate(None).await;
+                    "[]"
+                }
+            })
+            .layer(middleware::from_fn_with_state(
+                (cache, CachedEndpoint::Markets),
+                response_cache,
+            )),
+        );
+        let get = || {
+            Request::builder()
+                .uri("/markets")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let first = app.clone().oneshot(get()).await.unwrap();
+        let second = app.oneshot(get()).await.unwrap();
+        assert_eq!(first.headers()[CACHE_STATUS_HEADER], "MISS");
+        assert_eq!(second.headers()[CACHE_STATUS_HEADER], "MISS");
+    }
+}
@@ -0,0 +1,596 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sqlx::migrate::Migrator;
+use sqlx::{PgPool, Row};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use tracing::info;
+
+/// Migrations from `migrations/`, embedded at compile time. `.sql.disabled` files are ignored.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Tables and columns the queries in this crate rely on.
+///
+/// Keep this in sync when a migration adds a table or column that the code reads or writes.
+pub const EXPECTED_SCHEMA: &[(&str, &[&str])] = &[
+    (
+        "markets_extended",
+        &[
+            "id",
+            "blockchainMarketId",
+            "marketId",
+            "adjTicker",
+            "platform",
+            "question",
+            "description",
+            "rules",
+            "status",
+            "probability",
+            "volume",
+            "openInterest",
+            "endDate",
+            "resolutionDate",
+            "result",
+            "link",
+            "imageUrl",
+            "totalPoolSize",
+            "yesPoolSize",
+            "noPoolSize",
+            "countYes",
+            "countNo",
+            "currentYield",
+            "totalYieldEarned",
+            "unattributedYield",
+            "yieldAccruedUntil",
+            "marketType",
+            "winningOutcome",
+            "txHash",
+            "txStatus",
+            "createdAt",
+            "updatedAt",
+        ],
+    ),
+    (
+        "bets_extended",
+        &[
+            "id",
+            "blockchainBetId",
+            "userId",
+            "marketId",
+            "position",
+            "amount",
+            "odds",
+            "status",
+            "payout",
+            "yieldEarned",
+            "yieldAttributedAt",
+            "outcomeIndex",
+            "txHash",
+            "txStatus",
+            "createdAt",
+            "updatedAt",
+        ],
+    ),
+    (
+        "users",
+        &[
+            "id",
+            "address",
+            "email",
+            "username",
+            "avatarUrl",
+            "createdAt",
+            "updatedAt",
+        ],
+    ),
+    (
+        "protocols",
+        &[
+            "id",
+            "name",
+            "displayName",
+            "baseApy",
+            "isActive",
+            "description",
+            "iconUrl",
+            "createdAt",
+            "updatedAt",
+        ],
+    ),
+    (
+        "yield_records",
+        &[
+            "id",
+            "marketId",
+            "protocolId",
+            "amount",
+            "apy",
+            "yield",
+            "period",
+            "createdAt",
+        ],
+    ),
+    (
+        "bets",
+        &[
+            "bet_id",
+            "market_id",
+            "user_addr",
+            "position",
+            "amount",
+            "transaction_version",
+            "transaction_block_height",
+            "inserted_at",
+            "claimed",
+            "winning_amount",
+            "yield_share",
+            "claim_transaction_version",
+        ],
+    ),
+    (
+        "markets",
+        &[
+            "market_id",
+            "question",
+            "end_time",
+            "yield_protocol_addr",
+            "transaction_version",
+            "transaction_block_height",
+            "inserted_at",
+            "resolved",
+            "outcome",
+            "total_yield_earned",
+        ],
+    ),
+    (
+        "market_resolutions",
+        &[
+            "market_id",
+            "outcome",
+            "total_yield_earned",
+            "transaction_version",
+            "transaction_block_height",
+            "inserted_at",
+        ],
+    ),
+    (
+        "winnings_claims",
+        &[
+            "bet_id",
+            "user_addr",
+            "winning_amount",
+            "yield_share",
+            "transaction_version",
+            "transaction_block_height",
+            "inserted_at",
+        ],
+    ),
+    (
+        "yield_deposits",
+        &[
+            "market_id",
+            "amount",
+            "transaction_version",
+            "transaction_block_height",
+            "inserted_at",
+        ],
+    ),
+    ("indexer_state", &["indexer_name", "last_processed_version"]),
+    (
+        "event_processing_log",
+        &[
+            "event_type",
+            "event_data",
+            "transaction_version",
+            "processing_status",
+            "error_message",
+            "processing_duration_ms",
+            "created_at",
+        ],
+    ),
+    (
+        "price_history",
+        &[
+            "id",
+            "pair",
+            "price",
+            "sourceCount",
+            "sources",
+            "degraded",
+            "recordedAt",
+        ],
+    ),
+    (
+        "apy_history",
+        &["id", "protocolId", "apy", "source", "error", "recordedAt"],
+    ),
+    (
+        "market_allocations",
+        &[
+            "id",
+            "marketId",
+            "protocolId",
+            "strategy",
+            "weight",
+            "amount",
+            "apy",
+            "createdAt",
+            "updatedAt",
+        ],
+    ),
+    (
+        "market_outcomes",
+        &[
+            "id",
+            "marketId",
+            "outcomeIndex",
+            "label",
+            "poolSize",
+            "betCount",
+            "createdAt",
+            "updatedAt",
+        ],
+    ),
+    (
+        "idempotency_keys",
+        &[
+            "key",
+            "requestHash",
+            "status",
+            "responseStatus",
+            "responseBody",
+            "responseContentType",
+            "createdAt",
+            "completedAt",
+            "expiresAt",
+        ],
+    ),
+    (
+        "used_quotes",
+        &["nonce", "userAddress", "usedAt", "expiresAt"],
+    ),
+    (
+        "risk_limits",
+        &[
+            "scope",
+            "minBet",
+            "maxBet",
+            "maxUserMarketExposure",
+            "maxMarketExposure",
+            "closeCooldownSecs",
+            "bettingPaused",
+            "pauseReason",
+            "createdAt",
+            "updatedAt",
+        ],
+    ),
+    (
+        "chain_transactions",
+        &[
+            "id",
+            "kind",
+            "sender",
+            "payload",
+            "hash",
+            "status",
+            "vmStatus",
+            "gasUsed",
+            "version",
+            "marketId",
+            "betId",
+            "attempts",
+            "lastError",
+            "submittedAt",
+            "expiresAt",
+            "confirmedAt",
+            "updatedAt",
+        ],
+    ),
+    (
+        "scheduler_leaders",
+        &[
+            "job",
+            "instanceId",
+            "backendPid",
+            "acquiredAt",
+            "heartbeatAt",
+        ],
+    ),
+    (
+        "jobs",
+        &[
+            "id",
+            "kind",
+            "payload",
+            "state",
+            "attempts",
+            "maxAttempts",
+            "nextRunAt",
+            "lastError",
+            "result",
+            "lockedBy",
+            "createdAt",
+            "startedAt",
+            "finishedAt",
+            "updatedAt",
+        ],
+    ),
+    (
+        "job_schedules",
+        &[
+            "kind",
+            "schedule",
+            "paused",
+            "nextRunAt",
+            "lastEnqueuedAt",
+            "updatedAt",
+        ],
+    ),
+    (
+        "event_listener_state",
+        &[
+            "id",
+            "connected",
+            "connectedAt",
+            "disconnectedAt",
+            "lastNotificationAt",
+            "lastError",
+            "lastErrorAt",
+            "reconnects",
+            "betVersion",
+            "claimVersion",
+            "marketVersion",
+            "resolutionVersion",
+            "lastCatchUpAt",
+            "lastCatchUpEvents",
+            "updatedAt",
+        ],
+    ),
+    (
+        "sync_status",
+        &[
+            "id",
+            "eventType",
+            "lastSyncTime",
+            "lastVersion",
+            "lastScanned",
+            "lastWritten",
+            "lastSkipped",
+            "totalScanned",
+            "totalWritten",
+            "gapCursorVersion",
+            "gapCursorId",
+            "updatedAt",
+        ],
+    ),
+    (
+        "reconciliation_reports",
+        &[
+            "id",
+            "jobId",
+            "repair",
+            "marketsChecked",
+            "discrepancyCount",
+            "repairedCount",
+            "discrepancies",
+            "startedAt",
+            "finishedAt",
+        ],
+    ),
+    (
+        "audit_log",
+        &[
+            "id",
+            "actor",
+            "action",
+            "targetType",
+            "targetId",
+            "before",
+            "after",
+            "status",
+            "method",
+            "path",
+            "ipAddress",
+            "userAgent",
+            "requestId",
+            "durationMs",
+            "phase",
+            "startedId",
+            "createdAt",
+        ],
+    ),
+    (
+        "event_processing_stats",
+        &[
+            "event_type",
+            "total_processed",
+            "successful",
+            "errors",
+            "avg_duration_ms",
+            "last_processed_at",
+        ],
+    ),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+    pub checksum_matches: Option<bool>,
+    pub installed_on: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Differences between [`EXPECTED_SCHEMA`] and the live database.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaDiff {
+    pub missing_tables: Vec<String>,
+    pub missing_columns: Vec<(String, String)>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.missing_tables.is_empty() && self.missing_columns.is_empty()
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for table in &self.missing_tables {
+            writeln!(f, "- table {}", table)?;
+        }
+        for (table, column) in &self.missing_columns {
+            writeln!(f, "- column {}.\"{}\"", table, column)?;
+        }
+        Ok(())
+    }
+}
+
+pub async fn run_pending(pool: &PgPool) -> Result<()> {
+    info!("Applying pending database migrations...");
+    MIGRATOR
+        .run(pool)
+        .await
+        .context("Failed to apply migrations")?;
+    info!("Database migrations are up to date");
+    Ok(())
+}
+
+pub async fn status(pool: &PgPool) -> Result<Vec<MigrationStatus>> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('public._sqlx_migrations') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+
+    let mut applied = BTreeMap::new();
+    if table_exists {
+        let rows = sqlx::query(
+            "SELECT version, checksum, installed_on FROM _sqlx_migrations WHERE success = true",
+        )
+        .fetch_all(pool)
+        .await
+        .context("Failed to read _sqlx_migrations")?;
+
+        for row in rows {
+            let version: i64 = row.try_get("version")?;
+            let checksum: Vec<u8> = row.try_get("checksum")?;
+            let installed_on: chrono::DateTime<chrono::Utc> = row.try_get("installed_on")?;
+            applied.insert(version, (checksum, installed_on));
+        }
+    }
+
+    Ok(MIGRATOR
+        .iter()
+        .map(|migration| {
+            let applied_row = applied.get(&migration.version);
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied: applied_row.is_some(),
+                checksum_matches: applied_row
+                    .map(|(checksum, _)| checksum.as_slice() == migration.checksum.as_ref()),
+                installed_on: applied_row.map(|(_, installed_on)| *installed_on),
+            }
+        })
+        .collect())
+}
+
+pub async fn verify_schema(pool: &PgPool) -> Result<SchemaDiff> {
+    let rows = sqlx::query(
+        "SELECT table_name::text AS table_name, column_name::text AS column_name \
+         FROM information_schema.columns WHERE table_schema = current_schema()",
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to read information_schema.columns")?;
+
+    let mut actual: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for row in rows {
+        let table: String = row.try_get("table_name")?;
+        let column: String = row.try_get("column_name")?;
+        actual.entry(table).or_default().insert(column);
+    }
+
+    Ok(diff_schema(EXPECTED_SCHEMA, &actual))
+}
+
+/// Fails with a readable diff when the live schema is missing anything the code needs.
+pub async fn ensure_schema(pool: &PgPool) -> Result<()> {
+    let diff = verify_schema(pool).await?;
+    if diff.is_empty() {
+        info!("Database schema matches expectations");
+        return Ok(());
+    }
+
+    anyhow::bail!(
+        "Database schema drift detected. Run `kizo-server migrate up` or fix the schema:\n{}",
+        diff
+    )
+}
+
+fn diff_schema(
+    expected: &[(&str, &[&str])],
+    actual: &BTreeMap<String, BTreeSet<String>>,
+) -> SchemaDiff {
+    let mut diff = SchemaDiff::default();
+
+    for (table, columns) in expected {
+        match actual.get(*table) {
+            None => diff.missing_tables.push(table.to_string()),
+            Some(existing) => {
+                for column in columns.iter() {
+                    if !existing.contains(*column) {
+                        diff.missing_columns
+                            .push((table.to_string(), column.to_string()));
+                    }
+                }
+            }
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_schema_reports_missing_tables_and_columns() {
+        let expected: &[(&str, &[&str])] = &[
+            ("markets_extended", &["id", "yesPoolSize"]),
+            ("bets_extended", &["id"]),
+        ];
+
+        let mut actual = BTreeMap::new();
+        actual.insert(
+            "markets_extended".to_string(),
+            BTreeSet::from(["id".to_string(), "yes_pool_size".to_string()]),
+        );
+
+        let diff = diff_schema(expected, &actual);
+
+        assert_eq!(diff.missing_tables, vec!["bets_extended"]);
+        assert_eq!(
+            diff.missing_columns,
+            vec![("markets_extended".to_string(), "yesPoolSize".to_string())]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "- table bets_extended\n- column markets_extended.\"yesPoolSize\"\n"
+        );
+    }
+
+    #[test]
+    fn test_embedded_migrations_are_ordered() {
+        let versions: Vec<i64> = MIGRATOR.iter().map(|m| m.version).collect();
+        let mut sorted = versions.clone();
+        sorted.sort_unstable();
+
+        assert!(!versions.is_empty());
+        assert_eq!(versions, sorted);
+        assert!(versions.iter().all(|v| *v < 900));
+    }
+}
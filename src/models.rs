@@ -163,6 +163,15 @@ pub struct MarketExtended {
     pub current_yield: BigDecimal,
     #[sqlx(rename = "totalYieldEarned")]
     pub total_yield_earned: BigDecimal,
+    #[sqlx(rename = "marketType")]
+    pub market_type: String,
+    #[sqlx(rename = "winningOutcome")]
+    pub winning_outcome: Option<i32>,
+    #[sqlx(rename = "txHash")]
+    pub tx_hash: Option<String>,
+    /// `pending` until the creating transaction is committed; NULL when not tracked.
+    #[sqlx(rename = "txStatus")]
+    pub tx_status: Option<String>,
     #[sqlx(rename = "createdAt")]
     pub created_at: NaiveDateTime,
     #[sqlx(rename = "updatedAt")]
@@ -197,6 +206,10 @@ pub struct MarketResponse {
     pub count_no: i32,
     pub current_yield: String,
     pub total_yield_earned: String,
+    pub market_type: String,
+    pub winning_outcome: Option<i32>,
+    /// Every market's outcomes with pools and probabilities; YES/NO for binary markets.
+    pub outcomes: Vec<crate::services::market_outcomes::OutcomePool>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 
@@ -501,6 +514,25 @@ pub struct MarketChartData {
     pub bet_count: Vec<ChartDataPoint>,
 }
 
+/// Per-outcome series of a categorical market chart.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutcomeChartSeries {
+    pub index: i32,
+    pub label: String,
+    pub probability: Vec<ChartDataPoint>,
+    pub volume: Vec<ChartDataPoint>,
+    pub odds: Vec<ChartDataPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutcomeChartData {
+    pub outcomes: Vec<OutcomeChartSeries>,
+    pub total_volume: Vec<ChartDataPoint>,
+    pub bet_count: Vec<ChartDataPoint>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ChartQueryParams {
     #[serde(default = "default_interval")]
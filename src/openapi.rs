@@ -22,10 +22,15 @@ use utoipa::OpenApi;
     paths(
 
         crate::routes::health_check,
+        crate::routes::health::liveness,
+        crate::routes::health::readiness,
 
 
         crate::routes::markets::get_markets,
         crate::routes::markets::get_market_by_identifier,
+        crate::routes::markets::get_market_allocation,
+        crate::routes::markets::set_market_outcomes,
+        crate::routes::markets::get_bet_quote,
         crate::routes::markets::get_platform_stats,
 
 
@@ -34,16 +39,43 @@ use utoipa::OpenApi;
 
         crate::routes::protocols::get_protocols,
         crate::routes::protocols::get_protocol_by_id,
+        crate::routes::protocols::get_protocol_apy_history,
 
 
         crate::routes::yields::get_yields,
         crate::routes::yields::get_yield_protocols,
+        crate::routes::yields::get_apy_history,
+        crate::routes::yields::accrue_yields,
 
 
         crate::routes::charts::get_market_chart,
         crate::routes::charts::get_market_probability,
         crate::routes::charts::get_market_volume,
         crate::routes::charts::get_chart_config,
+
+
+        crate::routes::transactions::get_transaction,
+
+
+        crate::routes::admin::get_risk_limits,
+        crate::routes::admin::update_global_risk_limits,
+        crate::routes::admin::get_market_risk_limits,
+        crate::routes::admin::update_market_risk_limits,
+        crate::routes::admin::delete_market_risk_limits,
+        crate::routes::admin::list_jobs,
+        crate::routes::admin::enqueue_job,
+        crate::routes::admin::get_job,
+        crate::routes::admin::retry_job,
+        crate::routes::admin::cancel_job,
+        crate::routes::admin::list_job_schedules,
+        crate::routes::admin::pause_job_kind,
+        crate::routes::admin::resume_job_kind,
+        crate::routes::admin::get_reconciliation_report,
+        crate::routes::admin::run_reconciliation,
+        crate::routes::admin::get_cache_stats,
+        crate::routes::admin::get_signers,
+        crate::routes::admin::reload_signers,
+        crate::routes::admin::list_audit_log,
     ),
     components(
         schemas(
@@ -55,6 +87,10 @@ use utoipa::OpenApi;
             crate::models::PaginationParams,
             crate::routes::protocols::BetFilters,
             crate::routes::protocols::PlaceBetRequest,
+            crate::routes::markets::SetOutcomesRequest,
+            crate::services::risk_limits::RiskLimitsPatch,
+            crate::services::job_queue::NewJob,
+            crate::services::job_queue::JobKind,
 
 
             crate::models::MarketStats,
@@ -68,7 +104,9 @@ use utoipa::OpenApi;
         (name = "protocols", description = "Yield protocol management"),
         (name = "yields", description = "Yield tracking and statistics"),
         (name = "charts", description = "Chart data for market visualization"),
-        (name = "health", description = "Health check and status")
+        (name = "transactions", description = "Status of submitted chain transactions"),
+        (name = "health", description = "Health check and status"),
+        (name = "admin", description = "Operator endpoints (API key required)")
     ),
     modifiers(&SecurityAddon)
 )]
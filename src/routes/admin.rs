@@ -0,0 +1,795 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    middleware,
+    response::Json,
+    routing::{get, post},
+    Extension, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::info;
+
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+use crate::config::Config;
+use crate::services::audit::{AuditDetails, AuditFilter, AuditLog};
+use crate::services::job_queue::{JobFilter, JobKind, JobQueue, JobState, NewJob};
+use crate::services::reconciliation::ReconciliationService;
+use crate::services::response_cache::ResponseCache;
+use crate::services::risk_limits::{RiskLimitService, RiskLimitsPatch, GLOBAL_SCOPE};
+use crate::services::signer::SignerPool;
+use crate::{db::Database, error::AppError, state::AppState};
+
+/// Operator endpoints under `/api/admin`; every route requires the API key, and every
+/// change is written to the audit log.
+pub fn create_admin_router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/risk-limits",
+            get(get_risk_limits).patch(update_global_risk_limits),
+        )
+        .route(
+            "/risk-limits/:identifier",
+            get(get_market_risk_limits)
+                .patch(update_market_risk_limits)
+                .delete(delete_market_risk_limits),
+        )
+        .route("/jobs", get(list_jobs).post(enqueue_job))
+        .route("/jobs/schedules", get(list_job_schedules))
+        .route("/jobs/schedules/:kind/pause", post(pause_job_kind))
+        .route("/jobs/schedules/:kind/resume", post(resume_job_kind))
+        .route("/jobs/:id", get(get_job))
+        .route("/jobs/:id/retry", post(retry_job))
+        .route("/jobs/:id/cancel", post(cancel_job))
+        .route("/reconciliation", get(get_reconciliation_report))
+        .route("/reconciliation/run", post(run_reconciliation))
+        .route("/cache", get(get_cache_stats))
+        .route("/signers", get(get_signers))
+        .route("/signers/reload", post(reload_signers))
+        .route("/audit", get(list_audit_log))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::audit::audit,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state,
+            crate::middleware::auth::require_api_key,
+        ))
+}
+
+async fn resolve_market_id(db: &Database, identifier: &str) -> Result<String, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT id
+        FROM markets_extended
+        WHERE id = $1 OR "adjTicker" = $1 OR "blockchainMarketId"::text = $1
+        LIMIT 1
+        "#,
+        identifier
+    )
+    .fetch_optional(db.pool())
+    .await?
+    .ok_or_else(|| AppError::NotFound("Market not found".to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/risk-limits",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Global risk limits and every market override"),
+        (status = 401, description = "Missing or invalid API key")
+    )
+)]
+async fn get_risk_limits(State(db): State<Database>) -> Result<Json<Value>, AppError> {
+    let limits = RiskLimitService::new(db.pool().clone())
+        .list()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load risk limits: {}", e)))?;
+
+    let (global, markets): (Vec<_>, Vec<_>) =
+        limits.into_iter().partition(|l| l.scope == GLOBAL_SCOPE);
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "global": global.into_iter().next(),
+            "markets": markets
+        }
+    })))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/admin/risk-limits",
+    tag = "admin",
+    request_body = RiskLimitsPatch,
+    responses(
+        (status = 200, description = "Updated global limits; bettingPaused is the platform kill switch"),
+        (status = 400, description = "Invalid limits"),
+        (status = 401, description = "Missing or invalid API key")
+    )
+)]
+async fn update_global_risk_limits(
+    State(db): State<Database>,
+    Json(patch): Json<RiskLimitsPatch>,
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
+    info!("Admin: updating global risk limits");
+
+    let service = RiskLimitService::new(db.pool().clone());
+    let before = service
+        .get(GLOBAL_SCOPE)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load risk limits: {}", e)))?;
+    let limits = service
+        .update(GLOBAL_SCOPE, patch)
+        .await
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let audit = AuditDetails::new("risk_limits.update")
+        .target("risk_limits", GLOBAL_SCOPE)
+        .before(&before)
+        .after(&limits);
+
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "data": limits
+        })),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/risk-limits/{identifier}",
+    tag = "admin",
+    params(
+        ("identifier" = String, Path, description = "Market identifier (UUID, adjTicker, or blockchain ID)")
+    ),
+    responses(
+        (status = 200, description = "The market's overrides and the limits in force for it"),
+        (status = 404, description = "Market not found")
+    )
+)]
+async fn get_market_risk_limits(
+    State(db): State<Database>,
+    Path(identifier): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let market_id = resolve_market_id(&db, &identifier).await?;
+    let service = RiskLimitService::new(db.pool().clone());
+
+    let overrides = service
+        .get(&market_id)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load risk limits: {}", e)))?;
+    let effective = service
+        .effective(&market_id)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load risk limits: {}", e)))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "marketId": market_id,
+            "overrides": overrides,
+            "effective": effective
+        }
+    })))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/admin/risk-limits/{identifier}",
+    tag = "admin",
+    params(
+        ("identifier" = String, Path, description = "Market identifier (UUID, adjTicker, or blockchain ID)")
+    ),
+    request_body = RiskLimitsPatch,
+    responses(
+        (status = 200, description = "Updated market overrides; bettingPaused is the market kill switch"),
+        (status = 400, description = "Invalid limits"),
+        (status = 404, description = "Market not found")
+    )
+)]
+async fn update_market_risk_limits(
+    State(db): State<Database>,
+    Path(identifier): Path<String>,
+    Json(patch): Json<RiskLimitsPatch>,
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
+    let market_id = resolve_market_id(&db, &identifier).await?;
+    info!("Admin: updating risk limits for market {}", market_id);
+
+    let service = RiskLimitService::new(db.pool().clone());
+    let before = service
+        .get(&market_id)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load risk limits: {}", e)))?;
+    let limits = service
+        .update(&market_id, patch)
+        .await
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let audit = AuditDetails::new("risk_limits.update")
+        .target("market", &market_id)
+        .before(&before)
+        .after(&limits);
+
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "data": limits
+        })),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/risk-limits/{identifier}",
+    tag = "admin",
+    params(
+        ("identifier" = String, Path, description = "Market identifier (UUID, adjTicker, or blockchain ID)")
+    ),
+    responses(
+        (status = 200, description = "Market overrides removed; the global limits apply"),
+        (status = 404, description = "Market not found")
+    )
+)]
+async fn delete_market_risk_limits(
+    State(db): State<Database>,
+    Path(identifier): Path<String>,
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
+    let market_id = resolve_market_id(&db, &identifier).await?;
+    info!("Admin: clearing risk limits for market {}", market_id);
+
+    let service = RiskLimitService::new(db.pool().clone());
+    let before = service
+        .get(&market_id)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load risk limits: {}", e)))?;
+    let removed = service
+        .clear_market(&market_id)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to clear risk limits: {}", e)))?;
+    let mut audit = AuditDetails::new("risk_limits.clear").target("market", &market_id);
+    if removed {
+        audit = audit.before(&before);
+    }
+
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "data": {
+                "marketId": market_id,
+                "removed": removed
+            }
+        })),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/cache",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Response cache size, and per endpoint its TTL, entries, hits, misses, hit rate and invalidations on this instance"),
+        (status = 401, description = "Missing or invalid API key")
+    )
+)]
+async fn get_cache_stats(State(cache): State<Arc<ResponseCache>>) -> Json<Value> {
+    Json(json!({
+        "success": true,
+        "data": cache.stats().await
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/signers",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Signer backend, when its keys were loaded and each operator account with its signed transaction count"),
+        (status = 401, description = "Missing or invalid API key")
+    )
+)]
+async fn get_signers(State(signers): State<Arc<SignerPool>>) -> Json<Value> {
+    Json(json!({
+        "success": true,
+        "data": signers.status()
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/signers/reload",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Keys re-read from the signer backend; in-flight requests finish with the old accounts"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 500, description = "Backend could not be loaded; the previous accounts stay active")
+    )
+)]
+async fn reload_signers(
+    State(signers): State<Arc<SignerPool>>,
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
+    let accounts = signers
+        .reload()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to reload signing accounts: {:#}", e)))?;
+    info!(
+        "Reloaded {} signing account(s) via admin API",
+        accounts.len()
+    );
+    let status = signers.status();
+    let audit = AuditDetails::new("signers.reload").after(&status);
+
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "data": status
+        })),
+    ))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct JobListParams {
+    /// queued, running, succeeded, failed or cancelled
+    state: Option<String>,
+    /// indexer_sync, yield_calc, yield_accrual, apy_refresh or reconciliation
+    kind: Option<String>,
+    #[serde(default = "default_job_limit")]
+    limit: i64,
+}
+
+fn default_job_limit() -> i64 {
+    50
+}
+
+fn job_queue(db: &Database, config: &Config) -> JobQueue {
+    JobQueue::new(db.pool().clone(), &config.jobs)
+}
+
+/// Tells a missing job apart from one whose state does not allow the action.
+async fn job_action_error(queue: &JobQueue, id: &str, action: &str) -> AppError {
+    match queue.get(id).await {
+        Ok(Some(job)) => AppError::BadRequest(format!("Cannot {} a {} job", action, job.state)),
+        Ok(None) => AppError::NotFound("Job not found".to_string()),
+        Err(e) => AppError::Internal(format!("Failed to load job: {}", e)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/jobs",
+    tag = "admin",
+    params(JobListParams),
+    responses(
+        (status = 200, description = "Jobs, newest first, with attempts, next run, last error and result"),
+        (status = 400, description = "Unknown state or kind"),
+        (status = 401, description = "Missing or invalid API key")
+    )
+)]
+async fn list_jobs(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Query(params): Query<JobListParams>,
+) -> Result<Json<Value>, AppError> {
+    let filter = JobFilter {
+        state: params
+            .state
+            .as_deref()
+            .map(str::parse::<JobState>)
+            .transpose()
+            .map_err(|e| AppError::BadRequest(e.to_string()))?,
+        kind: params
+            .kind
+            .as_deref()
+            .map(str::parse::<JobKind>)
+            .transpose()
+            .map_err(|e| AppError::BadRequest(e.to_string()))?,
+        limit: params.limit.clamp(1, 500),
+    };
+
+    let jobs = job_queue(&db, &config)
+        .list(&filter)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to list jobs: {}", e)))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": jobs
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/jobs",
+    tag = "admin",
+    request_body = NewJob,
+    responses(
+        (status = 202, description = "Job queued"),
+        (status = 400, description = "Invalid job"),
+        (status = 401, description = "Missing or invalid API key")
+    )
+)]
+async fn enqueue_job(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Json(job): Json<NewJob>,
+) -> Result<(StatusCode, Extension<AuditDetails>, Json<Value>), AppError> {
+    info!("Admin: queueing {} job", job.kind);
+
+    let job = job_queue(&db, &config)
+        .enqueue(job)
+        .await
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let audit = AuditDetails::new("job.enqueue")
+        .target("job", &job.id)
+        .after(&job);
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "data": job
+        })),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/jobs/{id}",
+    tag = "admin",
+    params(
+        ("id" = String, Path, description = "Job id")
+    ),
+    responses(
+        (status = 200, description = "The job"),
+        (status = 404, description = "Job not found")
+    )
+)]
+async fn get_job(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let job = job_queue(&db, &config)
+        .get(&id)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load job: {}", e)))?
+        .ok_or_else(|| AppError::NotFound("Job not found".to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": job
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/jobs/{id}/retry",
+    tag = "admin",
+    params(
+        ("id" = String, Path, description = "Job id")
+    ),
+    responses(
+        (status = 200, description = "Failed or cancelled job queued again with a fresh set of attempts"),
+        (status = 400, description = "Job is not failed or cancelled"),
+        (status = 404, description = "Job not found")
+    )
+)]
+async fn retry_job(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(id): Path<String>,
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
+    info!("Admin: retrying job {}", id);
+
+    let queue = job_queue(&db, &config);
+    let job = match queue
+        .retry(&id)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to retry job: {}", e)))?
+    {
+        Some(job) => job,
+        None => return Err(job_action_error(&queue, &id, "retry").await),
+    };
+    let audit = AuditDetails::new("job.retry")
+        .target("job", &job.id)
+        .after(&job);
+
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "data": job
+        })),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/jobs/{id}/cancel",
+    tag = "admin",
+    params(
+        ("id" = String, Path, description = "Job id")
+    ),
+    responses(
+        (status = 200, description = "Job cancelled; a running job finishes but its result is discarded"),
+        (status = 400, description = "Job already finished"),
+        (status = 404, description = "Job not found")
+    )
+)]
+async fn cancel_job(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(id): Path<String>,
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
+    info!("Admin: cancelling job {}", id);
+
+    let queue = job_queue(&db, &config);
+    let job = match queue
+        .cancel(&id)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to cancel job: {}", e)))?
+    {
+        Some(job) => job,
+        None => return Err(job_action_error(&queue, &id, "cancel").await),
+    };
+    let audit = AuditDetails::new("job.cancel")
+        .target("job", &job.id)
+        .after(&job);
+
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "data": job
+        })),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/jobs/schedules",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Recurring jobs with their schedule, pause state and next run"),
+        (status = 401, description = "Missing or invalid API key")
+    )
+)]
+async fn list_job_schedules(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+) -> Result<Json<Value>, AppError> {
+    let schedules = job_queue(&db, &config)
+        .schedules()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load job schedules: {}", e)))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": schedules
+    })))
+}
+
+async fn set_job_kind_paused(
+    db: &Database,
+    config: &Config,
+    kind: &str,
+    paused: bool,
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
+    let kind: JobKind = kind
+        .parse()
+        .map_err(|e: anyhow::Error| AppError::BadRequest(e.to_string()))?;
+    info!(
+        "Admin: {} {} jobs",
+        if paused { "pausing" } else { "resuming" },
+        kind
+    );
+
+    let schedule = job_queue(db, config)
+        .set_paused(kind, paused)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to update job schedule: {}", e)))?
+        .ok_or_else(|| AppError::NotFound(format!("{} has no schedule", kind)))?;
+    let audit = AuditDetails::new(if paused {
+        "job_schedule.pause"
+    } else {
+        "job_schedule.resume"
+    })
+    .target("job_schedule", kind)
+    .after(&schedule);
+
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "data": schedule
+        })),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/jobs/schedules/{kind}/pause",
+    tag = "admin",
+    params(
+        ("kind" = String, Path, description = "Job kind")
+    ),
+    responses(
+        (status = 200, description = "Kind paused: nothing new is scheduled and queued jobs of the kind wait"),
+        (status = 404, description = "Kind has no schedule")
+    )
+)]
+async fn pause_job_kind(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(kind): Path<String>,
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
+    set_job_kind_paused(&db, &config, &kind, true).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/jobs/schedules/{kind}/resume",
+    tag = "admin",
+    params(
+        ("kind" = String, Path, description = "Job kind")
+    ),
+    responses(
+        (status = 200, description = "Kind resumed"),
+        (status = 404, description = "Kind has no schedule")
+    )
+)]
+async fn resume_job_kind(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(kind): Path<String>,
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
+    set_job_kind_paused(&db, &config, &kind, false).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/reconciliation",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Latest reconciliation report with its discrepancies; null before the first run"),
+        (status = 401, description = "Missing or invalid API key")
+    )
+)]
+async fn get_reconciliation_report(State(db): State<Database>) -> Result<Json<Value>, AppError> {
+    let report = ReconciliationService::new(db.pool().clone())
+        .latest()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load reconciliation report: {}", e)))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": report
+    })))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ReconciliationRunParams {
+    /// Rebuild drifted rows from the indexer; defaults to `scheduler.reconciliation_auto_repair`
+    repair: Option<bool>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/reconciliation/run",
+    tag = "admin",
+    params(ReconciliationRunParams),
+    responses(
+        (status = 202, description = "Reconciliation job queued"),
+        (status = 401, description = "Missing or invalid API key")
+    )
+)]
+async fn run_reconciliation(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Query(params): Query<ReconciliationRunParams>,
+) -> Result<(StatusCode, Extension<AuditDetails>, Json<Value>), AppError> {
+    info!(
+        "Admin: queueing reconciliation (repair: {:?})",
+        params.repair
+    );
+
+    let job = job_queue(&db, &config)
+        .enqueue(NewJob {
+            kind: JobKind::Reconciliation,
+            payload: params.repair.map(|repair| json!({ "repair": repair })),
+            delay_secs: None,
+            max_attempts: None,
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to queue reconciliation: {}", e)))?;
+    let audit = AuditDetails::new("reconciliation.run")
+        .target("job", &job.id)
+        .after(&job);
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "data": job
+        })),
+    ))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditListParams {
+    /// Actor, e.g. `api-key:3f1a9c0b2d4e`
+    actor: Option<String>,
+    /// Exact action, or a prefix ending in `.` such as `market.`
+    action: Option<String>,
+    /// market, protocol, job, job_schedule, risk_limits, ...
+    target_type: Option<String>,
+    target_id: Option<String>,
+    /// Unix seconds, inclusive
+    since: Option<i64>,
+    /// Unix seconds, exclusive
+    until: Option<i64>,
+    /// Only entries with a smaller id, to page back from the last entry returned
+    before_id: Option<i64>,
+    #[serde(default = "default_audit_limit")]
+    limit: i64,
+}
+
+fn default_audit_limit() -> i64 {
+    50
+}
+
+fn unix_time(secs: Option<i64>, name: &str) -> Result<Option<chrono::NaiveDateTime>, AppError> {
+    secs.map(|secs| {
+        chrono::DateTime::from_timestamp(secs, 0)
+            .map(|t| t.naive_utc())
+            .ok_or_else(|| AppError::BadRequest(format!("Invalid {} timestamp", name)))
+    })
+    .transpose()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/audit",
+    tag = "admin",
+    params(AuditListParams),
+    responses(
+        (status = 200, description = "Audit log entries, newest first: actor, action, target, before/after snapshots, response status and request metadata"),
+        (status = 400, description = "Invalid timestamp"),
+        (status = 401, description = "Missing or invalid API key")
+    )
+)]
+async fn list_audit_log(
+    State(db): State<Database>,
+    Query(params): Query<AuditListParams>,
+) -> Result<Json<Value>, AppError> {
+    let filter = AuditFilter {
+        since: unix_time(params.since, "since")?,
+        until: unix_time(params.until, "until")?,
+        actor: params.actor,
+        action: params.action,
+        target_type: params.target_type,
+        target_id: params.target_id,
+        before_id: params.before_id,
+        limit: params.limit.clamp(1, 500),
+    };
+
+    let entries = AuditLog::new(db.pool().clone())
+        .list(&filter)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load audit log: {}", e)))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": entries
+    })))
+}
@@ -6,8 +6,10 @@ use axum::{
     Extension, Router,
 };
 use serde_json::json;
+use std::sync::Arc;
 
 use crate::{
+    config::Config,
     db::Database,
     error::AppError,
     middleware::jwt::require_jwt,
@@ -16,17 +18,18 @@ use crate::{
         WalletConnectRequest, WalletConnectResponse,
     },
     services::user_service::UserService,
+    state::AppState,
     utils::jwt::{Claims, JwtService},
 };
 
-pub fn create_auth_router() -> Router<Database> {
+pub fn create_auth_router(state: AppState) -> Router<AppState> {
     let public_routes = Router::new().route("/wallet", post(connect_wallet));
 
     let protected_routes = Router::new()
         .route("/me", get(get_current_user))
         .route("/profile", put(update_profile))
         .route("/refresh", post(refresh_token))
-        .route_layer(middleware::from_fn(require_jwt));
+        .route_layer(middleware::from_fn_with_state(state, require_jwt));
 
     public_routes.merge(protected_routes)
 }
@@ -44,10 +47,11 @@ pub fn create_auth_router() -> Router<Database> {
 )]
 async fn connect_wallet(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
     Json(payload): Json<WalletConnectRequest>,
 ) -> Result<Json<WalletConnectResponse>, AppError> {
     let user_service = UserService::new(db.pool().clone());
-    let jwt_service = JwtService::new();
+    let jwt_service = JwtService::new(&config.auth);
 
     if payload.address.is_empty() {
         return Err(AppError::BadRequest("Address cannot be empty".to_string()));
@@ -139,10 +143,11 @@ async fn update_profile(
 )]
 async fn refresh_token(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
     Extension(claims): Extension<Claims>,
 ) -> Result<Json<serde_json::Value>, AppError> {
     let user_service = UserService::new(db.pool().clone());
-    let jwt_service = JwtService::new();
+    let jwt_service = JwtService::new(&config.auth);
 
     let user = user_service
         .get_user_by_id(&claims.sub)
@@ -1,5 +1,6 @@
 use axum::{
     extract::{Path, Query, State},
+    http::HeaderMap,
     middleware,
     response::Json,
     routing::{get, post},
@@ -8,12 +9,16 @@ use axum::{
 use serde_json::{json, Value};
 use tracing::info;
 
-use crate::{db::Database, error::AppError, models::PaginationParams};
+use std::sync::Arc;
+
+use crate::{
+    config::Config, db::Database, error::AppError, models::PaginationParams, state::AppState,
+};
 
 use super::protocols::{
     claim_winnings_route, get_bet_stats_summary, get_bets_with_filters, place_bet,
 };
-pub fn create_bets_router() -> Router<Database> {
+pub fn create_bets_router(state: AppState) -> Router<AppState> {
     let public_routes = Router::new()
         .route("/", get(get_bets_with_filters))
         .route("/stats/summary", get(get_bet_stats_summary))
@@ -26,7 +31,12 @@ pub fn create_bets_router() -> Router<Database> {
     let protected_routes = Router::new()
         .route("/", post(place_bet))
         .route("/claim", post(claim_winnings_route))
-        .layer(middleware::from_fn(
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::idempotency::idempotency,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state,
             crate::middleware::auth::require_api_key,
         ));
 
@@ -35,6 +45,7 @@ pub fn create_bets_router() -> Router<Database> {
 
 async fn get_bet_by_id(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
     Path(id): Path<String>,
 ) -> Result<Json<Value>, AppError> {
     let bet_id: i64 = id
@@ -48,20 +59,39 @@ async fn get_bet_by_id(
         .await?
         .ok_or_else(|| AppError::NotFound("Bet not found".to_string()))?;
 
+    let calculator =
+        crate::services::UserYieldCalculator::new(db.pool().clone(), &config.yield_allocation);
+    let attribution = calculator
+        .bet_yield(bet_id)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load bet yield: {}", e)))?;
+
+    let mut data = serde_json::to_value(&bet)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize bet: {}", e)))?;
+    data["yieldEarned"] = json!(attribution
+        .as_ref()
+        .map(|a| a.yield_earned.to_string())
+        .unwrap_or_else(|| "0".to_string()));
+    data["yieldAttributedAt"] = json!(attribution.and_then(|a| a.yield_attributed_at));
+
     Ok(Json(json!({
         "success": true,
-        "data": bet
+        "data": data
     })))
 }
 
 async fn get_user_bets(
     State(db): State<Database>,
+    headers: HeaderMap,
     Path(address): Path<String>,
     Query(params): Query<PaginationParams>,
 ) -> Result<Json<Value>, AppError> {
     info!("Fetching bets for user: {}", address);
 
-    let bets = db.get_bets_by_user(&address, &params).await?;
+    let bets = db
+        .for_user(&address, &headers)
+        .get_bets_by_user(&address, &params)
+        .await?;
 
     Ok(Json(json!({
         "success": true,
@@ -74,11 +104,15 @@ async fn get_user_bets(
 
 async fn get_user_stats(
     State(db): State<Database>,
+    headers: HeaderMap,
     Path(address): Path<String>,
 ) -> Result<Json<Value>, AppError> {
     info!("Fetching stats for user: {}", address);
 
-    let stats = db.get_user_stats(&address).await?;
+    let stats = db
+        .for_user(&address, &headers)
+        .get_user_stats(&address)
+        .await?;
 
     Ok(Json(json!({
         "success": true,
@@ -88,11 +122,21 @@ async fn get_user_stats(
 
 async fn get_user_yields(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    headers: HeaderMap,
     Path(address): Path<String>,
 ) -> Result<Json<Value>, AppError> {
     info!("Fetching yields for user: {}", address);
 
-    let calculator = crate::services::UserYieldCalculator::new(db.pool().clone());
+    let calculator = crate::services::UserYieldCalculator::new(
+        db.for_user(&address, &headers).read_pool().clone(),
+        &config.yield_allocation,
+    );
+
+    let bets = calculator
+        .user_bet_yields(&address)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load bet yields: {}", e)))?;
 
     match calculator.calculate_user_yields(&address).await {
         Ok(summary) => {
@@ -119,6 +163,7 @@ async fn get_user_yields(
                         "averageApy": summary.average_apy.to_string(),
                         "activePoolSize": summary.active_pool_size.to_string()
                     },
+                    "bets": bets,
                     "recentPerformance": []
                 }
             })))
@@ -1,24 +1,54 @@
 use axum::{
     extract::State,
+    middleware,
     response::Json,
     routing::{get, post},
-    Router,
+    Extension, Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tracing::{error, info};
 
+use std::sync::Arc;
+
 use crate::{
+    config::Config,
     db::Database,
     error::AppError,
     services::aptos_contract::{AptosContractService, CreateMarketParams},
+    services::audit::AuditDetails,
+    services::chain_transactions::{
+        ChainTransactionService, NewChainTransaction, TxKind, ROW_PENDING,
+    },
+    services::signer::SignerPool,
+    state::AppState,
 };
 
-pub fn create_blockchain_router(db: Database) -> Router<Database> {
-    Router::new()
+pub fn create_blockchain_router(state: AppState) -> Router<AppState> {
+    let public_routes = Router::new().route("/status", get(get_blockchain_status));
+
+    let protected_routes = Router::new()
         .route("/create-aptos", post(create_market_on_aptos))
-        .route("/status", get(get_blockchain_status))
-        .with_state(db)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::audit::audit,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state,
+            crate::middleware::auth::require_api_key,
+        ));
+
+    public_routes.merge(protected_routes)
+}
+
+/// The columns of a `markets_extended` row that creating it on chain rewrites.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MarketChainLink {
+    id: String,
+    blockchain_market_id: Option<i64>,
+    tx_hash: Option<String>,
+    tx_status: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -39,8 +69,10 @@ struct CreateAptosMarketResponse {
 
 async fn create_market_on_aptos(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    State(signers): State<Arc<SignerPool>>,
     Json(payload): Json<CreateAptosMarketRequest>,
-) -> Result<Json<Value>, AppError> {
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
     info!("Creating market on Aptos blockchain: {}", payload.question);
 
     if payload.question.trim().is_empty() {
@@ -53,21 +85,44 @@ async fn create_market_on_aptos(
         ));
     }
 
-    let contract_service = AptosContractService::new().map_err(|e| {
-        error!("Failed to initialize Aptos contract service: {}", e);
-        AppError::Internal(format!("Failed to initialize blockchain service: {}", e))
-    })?;
+    let before = match &payload.db_market_id {
+        Some(db_id) => Some(
+            sqlx::query_as!(
+                MarketChainLink,
+                r#"
+                SELECT id, "blockchainMarketId" as blockchain_market_id, "txHash" as tx_hash,
+                       "txStatus" as tx_status
+                FROM markets_extended
+                WHERE id = $1
+                "#,
+                db_id
+            )
+            .fetch_optional(db.pool())
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Market not found: {}", db_id)))?,
+        ),
+        None => None,
+    };
+
+    let contract_service = AptosContractService::new(&config.chain)
+        .map_err(|e| {
+            error!("Failed to initialize Aptos contract service: {}", e);
+            AppError::Internal(format!("Failed to initialize blockchain service: {}", e))
+        })?
+        .with_signers(signers);
 
-    let protocol_selector_addr = std::env::var("APTOS_PROTOCOL_SELECTOR_ADDR")
-        .unwrap_or_else(|_| contract_service.module_address.clone());
+    let protocol_selector_addr = config
+        .chain
+        .protocol_selector_addr()
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .to_string();
 
     let result = contract_service
         .create_market(CreateMarketParams {
             question: payload.question.clone(),
             description: payload.description.clone(),
             duration_seconds: payload.duration,
-            token_type: std::env::var("APTOS_TOKEN_TYPE")
-                .unwrap_or_else(|_| "0x1::aptos_coin::AptosCoin".to_string()),
+            token_type: config.chain.token_type.clone(),
             protocol_selector_addr,
         })
         .await
@@ -81,23 +136,32 @@ async fn create_market_on_aptos(
         result.market_id, result.tx_hash
     );
 
+    let mut after = None;
     if let Some(ref db_id) = payload.db_market_id {
-        let update_result = sqlx::query(
-            "UPDATE markets_extended
-             SET \"blockchainMarketId\" = $1, \"updatedAt\" = NOW()
-             WHERE id = $2",
+        let update_result = sqlx::query_as!(
+            MarketChainLink,
+            r#"
+            UPDATE markets_extended
+            SET "blockchainMarketId" = $1, "txHash" = $3, "txStatus" = $4, "updatedAt" = NOW()
+            WHERE id = $2
+            RETURNING id, "blockchainMarketId" as blockchain_market_id, "txHash" as tx_hash,
+                      "txStatus" as tx_status
+            "#,
+            result.market_id,
+            db_id,
+            result.tx_hash,
+            ROW_PENDING
         )
-        .bind(result.market_id)
-        .bind(db_id)
-        .execute(db.pool())
+        .fetch_optional(db.pool())
         .await;
 
         match update_result {
-            Ok(_) => {
+            Ok(updated) => {
                 info!(
                     "Updated market {} with blockchain ID {}",
                     db_id, result.market_id
                 );
+                after = updated;
             }
             Err(e) => {
                 error!("Failed to update market with blockchain ID: {}", e);
@@ -105,24 +169,56 @@ async fn create_market_on_aptos(
         }
     }
 
-    Ok(Json(json!({
-        "success": true,
-        "message": "Market created successfully on Aptos blockchain",
-        "data": {
-            "blockchain_market_id": result.market_id,
-            "tx_hash": result.tx_hash,
-            "version": result.version,
+    if let Err(e) = ChainTransactionService::new(db.pool().clone(), &config.chain.node_url)
+        .record(
+            NewChainTransaction {
+                kind: TxKind::CreateMarket,
+                sender: &result.sender,
+                payload: &result.payload,
+                hash: &result.tx_hash,
+                market_id: payload.db_market_id.as_deref(),
+                bet_id: None,
+            },
+            config.chain.tx_expiration_secs,
+        )
+        .await
+    {
+        error!("Failed to record transaction {}: {}", result.tx_hash, e);
+    }
+
+    let mut audit = AuditDetails::new("market.create_on_chain")
+        .target("blockchain_market", result.market_id)
+        .after(json!({
+            "market": after,
+            "txHash": result.tx_hash,
             "question": payload.question,
-            "description": payload.description,
-            "duration": payload.duration,
-            "explorer": format!("https://explorer.aptoslabs.com/txn/{}?network=testnet", result.tx_hash),
-            "db_market_id": payload.db_market_id
-        }
-    })))
+        }));
+    if let Some(before) = &before {
+        audit = audit.before(before);
+    }
+
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "message": "Market created successfully on Aptos blockchain",
+            "data": {
+                "blockchain_market_id": result.market_id,
+                "tx_hash": result.tx_hash,
+                "tx_status": ROW_PENDING,
+                "version": result.version,
+                "question": payload.question,
+                "description": payload.description,
+                "duration": payload.duration,
+                "explorer": format!("https://explorer.aptoslabs.com/txn/{}?network=testnet", result.tx_hash),
+                "db_market_id": payload.db_market_id
+            }
+        })),
+    ))
 }
 
-async fn get_blockchain_status() -> Result<Json<Value>, AppError> {
-    let contract_service = AptosContractService::new().map_err(|e| {
+async fn get_blockchain_status(State(config): State<Arc<Config>>) -> Result<Json<Value>, AppError> {
+    let contract_service = AptosContractService::new(&config.chain).map_err(|e| {
         error!("Failed to initialize Aptos contract service: {}", e);
         AppError::Internal(format!("Failed to initialize blockchain service: {}", e))
     })?;
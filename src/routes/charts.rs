@@ -8,8 +8,42 @@ use serde_json::{json, Value};
 use tracing::info;
 use utoipa;
 
-use crate::{chart::ChartService, db::Database, error::AppError, models::ChartQueryParams};
-pub fn create_charts_router() -> Router<Database> {
+use crate::{
+    chart::ChartService,
+    db::Database,
+    error::AppError,
+    models::{ChartDataPoint, ChartQueryParams, OutcomeChartData, OutcomeChartSeries},
+    state::AppState,
+};
+/// Looks up a categorical market by UUID or blockchain ID; `None` for binary markets.
+async fn categorical_market_id(db: &Database, id: &str) -> Result<Option<String>, AppError> {
+    let market_id = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM markets_extended
+        WHERE (id = $1 OR "blockchainMarketId"::text = $1) AND "marketType" = 'categorical'
+        LIMIT 1
+        "#,
+        id
+    )
+    .fetch_optional(db.read_pool())
+    .await?;
+
+    Ok(market_id)
+}
+
+/// One `{index, label, data}` entry per outcome, `data` picked from each outcome's series.
+fn outcome_series(
+    chart_data: &OutcomeChartData,
+    pick: fn(&OutcomeChartSeries) -> &Vec<ChartDataPoint>,
+) -> Value {
+    json!(chart_data
+        .outcomes
+        .iter()
+        .map(|o| json!({ "index": o.index, "label": o.label, "data": pick(o) }))
+        .collect::<Vec<_>>())
+}
+
+pub fn create_charts_router() -> Router<AppState> {
     Router::new()
         .route("/market/:id", get(get_market_chart))
         .route("/market/:id/probability", get(get_market_probability))
@@ -40,6 +74,10 @@ async fn get_market_chart(
     Path(id): Path<String>,
     Query(params): Query<ChartQueryParams>,
 ) -> Result<Json<Value>, AppError> {
+    if let Some(categorical_id) = categorical_market_id(&db, &id).await? {
+        return get_outcome_chart(&db, &id, &categorical_id, &params).await;
+    }
+
     let market_id: i64 = if let Ok(num_id) = id.parse::<i64>() {
         num_id
     } else {
@@ -47,7 +85,7 @@ async fn get_market_chart(
             r#"SELECT "blockchainMarketId" as blockchain_market_id FROM markets_extended WHERE id = $1 LIMIT 1"#,
             id
         )
-        .fetch_optional(db.pool())
+        .fetch_optional(db.read_pool())
         .await?
         .ok_or_else(|| AppError::NotFound("Market not found".to_string()))?;
 
@@ -76,7 +114,7 @@ async fn get_market_chart(
         market_id, params.interval
     );
 
-    let chart_service = ChartService::new(db.pool().clone());
+    let chart_service = ChartService::new(db.read_pool().clone());
     let chart_data = chart_service
         .get_market_chart_data(market_id, &params.interval, params.from, params.to)
         .await?;
@@ -124,6 +162,63 @@ async fn get_market_chart(
     })))
 }
 
+/// Categorical counterpart of `get_market_chart`: every series is split per outcome.
+async fn get_outcome_chart(
+    db: &Database,
+    id: &str,
+    market_id: &str,
+    params: &ChartQueryParams,
+) -> Result<Json<Value>, AppError> {
+    info!(
+        "Fetching outcome chart data for market {} with interval {}",
+        market_id, params.interval
+    );
+
+    let chart_data = ChartService::new(db.read_pool().clone())
+        .get_outcome_chart_data(market_id, &params.interval, params.from, params.to)
+        .await?;
+
+    let requested_series: Vec<&str> = params.series.split(',').map(|s| s.trim()).collect();
+
+    let mut response_data = json!({});
+
+    if requested_series.contains(&"probability") {
+        response_data["probability"] = json!({
+            "outcomes": outcome_series(&chart_data, |o| &o.probability)
+        });
+    }
+
+    if requested_series.contains(&"volume") {
+        response_data["volume"] = json!({
+            "outcomes": outcome_series(&chart_data, |o| &o.volume),
+            "total": chart_data.total_volume
+        });
+    }
+
+    if requested_series.contains(&"odds") {
+        response_data["odds"] = json!({
+            "outcomes": outcome_series(&chart_data, |o| &o.odds)
+        });
+    }
+
+    if requested_series.contains(&"bets") {
+        response_data["bets"] = json!(chart_data.bet_count);
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "meta": {
+            "symbol": id,
+            "marketType": "categorical",
+            "interval": params.interval,
+            "from": params.from,
+            "to": params.to,
+            "series": requested_series
+        },
+        "data": response_data
+    })))
+}
+
 #[utoipa::path(
     get,
     path = "/api/charts/market/{id}/probability",
@@ -145,6 +240,24 @@ async fn get_market_probability(
     Path(id): Path<String>,
     Query(params): Query<ChartQueryParams>,
 ) -> Result<Json<Value>, AppError> {
+    if let Some(market_id) = categorical_market_id(&db, &id).await? {
+        let chart_data = ChartService::new(db.read_pool().clone())
+            .get_outcome_chart_data(&market_id, &params.interval, params.from, params.to)
+            .await?;
+        return Ok(Json(json!({
+            "success": true,
+            "data": {
+                "outcomes": outcome_series(&chart_data, |o| &o.probability)
+            },
+            "meta": {
+                "symbol": id,
+                "marketType": "categorical",
+                "interval": params.interval,
+                "data_points": chart_data.total_volume.len()
+            }
+        })));
+    }
+
     let market_id: i64 = if let Ok(num_id) = id.parse::<i64>() {
         num_id
     } else {
@@ -152,7 +265,7 @@ async fn get_market_probability(
             r#"SELECT "blockchainMarketId" as blockchain_market_id FROM markets_extended WHERE id = $1 LIMIT 1"#,
             id
         )
-        .fetch_optional(db.pool())
+        .fetch_optional(db.read_pool())
         .await?
         .ok_or_else(|| AppError::NotFound("Market not found".to_string()))?;
 
@@ -161,7 +274,7 @@ async fn get_market_probability(
             .ok_or_else(|| AppError::BadRequest("Market has no blockchain ID".to_string()))?
     };
 
-    let chart_service = ChartService::new(db.pool().clone());
+    let chart_service = ChartService::new(db.read_pool().clone());
     let chart_data = chart_service
         .get_market_chart_data(market_id, &params.interval, params.from, params.to)
         .await?;
@@ -201,6 +314,25 @@ async fn get_market_volume(
     Path(id): Path<String>,
     Query(params): Query<ChartQueryParams>,
 ) -> Result<Json<Value>, AppError> {
+    if let Some(market_id) = categorical_market_id(&db, &id).await? {
+        let chart_data = ChartService::new(db.read_pool().clone())
+            .get_outcome_chart_data(&market_id, &params.interval, params.from, params.to)
+            .await?;
+        return Ok(Json(json!({
+            "success": true,
+            "data": {
+                "outcomes": outcome_series(&chart_data, |o| &o.volume),
+                "total": chart_data.total_volume
+            },
+            "meta": {
+                "symbol": id,
+                "marketType": "categorical",
+                "interval": params.interval,
+                "data_points": chart_data.total_volume.len()
+            }
+        })));
+    }
+
     let market_id: i64 = if let Ok(num_id) = id.parse::<i64>() {
         num_id
     } else {
@@ -208,7 +340,7 @@ async fn get_market_volume(
             r#"SELECT "blockchainMarketId" as blockchain_market_id FROM markets_extended WHERE id = $1 LIMIT 1"#,
             id
         )
-        .fetch_optional(db.pool())
+        .fetch_optional(db.read_pool())
         .await?
         .ok_or_else(|| AppError::NotFound("Market not found".to_string()))?;
 
@@ -217,7 +349,7 @@ async fn get_market_volume(
             .ok_or_else(|| AppError::BadRequest("Market has no blockchain ID".to_string()))?
     };
 
-    let chart_service = ChartService::new(db.pool().clone());
+    let chart_service = ChartService::new(db.read_pool().clone());
     let chart_data = chart_service
         .get_market_chart_data(market_id, &params.interval, params.from, params.to)
         .await?;
@@ -0,0 +1,63 @@
+use axum::{extract::State, http::StatusCode, response::Json, routing::get, Router};
+use serde_json::{json, Value};
+
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::services::health::{HealthService, HealthStatus};
+use crate::state::AppState;
+
+/// Probes for orchestrators, served outside `/api` at `/health/live` and `/health/ready`.
+pub fn create_health_router(state: AppState) -> Router {
+    Router::new()
+        .route("/live", get(liveness))
+        .route("/ready", get(readiness))
+        .with_state(state)
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses(
+        (status = 200, description = "Process is up and serving requests")
+    )
+)]
+pub async fn liveness() -> Json<Value> {
+    Json(json!({
+        "status": "ok",
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready; some checks may be degraded"),
+        (status = 503, description = "At least one check is unhealthy")
+    )
+)]
+pub async fn readiness(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+) -> (StatusCode, Json<Value>) {
+    let readiness = HealthService::new(db.pool().clone(), config)
+        .with_replica(db.replica_status())
+        .readiness()
+        .await;
+    let code = match readiness.status {
+        HealthStatus::Unhealthy => StatusCode::SERVICE_UNAVAILABLE,
+        HealthStatus::Ok | HealthStatus::Degraded => StatusCode::OK,
+    };
+    (
+        code,
+        Json(json!({
+            "status": readiness.status,
+            "checks": readiness.checks,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        })),
+    )
+}
@@ -3,32 +3,73 @@ use axum::{
     middleware,
     response::Json,
     routing::{get, post, put},
-    Router,
+    Extension, Router,
 };
+use serde::Deserialize;
 use serde_json::{json, Value};
-use tracing::info;
-use utoipa;
+use tracing::{info, warn};
+use utoipa::{self, ToSchema};
 
-use crate::{db::Database, error::AppError, models::MarketQueryParams};
+use std::sync::Arc;
+
+use crate::services::audit::AuditDetails;
+use crate::services::betting_service::{BetRejection, BettingService, QuoteBetParams};
+use crate::services::market_outcomes::{
+    binary_outcomes, MarketOutcomeService, MarketType, OutcomePool,
+};
+use crate::services::response_cache::{publish_invalidation, CachedEndpoint};
+use crate::services::yield_allocation::{AllocationPlan, YieldAllocator};
+use crate::services::yield_calculator::YieldData;
+use crate::{
+    config::Config, db::Database, error::AppError, models::MarketQueryParams, state::AppState,
+};
 
 use super::protocols::{
     create_blockchain_market_alias, get_blockchain_market, get_blockchain_status_alias,
     get_market_stats_by_identifier, place_bet_alias, update_market_image,
 };
-pub fn create_markets_router() -> Router<Database> {
+pub fn create_markets_router(state: AppState) -> Router<AppState> {
+    let cached = |endpoint| {
+        middleware::from_fn_with_state(
+            (state.cache.clone(), endpoint),
+            crate::middleware::response_cache::response_cache,
+        )
+    };
+
     let public_routes = Router::new()
-        .route("/", get(get_markets))
-        .route("/stats/platform", get(get_platform_stats))
+        .route("/", get(get_markets).layer(cached(CachedEndpoint::Markets)))
+        .route(
+            "/stats/platform",
+            get(get_platform_stats).layer(cached(CachedEndpoint::PlatformStats)),
+        )
         .route("/blockchain/status", get(get_blockchain_status_alias))
         .route("/blockchain/:marketId", get(get_blockchain_market))
-        .route("/:identifier/stats", get(get_market_stats_by_identifier))
+        .route(
+            "/:identifier/stats",
+            get(get_market_stats_by_identifier).layer(cached(CachedEndpoint::MarketStats)),
+        )
+        .route("/:identifier/allocation", get(get_market_allocation))
+        .route("/:identifier/quote", get(get_bet_quote))
         .route("/:identifier", get(get_market_by_identifier));
 
     let protected_routes = Router::new()
-        .route("/bet", post(place_bet_alias))
         .route("/create-blockchain", post(create_blockchain_market_alias))
         .route("/:identifier/image", put(update_market_image))
-        .layer(middleware::from_fn(
+        .route("/:identifier/outcomes", put(set_market_outcomes))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::audit::audit,
+        ))
+        // Added after the audit layer: bets are users' own actions, recorded as bets.
+        .route(
+            "/bet",
+            post(place_bet_alias).layer(middleware::from_fn_with_state(
+                state.clone(),
+                crate::middleware::idempotency::idempotency,
+            )),
+        )
+        .layer(middleware::from_fn_with_state(
+            state,
             crate::middleware::auth::require_api_key,
         ));
 
@@ -51,6 +92,7 @@ pub fn create_markets_router() -> Router<Database> {
 )]
 async fn get_markets(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
     Query(params): Query<MarketQueryParams>,
 ) -> Result<Json<Value>, AppError> {
     info!("Fetching markets with params: {:?}", params);
@@ -58,17 +100,32 @@ async fn get_markets(
     let markets = db.get_markets(&params).await?;
     let total = db.count_markets(&params).await?;
 
+    let allocator = YieldAllocator::new(db.pool().clone(), &config.yield_allocation);
+    let market_ids: Vec<String> = markets.iter().map(|m| m.id.clone()).collect();
+    let stored_plans = allocator
+        .stored_plans(&market_ids)
+        .await
+        .unwrap_or_default();
+    let current_plan = allocator.current_plan().await.ok();
+    let mut categorical_outcomes = MarketOutcomeService::new(db.pool().clone())
+        .outcomes_for_markets(&market_ids)
+        .await
+        .unwrap_or_default();
+
     let mut market_responses = Vec::new();
 
     for m in markets {
-        let yield_data = crate::services::yield_calculator::calculate_market_yield_data(
-            db.pool(),
-            &m.total_pool_size,
-            &m.volume,
-            &m.end_date,
-        )
-        .await
-        .ok();
+        let yield_data = stored_plans
+            .get(&m.id)
+            .or(current_plan.as_ref())
+            .map(|plan| {
+                crate::services::yield_calculator::calculate_market_yield_data(
+                    plan,
+                    &m.total_pool_size,
+                    &m.volume,
+                    &m.end_date,
+                )
+            });
 
         let mut market_json = json!({
             "id": m.id,
@@ -95,6 +152,19 @@ async fn get_markets(
             "countNo": m.count_no,
             "currentYield": m.current_yield.to_string(),
             "totalYieldEarned": m.total_yield_earned.to_string(),
+            "marketType": m.market_type,
+            "winningOutcome": m.winning_outcome,
+            "txHash": m.tx_hash,
+            "txStatus": m.tx_status,
+            "outcomes": categorical_outcomes.remove(&m.id).unwrap_or_else(|| {
+                binary_outcomes(
+                    &m.yes_pool_size,
+                    &m.no_pool_size,
+                    m.count_yes,
+                    m.count_no,
+                    m.probability,
+                )
+            }),
             "createdAt": m.created_at,
             "updatedAt": m.updated_at,
             "bets": [],
@@ -102,11 +172,7 @@ async fn get_markets(
         });
 
         if let Some(yd) = yield_data {
-            market_json["dailyYield"] = json!(yd.daily_yield);
-            market_json["totalYieldUntilEnd"] = json!(yd.total_yield_until_end);
-            market_json["daysRemaining"] = json!(yd.days_remaining);
-            market_json["bestProtocolApy"] = json!(yd.best_protocol_apy);
-            market_json["bestProtocolName"] = json!(yd.best_protocol_name);
+            apply_yield_data(&mut market_json, &yd);
         }
 
         market_responses.push(market_json);
@@ -138,6 +204,7 @@ async fn get_markets(
 )]
 async fn get_market_by_identifier(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
     Path(identifier): Path<String>,
 ) -> Result<Json<Value>, AppError> {
     info!("Fetching market by identifier: {}", identifier);
@@ -149,7 +216,9 @@ async fn get_market_by_identifier(
                "endDate" as end_date, "resolutionDate" as resolution_date, result, link, "imageUrl" as image_url,
                "totalPoolSize" as total_pool_size, "yesPoolSize" as yes_pool_size, "noPoolSize" as no_pool_size,
                "countYes" as count_yes, "countNo" as count_no, "currentYield" as current_yield,
-               "totalYieldEarned" as total_yield_earned, "createdAt" as created_at, "updatedAt" as updated_at
+               "totalYieldEarned" as total_yield_earned, "marketType" as market_type,
+               "winningOutcome" as winning_outcome, "txHash" as tx_hash, "txStatus" as tx_status,
+               "createdAt" as created_at, "updatedAt" as updated_at
         FROM markets_extended
         WHERE id = $1 OR "adjTicker" = $1 OR "blockchainMarketId"::text = $1
         LIMIT 1
@@ -160,14 +229,32 @@ async fn get_market_by_identifier(
     .await?
     .ok_or_else(|| AppError::NotFound("Market not found".to_string()))?;
 
-    let yield_data = crate::services::yield_calculator::calculate_market_yield_data(
-        db.pool(),
-        &market.total_pool_size,
-        &market.volume,
-        &market.end_date,
+    let yield_data = YieldAllocator::new(db.pool().clone(), &config.yield_allocation)
+        .plan_for_market(&market.id)
+        .await
+        .ok()
+        .map(|plan| {
+            crate::services::yield_calculator::calculate_market_yield_data(
+                &plan,
+                &market.total_pool_size,
+                &market.volume,
+                &market.end_date,
+            )
+        });
+
+    let outcomes = market_outcomes(
+        &db,
+        &market.id,
+        &market.market_type,
+        binary_outcomes(
+            &market.yes_pool_size,
+            &market.no_pool_size,
+            market.count_yes,
+            market.count_no,
+            market.probability,
+        ),
     )
-    .await
-    .ok();
+    .await?;
 
     let mut market_json = json!({
         "id": market.id,
@@ -194,6 +281,11 @@ async fn get_market_by_identifier(
         "countNo": market.count_no,
         "currentYield": market.current_yield.to_string(),
         "totalYieldEarned": market.total_yield_earned.to_string(),
+        "marketType": market.market_type,
+        "winningOutcome": market.winning_outcome,
+        "txHash": market.tx_hash,
+        "txStatus": market.tx_status,
+        "outcomes": outcomes,
         "createdAt": market.created_at,
         "updatedAt": market.updated_at,
         "bets": [],
@@ -201,11 +293,7 @@ async fn get_market_by_identifier(
     });
 
     if let Some(yd) = yield_data {
-        market_json["dailyYield"] = json!(yd.daily_yield);
-        market_json["totalYieldUntilEnd"] = json!(yd.total_yield_until_end);
-        market_json["daysRemaining"] = json!(yd.days_remaining);
-        market_json["bestProtocolApy"] = json!(yd.best_protocol_apy);
-        market_json["bestProtocolName"] = json!(yd.best_protocol_name);
+        apply_yield_data(&mut market_json, &yd);
     }
 
     Ok(Json(json!({
@@ -213,6 +301,271 @@ async fn get_market_by_identifier(
     })))
 }
 
+/// Outcomes of a market: stored outcomes for categorical markets, `binary` otherwise.
+async fn market_outcomes(
+    db: &Database,
+    market_id: &str,
+    market_type: &str,
+    binary: Vec<OutcomePool>,
+) -> Result<Vec<OutcomePool>, AppError> {
+    if market_type != MarketType::Categorical.as_str() {
+        return Ok(binary);
+    }
+    MarketOutcomeService::new(db.pool().clone())
+        .outcomes(market_id)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load outcomes: {}", e)))
+}
+
+/// Adds the projected yield fields to a market response.
+fn apply_yield_data(market_json: &mut Value, yd: &YieldData) {
+    market_json["dailyYield"] = json!(yd.daily_yield);
+    market_json["totalYieldUntilEnd"] = json!(yd.total_yield_until_end);
+    market_json["daysRemaining"] = json!(yd.days_remaining);
+    market_json["apy"] = json!(yd.apy);
+    market_json["allocationStrategy"] = json!(yd.strategy);
+    // Kept for existing clients: the protocol holding the largest share of the pool.
+    market_json["bestProtocolApy"] = json!(yd.primary_protocol_apy);
+    market_json["bestProtocolName"] = json!(yd.primary_protocol_name);
+}
+
+fn allocation_json(plan: &AllocationPlan, pool_size: f64) -> Value {
+    let slices: Vec<Value> = plan
+        .slices
+        .iter()
+        .map(|slice| {
+            json!({
+                "protocolId": slice.protocol_id,
+                "protocol": slice.protocol,
+                "apy": slice.apy,
+                "weight": slice.weight,
+                "amount": pool_size * slice.weight,
+            })
+        })
+        .collect();
+
+    json!({
+        "strategy": plan.strategy,
+        "apy": plan.blended_apy(),
+        "idleWeight": plan.idle_weight(),
+        "idleAmount": pool_size * plan.idle_weight(),
+        "persisted": plan.updated_at.is_some(),
+        "updatedAt": plan.updated_at,
+        "slices": slices,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/markets/{identifier}/allocation",
+    tag = "markets",
+    params(
+        ("identifier" = String, Path, description = "Market identifier (UUID, adjTicker, or blockchain ID)")
+    ),
+    responses(
+        (status = 200, description = "How the market pool is allocated across yield protocols"),
+        (status = 404, description = "Market not found")
+    )
+)]
+async fn get_market_allocation(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(identifier): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    info!("Fetching allocation for market: {}", identifier);
+
+    let market = sqlx::query!(
+        r#"
+        SELECT id, "totalPoolSize" as total_pool_size
+        FROM markets_extended
+        WHERE id = $1 OR "adjTicker" = $1 OR "blockchainMarketId"::text = $1
+        LIMIT 1
+        "#,
+        identifier
+    )
+    .fetch_optional(db.pool())
+    .await?
+    .ok_or_else(|| AppError::NotFound("Market not found".to_string()))?;
+
+    let plan = YieldAllocator::new(db.pool().clone(), &config.yield_allocation)
+        .plan_for_market(&market.id)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load allocation: {}", e)))?;
+
+    let pool_size = market
+        .total_pool_size
+        .to_string()
+        .parse::<f64>()
+        .unwrap_or(0.0);
+    let mut data = allocation_json(&plan, pool_size);
+    data["marketId"] = json!(market.id);
+    data["totalPoolSize"] = json!(market.total_pool_size.to_string());
+
+    Ok(Json(json!({
+        "success": true,
+        "data": data
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuoteQuery {
+    /// `yes` or `no`; binary markets only.
+    pub position: Option<String>,
+    /// Outcome index; categorical markets only.
+    pub outcome: Option<i32>,
+    pub amount: String,
+    /// Bettor the quote is issued to.
+    #[serde(rename = "userAddress")]
+    pub user_address: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/markets/{identifier}/quote",
+    tag = "markets",
+    params(
+        ("identifier" = String, Path, description = "Market identifier (UUID, adjTicker, or blockchain ID)"),
+        ("position" = Option<String>, Query, description = "yes or no (binary markets)"),
+        ("outcome" = Option<i32>, Query, description = "Outcome index (categorical markets)"),
+        ("amount" = String, Query, description = "Stake in base units"),
+        ("userAddress" = String, Query, description = "Address of the bettor the quote is issued to")
+    ),
+    responses(
+        (status = 200, description = "Odds before and after the bet, payout, fee, projected yield and a signed quote ID"),
+        (status = 400, description = "Invalid side or amount, or market not open for bets"),
+        (status = 404, description = "Market not found")
+    )
+)]
+async fn get_bet_quote(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(identifier): Path<String>,
+    Query(params): Query<QuoteQuery>,
+) -> Result<Json<Value>, AppError> {
+    info!("Quoting bet on market: {}", identifier);
+
+    let position = match params.position.as_deref().map(str::to_lowercase).as_deref() {
+        None => None,
+        Some("yes") | Some("true") => Some(true),
+        Some("no") | Some("false") => Some(false),
+        Some(other) => {
+            return Err(AppError::BadRequest(format!(
+                "Invalid position '{}'; use yes or no",
+                other
+            )))
+        }
+    };
+    if position.is_some() == params.outcome.is_some() {
+        return Err(AppError::BadRequest(
+            "Provide either position (binary markets) or outcome (categorical markets)".to_string(),
+        ));
+    }
+
+    let market_id = sqlx::query_scalar!(
+        r#"
+        SELECT id
+        FROM markets_extended
+        WHERE id = $1 OR "adjTicker" = $1 OR "blockchainMarketId"::text = $1
+        LIMIT 1
+        "#,
+        identifier
+    )
+    .fetch_optional(db.pool())
+    .await?
+    .ok_or_else(|| AppError::NotFound("Market not found".to_string()))?;
+
+    let betting_service = BettingService::new(db.pool().clone(), &config)
+        .map_err(|e| AppError::Internal(format!("Failed to initialize betting service: {}", e)))?;
+
+    let quote = betting_service
+        .quote_bet(QuoteBetParams {
+            market_identifier: market_id,
+            user_address: params.user_address,
+            position,
+            outcome: params.outcome,
+            amount: params.amount,
+        })
+        .await
+        .map_err(|e| match e.downcast_ref::<BetRejection>() {
+            Some(rejection) => AppError::Rejected {
+                code: rejection.code(),
+                message: rejection.to_string(),
+            },
+            None => AppError::BadRequest(e.to_string()),
+        })?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": quote
+    })))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetOutcomesRequest {
+    /// Outcome labels in index order (2 to 32).
+    outcomes: Vec<String>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/markets/{identifier}/outcomes",
+    tag = "markets",
+    params(
+        ("identifier" = String, Path, description = "Market identifier (UUID, adjTicker, or blockchain ID)")
+    ),
+    request_body = SetOutcomesRequest,
+    responses(
+        (status = 200, description = "Market is now categorical with the given outcomes"),
+        (status = 400, description = "Invalid outcomes, or the market already has bets"),
+        (status = 404, description = "Market not found")
+    )
+)]
+async fn set_market_outcomes(
+    State(db): State<Database>,
+    Path(identifier): Path<String>,
+    Json(payload): Json<SetOutcomesRequest>,
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
+    info!("Setting outcomes for market: {}", identifier);
+
+    let market_id = sqlx::query_scalar!(
+        r#"
+        SELECT id
+        FROM markets_extended
+        WHERE id = $1 OR "adjTicker" = $1 OR "blockchainMarketId"::text = $1
+        LIMIT 1
+        "#,
+        identifier
+    )
+    .fetch_optional(db.pool())
+    .await?
+    .ok_or_else(|| AppError::NotFound("Market not found".to_string()))?;
+
+    let outcomes = MarketOutcomeService::new(db.pool().clone())
+        .define_outcomes(&market_id, &payload.outcomes)
+        .await
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    if let Err(e) = publish_invalidation(db.pool(), None).await {
+        warn!("Failed to publish cache invalidation: {}", e);
+    }
+
+    let audit = AuditDetails::new("market.outcomes.set")
+        .target("market", &market_id)
+        .after(&outcomes);
+
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "data": {
+                "marketId": market_id,
+                "marketType": MarketType::Categorical,
+                "outcomes": outcomes
+            }
+        })),
+    ))
+}
+
 #[allow(dead_code)]
 async fn get_market_by_id(
     State(db): State<Database>,
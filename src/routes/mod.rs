@@ -3,31 +3,40 @@ use serde_json::{json, Value};
 
 use crate::db::Database;
 use crate::error::AppError;
+use crate::state::AppState;
 
+pub mod admin;
 mod auth;
 pub mod bets;
 mod blockchain;
 pub mod charts;
+pub mod health;
 pub mod markets;
 pub mod prices;
 pub mod protocols;
 pub mod sync;
+pub mod transactions;
 pub mod yields;
 
-pub fn create_router(db: Database) -> Router {
+pub fn create_router(state: AppState) -> Router {
     Router::new()
         .route("/", get(api_info))
         .route("/health", get(health_check))
-        .nest("/auth", auth::create_auth_router())
-        .nest("/markets", markets::create_markets_router())
-        .nest("/bets", bets::create_bets_router())
+        .nest("/auth", auth::create_auth_router(state.clone()))
+        .nest("/markets", markets::create_markets_router(state.clone()))
+        .nest("/bets", bets::create_bets_router(state.clone()))
         .nest("/charts", charts::create_charts_router())
-        .nest("/sync", sync::create_sync_router())
-        .nest("/protocols", protocols::create_protocols_router())
-        .nest("/yields", yields::create_yields_router())
+        .nest("/sync", sync::create_sync_router(state.clone()))
+        .nest(
+            "/protocols",
+            protocols::create_protocols_router(state.clone()),
+        )
+        .nest("/yields", yields::create_yields_router(state.clone()))
         .nest("/prices", prices::create_prices_router())
-        .merge(blockchain::create_blockchain_router(db.clone()))
-        .with_state(db)
+        .nest("/transactions", transactions::create_transactions_router())
+        .nest("/admin", admin::create_admin_router(state.clone()))
+        .merge(blockchain::create_blockchain_router(state.clone()))
+        .with_state(state)
 }
 
 async fn api_info() -> Result<Json<Value>, AppError> {
@@ -1,54 +1,171 @@
-use axum::{extract::State, response::Json, routing::get, Router};
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
 use serde_json::{json, Value};
 use tracing::{error, info};
 
+use std::sync::Arc;
+
+use crate::config::Config;
 use crate::db::Database;
 use crate::error::AppError;
-use crate::services::chainlink_price_feed::ChainlinkPriceFeed;
+use crate::services::chainlink_price_feed::{ChainlinkPriceFeed, PriceData, PriceMethod};
+use crate::state::AppState;
 
-pub fn create_prices_router() -> Router<Database> {
+pub fn create_prices_router() -> Router<AppState> {
     Router::new()
-        .route("/apt-usd", get(get_apt_usd_price))
-        .route("/apt-usd/refresh", get(refresh_apt_usd_price))
+        .route("/", get(get_all_prices))
+        .route("/sources", get(get_price_sources))
+        .route("/:pair", get(get_pair_price))
+        .route("/:pair/refresh", get(refresh_pair_price))
+        .route("/:pair/history", get(get_price_history))
+        .route("/:pair/twap", get(get_pair_twap))
+        .route("/:pair/convert", get(convert_amount))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PriceHistoryQuery {
+    /// Unix seconds; defaults to 24 hours before `to`
+    pub from: Option<i64>,
+    /// Unix seconds; defaults to now
+    pub to: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwapQuery {
+    /// Window in seconds; defaults to `price_feed.twap_window_secs`
+    pub window: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConvertQuery {
+    pub amount: f64,
+    /// `to_usd` (default) or `from_usd`
+    pub direction: Option<String>,
+    /// `spot` or `twap`; defaults to `price_feed.use_twap_for_conversion`
+    pub method: Option<PriceMethod>,
+}
+
+fn price_feed(config: &Config, db: &Database) -> Result<ChainlinkPriceFeed, AppError> {
+    ChainlinkPriceFeed::new(&config.price_feed)
+        .map(|feed| feed.with_history(db.pool().clone()))
+        .map_err(|e| {
+            error!("Failed to initialize price feed: {}", e);
+            AppError::InternalError(format!("Price feed initialization failed: {}", e))
+        })
+}
+
+fn price_json(pair: &str, price_data: &PriceData) -> Value {
+    json!({
+        "price": price_data.price,
+        "symbol": pair,
+        "source": "Multi-Exchange Aggregator",
+        "source_count": price_data.round_id,
+        "decimals": price_data.decimals,
+        "timestamp": price_data.timestamp,
+        "aggregation_method": "median",
+        "status": if price_data.degraded { "degraded" } else { "ok" },
+        "degraded": price_data.degraded,
+    })
 }
 
 #[utoipa::path(
     get,
-    path = "/api/prices/apt-usd",
+    path = "/api/prices",
     tag = "prices",
     responses(
-        (status = 200, description = "Successfully retrieved APT/USD price", body = Value),
-        (status = 500, description = "Failed to fetch price")
+        (status = 200, description = "Latest price for every configured pair", body = Value)
     )
 )]
-async fn get_apt_usd_price(State(_db): State<Database>) -> Result<Json<Value>, AppError> {
-    info!("Fetching APT/USD price from Chainlink");
+async fn get_all_prices(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+) -> Result<Json<Value>, AppError> {
+    let feed = price_feed(&config, &db)?;
+
+    let mut prices = Vec::new();
+    for asset in feed.pairs() {
+        match feed.get_price_data(asset).await {
+            Ok(price_data) => prices.push(price_json(&asset.pair(), &price_data)),
+            Err(e) => {
+                error!("Failed to fetch {} price: {}", asset.pair(), e);
+                prices.push(json!({ "symbol": asset.pair(), "error": e.to_string() }));
+            }
+        }
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "data": prices
+    })))
+}
 
-    let price_feed = ChainlinkPriceFeed::new().map_err(|e| {
+#[utoipa::path(
+    get,
+    path = "/api/prices/sources",
+    tag = "prices",
+    responses(
+        (status = 200, description = "Health and circuit breaker state of each price source", body = Value)
+    )
+)]
+async fn get_price_sources(State(config): State<Arc<Config>>) -> Result<Json<Value>, AppError> {
+    let feed = ChainlinkPriceFeed::new(&config.price_feed).map_err(|e| {
         error!("Failed to initialize price feed: {}", e);
         AppError::InternalError(format!("Price feed initialization failed: {}", e))
     })?;
 
-    match price_feed.get_apt_usd_price().await {
-        Ok(price) => {
-            let cached_data = price_feed.get_cached_price().await;
-
-            Ok(Json(json!({
-                "success": true,
-                "data": {
-                    "price": price,
-                    "symbol": "APT/USD",
-                    "source": "Multi-Exchange Aggregator",
-                    "source_count": cached_data.as_ref().map(|d| d.round_id).unwrap_or(0),
-                    "decimals": cached_data.as_ref().map(|d| d.decimals).unwrap_or(8),
-                    "timestamp": cached_data.as_ref().map(|d| d.timestamp).unwrap_or(chrono::Utc::now().timestamp()),
-                    "aggregation_method": "median",
-                },
-                "formatted": format!("${:.2}", price)
-            })))
+    let sources = feed.source_health();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "min_sources": feed.min_sources(),
+            "max_deviation_pct": config.price_feed.max_deviation_pct,
+            "circuit_breaker": {
+                "failure_threshold": config.price_feed.circuit_breaker_threshold,
+                "cooldown_secs": config.price_feed.circuit_breaker_cooldown_secs,
+            },
+            "sources": sources,
         }
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/prices/{pair}",
+    tag = "prices",
+    params(("pair" = String, Path, description = "Pair such as apt-usd or btc-usd")),
+    responses(
+        (status = 200, description = "Successfully retrieved price", body = Value),
+        (status = 400, description = "Unknown or disabled pair"),
+        (status = 500, description = "Failed to fetch price")
+    )
+)]
+async fn get_pair_price(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(pair): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let feed = price_feed(&config, &db)?;
+    let asset = feed
+        .configured_asset(&pair)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    info!("Fetching {} price", asset.pair());
+
+    match feed.get_price_data(asset).await {
+        Ok(price_data) => Ok(Json(json!({
+            "success": true,
+            "data": price_json(&asset.pair(), &price_data),
+            "formatted": format!("${:.2}", price_data.price)
+        }))),
         Err(e) => {
-            error!("Failed to fetch APT/USD price: {}", e);
+            error!("Failed to fetch {} price: {}", asset.pair(), e);
             Err(AppError::InternalError(format!(
                 "Failed to fetch price: {}",
                 e
@@ -59,38 +176,36 @@ async fn get_apt_usd_price(State(_db): State<Database>) -> Result<Json<Value>, A
 
 #[utoipa::path(
     get,
-    path = "/api/prices/apt-usd/refresh",
+    path = "/api/prices/{pair}/refresh",
     tag = "prices",
+    params(("pair" = String, Path, description = "Pair such as apt-usd or btc-usd")),
     responses(
-        (status = 200, description = "Successfully refreshed APT/USD price", body = Value),
+        (status = 200, description = "Successfully refreshed price", body = Value),
+        (status = 400, description = "Unknown or disabled pair"),
         (status = 500, description = "Failed to refresh price")
     )
 )]
-async fn refresh_apt_usd_price(State(_db): State<Database>) -> Result<Json<Value>, AppError> {
-    info!("Force refreshing APT/USD price from Chainlink");
+async fn refresh_pair_price(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(pair): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let feed = price_feed(&config, &db)?;
+    let asset = feed
+        .configured_asset(&pair)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
 
-    let price_feed = ChainlinkPriceFeed::new().map_err(|e| {
-        error!("Failed to initialize price feed: {}", e);
-        AppError::InternalError(format!("Price feed initialization failed: {}", e))
-    })?;
+    info!("Force refreshing {} price", asset.pair());
 
-    match price_feed.refresh_price().await {
+    match feed.refresh(asset).await {
         Ok(price_data) => Ok(Json(json!({
             "success": true,
-            "data": {
-                "price": price_data.price,
-                "symbol": "APT/USD",
-                "source": "Multi-Exchange Aggregator",
-                "source_count": price_data.round_id,
-                "decimals": price_data.decimals,
-                "timestamp": price_data.timestamp,
-                "aggregation_method": "median",
-            },
+            "data": price_json(&asset.pair(), &price_data),
             "formatted": format!("${:.2}", price_data.price),
             "message": "Price refreshed successfully"
         }))),
         Err(e) => {
-            error!("Failed to refresh APT/USD price: {}", e);
+            error!("Failed to refresh {} price: {}", asset.pair(), e);
             Err(AppError::InternalError(format!(
                 "Failed to refresh price: {}",
                 e
@@ -98,3 +213,171 @@ async fn refresh_apt_usd_price(State(_db): State<Database>) -> Result<Json<Value
         }
     }
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/prices/{pair}/history",
+    tag = "prices",
+    params(
+        ("pair" = String, Path, description = "Pair such as apt-usd or btc-usd"),
+        ("from" = Option<i64>, Query, description = "Start (unix seconds), default 24h ago"),
+        ("to" = Option<i64>, Query, description = "End (unix seconds), default now"),
+        ("limit" = Option<i64>, Query, description = "Max samples (default 500, max 5000)")
+    ),
+    responses(
+        (status = 200, description = "Recorded price samples, oldest first", body = Value),
+        (status = 400, description = "Invalid pair or range")
+    )
+)]
+async fn get_price_history(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(pair): Path<String>,
+    Query(params): Query<PriceHistoryQuery>,
+) -> Result<Json<Value>, AppError> {
+    let feed = price_feed(&config, &db)?;
+    let asset = feed
+        .configured_asset(&pair)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    let to = params.to.unwrap_or_else(|| chrono::Utc::now().timestamp());
+    let from = params.from.unwrap_or(to - 24 * 60 * 60);
+    if from > to {
+        return Err(AppError::BadRequest(
+            "'from' must not be after 'to'".to_string(),
+        ));
+    }
+    let limit = params.limit.unwrap_or(500).clamp(1, 5000);
+
+    let points = feed
+        .history(asset, from, to, limit)
+        .await
+        .map_err(|e| AppError::InternalError(format!("Failed to load price history: {}", e)))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "symbol": asset.pair(),
+            "from": from,
+            "to": to,
+            "count": points.len(),
+            "points": points,
+        }
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/prices/{pair}/twap",
+    tag = "prices",
+    params(
+        ("pair" = String, Path, description = "Pair such as apt-usd or btc-usd"),
+        ("window" = Option<i64>, Query, description = "Window in seconds")
+    ),
+    responses(
+        (status = 200, description = "Time-weighted average price", body = Value),
+        (status = 400, description = "Invalid pair or window"),
+        (status = 404, description = "No samples in the window")
+    )
+)]
+async fn get_pair_twap(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(pair): Path<String>,
+    Query(params): Query<TwapQuery>,
+) -> Result<Json<Value>, AppError> {
+    let feed = price_feed(&config, &db)?;
+    let asset = feed
+        .configured_asset(&pair)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    let window = params.window.unwrap_or(feed.twap_window_secs());
+    if window <= 0 {
+        return Err(AppError::BadRequest(
+            "window must be greater than 0".to_string(),
+        ));
+    }
+
+    let twap = feed
+        .twap(asset, window)
+        .await
+        .map_err(|e| AppError::NotFound(e.to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": twap,
+        "formatted": format!("${:.2}", twap.twap)
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/prices/{pair}/convert",
+    tag = "prices",
+    params(
+        ("pair" = String, Path, description = "Pair such as apt-usd or btc-usd"),
+        ("amount" = f64, Query, description = "Amount to convert"),
+        ("direction" = Option<String>, Query, description = "to_usd (default) or from_usd"),
+        ("method" = Option<String>, Query, description = "spot or twap")
+    ),
+    responses(
+        (status = 200, description = "Converted amount", body = Value),
+        (status = 400, description = "Invalid parameters")
+    )
+)]
+async fn convert_amount(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(pair): Path<String>,
+    Query(params): Query<ConvertQuery>,
+) -> Result<Json<Value>, AppError> {
+    let feed = price_feed(&config, &db)?;
+    let asset = feed
+        .configured_asset(&pair)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    let method = params
+        .method
+        .unwrap_or(if config.price_feed.use_twap_for_conversion {
+            PriceMethod::Twap
+        } else {
+            PriceMethod::Spot
+        });
+    let direction = params.direction.as_deref().unwrap_or("to_usd");
+
+    let price = feed
+        .price_for_conversion(asset.symbol, method)
+        .await
+        .map_err(|e| AppError::InternalError(format!("Failed to get price: {}", e)))?;
+
+    let result = match direction {
+        "to_usd" => params.amount * price,
+        "from_usd" => {
+            if price == 0.0 {
+                return Err(AppError::InternalError("Invalid price: 0".to_string()));
+            }
+            params.amount / price
+        }
+        other => {
+            return Err(AppError::BadRequest(format!(
+                "Invalid direction '{}', expected to_usd or from_usd",
+                other
+            )))
+        }
+    };
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "symbol": asset.pair(),
+            "amount": params.amount,
+            "direction": direction,
+            "method": match method {
+                PriceMethod::Spot => "spot",
+                PriceMethod::Twap => "twap",
+            },
+            "price": price,
+            "result": result,
+        }
+    })))
+}
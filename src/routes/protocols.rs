@@ -1,25 +1,95 @@
 use axum::{
     extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue},
     middleware,
-    response::Json,
+    response::{AppendHeaders, Json},
     routing::{get, post},
-    Router,
+    Extension, Router,
 };
 use serde_json::{json, Value};
-use tracing::info;
+use tracing::{error, info, warn};
 use utoipa;
 
-use crate::{db::Database, error::AppError};
-pub fn create_protocols_router() -> Router<Database> {
+use std::sync::Arc;
+
+use crate::services::audit::AuditDetails;
+use crate::services::chain_transactions::{
+    ChainTransactionService, NewChainTransaction, TxKind, ROW_PENDING,
+};
+use crate::services::response_cache::{publish_invalidation, CachedMarket};
+use crate::services::signer::SignerPool;
+use crate::services::yield_service::{ApyInterval, ApySeries};
+use crate::{config::Config, db::Database, error::AppError, state::AppState};
+
+/// A user's write, with the cookie that keeps their next reads on the primary.
+pub(super) type WriteResponse = (
+    AppendHeaders<Option<(HeaderName, HeaderValue)>>,
+    Json<Value>,
+);
+
+#[derive(Debug, Deserialize)]
+pub struct ApyHistoryQuery {
+    /// Protocol id or name; only used by `/api/yields/apy/history`
+    pub protocol: Option<String>,
+    /// Unix seconds; defaults to 7 days before `to`
+    pub from: Option<i64>,
+    /// Unix seconds; defaults to now
+    pub to: Option<i64>,
+    /// `raw`, `hour` (default), `day` or `week`
+    pub interval: Option<ApyInterval>,
+}
+
+/// Loads APY series for `protocol` (or all active protocols) over the query's range.
+pub(super) async fn load_apy_history(
+    db: &Database,
+    config: &Config,
+    protocol: Option<&str>,
+    params: &ApyHistoryQuery,
+) -> Result<(i64, i64, ApyInterval, Vec<ApySeries>), AppError> {
+    let to = params.to.unwrap_or_else(|| chrono::Utc::now().timestamp());
+    let from = params.from.unwrap_or(to - 7 * 24 * 60 * 60);
+    if from > to {
+        return Err(AppError::BadRequest(
+            "'from' must not be after 'to'".to_string(),
+        ));
+    }
+    let interval = params.interval.unwrap_or(ApyInterval::Hour);
+
+    let to_time = chrono::DateTime::from_timestamp(to, 0)
+        .ok_or_else(|| AppError::BadRequest("Invalid 'to' timestamp".to_string()))?
+        .naive_utc();
+    let from_time = chrono::DateTime::from_timestamp(from, 0)
+        .ok_or_else(|| AppError::BadRequest("Invalid 'from' timestamp".to_string()))?
+        .naive_utc();
+
+    let yield_service = crate::services::YieldService::new(
+        db.pool().clone(),
+        &config.chain,
+        &config.yield_allocation,
+    );
+    let series = yield_service
+        .get_apy_time_series(protocol, from_time, to_time, interval)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load APY history: {}", e)))?;
+
+    Ok((from, to, interval, series))
+}
+pub fn create_protocols_router(state: AppState) -> Router<AppState> {
     let public_routes = Router::new()
         .route("/", get(get_protocols))
         .route("/:id", get(get_protocol_by_id))
-        .route("/:id/yields", get(get_protocol_yields));
+        .route("/:id/yields", get(get_protocol_yields))
+        .route("/:id/apy-history", get(get_protocol_apy_history));
 
     let protected_routes = Router::new()
         .route("/:name/apy/update", post(update_protocol_apy))
         .route("/apy/update-all", post(update_all_protocols_apy))
-        .layer(middleware::from_fn(
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::audit::audit,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state,
             crate::middleware::auth::require_api_key,
         ));
 
@@ -122,34 +192,106 @@ async fn get_protocol_yields(
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/protocols/{id}/apy-history",
+    tag = "protocols",
+    params(
+        ("id" = String, Path, description = "Protocol ID or name"),
+        ("from" = Option<i64>, Query, description = "Start (unix seconds), default 7 days ago"),
+        ("to" = Option<i64>, Query, description = "End (unix seconds), default now"),
+        ("interval" = Option<String>, Query, description = "raw, hour (default), day or week")
+    ),
+    responses(
+        (status = 200, description = "APY time series for the protocol"),
+        (status = 400, description = "Invalid range"),
+        (status = 404, description = "Protocol not found")
+    )
+)]
+async fn get_protocol_apy_history(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(id): Path<String>,
+    Query(params): Query<ApyHistoryQuery>,
+) -> Result<Json<Value>, AppError> {
+    info!("Fetching APY history for protocol: {}", id);
+
+    let (from, to, interval, series) = load_apy_history(&db, &config, Some(&id), &params).await?;
+    let series = series
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::NotFound("Protocol not found".to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "protocolId": series.protocol_id,
+            "protocol": series.protocol,
+            "displayName": series.display_name,
+            "from": from,
+            "to": to,
+            "interval": interval,
+            "count": series.points.len(),
+            "points": series.points,
+        }
+    })))
+}
+
 async fn update_protocol_apy(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
     Path(name): Path<String>,
-) -> Result<Json<Value>, AppError> {
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
     info!("Updating APY from blockchain for protocol: {}", name);
 
-    let yield_service = crate::services::YieldService::new(db.pool().clone());
+    let previous_apy = sqlx::query_scalar!(
+        r#"SELECT "baseApy" as "base_apy" FROM protocols WHERE name = $1"#,
+        name
+    )
+    .fetch_optional(db.pool())
+    .await?;
+
+    let yield_service = crate::services::YieldService::new(
+        db.pool().clone(),
+        &config.chain,
+        &config.yield_allocation,
+    );
 
     let updated_apy = yield_service
         .update_protocol_apy_from_blockchain(&name)
         .await
         .map_err(|e| AppError::Internal(format!("Failed to update APY: {}", e)))?;
 
-    Ok(Json(json!({
-        "success": true,
-        "message": format!("Successfully updated {} APY from blockchain", name),
-        "data": {
-            "protocol": name,
-            "apy": updated_apy,
-            "updated_at": chrono::Utc::now()
-        }
-    })))
+    let audit = AuditDetails::new("protocol.apy.update")
+        .target("protocol", &name)
+        .before(json!({ "apy": previous_apy }))
+        .after(json!({ "apy": updated_apy }));
+
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "message": format!("Successfully updated {} APY from blockchain", name),
+            "data": {
+                "protocol": name,
+                "apy": updated_apy,
+                "updated_at": chrono::Utc::now()
+            }
+        })),
+    ))
 }
 
-async fn update_all_protocols_apy(State(db): State<Database>) -> Result<Json<Value>, AppError> {
+async fn update_all_protocols_apy(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
     info!("Updating APY from blockchain for all protocols");
 
-    let yield_service = crate::services::YieldService::new(db.pool().clone());
+    let yield_service = crate::services::YieldService::new(
+        db.pool().clone(),
+        &config.chain,
+        &config.yield_allocation,
+    );
 
     let results = yield_service
         .update_all_protocols_apy()
@@ -165,19 +307,23 @@ async fn update_all_protocols_apy(State(db): State<Database>) -> Result<Json<Val
             })
         })
         .collect();
+    let audit = AuditDetails::new("protocols.apy.update_all").after(&protocols);
 
-    Ok(Json(json!({
-        "success": true,
-        "message": format!("Successfully updated {} protocols from blockchain", results.len()),
-        "data": protocols,
-        "updated_at": chrono::Utc::now()
-    })))
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "message": format!("Successfully updated {} protocols from blockchain", results.len()),
+            "data": protocols,
+            "updated_at": chrono::Utc::now()
+        })),
+    ))
 }
 
 pub(super) async fn get_market_stats_by_identifier(
     State(db): State<Database>,
     Path(identifier): Path<String>,
-) -> Result<Json<Value>, AppError> {
+) -> Result<(Extension<CachedMarket>, Json<Value>), AppError> {
     info!("Fetching market stats by identifier: {}", identifier);
 
     let market = sqlx::query!(
@@ -190,10 +336,13 @@ pub(super) async fn get_market_stats_by_identifier(
 
     if let Some(blockchain_id) = market.blockchainMarketId {
         let stats = db.get_market_stats(blockchain_id).await?;
-        Ok(Json(json!({
-            "success": true,
-            "data": stats
-        })))
+        Ok((
+            Extension(CachedMarket(blockchain_id)),
+            Json(json!({
+                "success": true,
+                "data": stats
+            })),
+        ))
     } else {
         Err(AppError::NotFound("Market not on blockchain".to_string()))
     }
@@ -241,47 +390,69 @@ pub(super) async fn update_market_image(
     State(db): State<Database>,
     Path(identifier): Path<String>,
     Json(payload): Json<Value>,
-) -> Result<Json<Value>, AppError> {
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
     info!("Updating market image for: {}", identifier);
 
     let image_url = payload["imageUrl"].as_str();
 
+    let existing = sqlx::query!(
+        r#"SELECT id, "imageUrl" as image_url FROM markets_extended WHERE id = $1 OR "adjTicker" = $1"#,
+        identifier
+    )
+    .fetch_optional(db.pool())
+    .await?
+    .ok_or_else(|| AppError::NotFound("Market not found".to_string()))?;
+
     let result = sqlx::query!(
-        r#"UPDATE markets_extended SET "imageUrl" = $1, "updatedAt" = NOW() WHERE id = $1 OR "adjTicker" = $2 RETURNING id, "marketId" as market_id, "adjTicker" as adj_ticker, "imageUrl" as image_url, "updatedAt" as updated_at"#,
+        r#"UPDATE markets_extended SET "imageUrl" = $1, "updatedAt" = NOW() WHERE id = $2 RETURNING id, "marketId" as market_id, "adjTicker" as adj_ticker, "imageUrl" as image_url, "updatedAt" as updated_at"#,
         image_url,
-        identifier
+        existing.id
     )
     .fetch_optional(db.pool())
     .await?
     .ok_or_else(|| AppError::NotFound("Market not found".to_string()))?;
 
-    Ok(Json(json!({
-        "message": "Market image updated successfully",
-        "data": {
-            "id": result.id,
-            "marketId": result.market_id,
-            "adjTicker": result.adj_ticker,
-            "imageUrl": result.image_url,
-            "updatedAt": result.updated_at
-        }
-    })))
+    if let Err(e) = publish_invalidation(db.pool(), None).await {
+        warn!("Failed to publish cache invalidation: {}", e);
+    }
+
+    let audit = AuditDetails::new("market.image.update")
+        .target("market", &result.id)
+        .before(json!({ "imageUrl": existing.image_url }))
+        .after(json!({ "imageUrl": result.image_url }));
+
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "message": "Market image updated successfully",
+            "data": {
+                "id": result.id,
+                "marketId": result.market_id,
+                "adjTicker": result.adj_ticker,
+                "imageUrl": result.image_url,
+                "updatedAt": result.updated_at
+            }
+        })),
+    ))
 }
 
 pub(super) async fn place_bet_alias(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    State(signers): State<Arc<SignerPool>>,
     Json(payload): Json<Value>,
-) -> Result<Json<Value>, AppError> {
+) -> Result<WriteResponse, AppError> {
     let bet_request: PlaceBetRequest = serde_json::from_value(payload)
         .map_err(|e| AppError::BadRequest(format!("Invalid bet request: {}", e)))?;
-    place_bet(State(db), Json(bet_request)).await
+    place_bet(State(db), State(config), State(signers), Json(bet_request)).await
 }
 
 pub(super) async fn get_blockchain_status_alias(
-    State(_db): State<Database>,
+    State(config): State<Arc<Config>>,
 ) -> Result<Json<Value>, AppError> {
     use crate::services::aptos_contract::AptosContractService;
 
-    let contract_service = AptosContractService::new().map_err(|e| {
+    let contract_service = AptosContractService::new(&config.chain).map_err(|e| {
         AppError::Internal(format!("Failed to initialize blockchain service: {}", e))
     })?;
 
@@ -294,9 +465,11 @@ pub(super) async fn get_blockchain_status_alias(
 }
 
 pub(super) async fn create_blockchain_market_alias(
-    State(_db): State<Database>,
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    State(signers): State<Arc<SignerPool>>,
     Json(payload): Json<Value>,
-) -> Result<Json<Value>, AppError> {
+) -> Result<(Extension<AuditDetails>, Json<Value>), AppError> {
     use crate::services::aptos_contract::{AptosContractService, CreateMarketParams};
 
     let question = payload["question"]
@@ -309,37 +482,65 @@ pub(super) async fn create_blockchain_market_alias(
         .as_u64()
         .ok_or_else(|| AppError::BadRequest("Duration is required".to_string()))?;
 
-    let contract_service = AptosContractService::new().map_err(|e| {
-        AppError::Internal(format!("Failed to initialize blockchain service: {}", e))
-    })?;
+    let contract_service = AptosContractService::new(&config.chain)
+        .map_err(|e| AppError::Internal(format!("Failed to initialize blockchain service: {}", e)))?
+        .with_signers(signers);
 
-    let protocol_selector_addr = std::env::var("APTOS_PROTOCOL_SELECTOR_ADDR")
-        .unwrap_or_else(|_| contract_service.module_address.clone());
+    let protocol_selector_addr = config
+        .chain
+        .protocol_selector_addr()
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .to_string();
 
     let result = contract_service
         .create_market(CreateMarketParams {
             question: question.to_string(),
             description: description.to_string(),
             duration_seconds: duration,
-            token_type: std::env::var("APTOS_TOKEN_TYPE")
-                .unwrap_or_else(|_| "0x1::aptos_coin::AptosCoin".to_string()),
+            token_type: config.chain.token_type.clone(),
             protocol_selector_addr,
         })
         .await
         .map_err(|e| AppError::Internal(format!("Failed to create market: {}", e)))?;
 
-    Ok(Json(json!({
-        "success": true,
-        "message": "Market created successfully on blockchain",
-        "data": {
-            "blockchainMarketId": result.market_id,
-            "txHash": result.tx_hash,
-            "version": result.version,
-            "question": question,
-            "description": description,
-            "duration": duration
-        }
-    })))
+    if let Err(e) = ChainTransactionService::new(db.pool().clone(), &config.chain.node_url)
+        .record(
+            NewChainTransaction {
+                kind: TxKind::CreateMarket,
+                sender: &result.sender,
+                payload: &result.payload,
+                hash: &result.tx_hash,
+                market_id: None,
+                bet_id: None,
+            },
+            config.chain.tx_expiration_secs,
+        )
+        .await
+    {
+        error!("Failed to record transaction {}: {}", result.tx_hash, e);
+    }
+
+    let data = json!({
+        "blockchainMarketId": result.market_id,
+        "txHash": result.tx_hash,
+        "txStatus": ROW_PENDING,
+        "version": result.version,
+        "question": question,
+        "description": description,
+        "duration": duration
+    });
+    let audit = AuditDetails::new("market.create_on_chain")
+        .target("blockchain_market", result.market_id)
+        .after(&data);
+
+    Ok((
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "message": "Market created successfully on blockchain",
+            "data": data
+        })),
+    ))
 }
 
 use serde::Deserialize;
@@ -373,11 +574,17 @@ fn default_limit() -> i64 {
     )
 )]
 pub(super) async fn get_bets_with_filters(
-    State(_db): State<Database>,
+    State(db): State<Database>,
+    headers: HeaderMap,
     Query(filters): Query<BetFilters>,
 ) -> Result<Json<Value>, AppError> {
     info!("Fetching bets with filters: {:?}", filters);
 
+    let db = match &filters.user_address {
+        Some(addr) => db.for_user(addr, &headers),
+        None => db,
+    };
+
     let mut query_str = "SELECT * FROM bets".to_string();
     let mut count_str = "SELECT COUNT(*) FROM bets".to_string();
     let mut where_parts = Vec::new();
@@ -433,7 +640,7 @@ pub(super) async fn get_bets_with_filters(
     }
     main_query = main_query.bind(filters.limit).bind(filters.offset);
 
-    let bets = main_query.fetch_all(_db.pool()).await?;
+    let bets = main_query.fetch_all(db.read_pool()).await?;
 
     let mut count_query = sqlx::query_scalar::<_, i64>(&count_str);
     if let Some(ref addr) = filters.user_address {
@@ -448,7 +655,7 @@ pub(super) async fn get_bets_with_filters(
         count_query = count_query.bind(pos);
     }
 
-    let total: i64 = count_query.fetch_one(_db.pool()).await.unwrap_or(0);
+    let total: i64 = count_query.fetch_one(db.read_pool()).await.unwrap_or(0);
 
     Ok(Json(json!({
         "data": bets,
@@ -465,10 +672,21 @@ pub(super) async fn get_bets_with_filters(
 pub struct PlaceBetRequest {
     #[serde(rename = "marketIdentifier")]
     market_identifier: String,
-    position: bool,
+    /// YES (true) or NO (false); binary markets only.
+    #[serde(default)]
+    position: Option<bool>,
+    /// Outcome index; categorical markets only.
+    #[serde(default)]
+    outcome: Option<i32>,
     amount: String,
     #[serde(rename = "userAddress")]
     user_address: String,
+    /// Quote ID from `GET /api/markets/{identifier}/quote`, to hold the bet to the quoted odds.
+    #[serde(rename = "quoteId", default)]
+    quote_id: Option<String>,
+    /// Slippage allowed against the quote, in basis points.
+    #[serde(rename = "maxSlippageBps", default)]
+    max_slippage_bps: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -494,65 +712,98 @@ pub(super) struct ClaimWinningsRequest {
 )]
 pub(super) async fn place_bet(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    State(signers): State<Arc<SignerPool>>,
     Json(payload): Json<PlaceBetRequest>,
-) -> Result<Json<Value>, AppError> {
+) -> Result<WriteResponse, AppError> {
     info!("Placing bet on market: {}", payload.market_identifier);
 
-    let betting_service = crate::services::betting_service::BettingService::new(db.pool().clone())
-        .map_err(|e| AppError::Internal(format!("Failed to initialize betting service: {}", e)))?;
+    if payload.position.is_some() == payload.outcome.is_some() {
+        return Err(AppError::BadRequest(
+            "Provide either position (binary markets) or outcome (categorical markets)".to_string(),
+        ));
+    }
+
+    let betting_service =
+        crate::services::betting_service::BettingService::new(db.pool().clone(), &config)
+            .map_err(|e| {
+                AppError::Internal(format!("Failed to initialize betting service: {}", e))
+            })?
+            .with_signers(signers);
 
     let params = crate::services::betting_service::PlaceBetParams {
         market_identifier: payload.market_identifier,
         user_address: payload.user_address,
         position: payload.position,
+        outcome: payload.outcome,
         amount: payload.amount,
+        quote_id: payload.quote_id,
+        max_slippage_bps: payload.max_slippage_bps,
     };
 
-    let result = betting_service
-        .place_bet(params)
-        .await
-        .map_err(|e| AppError::Internal(format!("Failed to place bet: {}", e)))?;
-
-    Ok(Json(json!({
-        "success": true,
-        "message": "Bet placed successfully",
-        "data": {
-            "betId": result.bet_id,
-            "blockchainBetId": result.blockchain_bet_id,
-            "marketId": result.market_id,
-            "blockchainMarketId": result.blockchain_market_id,
-            "position": result.position,
-            "amount": result.amount,
-            "txHash": result.tx_hash,
-            "user": {
-                "address": result.user_address
+    let result = betting_service.place_bet(params).await.map_err(|e| {
+        match e.downcast_ref::<crate::services::betting_service::BetRejection>() {
+            Some(rejection) => AppError::Rejected {
+                code: rejection.code(),
+                message: rejection.to_string(),
             },
-            "blockchain": {
+            None => AppError::Internal(format!("Failed to place bet: {}", e)),
+        }
+    })?;
+    let cookie = db.read_primary_cookie(&result.user_address);
+
+    Ok((
+        AppendHeaders(cookie.map(|c| (header::SET_COOKIE, c))),
+        Json(json!({
+            "success": true,
+            "message": "Bet placed successfully",
+            "data": {
+                "betId": result.bet_id,
+                "blockchainBetId": result.blockchain_bet_id,
+                "marketId": result.market_id,
+                "blockchainMarketId": result.blockchain_market_id,
+                "position": result.position,
+                "outcome": result.outcome,
+                "amount": result.amount,
                 "txHash": result.tx_hash,
-                "betId": result.blockchain_bet_id
-            },
-            "explorer": {
-                "transaction": format!("https://explorer.aptoslabs.com/txn/{}?network=testnet", result.tx_hash)
+                "txStatus": ROW_PENDING,
+                "user": {
+                    "address": result.user_address
+                },
+                "blockchain": {
+                    "txHash": result.tx_hash,
+                    "betId": result.blockchain_bet_id
+                },
+                "explorer": {
+                    "transaction": format!("https://explorer.aptoslabs.com/txn/{}?network=testnet", result.tx_hash)
+                }
             }
-        }
-    })))
+        })),
+    ))
 }
 
 pub(super) async fn claim_winnings_route(
     State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    State(signers): State<Arc<SignerPool>>,
     Json(payload): Json<ClaimWinningsRequest>,
-) -> Result<Json<Value>, AppError> {
+) -> Result<WriteResponse, AppError> {
     info!(
         "Claiming winnings for market: {}",
         payload.market_identifier
     );
 
-    let betting_service = crate::services::betting_service::BettingService::new(db.pool().clone())
-        .map_err(|e| AppError::Internal(format!("Failed to initialize betting service: {}", e)))?;
+    let betting_service =
+        crate::services::betting_service::BettingService::new(db.pool().clone(), &config)
+            .map_err(|e| {
+                AppError::Internal(format!("Failed to initialize betting service: {}", e))
+            })?
+            .with_signers(signers);
 
+    let user_address = payload.user_address;
     let params = crate::services::betting_service::ClaimWinningsParams {
         market_identifier: payload.market_identifier,
-        user_address: payload.user_address,
+        user_address: user_address.clone(),
         bet_index: payload.bet_index,
     };
 
@@ -560,21 +811,26 @@ pub(super) async fn claim_winnings_route(
         .claim_winnings(params)
         .await
         .map_err(|e| AppError::Internal(format!("Failed to claim winnings: {}", e)))?;
+    let cookie = db.read_primary_cookie(&user_address);
 
-    Ok(Json(json!({
-        "success": true,
-        "message": "Winnings claimed successfully",
-        "data": {
-            "betId": result.bet_id,
-            "winningAmount": result.winning_amount,
-            "yieldShare": result.yield_share,
-            "totalClaimed": result.total_claimed,
-            "txHash": result.tx_hash,
-            "explorer": {
-                "transaction": format!("https://explorer.aptoslabs.com/txn/{}?network=testnet", result.tx_hash)
+    Ok((
+        AppendHeaders(cookie.map(|c| (header::SET_COOKIE, c))),
+        Json(json!({
+            "success": true,
+            "message": "Winnings claimed successfully",
+            "data": {
+                "betId": result.bet_id,
+                "winningAmount": result.winning_amount,
+                "yieldShare": result.yield_share,
+                "totalClaimed": result.total_claimed,
+                "txHash": result.tx_hash,
+                "txStatus": ROW_PENDING,
+                "explorer": {
+                    "transaction": format!("https://explorer.aptoslabs.com/txn/{}?network=testnet", result.tx_hash)
+                }
             }
-        }
-    })))
+        })),
+    ))
 }
 
 #[derive(Debug, Deserialize)]
@@ -608,7 +864,7 @@ pub(super) async fn webhook_sync_data(
                 .map_err(|e| AppError::Internal(format!("Bet sync failed: {}", e)))?;
 
             sync_service
-                .update_market_stats()
+                .update_market_stats_for_markets(&result.touched_markets)
                 .await
                 .map_err(|e| AppError::Internal(format!("Stats update failed: {}", e)))?;
 
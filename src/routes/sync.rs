@@ -1,23 +1,45 @@
 use axum::{
     extract::State,
+    http::StatusCode,
+    middleware,
     response::Json,
     routing::{get, post},
-    Router,
+    Extension, Router,
 };
 use serde_json::{json, Value};
 use tracing::info;
 
-use crate::{db::Database, error::AppError, services::scheduler::Scheduler};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::Config, db::Database, error::AppError, services::audit::AuditDetails,
+    services::blockchain_sync::BlockchainSyncService, services::job_queue::JobKind,
+    services::leader_election::SchedulerJob, services::scheduler::Scheduler, state::AppState,
+};
 
 use super::protocols::webhook_sync_data;
-pub fn create_sync_router() -> Router<Database> {
-    Router::new()
+pub fn create_sync_router(state: AppState) -> Router<AppState> {
+    let public_routes = Router::new()
         .route("/status", get(get_sync_status))
+        .route("/progress", get(get_sync_progress))
         .route("/webhook", post(webhook_sync_data))
         .route("/realtime-status", get(get_realtime_sync_status))
         .route("/event-stats", get(get_event_processing_stats))
+        .route("/scheduler-status", get(get_scheduler_status));
+
+    let protected_routes = Router::new()
         .route("/trigger-full-sync", post(trigger_manual_sync))
-        .route("/scheduler-status", get(get_scheduler_status))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::audit::audit,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state,
+            crate::middleware::auth::require_api_key,
+        ));
+
+    public_routes.merge(protected_routes)
 }
 
 async fn get_sync_status(State(db): State<Database>) -> Result<Json<Value>, AppError> {
@@ -31,10 +53,29 @@ async fn get_sync_status(State(db): State<Database>) -> Result<Json<Value>, AppE
     })))
 }
 
-async fn get_realtime_sync_status(State(db): State<Database>) -> Result<Json<Value>, AppError> {
+async fn get_sync_progress(State(db): State<Database>) -> Result<Json<Value>, AppError> {
+    let progress = BlockchainSyncService::new(db.pool().clone())
+        .progress()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to get sync progress: {}", e)))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "High-water marks and rows scanned/written by market and bet sync",
+        "data": progress
+    })))
+}
+
+async fn get_realtime_sync_status(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+) -> Result<Json<Value>, AppError> {
     info!("Fetching real-time sync status");
 
-    let realtime_sync = crate::services::realtime_sync::RealtimeSyncService::new(db.pool().clone());
+    let realtime_sync = crate::services::realtime_sync::RealtimeSyncService::new(
+        db.pool().clone(),
+        &config.yield_allocation,
+    );
     let stats = realtime_sync
         .get_sync_stats()
         .await
@@ -100,68 +141,118 @@ async fn get_event_processing_stats(State(db): State<Database>) -> Result<Json<V
     })))
 }
 
-async fn trigger_manual_sync(State(db): State<Database>) -> Result<Json<Value>, AppError> {
+async fn trigger_manual_sync(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+) -> Result<(StatusCode, Extension<AuditDetails>, Json<Value>), AppError> {
     info!("Manual sync triggered via API");
 
-    let scheduler = Scheduler::new(db.pool().clone());
-    match scheduler.trigger_sync_now().await {
-        Ok(summary) => {
-            info!(
-                "Manual sync completed: {} processed, {} errors in {}ms",
-                summary.total_processed, summary.total_errors, summary.duration_ms
-            );
-
-            let results: Vec<_> = summary
-                .results
-                .iter()
-                .map(|r| {
-                    json!({
-                        "eventType": r.event_type,
-                        "processed": r.processed,
-                        "newEvents": r.new_events,
-                        "errors": r.errors,
-                        "skipped": r.skipped
-                    })
-                })
-                .collect();
-
-            Ok(Json(json!({
-                "success": true,
-                "message": "Manual sync completed successfully",
-                "data": {
-                    "totalProcessed": summary.total_processed,
-                    "totalErrors": summary.total_errors,
-                    "durationMs": summary.duration_ms,
-                    "results": results
-                }
-            })))
-        }
-        Err(e) => {
-            let error_msg = format!("Manual sync failed: {}", e);
-            Err(AppError::Internal(error_msg))
-        }
-    }
+    let scheduler = Scheduler::new(db.pool().clone(), config, CancellationToken::new());
+    let (job, created) = scheduler
+        .enqueue_sync_now()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to queue sync: {}", e)))?;
+    let audit = AuditDetails::new("sync.trigger")
+        .target("job", &job.id)
+        .after(json!({ "created": created, "state": job.state }));
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Extension(audit),
+        Json(json!({
+            "success": true,
+            "message": if created {
+                "Sync queued"
+            } else {
+                "A sync is already queued or running"
+            },
+            "data": {
+                "jobId": job.id,
+                "state": job.state,
+                "nextRunAt": job.next_run_at
+            }
+        })),
+    ))
 }
 
-async fn get_scheduler_status(State(db): State<Database>) -> Result<Json<Value>, AppError> {
+async fn get_scheduler_status(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+) -> Result<Json<Value>, AppError> {
     info!("Fetching scheduler status");
 
-    let scheduler = Scheduler::new(db.pool().clone());
+    let scheduler = Scheduler::new(db.pool().clone(), config.clone(), CancellationToken::new());
     let status = scheduler.get_status();
+    let leaders = scheduler
+        .get_leaders()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load job leaders: {}", e)))?;
+    let leader = |job: SchedulerJob| leaders.iter().find(|l| l.job == job.as_str());
+    let schedules = scheduler
+        .get_schedules()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load job schedules: {}", e)))?;
+    let schedule = |kind: JobKind| schedules.iter().find(|s| s.kind == kind.as_str());
+    let listener_state =
+        crate::services::db_event_listener::DbEventListener::new(db.pool().clone())
+            .state()
+            .await
+            .map_err(|e| {
+                AppError::Internal(format!("Failed to load event listener state: {}", e))
+            })?;
 
     Ok(Json(json!({
         "success": true,
         "message": "Scheduler status",
         "data": {
+            "leaderElection": {
+                "enabled": status.leader_election_enabled,
+                "instanceId": status.instance_id
+            },
+            "jobScheduler": {
+                "workersPerInstance": config.jobs.workers,
+                "leader": leader(SchedulerJob::JobScheduler)
+            },
             "indexerSync": {
                 "enabled": status.indexer_sync_enabled,
                 "intervalSeconds": status.indexer_sync_interval_secs,
-                "nextRunEstimate": "background job running"
+                "schedule": schedule(JobKind::IndexerSync)
             },
             "yieldCalculation": {
                 "enabled": status.yield_calc_enabled,
                 "intervalSeconds": status.yield_calc_interval_secs,
-                "nextRunEstimate": "background job running"
+                "schedule": schedule(JobKind::YieldCalc)
+            },
+            "yieldAccrual": {
+                "enabled": status.yield_accrual_enabled,
+                "intervalSeconds": status.yield_accrual_interval_secs,
+                "schedule": schedule(JobKind::YieldAccrual)
+            },
+            "apyRefresh": {
+                "enabled": status.apy_refresh_enabled,
+                "intervalSeconds": status.apy_refresh_interval_secs,
+                "schedule": schedule(JobKind::ApyRefresh)
+            },
+            "reconciliation": {
+                "enabled": status.reconciliation_enabled,
+                "intervalSeconds": status.reconciliation_interval_secs,
+                "autoRepair": status.reconciliation_auto_repair,
+                "schedule": schedule(JobKind::Reconciliation)
+            },
+            "priceSampling": {
+                "enabled": status.price_sampling_enabled,
+                "intervalSeconds": status.price_sample_interval_secs,
+                "leader": leader(SchedulerJob::PriceSampling)
+            },
+            "transactionConfirmation": {
+                "enabled": status.tx_confirmation_enabled,
+                "intervalSeconds": status.tx_confirm_interval_secs,
+                "leader": leader(SchedulerJob::TxConfirmation)
+            },
+            "eventListener": {
+                "enabled": true,
+                "leader": leader(SchedulerJob::EventListener),
+                "health": listener_state
             }
         }
     })))
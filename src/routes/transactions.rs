@@ -0,0 +1,44 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::services::chain_transactions::ChainTransactionService;
+use crate::{config::Config, db::Database, error::AppError, state::AppState};
+
+pub fn create_transactions_router() -> Router<AppState> {
+    Router::new().route("/:hash", get(get_transaction))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/transactions/{hash}",
+    tag = "transactions",
+    params(
+        ("hash" = String, Path, description = "Transaction hash returned when it was submitted")
+    ),
+    responses(
+        (status = 200, description = "Transaction with its status (pending, committed, failed or expired), VM status and gas used"),
+        (status = 404, description = "Transaction not found")
+    )
+)]
+async fn get_transaction(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Path(hash): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let tx = ChainTransactionService::new(db.pool().clone(), &config.chain.node_url)
+        .get_by_hash(&hash)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load transaction: {}", e)))?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": tx
+    })))
+}
@@ -9,22 +9,34 @@ use serde_json::{json, Value};
 use tracing::info;
 use utoipa;
 
-use crate::{db::Database, error::AppError};
-pub fn create_yields_router() -> Router<Database> {
+use std::sync::Arc;
+
+use super::protocols::{load_apy_history, ApyHistoryQuery};
+use crate::{
+    config::Config, db::Database, error::AppError, services::yield_accrual::YieldAccrualService,
+    state::AppState,
+};
+pub fn create_yields_router(state: AppState) -> Router<AppState> {
     let public_routes = Router::new()
         .route("/", get(get_yields))
         .route("/summary", get(get_yield_summary))
         .route("/apy/current", get(get_current_apy))
+        .route("/apy/history", get(get_apy_history))
         .route("/protocols", get(get_yield_protocols))
         .route("/contract/test", get(test_contract_connectivity))
         .route("/contract/apy", get(get_contract_apy));
 
-    let protected_routes =
-        Router::new()
-            .route("/update", post(update_yields))
-            .layer(middleware::from_fn(
-                crate::middleware::auth::require_api_key,
-            ));
+    let protected_routes = Router::new()
+        .route("/update", post(update_yields))
+        .route("/accrue", post(accrue_yields))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::audit::audit,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state,
+            crate::middleware::auth::require_api_key,
+        ));
 
     public_routes.merge(protected_routes)
 }
@@ -69,7 +81,7 @@ async fn get_yields(
             LIMIT $2 OFFSET $3
             "#
         )
-        .bind(mid.parse::<i64>().unwrap_or(0))
+        .bind(mid)
         .bind(limit)
         .bind(offset)
         .fetch_all(_db.pool())
@@ -91,7 +103,7 @@ async fn get_yields(
 
     let total = if let Some(mid) = market_id {
         sqlx::query_scalar::<_, i64>(r#"SELECT COUNT(*) FROM yield_records WHERE "marketId" = $1"#)
-            .bind(mid.parse::<i64>().unwrap_or(0))
+            .bind(mid)
             .fetch_one(_db.pool())
             .await
             .unwrap_or(0)
@@ -115,10 +127,14 @@ async fn get_yields(
     })))
 }
 
-async fn get_yield_summary(State(db): State<Database>) -> Result<Json<Value>, AppError> {
+async fn get_yield_summary(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+) -> Result<Json<Value>, AppError> {
     info!("Fetching yield summary");
 
-    let calculator = crate::services::UserYieldCalculator::new(db.pool().clone());
+    let calculator =
+        crate::services::UserYieldCalculator::new(db.pool().clone(), &config.yield_allocation);
 
     match calculator.calculate_global_yields().await {
         Ok(summary) => {
@@ -187,6 +203,42 @@ async fn get_current_apy(State(db): State<Database>) -> Result<Json<Value>, AppE
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/yields/apy/history",
+    tag = "yields",
+    params(
+        ("protocol" = Option<String>, Query, description = "Protocol ID or name; all active protocols when omitted"),
+        ("from" = Option<i64>, Query, description = "Start (unix seconds), default 7 days ago"),
+        ("to" = Option<i64>, Query, description = "End (unix seconds), default now"),
+        ("interval" = Option<String>, Query, description = "raw, hour (default), day or week")
+    ),
+    responses(
+        (status = 200, description = "APY time series per protocol"),
+        (status = 400, description = "Invalid range")
+    )
+)]
+async fn get_apy_history(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+    Query(params): Query<ApyHistoryQuery>,
+) -> Result<Json<Value>, AppError> {
+    info!("Fetching APY history");
+
+    let (from, to, interval, series) =
+        load_apy_history(&db, &config, params.protocol.as_deref(), &params).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "from": from,
+            "to": to,
+            "interval": interval,
+            "protocols": series,
+        }
+    })))
+}
+
 #[utoipa::path(
     get,
     path = "/api/yields/protocols",
@@ -236,14 +288,42 @@ async fn update_yields(State(db): State<Database>) -> Result<Json<Value>, AppErr
     })))
 }
 
-async fn test_contract_connectivity(State(_db): State<Database>) -> Result<Json<Value>, AppError> {
+#[utoipa::path(
+    post,
+    path = "/api/yields/accrue",
+    tag = "yields",
+    responses(
+        (status = 200, description = "Completed days accrued into the yield ledger"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 500, description = "Internal server error")
+    ),
+)]
+async fn accrue_yields(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+) -> Result<Json<Value>, AppError> {
+    info!("Triggering yield accrual");
+
+    let service = YieldAccrualService::new(db.pool().clone(), &config);
+    let summary = service
+        .accrue_all()
+        .await
+        .map_err(|e| AppError::Internal(format!("Yield accrual failed: {}", e)))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Yield accrual complete",
+        "data": summary
+    })))
+}
+
+async fn test_contract_connectivity(
+    State(config): State<Arc<Config>>,
+) -> Result<Json<Value>, AppError> {
     info!("Testing contract connectivity");
 
-    let node_url = std::env::var("APTOS_NODE_URL")
-        .unwrap_or_else(|_| "https://fullnode.testnet.aptoslabs.com/v1".to_string());
-    let module_address = std::env::var("APTOS_MODULE_ADDRESS").unwrap_or_else(|_| {
-        "0xaab1ca043fb6cd7b2f264f3ce32f301427e7f67b0e816b30d4e95f1e2bcbabfa".to_string()
-    });
+    let node_url = &config.chain.node_url;
+    let module_address = config.chain.module_address_or_default().to_string();
 
     let client = reqwest::Client::new();
     let health_response = client.get(format!("{}/", node_url)).send().await;
@@ -251,8 +331,8 @@ async fn test_contract_connectivity(State(_db): State<Database>) -> Result<Json<
     let node_healthy = health_response.is_ok();
 
     let module_url = format!(
-        "{}/accounts/{}/module/kizo_prediction_market",
-        node_url, module_address
+        "{}/accounts/{}/module/{}",
+        node_url, module_address, config.chain.module_name
     );
     let module_response = client.get(&module_url).send().await;
 
@@ -274,10 +354,17 @@ async fn test_contract_connectivity(State(_db): State<Database>) -> Result<Json<
     })))
 }
 
-async fn get_contract_apy(State(_db): State<Database>) -> Result<Json<Value>, AppError> {
+async fn get_contract_apy(
+    State(db): State<Database>,
+    State(config): State<Arc<Config>>,
+) -> Result<Json<Value>, AppError> {
     info!("Fetching contract APY data");
 
-    let yield_service = crate::services::YieldService::new(_db.pool().clone());
+    let yield_service = crate::services::YieldService::new(
+        db.pool().clone(),
+        &config.chain,
+        &config.yield_allocation,
+    );
 
     match yield_service.update_all_protocols_apy().await {
         Ok(results) => {
@@ -289,8 +376,7 @@ async fn get_contract_apy(State(_db): State<Database>) -> Result<Json<Value>, Ap
                     json!({
                         "apy": apy.to_string(),
                         "lastUpdated": chrono::Utc::now().to_rfc3339(),
-                        "contractAddress": std::env::var("APTOS_MODULE_ADDRESS")
-                            .unwrap_or_else(|_| "0x...".to_string())
+                        "contractAddress": config.chain.module_address.as_deref().unwrap_or("0x...")
                     }),
                 );
             }
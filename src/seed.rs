@@ -3,9 +3,10 @@ use sqlx::PgPool;
 use tracing::{error, info};
 use uuid::Uuid;
 
+use crate::config::Config;
 use crate::services::{market_seeder::MarketSeeder, YieldService};
 
-pub async fn seed_protocols(pool: &PgPool) -> Result<()> {
+pub async fn seed_protocols(pool: &PgPool, config: &Config) -> Result<()> {
     info!("Seeding protocols...");
 
     let protocols = vec![
@@ -63,7 +64,7 @@ pub async fn seed_protocols(pool: &PgPool) -> Result<()> {
     info!("Protocol seeding complete");
 
     info!("Updating protocol APY from blockchain...");
-    let yield_service = YieldService::new(pool.clone());
+    let yield_service = YieldService::new(pool.clone(), &config.chain, &config.yield_allocation);
     match yield_service.update_all_protocols_apy().await {
         Ok(results) => {
             for (protocol, apy) in results {
@@ -81,12 +82,10 @@ pub async fn seed_protocols(pool: &PgPool) -> Result<()> {
     Ok(())
 }
 
-pub async fn seed_markets(pool: &PgPool, count: usize) -> Result<()> {
+pub async fn seed_markets(pool: &PgPool, config: &Config, count: usize) -> Result<()> {
     info!("Seeding {} markets from Adjacent API...", count);
 
-    let api_key = std::env::var("ADJACENT_API_KEY").expect("ADJACENT_API_KEY must be set");
-
-    let seeder = MarketSeeder::new(pool.clone(), api_key)?;
+    let seeder = MarketSeeder::new(pool.clone(), &config.adjacent, &config.images)?;
     let result = seeder.seed_markets(count).await?;
 
     info!(
@@ -97,17 +96,12 @@ pub async fn seed_markets(pool: &PgPool, count: usize) -> Result<()> {
     Ok(())
 }
 
-pub async fn run_all_seeds(pool: &PgPool) -> Result<()> {
+pub async fn run_all_seeds(pool: &PgPool, config: &Config) -> Result<()> {
     info!("🌱 Running all seeds...");
 
-    seed_protocols(pool).await?;
-
-    let market_count = std::env::var("SEED_MARKET_COUNT")
-        .ok()
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(10);
+    seed_protocols(pool, config).await?;
 
-    match seed_markets(pool, market_count).await {
+    match seed_markets(pool, config, config.seeding.market_count).await {
         Ok(_) => info!("✅ Market seeding successful"),
         Err(e) => error!("⚠️  Market seeding failed: {}. Continuing...", e),
     }
@@ -3,7 +3,9 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
-const DEFAULT_API_BASE_URL: &str = "https://api.data.adj.news/api";
+use crate::config::AdjacentConfig;
+
+pub const DEFAULT_API_BASE_URL: &str = "https://api.data.adj.news/api";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdjacentMarket {
@@ -58,9 +60,9 @@ pub struct AdjacentService {
 
 #[allow(dead_code)]
 impl AdjacentService {
-    pub fn new(api_key: String) -> Result<Self> {
-        let base_url = std::env::var("ADJACENT_API_BASE_URL")
-            .unwrap_or_else(|_| DEFAULT_API_BASE_URL.to_string());
+    pub fn new(config: &AdjacentConfig) -> Result<Self> {
+        let api_key = config.require_api_key()?.to_string();
+        let base_url = config.base_url.clone();
 
         let client = Client::builder()
             .timeout(std::time::Duration::from_secs(15))
@@ -1,8 +1,13 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::sync::Arc;
 use tracing::{error, info};
 
+use crate::config::ChainConfig;
+
+use super::signer::{SignerPool, SignerRole};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateMarketParams {
     pub question: String,
@@ -17,39 +22,44 @@ pub struct CreateMarketResult {
     pub market_id: i64,
     pub tx_hash: String,
     pub version: u64,
+    /// Account that signed the transaction and the submitted payload, for
+    /// `ChainTransactionService::record`.
+    pub sender: String,
+    pub payload: serde_json::Value,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct AptosContractService {
     pub node_url: String,
     pub module_address: String,
     pub module_name: String,
+    signers: Option<Arc<SignerPool>>,
 }
 
 #[allow(dead_code)]
 impl AptosContractService {
-    pub fn new() -> Result<Self> {
-        let node_url = std::env::var("APTOS_NODE_URL")
-            .unwrap_or_else(|_| "https://fullnode.testnet.aptoslabs.com/v1".to_string());
-
-        let module_address = std::env::var("APTOS_MODULE_ADDRESS")
-            .map_err(|_| anyhow!("APTOS_MODULE_ADDRESS environment variable is required"))?;
-
-        let module_name =
-            std::env::var("APTOS_MODULE_NAME").unwrap_or_else(|_| "prediction_market".to_string());
-
+    pub fn new(config: &ChainConfig) -> Result<Self> {
         Ok(Self {
-            node_url,
-            module_address,
-            module_name,
+            node_url: config.node_url.clone(),
+            module_address: config.require_module_address()?.to_string(),
+            module_name: config.module_name.clone(),
+            signers: None,
         })
     }
 
+    /// Accounts `create_market` signs with; without them it fails.
+    pub fn with_signers(mut self, signers: Arc<SignerPool>) -> Self {
+        self.signers = Some(signers);
+        self
+    }
+
     pub async fn create_market(&self, params: CreateMarketParams) -> Result<CreateMarketResult> {
         info!("Creating market on Aptos blockchain: {}", params.question);
 
-        let _private_key = std::env::var("APTOS_PRIVATE_KEY")
-            .map_err(|_| anyhow!("APTOS_PRIVATE_KEY environment variable is required"))?;
+        let signers = self
+            .signers
+            .as_ref()
+            .ok_or_else(|| anyhow!("No signer available for market creation"))?;
 
         let function_id = format!(
             "{}::{}::create_market",
@@ -70,10 +80,13 @@ impl AptosContractService {
         });
 
         info!("Transaction payload prepared: {:?}", payload);
+        let signed = signers
+            .sign_transaction(SignerRole::Admin, &payload)
+            .await?;
         info!("NOTE: Actual Aptos SDK integration required. Using mock response for development.");
 
         let mock_market_id = chrono::Utc::now().timestamp() % 1000000;
-        let mock_tx_hash = format!("0x{:x}", mock_market_id);
+        let mock_tx_hash = signed.hash();
 
         info!(
             "Market creation transaction would be submitted with function: {}",
@@ -84,6 +97,8 @@ impl AptosContractService {
             market_id: mock_market_id,
             tx_hash: mock_tx_hash,
             version: 0,
+            sender: signed.account.address,
+            payload,
         })
     }
 
@@ -144,9 +159,12 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_status() {
-        std::env::set_var("APTOS_MODULE_ADDRESS", "0x123");
+        let config = ChainConfig {
+            module_address: Some("0x123".to_string()),
+            ..ChainConfig::default()
+        };
 
-        let service = AptosContractService::new().unwrap();
+        let service = AptosContractService::new(&config).unwrap();
         let result = service.get_status().await;
 
         match result {
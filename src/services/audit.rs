@@ -0,0 +1,307 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+/// Actor recorded when no API key is configured and privileged routes are open.
+pub const ANONYMOUS_ACTOR: &str = "anonymous";
+
+/// Identifies the API key a request authenticated with, without storing the key: a prefix
+/// of its SHA-256, e.g. `api-key:3f1a9c0b2d4e`.
+pub fn api_key_actor(api_key: &str) -> String {
+    let digest = hex::encode(Sha256::digest(api_key.as_bytes()));
+    format!("api-key:{}", &digest[..12])
+}
+
+/// Identifies the OS user who ran a `kizo-admin` command, e.g. `cli:alice`.
+#[allow(dead_code)]
+pub fn cli_actor() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("LOGNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!("cli:{}", user)
+}
+
+/// Who made a privileged request. Set as a request extension once the caller is
+/// authenticated.
+#[derive(Debug, Clone)]
+pub struct Actor(pub String);
+
+/// What a privileged handler did, attached to its response as an extension. Requests whose
+/// handler attaches none are recorded under their method and route.
+#[derive(Debug, Clone, Default)]
+pub struct AuditDetails {
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl AuditDetails {
+    pub fn new(action: impl Into<String>) -> Self {
+        Self {
+            action: action.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn target(mut self, target_type: &str, target_id: impl ToString) -> Self {
+        self.target_type = Some(target_type.to_string());
+        self.target_id = Some(target_id.to_string());
+        self
+    }
+
+    pub fn before(mut self, before: impl Serialize) -> Self {
+        self.before = serde_json::to_value(before).ok();
+        self
+    }
+
+    pub fn after(mut self, after: impl Serialize) -> Self {
+        self.after = serde_json::to_value(after).ok();
+        self
+    }
+}
+
+/// A row about to be appended to `audit_log`.
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry {
+    pub actor: String,
+    pub details: AuditDetails,
+    pub status: u16,
+    pub method: String,
+    pub path: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub duration_ms: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor: String,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub status: i32,
+    pub method: String,
+    pub path: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub duration_ms: i32,
+    /// `started` for the row written before the action ran, `completed` once it finished.
+    pub phase: String,
+    /// The started row a completed row closes.
+    pub started_id: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    /// Exact action, or a prefix ending in `.`, e.g. `market.` for every market action.
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+    /// Only entries older than this id, to page back through the log.
+    pub before_id: Option<i64>,
+    pub limit: i64,
+}
+
+/// The append-only `audit_log`: entries are only ever inserted and read.
+pub struct AuditLog {
+    pool: PgPool,
+}
+
+impl AuditLog {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Records that an action is about to run, before it runs: an action whose entry cannot
+    /// be written is refused rather than run unaudited. The entry's status is 0 until
+    /// [`AuditLog::complete`] records the outcome.
+    pub async fn start(&self, entry: &NewAuditEntry) -> Result<i64> {
+        self.insert(entry, "started", None)
+            .await
+            .context("Audit log is unavailable")
+    }
+
+    /// Records the outcome of the action `started_id` announced.
+    pub async fn complete(&self, started_id: i64, entry: &NewAuditEntry) -> Result<i64> {
+        self.insert(entry, "completed", Some(started_id)).await
+    }
+
+    /// Entries matching `filter`, newest first.
+    pub async fn list(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        let (action, action_prefix) = match filter.action.as_deref() {
+            Some(action) if action.ends_with('.') => {
+                (None, Some(format!("{}%", escape_like(action))))
+            }
+            Some(action) => (Some(action), None),
+            None => (None, None),
+        };
+
+        let entries = sqlx::query_as!(
+            AuditEntry,
+            r#"
+            SELECT id, actor, action, "targetType" as target_type, "targetId" as target_id,
+                   before, after, status, method, path, "ipAddress" as ip_address,
+                   "userAgent" as user_agent, "requestId" as request_id,
+                   "durationMs" as duration_ms, phase, "startedId" as started_id,
+                   "createdAt" as created_at
+            FROM audit_log
+            WHERE ($1::text IS NULL OR actor = $1)
+              AND ($2::text IS NULL OR action = $2)
+              AND ($3::text IS NULL OR action LIKE $3 ESCAPE '\')
+              AND ($4::text IS NULL OR "targetType" = $4)
+              AND ($5::text IS NULL OR "targetId" = $5)
+              AND ($6::timestamp IS NULL OR "createdAt" >= $6)
+              AND ($7::timestamp IS NULL OR "createdAt" < $7)
+              AND ($8::bigint IS NULL OR id < $8)
+            ORDER BY id DESC
+            LIMIT $9
+            "#,
+            filter.actor,
+            action,
+            action_prefix,
+            filter.target_type,
+            filter.target_id,
+            filter.since,
+            filter.until,
+            filter.before_id,
+            filter.limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    async fn insert(
+        &self,
+        entry: &NewAuditEntry,
+        phase: &str,
+        started_id: Option<i64>,
+    ) -> Result<i64> {
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO audit_log (
+                actor, action, "targetType", "targetId", before, after, status, method, path,
+                "ipAddress", "userAgent", "requestId", "durationMs", phase, "startedId"
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            RETURNING id
+            "#,
+            entry.actor,
+            entry.details.action,
+            entry.details.target_type,
+            entry.details.target_id,
+            entry.details.before,
+            entry.details.after,
+            entry.status as i32,
+            entry.method,
+            entry.path,
+            entry.ip_address,
+            entry.user_agent,
+            entry.request_id,
+            entry.duration_ms,
+            phase,
+            started_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to write audit log entry")?;
+        Ok(id)
+    }
+}
+
+/// Escapes `LIKE` wildcards so `value` only matches itself.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_key_actor_does_not_reveal_the_key() {
+        let actor = api_key_actor("super-secret-key");
+        assert!(actor.starts_with("api-key:"));
+        assert_eq!(actor.len(), "api-key:".len() + 12);
+        assert!(!actor.contains("secret"));
+        assert_eq!(actor, api_key_actor("super-secret-key"));
+        assert_ne!(actor, api_key_actor("another-key"));
+    }
+
+    #[test]
+    fn test_details_builder_serializes_snapshots() {
+        let details = AuditDetails::new("market.image.update")
+            .target("market", "m-1")
+            .before(serde_json::json!({ "imageUrl": null }))
+            .after(serde_json::json!({ "imageUrl": "https://img" }));
+        assert_eq!(details.target_id.as_deref(), Some("m-1"));
+        assert_eq!(details.after.unwrap()["imageUrl"], "https://img");
+    }
+
+    fn entry(action: &str) -> NewAuditEntry {
+        NewAuditEntry {
+            actor: "cli:test".to_string(),
+            details: AuditDetails::new(action),
+            status: 0,
+            method: "CLI".to_string(),
+            path: "jobs cancel".to_string(),
+            ip_address: None,
+            user_agent: None,
+            request_id: None,
+            duration_ms: 1,
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_action_prefix_matches_wildcards_literally(pool: PgPool) {
+        let log = AuditLog::new(pool);
+        for action in ["risk_limits.update", "riskXlimits.update", "job.cancel"] {
+            let started = log.start(&entry(action)).await.unwrap();
+            log.complete(started, &entry(action)).await.unwrap();
+        }
+
+        let filter = AuditFilter {
+            action: Some("risk_limits.".to_string()),
+            limit: 10,
+            ..AuditFilter::default()
+        };
+        let entries = log.list(&filter).await.unwrap();
+        let rows: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|e| (e.action.as_str(), e.phase.as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("risk_limits.update", "completed"),
+                ("risk_limits.update", "started")
+            ]
+        );
+        assert_eq!(entries[0].started_id, Some(entries[1].id));
+
+        let filter = AuditFilter {
+            action: Some("%.".to_string()),
+            limit: 10,
+            ..AuditFilter::default()
+        };
+        assert!(log.list(&filter).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use super::market_outcomes::{outcome_odds, outcome_probabilities};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// What a quote ID commits to. Field names are kept short since the claims travel in the ID.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuoteClaims {
+    /// Internal market id.
+    #[serde(rename = "m")]
+    pub market_id: String,
+    #[serde(rename = "p", default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<bool>,
+    #[serde(rename = "o", default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<i32>,
+    #[serde(rename = "a")]
+    pub amount: String,
+    /// Address of the bettor the quote was issued to, lowercased.
+    #[serde(rename = "u")]
+    pub user_address: String,
+    /// Random value that makes the quote single-use.
+    #[serde(rename = "n")]
+    pub nonce: String,
+    /// Quoted decimal odds after the bet.
+    #[serde(rename = "q")]
+    pub odds: f64,
+    /// Unix seconds after which the quote can no longer be used.
+    #[serde(rename = "e")]
+    pub expires_at: i64,
+}
+
+/// Pool maths of a quote, before fees and yield.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuotePricing {
+    pub probability_before: f64,
+    pub probability_after: f64,
+    pub odds_before: f64,
+    pub odds_after: f64,
+    /// Relative drop in odds caused by the bet itself, between 0 and 1.
+    pub price_impact: f64,
+}
+
+/// Prices a bet of `amount` on outcome `index` against the current `pools`.
+/// Binary markets pass `[yes, no]`.
+pub fn price_bet(pools: &[f64], index: usize, amount: f64) -> QuotePricing {
+    let probability_before = outcome_probabilities(pools)
+        .get(index)
+        .copied()
+        .unwrap_or(0.0);
+    let odds_before = if probability_before > 0.0 {
+        1.0 / probability_before
+    } else {
+        1.0
+    };
+    let odds_after = outcome_odds(pools, index, amount);
+    let price_impact = if odds_before > odds_after {
+        (odds_before - odds_after) / odds_before
+    } else {
+        0.0
+    };
+
+    QuotePricing {
+        probability_before,
+        probability_after: 1.0 / odds_after,
+        odds_before,
+        odds_after,
+        price_impact,
+    }
+}
+
+/// Fee in basis points taken from a gross payout.
+pub fn payout_fee(gross_payout: f64, fee_bps: u32) -> f64 {
+    gross_payout * fee_bps as f64 / 10_000.0
+}
+
+/// Yield a stake earns at `apy_pct` over `days`. With `compounding` the APY is treated as an
+/// effective annual rate; otherwise it accrues as simple interest.
+pub fn projected_yield(stake: f64, apy_pct: f64, days: f64, compounding: bool) -> f64 {
+    if days <= 0.0 {
+        return 0.0;
+    }
+    let apy = apy_pct / 100.0;
+    if compounding {
+        stake * ((1.0 + apy).powf(days / 365.0) - 1.0)
+    } else {
+        stake * apy * days / 365.0
+    }
+}
+
+/// How far `current_odds` fell below `quoted_odds`, as a fraction; 0 when odds improved.
+pub fn slippage(quoted_odds: f64, current_odds: f64) -> f64 {
+    if quoted_odds <= 0.0 || current_odds >= quoted_odds {
+        return 0.0;
+    }
+    (quoted_odds - current_odds) / quoted_odds
+}
+
+/// Encodes the claims and signs them: `<base64url claims>.<hex HMAC-SHA256>`.
+pub fn sign_quote(claims: &QuoteClaims, secret: &str) -> Result<String> {
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims)?);
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())?;
+    mac.update(payload.as_bytes());
+    Ok(format!(
+        "{}.{}",
+        payload,
+        hex::encode(mac.finalize().into_bytes())
+    ))
+}
+
+/// Checks a quote ID's signature and expiry and returns its claims.
+pub fn verify_quote(quote_id: &str, secret: &str, now: i64) -> Result<QuoteClaims> {
+    let (payload, signature) = quote_id
+        .split_once('.')
+        .ok_or_else(|| anyhow!("Malformed quote ID"))?;
+    let signature = hex::decode(signature).map_err(|_| anyhow!("Malformed quote ID"))?;
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())?;
+    mac.update(payload.as_bytes());
+    mac.verify_slice(&signature)
+        .map_err(|_| anyhow!("Quote signature is invalid"))?;
+
+    let claims: QuoteClaims = URL_SAFE_NO_PAD
+        .decode(payload)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| anyhow!("Malformed quote ID"))?;
+
+    if claims.expires_at < now {
+        return Err(anyhow!("Quote expired; request a new one"));
+    }
+
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "a-long-enough-test-secret";
+
+    fn claims() -> QuoteClaims {
+        QuoteClaims {
+            market_id: "market-1".to_string(),
+            position: Some(true),
+            outcome: None,
+            amount: "100".to_string(),
+            user_address: "0xabc".to_string(),
+            nonce: "3f1a9c0b".to_string(),
+            odds: 2.5,
+            expires_at: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_price_bet_reports_impact() {
+        // YES holds 100 of 400: 4.0 before, (400 + 100) / (100 + 100) = 2.5 after.
+        let pricing = price_bet(&[100.0, 300.0], 0, 100.0);
+        assert_eq!(pricing.probability_before, 0.25);
+        assert_eq!(pricing.odds_before, 4.0);
+        assert_eq!(pricing.odds_after, 2.5);
+        assert_eq!(pricing.probability_after, 0.4);
+        assert!((pricing.price_impact - 0.375).abs() < 1e-12);
+
+        let empty = price_bet(&[0.0, 0.0], 1, 10.0);
+        assert_eq!(empty.odds_before, 2.0);
+        assert_eq!(empty.odds_after, 1.0);
+    }
+
+    #[test]
+    fn test_fee_yield_and_slippage() {
+        assert_eq!(payout_fee(250.0, 200), 5.0);
+        assert!((projected_yield(365.0, 10.0, 73.0, false) - 7.3).abs() < 1e-9);
+        assert!((projected_yield(100.0, 10.0, 365.0, true) - 10.0).abs() < 1e-9);
+        assert_eq!(projected_yield(100.0, 10.0, -1.0, false), 0.0);
+        assert_eq!(slippage(2.5, 2.0), 0.2);
+        assert_eq!(slippage(2.5, 3.0), 0.0);
+    }
+
+    #[test]
+    fn test_quote_id_round_trip() {
+        let id = sign_quote(&claims(), SECRET).unwrap();
+        assert_eq!(verify_quote(&id, SECRET, 999).unwrap(), claims());
+
+        assert!(verify_quote(&id, SECRET, 1_001).is_err());
+        assert!(verify_quote(&id, "another-secret-of-length", 999).is_err());
+
+        let mut tampered = claims();
+        tampered.odds = 9.0;
+        let forged_payload = sign_quote(&tampered, SECRET)
+            .unwrap()
+            .split_once('.')
+            .unwrap()
+            .0
+            .to_string();
+        let signature = id.split_once('.').unwrap().1;
+        assert!(verify_quote(&format!("{}.{}", forged_payload, signature), SECRET, 999).is_err());
+        assert!(verify_quote("not-a-quote", SECRET, 999).is_err());
+    }
+}
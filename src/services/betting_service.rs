@@ -1,16 +1,107 @@
 use anyhow::{anyhow, Result};
+use bigdecimal::ToPrimitive;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use sqlx::PgPool;
+use serde_json::{json, Value};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use std::sync::Arc;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use crate::config::{Config, QuoteConfig, YieldAllocationConfig};
+
+use super::bet_quote::{
+    payout_fee, price_bet, projected_yield, sign_quote, slippage, verify_quote, QuoteClaims,
+};
+use super::chain_transactions::{
+    ChainTransactionService, NewChainTransaction, TxKind, ROW_PENDING,
+};
+use super::market_outcomes::{MarketOutcomeService, MarketType};
+use super::response_cache::publish_invalidation;
+use super::risk_limits::{RiskLimitService, RiskViolation};
+use super::signer::{SignerPool, SignerRole};
+use super::yield_allocation::YieldAllocator;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PlaceBetParams {
     pub market_identifier: String,
     pub user_address: String,
-    pub position: bool,
+    /// YES/NO side, for binary markets.
+    pub position: Option<bool>,
+    /// Outcome index, for categorical markets.
+    pub outcome: Option<i32>,
+    pub amount: String,
+    /// Quote ID from `quote_bet`; the bet is refused if the odds slipped past the limit.
+    pub quote_id: Option<String>,
+    /// Slippage allowed against the quote, in basis points; defaults to the configured limit.
+    pub max_slippage_bps: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuoteBetParams {
+    pub market_identifier: String,
+    /// Bettor the quote is issued to; only their bet can use it.
+    pub user_address: String,
+    pub position: Option<bool>,
+    pub outcome: Option<i32>,
+    pub amount: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BetQuote {
+    pub quote_id: String,
+    pub market_id: String,
+    pub position: Option<bool>,
+    pub outcome: Option<i32>,
     pub amount: String,
+    pub probability_before: f64,
+    pub probability_after: f64,
+    pub odds_before: f64,
+    pub odds_after: f64,
+    /// Relative drop in odds caused by this bet, between 0 and 1.
+    pub price_impact: f64,
+    /// Payout if the side wins and the pools stay as they are after this bet.
+    pub estimated_payout: f64,
+    pub fee: f64,
+    pub net_payout: f64,
+    /// Blended APY of the market's allocation, in percent.
+    pub projected_apy: f64,
+    /// Yield the stake earns from now until the market's end date.
+    pub projected_yield: f64,
+    pub end_date: chrono::NaiveDateTime,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A bet refused because of what was asked rather than a failure; routes answer these with 400
+/// and the rejection's `code()`.
+#[derive(Debug, thiserror::Error)]
+pub enum BetRejection {
+    #[error(transparent)]
+    Risk(#[from] RiskViolation),
+    #[error("Invalid quote: {0}")]
+    InvalidQuote(String),
+    #[error("Quote does not match this bet: different {0}")]
+    QuoteMismatch(&'static str),
+    #[error(
+        "Odds moved from {quoted:.4} to {current:.4} ({slippage_bps} bps), above the {max_bps} bps limit"
+    )]
+    SlippageExceeded {
+        quoted: f64,
+        current: f64,
+        slippage_bps: u32,
+        max_bps: u32,
+    },
+}
+
+impl BetRejection {
+    pub fn code(&self) -> &'static str {
+        match self {
+            BetRejection::Risk(violation) => violation.code(),
+            BetRejection::InvalidQuote(_) => "QUOTE_INVALID",
+            BetRejection::QuoteMismatch(_) => "QUOTE_MISMATCH",
+            BetRejection::SlippageExceeded { .. } => "SLIPPAGE_EXCEEDED",
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,7 +110,8 @@ pub struct PlaceBetResult {
     pub blockchain_bet_id: u64,
     pub market_id: String,
     pub blockchain_market_id: u64,
-    pub position: bool,
+    pub position: Option<bool>,
+    pub outcome: Option<i32>,
     pub amount: String,
     pub tx_hash: String,
     pub user_address: String,
@@ -41,35 +133,57 @@ pub struct ClaimWinningsResult {
     pub tx_hash: String,
 }
 
+/// A signed transaction as handed to the node.
+struct SubmittedTransaction {
+    hash: String,
+    /// Operator account that signed it.
+    sender: String,
+    payload: Value,
+}
+
 pub struct BettingService {
     pool: PgPool,
     #[allow(dead_code)]
-    node_url: String,
-    #[allow(dead_code)]
     module_address: String,
     #[allow(dead_code)]
     module_name: String,
+    contract_address: String,
+    token_type: String,
+    signers: Option<Arc<SignerPool>>,
+    tx_expiration_secs: u64,
+    post_bet_webhook_url: Option<String>,
+    yield_allocation: YieldAllocationConfig,
+    compounding: bool,
+    quotes: QuoteConfig,
+    quote_secret: String,
 }
 
 impl BettingService {
-    pub fn new(pool: PgPool) -> Result<Self> {
-        let node_url = std::env::var("APTOS_NODE_URL")
-            .unwrap_or_else(|_| "https://fullnode.testnet.aptoslabs.com/v1".to_string());
-
-        let module_address = std::env::var("APTOS_MODULE_ADDRESS")
-            .map_err(|_| anyhow!("APTOS_MODULE_ADDRESS environment variable is required"))?;
-
-        let module_name = std::env::var("APTOS_MODULE_NAME")
-            .unwrap_or_else(|_| "kizo_prediction_market".to_string());
+    pub fn new(pool: PgPool, config: &Config) -> Result<Self> {
+        let chain = &config.chain;
 
         Ok(Self {
             pool,
-            node_url,
-            module_address,
-            module_name,
+            module_address: chain.require_module_address()?.to_string(),
+            module_name: chain.module_name.clone(),
+            contract_address: chain.contract_address()?.to_string(),
+            token_type: chain.token_type.clone(),
+            signers: None,
+            tx_expiration_secs: chain.tx_expiration_secs,
+            post_bet_webhook_url: config.webhooks.post_bet_url.clone(),
+            yield_allocation: config.yield_allocation.clone(),
+            compounding: config.yield_accrual.compounding,
+            quotes: config.quotes.clone(),
+            quote_secret: config.quote_signing_secret(),
         })
     }
 
+    /// Accounts bet and claim transactions are signed with; without them both fail.
+    pub fn with_signers(mut self, signers: Arc<SignerPool>) -> Self {
+        self.signers = Some(signers);
+        self
+    }
+
     pub async fn place_bet(&self, params: PlaceBetParams) -> Result<PlaceBetResult> {
         info!("Placing bet on market: {}", params.market_identifier);
 
@@ -96,27 +210,59 @@ impl BettingService {
             return Err(anyhow!("Amount must be greater than 0"));
         }
 
-        let contract_addr =
-            std::env::var("APTOS_CONTRACT_ADDRESS").unwrap_or_else(|_| self.module_address.clone());
+        let side = self
+            .bet_side(&market, params.position, params.outcome)
+            .await?;
+
+        // Everything up to the commit runs on the locked transaction, so a bet never holds
+        // a second connection while others queue on its market's lock.
+        let mut bet_lock = RiskLimitService::new(self.pool.clone())
+            .lock_market(&market.id)
+            .await?;
+        let user_id = Self::user_id(&mut bet_lock, &params.user_address).await?;
+        self.check_risk_limits(
+            &mut bet_lock,
+            &market,
+            Some(&params.user_address),
+            amount_u64,
+        )
+        .await?;
+
+        let odds = self
+            .calculate_bet_odds(&mut bet_lock, &market.id, side, amount_u64 as f64)
+            .await?;
+        if let Some(quote_id) = &params.quote_id {
+            let claims = self.check_quote(
+                quote_id,
+                &market.id,
+                &params.user_address,
+                side,
+                &params.amount,
+                odds,
+                params.max_slippage_bps,
+            )?;
+            Self::consume_quote(&mut bet_lock, &claims).await?;
+        }
+
+        let contract_addr = self.contract_address.clone();
 
-        let (tx_hash, blockchain_bet_id) = self
+        let (submitted, blockchain_bet_id) = self
             .submit_bet_transaction(
                 &params.user_address,
                 &contract_addr,
                 blockchain_market_id,
-                params.position,
+                side,
                 amount_u64,
             )
             .await?;
 
         info!(
             "Bet transaction submitted: {} with bet ID {}",
-            tx_hash, blockchain_bet_id
+            submitted.hash, blockchain_bet_id
         );
+        let tx_hash = submitted.hash.clone();
 
-        let odds = self
-            .calculate_bet_odds(&market.id, params.position, &params.amount)
-            .await?;
+        let (position, outcome) = side.split();
         let odds_decimal = format!("{:.4}", odds)
             .parse::<sqlx::types::BigDecimal>()
             .unwrap_or_else(|_| "1.0".parse::<sqlx::types::BigDecimal>().unwrap());
@@ -126,27 +272,44 @@ impl BettingService {
         sqlx::query!(
             r#"
             INSERT INTO bets_extended (
-                id, "userId", "marketId", "blockchainBetId", position, amount,
-                odds, status, "createdAt", "updatedAt"
+                id, "userId", "marketId", "blockchainBetId", position, "outcomeIndex", amount,
+                odds, status, "txHash", "txStatus", "createdAt", "updatedAt"
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, 'active', NOW(), NOW())
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'active', $9, $10, NOW(), NOW())
             "#,
             bet_id,
-            params.user_address,
+            user_id,
             market.id,
             blockchain_bet_id as i64,
-            params.position,
+            position,
+            outcome,
             params
                 .amount
                 .parse::<sqlx::types::BigDecimal>()
                 .unwrap_or_default(),
             odds_decimal,
+            tx_hash,
+            ROW_PENDING,
         )
-        .execute(&self.pool)
+        .execute(&mut *bet_lock)
         .await?;
 
-        self.update_market_pools(&market.id, params.position, &params.amount)
-            .await?;
+        self.record_transaction(
+            &mut *bet_lock,
+            NewChainTransaction {
+                kind: TxKind::PlaceBet,
+                sender: &submitted.sender,
+                payload: &submitted.payload,
+                hash: &tx_hash,
+                market_id: Some(&market.id),
+                bet_id: Some(&bet_id),
+            },
+        )
+        .await?;
+
+        Self::update_market_pools(&mut bet_lock, &market.id, side, &params.amount).await?;
+        bet_lock.commit().await?;
+        self.invalidate_cache(blockchain_market_id as i64).await;
 
         if let Err(e) = self.trigger_data_sync(&market.id, blockchain_bet_id).await {
             info!(
@@ -160,13 +323,200 @@ impl BettingService {
             blockchain_bet_id,
             market_id: market.id,
             blockchain_market_id,
-            position: params.position,
+            position,
+            outcome,
             amount: params.amount,
             tx_hash,
             user_address: params.user_address,
         })
     }
 
+    /// Prices a bet without placing it and signs the result, so `place_bet` can hold the
+    /// bettor to the quoted odds.
+    pub async fn quote_bet(&self, params: QuoteBetParams) -> Result<BetQuote> {
+        let market = self
+            .get_market_by_identifier(&params.market_identifier)
+            .await?;
+
+        if market.status != "active" {
+            return Err(anyhow!("Market is not active"));
+        }
+
+        let amount: u64 = params
+            .amount
+            .parse()
+            .map_err(|_| anyhow!("Invalid amount"))?;
+        if amount == 0 {
+            return Err(anyhow!("Amount must be greater than 0"));
+        }
+
+        let side = self
+            .bet_side(&market, params.position, params.outcome)
+            .await?;
+        let (pools, index) = {
+            let mut conn = self.pool.acquire().await?;
+            self.check_risk_limits(&mut conn, &market, None, amount)
+                .await?;
+            Self::side_pools(&mut conn, &market.id, side).await?
+        };
+        let pricing = price_bet(&pools, index, amount as f64);
+
+        let estimated_payout = amount as f64 * pricing.odds_after;
+        let fee = payout_fee(estimated_payout, self.quotes.fee_bps);
+
+        let projected_apy = YieldAllocator::new(self.pool.clone(), &self.yield_allocation)
+            .plan_for_market(&market.id)
+            .await?
+            .blended_apy();
+        let now = chrono::Utc::now();
+        let days_left = (market.end_date - now.naive_utc()).num_seconds() as f64 / 86_400.0;
+
+        let expires_at = now + chrono::Duration::seconds(self.quotes.ttl_secs);
+        let (position, outcome) = side.split();
+        let quote_id = sign_quote(
+            &QuoteClaims {
+                market_id: market.id.clone(),
+                position,
+                outcome,
+                amount: params.amount.clone(),
+                user_address: params.user_address.to_lowercase(),
+                nonce: Uuid::new_v4().simple().to_string(),
+                odds: pricing.odds_after,
+                expires_at: expires_at.timestamp(),
+            },
+            &self.quote_secret,
+        )?;
+
+        Ok(BetQuote {
+            quote_id,
+            market_id: market.id,
+            position,
+            outcome,
+            amount: params.amount,
+            probability_before: pricing.probability_before,
+            probability_after: pricing.probability_after,
+            odds_before: pricing.odds_before,
+            odds_after: pricing.odds_after,
+            price_impact: pricing.price_impact,
+            estimated_payout,
+            fee,
+            net_payout: estimated_payout - fee,
+            projected_apy,
+            projected_yield: projected_yield(
+                amount as f64,
+                projected_apy,
+                days_left,
+                self.compounding,
+            ),
+            end_date: market.end_date,
+            expires_at,
+        })
+    }
+
+    /// Enforces the market's risk limits, turning violations into a `BetRejection`.
+    async fn check_risk_limits(
+        &self,
+        conn: &mut PgConnection,
+        market: &MarketRecord,
+        user_address: Option<&str>,
+        amount: u64,
+    ) -> Result<()> {
+        let result = RiskLimitService::check_bet(
+            conn,
+            &market.id,
+            user_address,
+            &amount.into(),
+            market.end_date,
+        )
+        .await;
+
+        match result {
+            Err(e) => match e.downcast::<RiskViolation>() {
+                Ok(violation) => {
+                    warn!(
+                        "Bet on market {} refused: {} ({})",
+                        market.id,
+                        violation,
+                        violation.code()
+                    );
+                    Err(BetRejection::from(violation).into())
+                }
+                Err(e) => Err(e),
+            },
+            Ok(()) => Ok(()),
+        }
+    }
+
+    /// Refuses the bet unless `quote_id` is a live quote issued to this bettor for exactly this
+    /// bet and the odds have not slipped more than allowed since.
+    #[allow(clippy::too_many_arguments)]
+    fn check_quote(
+        &self,
+        quote_id: &str,
+        market_id: &str,
+        user_address: &str,
+        side: BetSide,
+        amount: &str,
+        current_odds: f64,
+        max_slippage_bps: Option<u32>,
+    ) -> Result<QuoteClaims> {
+        let claims = verify_quote(quote_id, &self.quote_secret, chrono::Utc::now().timestamp())
+            .map_err(|e| BetRejection::InvalidQuote(e.to_string()))?;
+
+        if claims.market_id != market_id {
+            return Err(BetRejection::QuoteMismatch("market").into());
+        }
+        if !claims.user_address.eq_ignore_ascii_case(user_address) {
+            return Err(BetRejection::QuoteMismatch("user").into());
+        }
+        if (claims.position, claims.outcome) != side.split() {
+            return Err(BetRejection::QuoteMismatch("side").into());
+        }
+        if claims.amount != amount {
+            return Err(BetRejection::QuoteMismatch("amount").into());
+        }
+
+        let max_bps = max_slippage_bps.unwrap_or(self.quotes.default_max_slippage_bps);
+        let slipped = slippage(claims.odds, current_odds);
+        if slipped * 10_000.0 > max_bps as f64 {
+            return Err(BetRejection::SlippageExceeded {
+                quoted: claims.odds,
+                current: current_odds,
+                slippage_bps: (slipped * 10_000.0).round() as u32,
+                max_bps,
+            }
+            .into());
+        }
+
+        Ok(claims)
+    }
+
+    /// Marks the quote's nonce as spent so it cannot back a second bet.
+    async fn consume_quote(conn: &mut PgConnection, claims: &QuoteClaims) -> Result<()> {
+        sqlx::query!(r#"DELETE FROM used_quotes WHERE "expiresAt" < NOW()"#)
+            .execute(&mut *conn)
+            .await?;
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO used_quotes (nonce, "userAddress", "expiresAt")
+            VALUES ($1, $2, to_timestamp($3)::timestamp)
+            ON CONFLICT (nonce) DO NOTHING
+            "#,
+            claims.nonce,
+            claims.user_address,
+            claims.expires_at as f64,
+        )
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+        if inserted == 0 {
+            return Err(BetRejection::InvalidQuote("Quote has already been used".into()).into());
+        }
+        Ok(())
+    }
+
     pub async fn claim_winnings(&self, params: ClaimWinningsParams) -> Result<ClaimWinningsResult> {
         info!("Claiming winnings for market: {}", params.market_identifier);
 
@@ -184,10 +534,9 @@ impl BettingService {
 
         let blockchain_market_id = market.blockchain_market_id.unwrap() as u64;
 
-        let contract_addr =
-            std::env::var("APTOS_CONTRACT_ADDRESS").unwrap_or_else(|_| self.module_address.clone());
+        let contract_addr = self.contract_address.clone();
 
-        let (tx_hash, winning_amount, yield_share) = self
+        let (submitted, winning_amount, yield_share) = self
             .submit_claim_transaction(
                 &params.user_address,
                 &contract_addr,
@@ -196,7 +545,8 @@ impl BettingService {
             )
             .await?;
 
-        info!("Claim transaction submitted: {}", tx_hash);
+        info!("Claim transaction submitted: {}", submitted.hash);
+        let tx_hash = submitted.hash.clone();
 
         let total_claimed = winning_amount + yield_share;
 
@@ -205,9 +555,12 @@ impl BettingService {
             UPDATE bets_extended
             SET status = 'claimed',
                 payout = $1,
+                "txHash" = $5,
+                "txStatus" = $6,
                 "updatedAt" = NOW()
             WHERE "marketId" = $2
-              AND "userId" = $3
+              AND ("userId" IN (SELECT id FROM users WHERE address = LOWER($3))
+                   OR LOWER("userId") = LOWER($3))
               AND "blockchainBetId" = $4
             "#,
             total_claimed
@@ -217,12 +570,20 @@ impl BettingService {
             market.id,
             params.user_address,
             params.bet_index as i64,
+            tx_hash,
+            ROW_PENDING,
         )
         .execute(&self.pool)
         .await?;
 
         let bet = sqlx::query!(
-            r#"SELECT id FROM bets_extended WHERE "marketId" = $1 AND "userId" = $2 AND "blockchainBetId" = $3"#,
+            r#"
+            SELECT id FROM bets_extended
+            WHERE "marketId" = $1
+              AND ("userId" IN (SELECT id FROM users WHERE address = LOWER($2))
+                   OR LOWER("userId") = LOWER($2))
+              AND "blockchainBetId" = $3
+            "#,
             market.id,
             params.user_address,
             params.bet_index as i64
@@ -230,6 +591,20 @@ impl BettingService {
         .fetch_one(&self.pool)
         .await?;
 
+        self.record_transaction(
+            &self.pool,
+            NewChainTransaction {
+                kind: TxKind::ClaimWinnings,
+                sender: &submitted.sender,
+                payload: &submitted.payload,
+                hash: &tx_hash,
+                market_id: Some(&market.id),
+                bet_id: Some(&bet.id),
+            },
+        )
+        .await?;
+        self.invalidate_cache(blockchain_market_id as i64).await;
+
         Ok(ClaimWinningsResult {
             bet_id: bet.id,
             winning_amount: winning_amount.to_string(),
@@ -243,7 +618,8 @@ impl BettingService {
         let market = sqlx::query_as!(
             MarketRecord,
             r#"
-            SELECT id, "blockchainMarketId" as blockchain_market_id, status
+            SELECT id, "blockchainMarketId" as blockchain_market_id, status,
+                   "marketType" as market_type, "endDate" as end_date
             FROM markets_extended
             WHERE id = $1 OR "adjTicker" = $1 OR "blockchainMarketId"::text = $1
             LIMIT 1
@@ -257,37 +633,95 @@ impl BettingService {
         Ok(market)
     }
 
+    /// The `users` id of `address`, creating the user if needed. Addresses are stored
+    /// lowercased, as the indexer sync does, so bets from either path share one user.
+    async fn user_id(conn: &mut PgConnection, address: &str) -> Result<String> {
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO users (id, address, "createdAt", "updatedAt")
+            VALUES ($1, $2, NOW(), NOW())
+            ON CONFLICT (address) DO UPDATE SET address = EXCLUDED.address
+            RETURNING id
+            "#,
+            Uuid::new_v4().to_string(),
+            address.to_lowercase()
+        )
+        .fetch_one(conn)
+        .await?;
+        Ok(id)
+    }
+
+    /// Checks the bet's side against the market type: binary markets take a YES/NO `position`,
+    /// categorical markets an `outcome` index.
+    async fn bet_side(
+        &self,
+        market: &MarketRecord,
+        position: Option<bool>,
+        outcome: Option<i32>,
+    ) -> Result<BetSide> {
+        match market.market_type.parse::<MarketType>()? {
+            MarketType::Binary => match (position, outcome) {
+                (Some(position), None) => Ok(BetSide::Position(position)),
+                (None, Some(_)) => Err(anyhow!(
+                    "Market is binary; bet with position instead of outcome"
+                )),
+                _ => Err(anyhow!("Binary market bets need a position (true = YES)")),
+            },
+            MarketType::Categorical => {
+                let index = match (position, outcome) {
+                    (None, Some(index)) => index,
+                    _ => return Err(anyhow!("Categorical market bets need an outcome index")),
+                };
+                let outcomes = MarketOutcomeService::new(self.pool.clone())
+                    .outcomes(&market.id)
+                    .await?;
+                if !outcomes.iter().any(|o| o.index == index) {
+                    return Err(anyhow!(
+                        "Outcome {} does not exist; market has {} outcomes",
+                        index,
+                        outcomes.len()
+                    ));
+                }
+                Ok(BetSide::Outcome(index))
+            }
+        }
+    }
+
     async fn submit_bet_transaction(
         &self,
         _user_address: &str,
         contract_addr: &str,
         market_id: u64,
-        position: bool,
+        side: BetSide,
         amount: u64,
-    ) -> Result<(String, u64)> {
-        let _private_key = std::env::var("USER_PRIVATE_KEY")
-            .map_err(|_| anyhow!("USER_PRIVATE_KEY not configured"))?;
-
-        let function_id = format!("{}::{}::place_bet", self.module_address, self.module_name);
+    ) -> Result<(SubmittedTransaction, u64)> {
+        let (function_name, side_argument) = match side {
+            BetSide::Position(position) => ("place_bet", json!(position)),
+            BetSide::Outcome(index) => ("place_outcome_bet", json!(index.to_string())),
+        };
+        let function_id = format!(
+            "{}::{}::{}",
+            self.module_address, self.module_name, function_name
+        );
 
         let payload = json!({
             "type": "entry_function_payload",
             "function": function_id,
-            "type_arguments": ["0x1::aptos_coin::AptosCoin"],
+            "type_arguments": [self.token_type],
             "arguments": [
                 contract_addr,
                 market_id.to_string(),
-                position,
+                side_argument,
                 amount.to_string()
             ]
         });
 
         info!("Submitting bet transaction: {:?}", payload);
+        let submitted = self.sign_transaction(payload).await?;
 
         let mock_bet_id = (chrono::Utc::now().timestamp() % 100000) as u64;
-        let mock_tx_hash = format!("0x{:x}", mock_bet_id);
 
-        Ok((mock_tx_hash, mock_bet_id))
+        Ok((submitted, mock_bet_id))
     }
 
     async fn submit_claim_transaction(
@@ -296,10 +730,7 @@ impl BettingService {
         contract_addr: &str,
         market_id: u64,
         bet_index: u64,
-    ) -> Result<(String, u64, u64)> {
-        let _private_key = std::env::var("USER_PRIVATE_KEY")
-            .map_err(|_| anyhow!("USER_PRIVATE_KEY not configured"))?;
-
+    ) -> Result<(SubmittedTransaction, u64, u64)> {
         let function_id = format!(
             "{}::{}::claim_winnings",
             self.module_address, self.module_name
@@ -308,7 +739,7 @@ impl BettingService {
         let payload = json!({
             "type": "entry_function_payload",
             "function": function_id,
-            "type_arguments": ["0x1::aptos_coin::AptosCoin"],
+            "type_arguments": [self.token_type],
             "arguments": [
                 contract_addr,
                 market_id.to_string(),
@@ -317,73 +748,144 @@ impl BettingService {
         });
 
         info!("Submitting claim transaction: {:?}", payload);
+        let submitted = self.sign_transaction(payload).await?;
 
-        let mock_tx_hash = format!("0x{:x}", chrono::Utc::now().timestamp());
         let mock_winning = 1000u64;
         let mock_yield = 50u64;
 
-        Ok((mock_tx_hash, mock_winning, mock_yield))
+        Ok((submitted, mock_winning, mock_yield))
     }
 
-    async fn calculate_bet_odds(
-        &self,
-        market_id: &str,
-        position: bool,
-        amount: &str,
-    ) -> Result<f64> {
-        let market = sqlx::query!(
-            r#"
-            SELECT "yesPoolSize", "noPoolSize", "totalPoolSize"
-            FROM markets_extended
-            WHERE id = $1
-            "#,
-            market_id
-        )
-        .fetch_one(&self.pool)
-        .await?;
-
-        let amount_decimal: f64 = amount
-            .parse::<f64>()
-            .map_err(|_| anyhow!("Invalid amount format"))?;
-
-        let yes_pool: f64 = market.yesPoolSize.to_string().parse::<f64>().unwrap_or(0.0);
-        let no_pool: f64 = market.noPoolSize.to_string().parse::<f64>().unwrap_or(0.0);
+    /// Signs `payload` with the next user account.
+    async fn sign_transaction(&self, payload: Value) -> Result<SubmittedTransaction> {
+        let signers = self
+            .signers
+            .as_ref()
+            .ok_or_else(|| anyhow!("No signer available for bet transactions"))?;
+        let signed = signers.sign_transaction(SignerRole::User, &payload).await?;
+
+        Ok(SubmittedTransaction {
+            hash: signed.hash(),
+            sender: signed.account.address,
+            payload,
+        })
+    }
 
-        let (final_yes_pool, final_no_pool) = if position {
-            (yes_pool + amount_decimal, no_pool)
-        } else {
-            (yes_pool, no_pool + amount_decimal)
-        };
+    /// Tells every replica's response cache that the market changed.
+    async fn invalidate_cache(&self, blockchain_market_id: i64) {
+        if let Err(e) = publish_invalidation(&self.pool, Some(blockchain_market_id)).await {
+            warn!("Failed to publish cache invalidation: {}", e);
+        }
+    }
 
-        let total_pool = final_yes_pool + final_no_pool;
+    /// Tracks a submitted transaction until the confirmation job settles it.
+    async fn record_transaction<'e>(
+        &self,
+        executor: impl PgExecutor<'e>,
+        tx: NewChainTransaction<'_>,
+    ) -> Result<()> {
+        ChainTransactionService::record_in(executor, tx, self.tx_expiration_secs).await
+    }
 
-        let raw_odds = if position {
-            if final_yes_pool > 0.0 {
-                total_pool / final_yes_pool
-            } else {
-                1.0
+    /// Current pools of the market as outcomes, and the index `side` bets on.
+    /// Binary markets are `[yes, no]`.
+    async fn side_pools(
+        conn: &mut PgConnection,
+        market_id: &str,
+        side: BetSide,
+    ) -> Result<(Vec<f64>, usize)> {
+        match side {
+            BetSide::Position(position) => {
+                let market = sqlx::query!(
+                    r#"
+                    SELECT "yesPoolSize", "noPoolSize"
+                    FROM markets_extended
+                    WHERE id = $1
+                    "#,
+                    market_id
+                )
+                .fetch_one(&mut *conn)
+                .await?;
+
+                let pools = vec![
+                    market.yesPoolSize.to_f64().unwrap_or(0.0),
+                    market.noPoolSize.to_f64().unwrap_or(0.0),
+                ];
+                Ok((pools, if position { 0 } else { 1 }))
             }
-        } else if final_no_pool > 0.0 {
-            total_pool / final_no_pool
-        } else {
-            1.0
-        };
-
-        let odds = raw_odds.max(1.0);
+            BetSide::Outcome(index) => {
+                let outcomes = MarketOutcomeService::outcomes_in(&mut *conn, market_id).await?;
+                let position = outcomes
+                    .iter()
+                    .position(|o| o.index == index)
+                    .ok_or_else(|| anyhow!("Outcome {} does not exist", index))?;
+                let pools = outcomes
+                    .iter()
+                    .map(|o| o.pool_size.to_f64().unwrap_or(0.0))
+                    .collect();
+                Ok((pools, position))
+            }
+        }
+    }
 
-        Ok(odds)
+    /// Parimutuel odds of the bet once its own stake is in the pool.
+    async fn calculate_bet_odds(
+        &self,
+        conn: &mut PgConnection,
+        market_id: &str,
+        side: BetSide,
+        amount: f64,
+    ) -> Result<f64> {
+        let (pools, index) = Self::side_pools(conn, market_id, side).await?;
+        Ok(price_bet(&pools, index, amount).odds_after)
     }
 
     async fn update_market_pools(
-        &self,
+        conn: &mut PgConnection,
         market_id: &str,
-        position: bool,
+        side: BetSide,
         amount: &str,
     ) -> Result<()> {
         let amount_decimal = amount
             .parse::<sqlx::types::BigDecimal>()
             .unwrap_or_default();
 
+        let position = match side {
+            BetSide::Position(position) => position,
+            BetSide::Outcome(index) => {
+                sqlx::query!(
+                    r#"
+                    UPDATE market_outcomes
+                    SET "poolSize" = "poolSize" + $1,
+                        "betCount" = "betCount" + 1,
+                        "updatedAt" = NOW()
+                    WHERE "marketId" = $2 AND "outcomeIndex" = $3
+                    "#,
+                    amount_decimal,
+                    market_id,
+                    index
+                )
+                .execute(&mut *conn)
+                .await?;
+                sqlx::query!(
+                    r#"
+                    UPDATE markets_extended
+                    SET "totalPoolSize" = "totalPoolSize" + $1,
+                        "updatedAt" = NOW()
+                    WHERE id = $2
+                    "#,
+                    amount_decimal,
+                    market_id
+                )
+                .execute(&mut *conn)
+                .await?;
+
+                return MarketOutcomeService::refresh_pools_in(conn, Some(market_id))
+                    .await
+                    .map(|_| ());
+            }
+        };
+
         if position {
             sqlx::query!(
                 r#"
@@ -397,7 +899,7 @@ impl BettingService {
                 amount_decimal,
                 market_id
             )
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
         } else {
             sqlx::query!(
@@ -412,7 +914,7 @@ impl BettingService {
                 amount_decimal,
                 market_id
             )
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
         }
 
@@ -425,17 +927,26 @@ impl BettingService {
             market_id, bet_id
         );
 
-        let realtime_sync = super::realtime_sync::RealtimeSyncService::new(self.pool.clone());
+        let realtime_sync = super::realtime_sync::RealtimeSyncService::new(
+            self.pool.clone(),
+            &self.yield_allocation,
+        );
 
         if let Err(e) = realtime_sync.sync_market_immediately(market_id).await {
             error!("Failed to sync market {} immediately: {}", market_id, e);
 
             let sync_service =
                 super::blockchain_sync::BlockchainSyncService::new(self.pool.clone());
-            if let Err(e2) = sync_service.sync_bets().await {
-                error!("Fallback sync also failed: {}", e2);
-            } else if let Err(e3) = sync_service.update_market_stats().await {
-                error!("Failed to update market stats in fallback: {}", e3);
+            match sync_service.sync_bets().await {
+                Err(e2) => error!("Fallback sync also failed: {}", e2),
+                Ok(result) => {
+                    if let Err(e3) = sync_service
+                        .update_market_stats_for_markets(&result.touched_markets)
+                        .await
+                    {
+                        error!("Failed to update market stats in fallback: {}", e3);
+                    }
+                }
             }
         }
 
@@ -449,7 +960,7 @@ impl BettingService {
     }
 
     async fn call_webhook_if_configured(&self, market_id: &str, bet_id: u64) {
-        if let Ok(webhook_url) = std::env::var("POST_BET_WEBHOOK_URL") {
+        if let Some(webhook_url) = &self.post_bet_webhook_url {
             let client = reqwest::Client::new();
             let payload = serde_json::json!({
                 "type": "bet_placed",
@@ -458,7 +969,7 @@ impl BettingService {
                 "timestamp": chrono::Utc::now().to_rfc3339()
             });
 
-            match client.post(&webhook_url).json(&payload).send().await {
+            match client.post(webhook_url).json(&payload).send().await {
                 Ok(response) => {
                     if response.status().is_success() {
                         info!("Successfully notified webhook after bet placement");
@@ -482,4 +993,58 @@ struct MarketRecord {
     id: String,
     blockchain_market_id: Option<i64>,
     status: String,
+    market_type: String,
+    end_date: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BetSide {
+    Position(bool),
+    Outcome(i32),
+}
+
+impl BetSide {
+    /// `(position, outcome)` as stored on the bet.
+    fn split(self) -> (Option<bool>, Option<i32>) {
+        match self {
+            BetSide::Position(position) => (Some(position), None),
+            BetSide::Outcome(index) => (None, Some(index)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims() -> QuoteClaims {
+        QuoteClaims {
+            market_id: "m1".into(),
+            position: Some(true),
+            outcome: None,
+            amount: "1000".into(),
+            user_address: "0xabc".into(),
+            nonce: "3f1a9c0b".into(),
+            odds: 1.8,
+            expires_at: chrono::Utc::now().timestamp() + 60,
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_quote_can_only_be_used_once(pool: PgPool) {
+        let mut conn = pool.acquire().await.unwrap();
+
+        let quote = claims();
+        BettingService::consume_quote(&mut conn, &quote)
+            .await
+            .unwrap();
+
+        let err = BettingService::consume_quote(&mut conn, &quote)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BetRejection>(),
+            Some(BetRejection::InvalidQuote(_))
+        ));
+    }
 }
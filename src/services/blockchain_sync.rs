@@ -1,28 +1,97 @@
 use anyhow::Result;
 use bigdecimal::ToPrimitive;
+use chrono::NaiveDateTime;
+use serde::Serialize;
 use sqlx::PgPool;
+use std::collections::BTreeSet;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use super::market_outcomes::MarketOutcomeService;
+
+/// `sync_status` event types holding the high-water marks of market and bet sync.
+const MARKET_SYNC: &str = "MarketSync";
+const BET_SYNC: &str = "BetSync";
+
+const MARKET_BATCH_SIZE: i64 = 100;
+const BET_BATCH_SIZE: i64 = 500;
+/// Most rows at or below the high-water mark retried per run because they have no
+/// extended row. Each run continues after the last row the previous one retried.
+const GAP_SCAN_LIMIT: i64 = 500;
+
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SyncResult {
     pub event_type: String,
+    /// Indexer rows scanned, above the high-water mark or by the gap scan.
     pub processed: i64,
     pub errors: i64,
+    /// Extended rows written.
     pub new_events: i64,
     pub skipped: i64,
+    /// Highest transaction version scanned; rows below it that were left behind are
+    /// retried by the gap scan.
+    pub high_water_version: i64,
+    /// Transaction version and id of the last row the gap scan retried, when it filled a
+    /// page; the next run's gap scan continues after it, or from the oldest row when `None`.
+    #[serde(skip)]
+    pub gap_cursor: Option<(i64, i64)>,
+    /// Blockchain ids of markets that got new bets.
+    #[serde(skip)]
+    pub touched_markets: Vec<i64>,
+}
+
+impl SyncResult {
+    fn new(event_type: &str) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            processed: 0,
+            errors: 0,
+            new_events: 0,
+            skipped: 0,
+            high_water_version: 0,
+            gap_cursor: None,
+            touched_markets: Vec::new(),
+        }
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SyncSummary {
     pub total_processed: i64,
     pub total_errors: i64,
     pub results: Vec<SyncResult>,
+    /// Markets whose stats were recomputed because they got new bets.
+    pub markets_updated: u64,
     pub duration_ms: u128,
 }
 
+/// A row of `sync_status` for market or bet sync.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncProgress {
+    pub event_type: String,
+    pub last_version: i64,
+    pub last_sync_time: NaiveDateTime,
+    pub last_scanned: i32,
+    pub last_written: i32,
+    pub last_skipped: i32,
+    pub total_scanned: i64,
+    pub total_written: i64,
+}
+
+/// What happened to an indexer bet copied into `bets_extended`.
+enum BetWrite {
+    Written,
+    /// Already synced, e.g. by the event listener.
+    Exists,
+    /// The bet or its market is not available yet.
+    Skipped,
+}
+
 pub struct BlockchainSyncService {
     pool: PgPool,
 }
@@ -32,69 +101,121 @@ impl BlockchainSyncService {
         Self { pool }
     }
 
+    /// Copies indexer markets above the `MarketSync` high-water mark into `markets_extended`,
+    /// after retrying markets at or below it that still have no extended row.
     pub async fn sync_markets(&self) -> Result<SyncResult> {
         let start = std::time::Instant::now();
-        let mut result = SyncResult {
-            event_type: "MarketSync".to_string(),
-            processed: 0,
-            errors: 0,
-            new_events: 0,
-            skipped: 0,
-        };
+        let mut result = SyncResult::new(MARKET_SYNC);
+        let mark = self.high_water_mark(MARKET_SYNC).await?;
+        let mut cursor = mark;
 
-        info!("Starting market sync from indexer tables");
+        info!("Starting market sync from indexer version {}", mark);
 
-        let markets = sqlx::query!(
+        let gap_cursor = self.gap_cursor(MARKET_SYNC).await?;
+        let gaps = sqlx::query!(
             r#"
-            SELECT m.market_id, m.question, m.end_time, 
-                   m.transaction_version, m.resolved as status
+            SELECT m.market_id, m.transaction_version
             FROM markets m
-            LEFT JOIN markets_extended me ON m.market_id = me."blockchainMarketId"
-            WHERE me.id IS NULL
-            ORDER BY m.transaction_version DESC
-            LIMIT 100
-            "#
+            WHERE m.transaction_version <= $1
+              AND ($3::bigint IS NULL OR (m.transaction_version, m.market_id) > ($3, $4))
+              AND NOT EXISTS (
+                  SELECT 1 FROM markets_extended me WHERE me."blockchainMarketId" = m.market_id
+              )
+            ORDER BY m.transaction_version, m.market_id
+            LIMIT $2
+            "#,
+            mark,
+            GAP_SCAN_LIMIT,
+            gap_cursor.map(|(version, _)| version),
+            gap_cursor.map(|(_, id)| id)
         )
         .fetch_all(&self.pool)
         .await?;
+        if !gaps.is_empty() {
+            info!("Retrying {} markets below the high-water mark", gaps.len());
+        }
+        if gaps.len() as i64 >= GAP_SCAN_LIMIT {
+            result.gap_cursor = gaps.last().map(|m| (m.transaction_version, m.market_id));
+        }
+        for market in gaps {
+            self.sync_market_row(market.market_id, &mut result).await;
+        }
 
-        info!("Found {} markets to sync", markets.len());
+        loop {
+            let markets = sqlx::query!(
+                r#"
+                SELECT market_id, transaction_version
+                FROM markets
+                WHERE transaction_version > $1
+                  AND transaction_version <= (
+                      SELECT MAX(transaction_version) FROM (
+                          SELECT transaction_version FROM markets
+                          WHERE transaction_version > $1
+                          ORDER BY transaction_version
+                          LIMIT $2
+                      ) batch
+                  )
+                ORDER BY transaction_version, market_id
+                "#,
+                cursor,
+                MARKET_BATCH_SIZE
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            let Some(last) = markets.last() else {
+                break;
+            };
+            cursor = last.transaction_version;
+            let full_batch = markets.len() as i64 >= MARKET_BATCH_SIZE;
 
-        for market in markets {
-            result.processed += 1;
+            for market in markets {
+                self.sync_market_row(market.market_id, &mut result).await;
+            }
 
-            let market_id_val = market.market_id;
-            match self
-                .create_extended_market(market.market_id, chrono::Utc::now().timestamp())
-                .await
-            {
-                Ok(_) => {
-                    result.new_events += 1;
-                    info!("Created extended record for market {}", market_id_val);
-                }
-                Err(e) => {
-                    result.errors += 1;
-                    error!("Failed to create extended market {}: {}", market_id_val, e);
-                }
+            if !full_batch {
+                break;
             }
         }
 
+        result.high_water_version = cursor;
+        self.record_progress(&result).await?;
+
         let duration = start.elapsed();
         info!(
-            "Market sync completed in {:?}: {} processed, {} new, {} errors",
-            duration, result.processed, result.new_events, result.errors
+            "Market sync completed in {:?}: {} scanned, {} written, {} errors, high-water version {}",
+            duration, result.processed, result.new_events, result.errors, result.high_water_version
         );
 
         Ok(result)
     }
 
-    async fn create_extended_market(&self, market_id: i64, end_time: i64) -> Result<()> {
+    async fn sync_market_row(&self, market_id: i64, result: &mut SyncResult) {
+        result.processed += 1;
+
+        match self
+            .create_extended_market(market_id, chrono::Utc::now().timestamp())
+            .await
+        {
+            Ok(true) => {
+                result.new_events += 1;
+                info!("Created extended record for market {}", market_id);
+            }
+            Ok(false) => {}
+            Err(e) => {
+                result.errors += 1;
+                error!("Failed to create extended market {}: {}", market_id, e);
+            }
+        }
+    }
+
+    /// Returns false when the market already had an extended row.
+    async fn create_extended_market(&self, market_id: i64, end_time: i64) -> Result<bool> {
         let id = Uuid::new_v4().to_string();
         let end_date = chrono::DateTime::from_timestamp(end_time, 0)
             .map(|dt| dt.naive_utc())
             .unwrap_or_else(|| chrono::Utc::now().naive_utc());
 
-        sqlx::query!(
+        let inserted = sqlx::query!(
             r#"
             INSERT INTO markets_extended (
                 id, "blockchainMarketId", platform, status, probability,
@@ -110,73 +231,238 @@ impl BlockchainSyncService {
         .execute(&self.pool)
         .await?;
 
-        Ok(())
+        Ok(inserted.rows_affected() > 0)
     }
 
+    /// Copies indexer bets above the `BetSync` high-water mark into `bets_extended`. The mark
+    /// moves past bets that fail or whose market is not synced yet; they are picked up again by
+    /// the gap scan at the start of later runs, which retries up to `GAP_SCAN_LIMIT` bets at or
+    /// below the mark that have no extended row, oldest first, continuing after the last bet the
+    /// previous run retried and starting over once it reaches the mark.
     pub async fn sync_bets(&self) -> Result<SyncResult> {
         let start = std::time::Instant::now();
-        let mut result = SyncResult {
-            event_type: "BetSync".to_string(),
-            processed: 0,
-            errors: 0,
-            new_events: 0,
-            skipped: 0,
-        };
+        let mut result = SyncResult::new(BET_SYNC);
+        let mark = self.high_water_mark(BET_SYNC).await?;
+        let mut cursor = mark;
+        let mut touched = BTreeSet::new();
 
-        info!("Starting bet sync from indexer tables");
+        info!("Starting bet sync from indexer version {}", mark);
 
-        let bets = sqlx::query!(
+        let gap_cursor = self.gap_cursor(BET_SYNC).await?;
+        let gaps = sqlx::query!(
             r#"
-            SELECT b.bet_id, b.user_addr, b.amount
+            SELECT b.bet_id, b.market_id, b.user_addr, b.amount, b.transaction_version
             FROM bets b
-            LEFT JOIN bets_extended be ON b.bet_id = be."blockchainBetId"
-            WHERE be.id IS NULL
-            ORDER BY b.transaction_version DESC
-            LIMIT 500
-            "#
+            WHERE b.transaction_version <= $1
+              AND ($3::bigint IS NULL OR (b.transaction_version, b.bet_id) > ($3, $4))
+              AND NOT EXISTS (
+                  SELECT 1 FROM bets_extended be WHERE be."blockchainBetId" = b.bet_id
+              )
+            ORDER BY b.transaction_version, b.bet_id
+            LIMIT $2
+            "#,
+            mark,
+            GAP_SCAN_LIMIT,
+            gap_cursor.map(|(version, _)| version),
+            gap_cursor.map(|(_, id)| id)
         )
         .fetch_all(&self.pool)
         .await?;
+        if !gaps.is_empty() {
+            info!("Retrying {} bets below the high-water mark", gaps.len());
+        }
+        if gaps.len() as i64 >= GAP_SCAN_LIMIT {
+            result.gap_cursor = gaps.last().map(|b| (b.transaction_version, b.bet_id));
+        }
+        for bet in gaps {
+            self.sync_bet_row(
+                bet.bet_id,
+                bet.market_id,
+                &bet.user_addr,
+                bet.amount.to_i64().unwrap_or(0),
+                &mut result,
+                &mut touched,
+            )
+            .await;
+        }
 
-        info!("Found {} bets to sync", bets.len());
-
-        for bet in bets {
-            result.processed += 1;
-
-            let user_id = match self.get_or_create_user(&bet.user_addr).await {
-                Ok(id) => id,
-                Err(e) => {
-                    error!("Failed to get/create user {}: {}", bet.user_addr, e);
-                    result.errors += 1;
-                    continue;
-                }
+        loop {
+            let bets = sqlx::query!(
+                r#"
+                SELECT bet_id, market_id, user_addr, amount, transaction_version
+                FROM bets
+                WHERE transaction_version > $1
+                  AND transaction_version <= (
+                      SELECT MAX(transaction_version) FROM (
+                          SELECT transaction_version FROM bets
+                          WHERE transaction_version > $1
+                          ORDER BY transaction_version
+                          LIMIT $2
+                      ) batch
+                  )
+                ORDER BY transaction_version, bet_id
+                "#,
+                cursor,
+                BET_BATCH_SIZE
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            let Some(last) = bets.last() else {
+                break;
             };
+            cursor = last.transaction_version;
+            let full_batch = bets.len() as i64 >= BET_BATCH_SIZE;
+
+            for bet in bets {
+                self.sync_bet_row(
+                    bet.bet_id,
+                    bet.market_id,
+                    &bet.user_addr,
+                    bet.amount.to_i64().unwrap_or(0),
+                    &mut result,
+                    &mut touched,
+                )
+                .await;
+            }
 
-            let amount_i64 = bet.amount.to_i64().unwrap_or(0);
-
-            match self
-                .create_extended_bet(bet.bet_id, &user_id, amount_i64)
-                .await
-            {
-                Ok(_) => {
-                    result.new_events += 1;
-                }
-                Err(e) => {
-                    error!("Failed to create extended bet {}: {}", bet.bet_id, e);
-                    result.errors += 1;
-                }
+            if !full_batch {
+                break;
             }
         }
 
+        result.high_water_version = cursor;
+        result.touched_markets = touched.into_iter().collect();
+        self.record_progress(&result).await?;
+
         let duration = start.elapsed();
         info!(
-            "Bet sync completed in {:?}: {} processed, {} new, {} errors",
-            duration, result.processed, result.new_events, result.errors
+            "Bet sync completed in {:?}: {} scanned, {} written, {} skipped, {} errors, high-water version {}",
+            duration,
+            result.processed,
+            result.new_events,
+            result.skipped,
+            result.errors,
+            result.high_water_version
         );
 
         Ok(result)
     }
 
+    async fn sync_bet_row(
+        &self,
+        bet_id: i64,
+        market_id: i64,
+        user_addr: &str,
+        amount: i64,
+        result: &mut SyncResult,
+        touched: &mut BTreeSet<i64>,
+    ) {
+        result.processed += 1;
+
+        let user_id = match self.get_or_create_user(user_addr).await {
+            Ok(id) => id,
+            Err(e) => {
+                error!("Failed to get/create user {}: {}", user_addr, e);
+                result.errors += 1;
+                return;
+            }
+        };
+
+        match self.create_extended_bet(bet_id, &user_id, amount).await {
+            Ok(BetWrite::Written) => {
+                result.new_events += 1;
+                touched.insert(market_id);
+            }
+            Ok(BetWrite::Exists) => {}
+            Ok(BetWrite::Skipped) => result.skipped += 1,
+            Err(e) => {
+                error!("Failed to create extended bet {}: {}", bet_id, e);
+                result.errors += 1;
+            }
+        }
+    }
+
+    async fn high_water_mark(&self, event_type: &str) -> Result<i64> {
+        let mark = sqlx::query_scalar!(
+            r#"SELECT "lastVersion" FROM sync_status WHERE "eventType" = $1"#,
+            event_type
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(mark.unwrap_or(0))
+    }
+
+    /// Where the gap scan of `event_type` resumes; `None` to start from the oldest row.
+    async fn gap_cursor(&self, event_type: &str) -> Result<Option<(i64, i64)>> {
+        let cursor = sqlx::query!(
+            r#"
+            SELECT "gapCursorVersion" as version, "gapCursorId" as id
+            FROM sync_status
+            WHERE "eventType" = $1
+            "#,
+            event_type
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(cursor.and_then(|c| c.version.zip(c.id)))
+    }
+
+    /// Stores a run's high-water mark, gap scan cursor, and how many rows it scanned and wrote.
+    /// The mark never moves back, e.g. when a run that started earlier finishes after a later
+    /// one.
+    async fn record_progress(&self, result: &SyncResult) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO sync_status (
+                id, "eventType", "lastVersion", "lastSyncTime", "lastScanned", "lastWritten",
+                "lastSkipped", "totalScanned", "totalWritten", "gapCursorVersion", "gapCursorId"
+            )
+            VALUES ($1, $1, $2, NOW(), $3::int, $4::int, $5, $3::int, $4::int, $6, $7)
+            ON CONFLICT ("eventType") DO UPDATE
+            SET "lastVersion" = GREATEST(sync_status."lastVersion", EXCLUDED."lastVersion"),
+                "lastSyncTime" = NOW(),
+                "lastScanned" = EXCLUDED."lastScanned",
+                "lastWritten" = EXCLUDED."lastWritten",
+                "lastSkipped" = EXCLUDED."lastSkipped",
+                "totalScanned" = sync_status."totalScanned" + EXCLUDED."lastScanned",
+                "totalWritten" = sync_status."totalWritten" + EXCLUDED."lastWritten",
+                "gapCursorVersion" = EXCLUDED."gapCursorVersion",
+                "gapCursorId" = EXCLUDED."gapCursorId"
+            "#,
+            result.event_type,
+            result.high_water_version,
+            result.processed as i32,
+            result.new_events as i32,
+            result.skipped as i32,
+            result.gap_cursor.map(|(version, _)| version),
+            result.gap_cursor.map(|(_, id)| id)
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// High-water marks and scan metrics of market and bet sync.
+    pub async fn progress(&self) -> Result<Vec<SyncProgress>> {
+        let progress = sqlx::query_as!(
+            SyncProgress,
+            r#"
+            SELECT "eventType" as event_type, "lastVersion" as last_version,
+                   "lastSyncTime" as last_sync_time, "lastScanned" as last_scanned,
+                   "lastWritten" as last_written, "lastSkipped" as last_skipped,
+                   "totalScanned" as total_scanned, "totalWritten" as total_written
+            FROM sync_status
+            WHERE "eventType" IN ($1, $2)
+            ORDER BY "eventType"
+            "#,
+            MARKET_SYNC,
+            BET_SYNC
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(progress)
+    }
+
     async fn get_or_create_user(&self, address: &str) -> Result<String> {
         let normalized_addr = address.to_lowercase();
 
@@ -227,7 +513,12 @@ impl BlockchainSyncService {
         }
     }
 
-    async fn create_extended_bet(&self, bet_id: i64, user_id: &str, amount: i64) -> Result<()> {
+    async fn create_extended_bet(
+        &self,
+        bet_id: i64,
+        user_id: &str,
+        amount: i64,
+    ) -> Result<BetWrite> {
         let bet = sqlx::query!(
             r#"
             SELECT bet_id, market_id, user_addr, position, amount
@@ -243,7 +534,7 @@ impl BlockchainSyncService {
             Some(b) => b,
             None => {
                 warn!("Bet {} not found in indexer, skipping", bet_id);
-                return Ok(());
+                return Ok(BetWrite::Skipped);
             }
         };
 
@@ -261,7 +552,7 @@ impl BlockchainSyncService {
                     "Market {} not found for bet {}, skipping",
                     bet.market_id, bet_id
                 );
-                return Ok(());
+                return Ok(BetWrite::Skipped);
             }
         };
 
@@ -271,7 +562,7 @@ impl BlockchainSyncService {
 
         let position_bool = bet.position;
 
-        sqlx::query!(
+        let inserted = sqlx::query!(
             r#"
             INSERT INTO bets_extended (
                 id, "blockchainBetId", "userId", "marketId", position, amount, odds, status, "createdAt", "updatedAt"
@@ -290,9 +581,16 @@ impl BlockchainSyncService {
         .execute(&self.pool)
         .await?;
 
-        Ok(())
+        Ok(if inserted.rows_affected() > 0 {
+            BetWrite::Written
+        } else {
+            BetWrite::Exists
+        })
     }
 
+    /// Recomputes every market's stats; sync only touches markets with new bets, so this is
+    /// left to `kizo-admin sync stats`.
+    #[allow(dead_code)]
     pub async fn update_market_stats(&self) -> Result<()> {
         info!("Updating market statistics");
 
@@ -307,6 +605,7 @@ impl BlockchainSyncService {
                 "countNo" = COALESCE(subq.no_count, 0),
                 volume = COALESCE(subq.total_pool, 0),
                 probability = CASE
+                    WHEN me."marketType" = 'categorical' THEN me.probability
                     WHEN COALESCE(subq.total_pool, 0) > 0 THEN
                         ROUND((COALESCE(subq.yes_pool, 0) / subq.total_pool * 100)::numeric)::int
                     ELSE 50
@@ -331,6 +630,10 @@ impl BlockchainSyncService {
         .execute(&self.pool)
         .await?;
 
+        MarketOutcomeService::new(self.pool.clone())
+            .refresh_pools(None)
+            .await?;
+
         info!(
             "Market statistics updated for {} markets",
             updated_rows.rows_affected()
@@ -352,7 +655,27 @@ impl BlockchainSyncService {
             -1
         });
 
-        let updated_rows = sqlx::query!(
+        let updated = self
+            .update_market_stats_for_markets(&[market_id_i64])
+            .await?;
+        info!(
+            "Market {} statistics updated ({} rows affected)",
+            blockchain_market_id, updated
+        );
+        Ok(())
+    }
+
+    /// Recomputes pools, counts and probability of the given blockchain markets only, and
+    /// returns how many were updated.
+    pub async fn update_market_stats_for_markets(
+        &self,
+        blockchain_market_ids: &[i64],
+    ) -> Result<u64> {
+        if blockchain_market_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let market_uuids = sqlx::query_scalar!(
             r#"
             UPDATE markets_extended me
             SET
@@ -363,6 +686,7 @@ impl BlockchainSyncService {
                 "countNo" = COALESCE(subq.no_count, 0),
                 volume = COALESCE(subq.total_pool, 0),
                 probability = CASE
+                    WHEN me."marketType" = 'categorical' THEN me.probability
                     WHEN COALESCE(subq.total_pool, 0) > 0 THEN
                         ROUND((COALESCE(subq.yes_pool, 0) / subq.total_pool * 100)::numeric)::int
                     ELSE 50
@@ -378,22 +702,23 @@ impl BlockchainSyncService {
                     COUNT(CASE WHEN be.position = false THEN 1 END)::int as no_count
                 FROM markets_extended me2
                 LEFT JOIN bets_extended be ON be."marketId" = me2.id AND be.status = 'active'
-                WHERE me2."blockchainMarketId" = $1
+                WHERE me2."blockchainMarketId" = ANY($1)
                 GROUP BY me2."blockchainMarketId"
             ) subq
             WHERE me."blockchainMarketId" = subq."blockchainMarketId"
+            RETURNING me.id
             "#,
-            market_id_i64
+            blockchain_market_ids
         )
-        .execute(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
 
-        info!(
-            "Market {} statistics updated ({} rows affected)",
-            blockchain_market_id,
-            updated_rows.rows_affected()
-        );
-        Ok(())
+        let outcomes = MarketOutcomeService::new(self.pool.clone());
+        for market_uuid in &market_uuids {
+            outcomes.refresh_pools(Some(market_uuid)).await?;
+        }
+
+        Ok(market_uuids.len() as u64)
     }
 
     pub async fn run_full_sync(&self) -> Result<SyncSummary> {
@@ -426,21 +751,143 @@ impl BlockchainSyncService {
             }
         }
 
-        if let Err(e) = self.update_market_stats().await {
-            error!("Failed to update market stats: {}", e);
-        }
+        let touched: Vec<i64> = results
+            .iter()
+            .flat_map(|r| r.touched_markets.iter().copied())
+            .collect();
+        let markets_updated = match self.update_market_stats_for_markets(&touched).await {
+            Ok(updated) => updated,
+            Err(e) => {
+                error!("Failed to update market stats: {}", e);
+                0
+            }
+        };
 
         let duration_ms = start.elapsed().as_millis();
         info!(
-            "Full sync completed in {}ms: {} total processed, {} errors",
-            duration_ms, total_processed, total_errors
+            "Full sync completed in {}ms: {} total processed, {} errors, stats updated for {} markets",
+            duration_ms, total_processed, total_errors, markets_updated
         );
 
         Ok(SyncSummary {
             total_processed,
             total_errors,
             results,
+            markets_updated,
             duration_ms,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn indexer_bet(pool: &PgPool, bet_id: i64, market_id: i64, version: i64) {
+        sqlx::query!(
+            r#"
+            INSERT INTO bets (bet_id, market_id, user_addr, position, amount,
+                              transaction_version, transaction_block_height)
+            VALUES ($1, $2, '0xu1', true, 100, $3, $3)
+            "#,
+            bet_id,
+            market_id,
+            version
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_skipped_bet_is_retried_below_the_mark(pool: PgPool) {
+        sqlx::query!(
+            r#"
+            INSERT INTO markets_extended (id, "endDate", "blockchainMarketId")
+            VALUES ('m-1', NOW() + INTERVAL '1 day', 1)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        // Bet 10's market is not synced yet; bet 11 is.
+        indexer_bet(&pool, 10, 2, 5).await;
+        indexer_bet(&pool, 11, 1, 6).await;
+
+        let sync = BlockchainSyncService::new(pool.clone());
+        let first = sync.sync_bets().await.unwrap();
+        assert_eq!((first.new_events, first.skipped), (1, 1));
+        assert_eq!(first.high_water_version, 6);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO markets_extended (id, "endDate", "blockchainMarketId")
+            VALUES ('m-2', NOW() + INTERVAL '1 day', 2)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let second = sync.sync_bets().await.unwrap();
+        assert_eq!((second.processed, second.new_events), (1, 1));
+        assert_eq!(second.touched_markets, vec![2]);
+        assert_eq!(second.high_water_version, 6);
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_gap_scan_pages_past_rows_that_keep_failing(pool: PgPool) {
+        // More stuck bets than one gap scan retries: the first GAP_SCAN_LIMIT belong to market
+        // 1, which never syncs; the 5 behind them to market 2, which syncs later.
+        sqlx::query!(
+            r#"
+            INSERT INTO bets (bet_id, market_id, user_addr, position, amount,
+                              transaction_version, transaction_block_height)
+            SELECT n, CASE WHEN n <= $1::bigint THEN 1 ELSE 2 END, '0xu1', true, 100, n, n
+            FROM generate_series(1, $1::bigint + 5) n
+            "#,
+            GAP_SCAN_LIMIT
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let sync = BlockchainSyncService::new(pool.clone());
+        let first = sync.sync_bets().await.unwrap();
+        assert_eq!(first.skipped, GAP_SCAN_LIMIT + 5);
+        assert_eq!(first.high_water_version, GAP_SCAN_LIMIT + 5);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO markets_extended (id, "endDate", "blockchainMarketId")
+            VALUES ('m-2', NOW() + INTERVAL '1 day', 2)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // A full page of market 1's bets, then the rest of the gaps.
+        let second = sync.sync_bets().await.unwrap();
+        assert_eq!((second.processed, second.new_events), (GAP_SCAN_LIMIT, 0));
+        let third = sync.sync_bets().await.unwrap();
+        assert_eq!((third.processed, third.new_events), (5, 5));
+        assert_eq!(third.touched_markets, vec![2]);
+
+        // Having reached the mark, the scan starts over from the oldest stuck bet.
+        assert_eq!(sync.gap_cursor(BET_SYNC).await.unwrap(), None);
+        let fourth = sync.sync_bets().await.unwrap();
+        assert_eq!((fourth.processed, fourth.new_events), (GAP_SCAN_LIMIT, 0));
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_progress_never_moves_the_mark_back(pool: PgPool) {
+        let sync = BlockchainSyncService::new(pool);
+        let mut result = SyncResult::new(BET_SYNC);
+        result.high_water_version = 20;
+        sync.record_progress(&result).await.unwrap();
+        result.high_water_version = 12;
+        sync.record_progress(&result).await.unwrap();
+
+        assert_eq!(sync.high_water_mark(BET_SYNC).await.unwrap(), 20);
+    }
+}
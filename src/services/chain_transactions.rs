@@ -0,0 +1,809 @@
+use anyhow::{anyhow, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::market_outcomes::MarketOutcomeService;
+use super::response_cache::publish_invalidation;
+
+/// Status of an extended row whose transaction is still waiting on the node.
+pub const ROW_PENDING: &str = "pending";
+const ROW_CONFIRMED: &str = "confirmed";
+const ROW_FAILED: &str = "failed";
+
+const NODE_TIMEOUT_SECS: u64 = 10;
+
+/// Entry function a recorded transaction called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxKind {
+    PlaceBet,
+    ClaimWinnings,
+    CreateMarket,
+}
+
+impl TxKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxKind::PlaceBet => "place_bet",
+            TxKind::ClaimWinnings => "claim_winnings",
+            TxKind::CreateMarket => "create_market",
+        }
+    }
+}
+
+impl FromStr for TxKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "place_bet" => Ok(TxKind::PlaceBet),
+            "claim_winnings" => Ok(TxKind::ClaimWinnings),
+            "create_market" => Ok(TxKind::CreateMarket),
+            other => Err(anyhow!("Unknown transaction kind: {}", other)),
+        }
+    }
+}
+
+/// Final status of a row in `chain_transactions`; rows start out `pending` and are polled
+/// until they reach one of these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatus {
+    Committed,
+    Failed,
+    Expired,
+}
+
+impl TxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxStatus::Committed => "committed",
+            TxStatus::Failed => "failed",
+            TxStatus::Expired => "expired",
+        }
+    }
+}
+
+impl fmt::Display for TxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What the node reports for a transaction hash.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeTransaction {
+    /// The node has never seen the hash (404).
+    Unknown,
+    /// Still in the mempool.
+    Pending,
+    /// Executed on chain; `success` is false when the VM aborted it.
+    Executed {
+        success: bool,
+        vm_status: Option<String>,
+        gas_used: Option<i64>,
+        version: Option<i64>,
+    },
+}
+
+impl NodeTransaction {
+    /// Reads a `/transactions/by_hash/{hash}` response body.
+    pub fn from_json(body: &Value) -> Self {
+        if body["type"].as_str() == Some("pending_transaction") {
+            return NodeTransaction::Pending;
+        }
+        // The node encodes u64 fields as strings.
+        let number = |field: &str| match &body[field] {
+            Value::String(s) => s.parse().ok(),
+            other => other.as_i64(),
+        };
+        NodeTransaction::Executed {
+            success: body["success"].as_bool().unwrap_or(false),
+            vm_status: body["vm_status"].as_str().map(str::to_string),
+            gas_used: number("gas_used"),
+            version: number("version"),
+        }
+    }
+
+    /// Final status of a pending transaction given this report, or `None` to keep waiting.
+    pub fn resolve(&self, expired: bool) -> Option<TxStatus> {
+        match self {
+            NodeTransaction::Executed { success: true, .. } => Some(TxStatus::Committed),
+            NodeTransaction::Executed { success: false, .. } => Some(TxStatus::Failed),
+            NodeTransaction::Unknown | NodeTransaction::Pending if expired => {
+                Some(TxStatus::Expired)
+            }
+            NodeTransaction::Unknown | NodeTransaction::Pending => None,
+        }
+    }
+}
+
+/// A transaction about to be recorded right after submission.
+pub struct NewChainTransaction<'a> {
+    pub kind: TxKind,
+    pub sender: &'a str,
+    pub payload: &'a Value,
+    pub hash: &'a str,
+    pub market_id: Option<&'a str>,
+    pub bet_id: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainTransaction {
+    pub id: String,
+    pub kind: String,
+    pub sender: String,
+    pub payload: Value,
+    pub hash: String,
+    pub status: String,
+    pub vm_status: Option<String>,
+    pub gas_used: Option<i64>,
+    pub version: Option<i64>,
+    pub market_id: Option<String>,
+    pub bet_id: Option<String>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub submitted_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub confirmed_at: Option<NaiveDateTime>,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmationSummary {
+    pub checked: usize,
+    pub committed: usize,
+    pub failed: usize,
+    pub expired: usize,
+    pub still_pending: usize,
+    pub errors: usize,
+}
+
+struct PendingTransaction {
+    id: String,
+    hash: String,
+    kind: String,
+    market_id: Option<String>,
+    bet_id: Option<String>,
+    expired: bool,
+}
+
+/// Records submitted transactions and confirms them against the node.
+pub struct ChainTransactionService {
+    pool: PgPool,
+    node_url: String,
+    client: reqwest::Client,
+}
+
+impl ChainTransactionService {
+    pub fn new(pool: PgPool, node_url: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(NODE_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            pool,
+            node_url: node_url.trim_end_matches('/').to_string(),
+            client,
+        }
+    }
+
+    /// Stores a just-submitted transaction as pending; it expires `expires_in_secs` from now
+    /// if the node never executes it.
+    pub async fn record(&self, tx: NewChainTransaction<'_>, expires_in_secs: u64) -> Result<()> {
+        Self::record_in(&self.pool, tx, expires_in_secs).await
+    }
+
+    /// [`Self::record`] through `executor`, so the row commits with the write it tracks.
+    pub async fn record_in<'e>(
+        executor: impl PgExecutor<'e>,
+        tx: NewChainTransaction<'_>,
+        expires_in_secs: u64,
+    ) -> Result<()> {
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO chain_transactions (
+                id, kind, sender, payload, hash, "marketId", "betId", "expiresAt"
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW() + make_interval(secs => $8))
+            ON CONFLICT (hash) DO NOTHING
+            "#,
+            Uuid::new_v4().to_string(),
+            tx.kind.as_str(),
+            tx.sender,
+            tx.payload,
+            tx.hash,
+            tx.market_id,
+            tx.bet_id,
+            expires_in_secs as f64
+        )
+        .execute(executor)
+        .await?;
+
+        if inserted.rows_affected() == 0 {
+            warn!("Transaction {} is already recorded", tx.hash);
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_by_hash(&self, hash: &str) -> Result<Option<ChainTransaction>> {
+        let tx = sqlx::query_as!(
+            ChainTransaction,
+            r#"
+            SELECT id, kind, sender, payload, hash, status,
+                   "vmStatus" as vm_status, "gasUsed" as gas_used, version,
+                   "marketId" as market_id, "betId" as bet_id, attempts,
+                   "lastError" as last_error, "submittedAt" as submitted_at,
+                   "expiresAt" as expires_at, "confirmedAt" as confirmed_at,
+                   "updatedAt" as updated_at
+            FROM chain_transactions
+            WHERE hash = $1
+            "#,
+            hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(tx)
+    }
+
+    /// Checks up to `batch_size` pending transactions, oldest first, and settles every one
+    /// the node has executed or that passed its expiry. A node error leaves the transaction
+    /// pending for the next run.
+    pub async fn confirm_pending(&self, batch_size: i64) -> Result<ConfirmationSummary> {
+        let pending = sqlx::query_as!(
+            PendingTransaction,
+            r#"
+            SELECT id, hash, kind, "marketId" as market_id, "betId" as bet_id,
+                   "expiresAt" < NOW() as "expired!"
+            FROM chain_transactions
+            WHERE status = 'pending'
+            ORDER BY "submittedAt"
+            LIMIT $1
+            "#,
+            batch_size
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut summary = ConfirmationSummary::default();
+
+        for tx in pending {
+            summary.checked += 1;
+
+            let report = match self.fetch(&tx.hash).await {
+                Ok(report) => report,
+                Err(e) => {
+                    warn!("Failed to look up transaction {}: {}", tx.hash, e);
+                    summary.errors += 1;
+                    sqlx::query!(
+                        r#"
+                        UPDATE chain_transactions
+                        SET attempts = attempts + 1, "lastError" = $2, "updatedAt" = NOW()
+                        WHERE id = $1
+                        "#,
+                        tx.id,
+                        e.to_string()
+                    )
+                    .execute(&self.pool)
+                    .await?;
+                    continue;
+                }
+            };
+
+            match report.resolve(tx.expired) {
+                Some(status) => {
+                    self.settle(&tx, status, &report).await?;
+                    info!("Transaction {} {}", tx.hash, status);
+                    match status {
+                        TxStatus::Committed => summary.committed += 1,
+                        TxStatus::Failed => summary.failed += 1,
+                        TxStatus::Expired => summary.expired += 1,
+                    }
+                }
+                None => {
+                    summary.still_pending += 1;
+                    sqlx::query!(
+                        r#"
+                        UPDATE chain_transactions
+                        SET attempts = attempts + 1, "lastError" = NULL, "updatedAt" = NOW()
+                        WHERE id = $1
+                        "#,
+                        tx.id
+                    )
+                    .execute(&self.pool)
+                    .await?;
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    async fn fetch(&self, hash: &str) -> Result<NodeTransaction> {
+        let url = format!("{}/transactions/by_hash/{}", self.node_url, hash);
+        let response = self.client.get(&url).send().await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(NodeTransaction::Unknown);
+        }
+        if !response.status().is_success() {
+            return Err(anyhow!("Node returned {}", response.status()));
+        }
+
+        let body: Value = response.json().await?;
+        Ok(NodeTransaction::from_json(&body))
+    }
+
+    /// Writes the final status and flips the rows the transaction wrote, undoing what a
+    /// failed transaction had already applied. Rows are matched on `"txHash"` too, so a newer
+    /// transaction on the same row (a claim after the bet) is not overwritten by an older one.
+    async fn settle(
+        &self,
+        tx: &PendingTransaction,
+        status: TxStatus,
+        report: &NodeTransaction,
+    ) -> Result<()> {
+        let (vm_status, gas_used, version) = match report {
+            NodeTransaction::Executed {
+                vm_status,
+                gas_used,
+                version,
+                ..
+            } => (vm_status.clone(), *gas_used, *version),
+            _ => (None, None, None),
+        };
+        let row_status = if status == TxStatus::Committed {
+            ROW_CONFIRMED
+        } else {
+            ROW_FAILED
+        };
+
+        let mut db_tx = self.pool.begin().await?;
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE chain_transactions
+            SET status = $2,
+                "vmStatus" = $3,
+                "gasUsed" = $4,
+                version = $5,
+                "confirmedAt" = CASE WHEN $2 = 'committed' THEN NOW() END,
+                attempts = attempts + 1,
+                "lastError" = NULL,
+                "updatedAt" = NOW()
+            WHERE id = $1 AND status = 'pending'
+            "#,
+            tx.id,
+            status.as_str(),
+            vm_status,
+            gas_used,
+            version
+        )
+        .execute(&mut *db_tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            // Settled concurrently by another poller.
+            return Ok(());
+        }
+
+        match tx.kind.parse::<TxKind>()? {
+            TxKind::PlaceBet => {
+                sqlx::query!(
+                    r#"
+                    UPDATE bets_extended
+                    SET "txStatus" = $3, "updatedAt" = NOW()
+                    WHERE id = $1 AND "txHash" = $2
+                    "#,
+                    tx.bet_id,
+                    tx.hash,
+                    row_status
+                )
+                .execute(&mut *db_tx)
+                .await?;
+                if row_status == ROW_FAILED {
+                    Self::unwind_failed_bet(&mut db_tx, tx).await?;
+                }
+            }
+            TxKind::ClaimWinnings => {
+                // A claim that never made it on chain leaves the bet claimable again.
+                sqlx::query!(
+                    r#"
+                    UPDATE bets_extended
+                    SET "txStatus" = $3,
+                        status = CASE
+                            WHEN $3 = 'failed' AND status = 'claimed' THEN 'won'
+                            ELSE status
+                        END,
+                        "updatedAt" = NOW()
+                    WHERE id = $1 AND "txHash" = $2
+                    "#,
+                    tx.bet_id,
+                    tx.hash,
+                    row_status
+                )
+                .execute(&mut *db_tx)
+                .await?;
+            }
+            TxKind::CreateMarket => {
+                // A market whose creation failed has no ID on chain; clearing it lets the
+                // next market sync submit it again.
+                sqlx::query!(
+                    r#"
+                    UPDATE markets_extended
+                    SET "txStatus" = $3,
+                        "blockchainMarketId" = CASE
+                            WHEN $3 = 'failed' THEN NULL
+                            ELSE "blockchainMarketId"
+                        END,
+                        "updatedAt" = NOW()
+                    WHERE id = $1 AND "txHash" = $2
+                    "#,
+                    tx.market_id,
+                    tx.hash,
+                    row_status
+                )
+                .execute(&mut *db_tx)
+                .await?;
+            }
+        }
+
+        db_tx.commit().await?;
+
+        if status != TxStatus::Committed {
+            warn!(
+                "{} transaction {} {}: {}",
+                tx.kind,
+                tx.hash,
+                status,
+                vm_status.as_deref().unwrap_or("not executed before expiry")
+            );
+            // Pools or chain ids may have been rolled back above.
+            if let Err(e) = publish_invalidation(&self.pool, None).await {
+                warn!("Failed to publish cache invalidation: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Marks a bet whose transaction failed as failed and takes its stake back out of the
+    /// market's pools and counts, which `place_bet` added when it was submitted.
+    async fn unwind_failed_bet(conn: &mut PgConnection, tx: &PendingTransaction) -> Result<()> {
+        let Some(bet) = sqlx::query!(
+            r#"
+            UPDATE bets_extended
+            SET status = 'failed', "updatedAt" = NOW()
+            WHERE id = $1 AND "txHash" = $2 AND status = 'active'
+            RETURNING "marketId" as market_id, position, "outcomeIndex" as outcome_index,
+                      amount
+            "#,
+            tx.bet_id,
+            tx.hash
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+        else {
+            return Ok(());
+        };
+        let (Some(market_id), Some(amount)) = (bet.market_id, bet.amount) else {
+            return Ok(());
+        };
+
+        if let Some(outcome_index) = bet.outcome_index {
+            sqlx::query!(
+                r#"
+                UPDATE market_outcomes
+                SET "poolSize" = GREATEST("poolSize" - $1, 0),
+                    "betCount" = GREATEST("betCount" - 1, 0),
+                    "updatedAt" = NOW()
+                WHERE "marketId" = $2 AND "outcomeIndex" = $3
+                "#,
+                amount,
+                market_id,
+                outcome_index
+            )
+            .execute(&mut *conn)
+            .await?;
+            sqlx::query!(
+                r#"
+                UPDATE markets_extended
+                SET "totalPoolSize" = GREATEST("totalPoolSize" - $1, 0), "updatedAt" = NOW()
+                WHERE id = $2
+                "#,
+                amount,
+                market_id
+            )
+            .execute(&mut *conn)
+            .await?;
+            MarketOutcomeService::refresh_probability(&mut *conn, Some(&market_id)).await?;
+        } else {
+            let yes = bet.position.unwrap_or(false);
+            sqlx::query!(
+                r#"
+                UPDATE markets_extended
+                SET "yesPoolSize" = CASE WHEN $2 THEN GREATEST("yesPoolSize" - $1, 0)
+                                         ELSE "yesPoolSize" END,
+                    "noPoolSize" = CASE WHEN $2 THEN "noPoolSize"
+                                        ELSE GREATEST("noPoolSize" - $1, 0) END,
+                    "countYes" = CASE WHEN $2 THEN GREATEST("countYes" - 1, 0) ELSE "countYes" END,
+                    "countNo" = CASE WHEN $2 THEN "countNo" ELSE GREATEST("countNo" - 1, 0) END,
+                    "totalPoolSize" = GREATEST("totalPoolSize" - $1, 0),
+                    "updatedAt" = NOW()
+                WHERE id = $3
+                "#,
+                amount,
+                yes,
+                market_id
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        info!(
+            "Bet {} failed on chain; removed its stake of {} from market {}",
+            tx.bet_id.as_deref().unwrap_or_default(),
+            amount,
+            market_id
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_node_transaction_from_json() {
+        assert_eq!(
+            NodeTransaction::from_json(&json!({"type": "pending_transaction", "hash": "0x1"})),
+            NodeTransaction::Pending
+        );
+        assert_eq!(
+            NodeTransaction::from_json(&json!({
+                "type": "user_transaction",
+                "success": false,
+                "vm_status": "Move abort in 0x1::coin: EINSUFFICIENT_BALANCE(0x10006)",
+                "gas_used": "512",
+                "version": "123456"
+            })),
+            NodeTransaction::Executed {
+                success: false,
+                vm_status: Some(
+                    "Move abort in 0x1::coin: EINSUFFICIENT_BALANCE(0x10006)".to_string()
+                ),
+                gas_used: Some(512),
+                version: Some(123456),
+            }
+        );
+    }
+
+    #[test]
+    fn test_resolve_only_expires_unexecuted_transactions() {
+        let executed = |success| NodeTransaction::Executed {
+            success,
+            vm_status: None,
+            gas_used: None,
+            version: None,
+        };
+
+        assert_eq!(executed(true).resolve(true), Some(TxStatus::Committed));
+        assert_eq!(executed(false).resolve(false), Some(TxStatus::Failed));
+        assert_eq!(NodeTransaction::Pending.resolve(false), None);
+        assert_eq!(NodeTransaction::Unknown.resolve(false), None);
+        assert_eq!(
+            NodeTransaction::Unknown.resolve(true),
+            Some(TxStatus::Expired)
+        );
+        assert_eq!(
+            NodeTransaction::Pending.resolve(true),
+            Some(TxStatus::Expired)
+        );
+    }
+
+    async fn pending(
+        pool: &PgPool,
+        kind: TxKind,
+        hash: &str,
+        market_id: &str,
+        bet_id: Option<&str>,
+    ) -> PendingTransaction {
+        sqlx::query!(
+            r#"
+            INSERT INTO chain_transactions (id, kind, sender, payload, hash, "marketId", "betId", "expiresAt")
+            VALUES ($1, $2, '0xsender', '{}', $1, $3, $4, NOW())
+            "#,
+            hash,
+            kind.as_str(),
+            market_id,
+            bet_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        PendingTransaction {
+            id: hash.to_string(),
+            hash: hash.to_string(),
+            kind: kind.as_str().to_string(),
+            market_id: Some(market_id.to_string()),
+            bet_id: bet_id.map(str::to_string),
+            expired: true,
+        }
+    }
+
+    fn aborted() -> NodeTransaction {
+        NodeTransaction::Executed {
+            success: false,
+            vm_status: Some("Move abort".to_string()),
+            gas_used: Some(10),
+            version: Some(1),
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_failed_bet_leaves_the_pools(pool: PgPool) {
+        sqlx::query!(r#"INSERT INTO users (id, address) VALUES ('u-1', '0xu1')"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO markets_extended (id, "endDate", "yesPoolSize", "noPoolSize",
+                                          "totalPoolSize", "countYes", "countNo")
+            VALUES ('m-1', NOW() + INTERVAL '1 day', 300, 200, 500, 2, 1)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO bets_extended (id, "blockchainBetId", "userId", "marketId", position,
+                                       amount, odds, "txHash", "txStatus")
+            VALUES ('b-1', 1, 'u-1', 'm-1', true, 100, 1.5, '0xb1', 'pending')
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let service = ChainTransactionService::new(pool.clone(), "http://localhost:1");
+        let tx = pending(&pool, TxKind::PlaceBet, "0xb1", "m-1", Some("b-1")).await;
+        service
+            .settle(&tx, TxStatus::Failed, &aborted())
+            .await
+            .unwrap();
+
+        let bet = sqlx::query!(
+            r#"SELECT status, "txStatus" as tx_status FROM bets_extended WHERE id = 'b-1'"#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(bet.status, "failed");
+        assert_eq!(bet.tx_status.as_deref(), Some(ROW_FAILED));
+
+        let market = sqlx::query!(
+            r#"
+            SELECT "yesPoolSize"::float8 as "yes!", "noPoolSize"::float8 as "no!",
+                   "totalPoolSize"::float8 as "total!", "countYes" as count_yes,
+                   "countNo" as count_no
+            FROM markets_extended WHERE id = 'm-1'
+            "#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((market.yes, market.no, market.total), (200.0, 200.0, 400.0));
+        assert_eq!((market.count_yes, market.count_no), (1, 1));
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_expired_bet_leaves_its_outcome_pool(pool: PgPool) {
+        sqlx::query!(r#"INSERT INTO users (id, address) VALUES ('u-1', '0xu1')"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO markets_extended (id, "endDate", "marketType", "totalPoolSize")
+            VALUES ('m-1', NOW() + INTERVAL '1 day', 'categorical', 400)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO market_outcomes (id, "marketId", "outcomeIndex", label, "poolSize", "betCount")
+            VALUES ('o-0', 'm-1', 0, 'A', 100, 1), ('o-1', 'm-1', 1, 'B', 300, 2)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO bets_extended (id, "blockchainBetId", "userId", "marketId", "outcomeIndex",
+                                       amount, odds, "txHash", "txStatus")
+            VALUES ('b-1', 1, 'u-1', 'm-1', 1, 100, 1.5, '0xb1', 'pending')
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let service = ChainTransactionService::new(pool.clone(), "http://localhost:1");
+        let tx = pending(&pool, TxKind::PlaceBet, "0xb1", "m-1", Some("b-1")).await;
+        service
+            .settle(&tx, TxStatus::Expired, &NodeTransaction::Unknown)
+            .await
+            .unwrap();
+
+        let outcome = sqlx::query!(
+            r#"
+            SELECT "poolSize"::float8 as "pool!", "betCount" as bet_count
+            FROM market_outcomes WHERE id = 'o-1'
+            "#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((outcome.pool, outcome.bet_count), (200.0, 1));
+
+        let market = sqlx::query!(
+            r#"
+            SELECT "totalPoolSize"::float8 as "total!", probability
+            FROM markets_extended WHERE id = 'm-1'
+            "#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((market.total, market.probability), (300.0, 67));
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_failed_market_creation_clears_its_chain_id(pool: PgPool) {
+        sqlx::query!(
+            r#"
+            INSERT INTO markets_extended (id, "endDate", "blockchainMarketId", "txHash", "txStatus")
+            VALUES ('m-1', NOW() + INTERVAL '1 day', 7, '0xm1', 'pending')
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let service = ChainTransactionService::new(pool.clone(), "http://localhost:1");
+        let tx = pending(&pool, TxKind::CreateMarket, "0xm1", "m-1", None).await;
+        service
+            .settle(&tx, TxStatus::Failed, &aborted())
+            .await
+            .unwrap();
+
+        let market = sqlx::query!(
+            r#"
+            SELECT "blockchainMarketId" as blockchain_market_id, "txStatus" as tx_status
+            FROM markets_extended WHERE id = 'm-1'
+            "#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(market.blockchain_market_id, None);
+        assert_eq!(market.tx_status.as_deref(), Some(ROW_FAILED));
+    }
+}
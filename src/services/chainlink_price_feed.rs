@@ -1,45 +1,163 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 
-const PRICE_SOURCES: &[PriceSource] = &[
-    PriceSource {
-        name: "Binance",
-        url: "https://api.binance.com/api/v3/ticker/price?symbol=APTUSDT",
-    },
-    PriceSource {
-        name: "Coinbase",
-        url: "https://api.exchange.coinbase.com/products/APT-USD/ticker",
-    },
-    PriceSource {
-        name: "Kraken",
-        url: "https://api.kraken.com/0/public/Ticker?pair=APTUSD",
-    },
-    PriceSource {
-        name: "Bybit",
-        url: "https://api.bybit.com/v5/market/tickers?category=spot&symbol=APTUSDT",
+use crate::config::PriceFeedConfig;
+use crate::utils::time_series::time_weighted_average;
+
+use super::price_source_health::{
+    CircuitBreakerPolicy, SourceHealthRegistry, SourceHealthSnapshot, SOURCE_HEALTH,
+};
+
+/// Exchange symbols for an asset quoted in USD. `None` means the exchange has no usable market.
+#[derive(Debug)]
+pub struct AssetSpec {
+    pub symbol: &'static str,
+    binance: Option<&'static str>,
+    coinbase: Option<&'static str>,
+    kraken: Option<&'static str>,
+    bybit: Option<&'static str>,
+    okx: Option<&'static str>,
+    coingecko: Option<&'static str>,
+    coinpaprika: Option<&'static str>,
+    max_price: f64,
+}
+
+pub const SUPPORTED_ASSETS: &[AssetSpec] = &[
+    AssetSpec {
+        symbol: "APT",
+        binance: Some("APTUSDT"),
+        coinbase: Some("APT-USD"),
+        kraken: Some("APTUSD"),
+        bybit: Some("APTUSDT"),
+        okx: Some("APT-USDT"),
+        coingecko: Some("aptos"),
+        coinpaprika: Some("apt-aptos"),
+        max_price: 10_000.0,
     },
-    PriceSource {
-        name: "OKX",
-        url: "https://www.okx.com/api/v5/market/ticker?instId=APT-USDT",
+    AssetSpec {
+        symbol: "USDC",
+        binance: Some("USDCUSDT"),
+        coinbase: None,
+        kraken: Some("USDCUSD"),
+        bybit: Some("USDCUSDT"),
+        okx: Some("USDC-USDT"),
+        coingecko: Some("usd-coin"),
+        coinpaprika: Some("usdc-usd-coin"),
+        max_price: 10.0,
     },
-    PriceSource {
-        name: "CoinGecko",
-        url: "https://api.coingecko.com/api/v3/simple/price?ids=aptos&vs_currencies=usd",
+    AssetSpec {
+        symbol: "BTC",
+        binance: Some("BTCUSDT"),
+        coinbase: Some("BTC-USD"),
+        kraken: Some("XBTUSD"),
+        bybit: Some("BTCUSDT"),
+        okx: Some("BTC-USDT"),
+        coingecko: Some("bitcoin"),
+        coinpaprika: Some("btc-bitcoin"),
+        max_price: 10_000_000.0,
     },
-    PriceSource {
-        name: "CoinPaprika",
-        url: "https://api.coinpaprika.com/v1/tickers/apt-aptos?quotes=USD",
+    AssetSpec {
+        symbol: "ETH",
+        binance: Some("ETHUSDT"),
+        coinbase: Some("ETH-USD"),
+        kraken: Some("ETHUSD"),
+        bybit: Some("ETHUSDT"),
+        okx: Some("ETH-USDT"),
+        coingecko: Some("ethereum"),
+        coinpaprika: Some("eth-ethereum"),
+        max_price: 1_000_000.0,
     },
 ];
 
+impl AssetSpec {
+    pub fn pair(&self) -> String {
+        format!("{}/USD", self.symbol)
+    }
+}
+
+/// Looks up an asset by symbol (`apt`) or pair (`APT/USD`, `apt-usd`).
+pub fn find_asset(value: &str) -> Option<&'static AssetSpec> {
+    let upper = value.trim().to_uppercase();
+    let symbol = upper
+        .strip_suffix("/USD")
+        .or_else(|| upper.strip_suffix("-USD"))
+        .unwrap_or(&upper);
+    SUPPORTED_ASSETS.iter().find(|a| a.symbol == symbol)
+}
+
 #[derive(Debug, Clone)]
 struct PriceSource {
     name: &'static str,
-    url: &'static str,
+    url: String,
+}
+
+fn sources_for(asset: &AssetSpec) -> Vec<PriceSource> {
+    let mut sources = Vec::new();
+    if let Some(symbol) = asset.binance {
+        sources.push(PriceSource {
+            name: "Binance",
+            url: format!(
+                "https://api.binance.com/api/v3/ticker/price?symbol={}",
+                symbol
+            ),
+        });
+    }
+    if let Some(product) = asset.coinbase {
+        sources.push(PriceSource {
+            name: "Coinbase",
+            url: format!(
+                "https://api.exchange.coinbase.com/products/{}/ticker",
+                product
+            ),
+        });
+    }
+    if let Some(pair) = asset.kraken {
+        sources.push(PriceSource {
+            name: "Kraken",
+            url: format!("https://api.kraken.com/0/public/Ticker?pair={}", pair),
+        });
+    }
+    if let Some(symbol) = asset.bybit {
+        sources.push(PriceSource {
+            name: "Bybit",
+            url: format!(
+                "https://api.bybit.com/v5/market/tickers?category=spot&symbol={}",
+                symbol
+            ),
+        });
+    }
+    if let Some(inst_id) = asset.okx {
+        sources.push(PriceSource {
+            name: "OKX",
+            url: format!(
+                "https://www.okx.com/api/v5/market/ticker?instId={}",
+                inst_id
+            ),
+        });
+    }
+    if let Some(id) = asset.coingecko {
+        sources.push(PriceSource {
+            name: "CoinGecko",
+            url: format!(
+                "https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies=usd",
+                id
+            ),
+        });
+    }
+    if let Some(id) = asset.coinpaprika {
+        sources.push(PriceSource {
+            name: "CoinPaprika",
+            url: format!("https://api.coinpaprika.com/v1/tickers/{}?quotes=USD", id),
+        });
+    }
+    sources
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,183 +166,670 @@ pub struct PriceData {
     pub decimals: u8,
     pub timestamp: i64,
     pub round_id: u64,
+    /// Fewer than `price_feed.min_sources` agreeing sources, or a stale cached value.
+    #[serde(default)]
+    pub degraded: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PricePoint {
+    pub price: f64,
+    pub source_count: i32,
+    pub degraded: bool,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TwapData {
+    pub pair: String,
+    pub twap: f64,
+    pub window_secs: i64,
+    pub from: i64,
+    pub to: i64,
+    pub sample_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceMethod {
+    Spot,
+    Twap,
 }
 
 #[derive(Clone)]
 pub struct ChainlinkPriceFeed {
-    cached_price: Arc<RwLock<Option<PriceData>>>,
+    cached_prices: Arc<RwLock<HashMap<&'static str, PriceData>>>,
+    pairs: Vec<&'static AssetSpec>,
+    history: Option<PgPool>,
+    cache_ttl_secs: i64,
+    request_timeout_secs: u64,
+    twap_window_secs: i64,
+    conversion_method: PriceMethod,
+    min_sources: usize,
+    max_deviation_pct: f64,
+    circuit_breaker: CircuitBreakerPolicy,
+    health: Arc<SourceHealthRegistry>,
 }
 
 impl ChainlinkPriceFeed {
-    pub fn new() -> Result<Self> {
+    pub fn new(config: &PriceFeedConfig) -> Result<Self> {
+        let pairs = config
+            .pairs
+            .iter()
+            .map(|p| find_asset(p).ok_or_else(|| anyhow!("Unsupported price pair: {}", p)))
+            .collect::<Result<Vec<_>>>()?;
+
         Ok(Self {
-            cached_price: Arc::new(RwLock::new(None)),
+            cached_prices: Arc::new(RwLock::new(HashMap::new())),
+            pairs,
+            history: None,
+            cache_ttl_secs: config.cache_ttl_secs,
+            request_timeout_secs: config.request_timeout_secs,
+            twap_window_secs: config.twap_window_secs,
+            conversion_method: if config.use_twap_for_conversion {
+                PriceMethod::Twap
+            } else {
+                PriceMethod::Spot
+            },
+            min_sources: config.min_sources,
+            max_deviation_pct: config.max_deviation_pct,
+            circuit_breaker: CircuitBreakerPolicy::from(config),
+            health: SOURCE_HEALTH.clone(),
         })
     }
 
+    /// Persists every aggregated sample to `price_history` and serves recent samples from it.
+    pub fn with_history(mut self, pool: PgPool) -> Self {
+        self.history = Some(pool);
+        self
+    }
+
+    pub fn pairs(&self) -> &[&'static AssetSpec] {
+        &self.pairs
+    }
+
+    pub fn twap_window_secs(&self) -> i64 {
+        self.twap_window_secs
+    }
+
+    pub fn min_sources(&self) -> usize {
+        self.min_sources
+    }
+
+    /// Health of every source queried so far for the configured pairs.
+    pub fn source_health(&self) -> Vec<SourceHealthSnapshot> {
+        let pairs: Vec<String> = self.pairs.iter().map(|a| a.pair()).collect();
+        self.health
+            .snapshot(chrono::Utc::now().timestamp())
+            .into_iter()
+            .filter(|s| pairs.contains(&s.pair))
+            .collect()
+    }
+
+    /// Resolves a configured pair; assets that exist but are not configured are rejected.
+    pub fn configured_asset(&self, value: &str) -> Result<&'static AssetSpec> {
+        let asset = find_asset(value).ok_or_else(|| anyhow!("Unknown price pair: {}", value))?;
+        if !self.pairs.iter().any(|a| a.symbol == asset.symbol) {
+            return Err(anyhow!("Price pair {} is not enabled", asset.pair()));
+        }
+        Ok(asset)
+    }
+
+    #[allow(dead_code)]
     pub async fn get_apt_usd_price(&self) -> Result<f64> {
-        {
-            let cache = self.cached_price.read().await;
-            if let Some(price_data) = cache.as_ref() {
-                let now = chrono::Utc::now().timestamp();
+        self.get_price("APT").await
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_price(&self, asset: &str) -> Result<f64> {
+        let asset = self.configured_asset(asset)?;
+        Ok(self.get_price_data(asset).await?.price)
+    }
+
+    pub async fn get_price_data(&self, asset: &'static AssetSpec) -> Result<PriceData> {
+        let now = chrono::Utc::now().timestamp();
 
-                if now - price_data.timestamp < 300 {
-                    info!("Using cached APT/USD price: ${}", price_data.price);
-                    return Ok(price_data.price);
+        {
+            let cache = self.cached_prices.read().await;
+            if let Some(price_data) = cache.get(asset.symbol) {
+                if now - price_data.timestamp < self.cache_ttl_secs {
+                    info!("Using cached {} price: ${}", asset.pair(), price_data.price);
+                    return Ok(price_data.clone());
                 }
             }
         }
 
-        match self.fetch_price_from_chainlink().await {
-            Ok(price_data) => {
-                let price = price_data.price;
-
-                {
-                    let mut cache = self.cached_price.write().await;
-                    *cache = Some(price_data);
-                }
+        match self.latest_sample(asset).await {
+            Ok(Some(point)) if now - point.timestamp < self.cache_ttl_secs => {
+                let price_data = PriceData {
+                    price: point.price,
+                    decimals: 8,
+                    timestamp: point.timestamp,
+                    round_id: point.source_count as u64,
+                    degraded: point.degraded,
+                };
+                self.cached_prices
+                    .write()
+                    .await
+                    .insert(asset.symbol, price_data.clone());
+                info!(
+                    "Using recent {} sample from price history: ${}",
+                    asset.pair(),
+                    point.price
+                );
+                return Ok(price_data);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to read price history for {}: {}", asset.pair(), e),
+        }
 
-                info!("Fetched fresh APT/USD price from Chainlink: ${}", price);
-                Ok(price)
+        match self.refresh(asset).await {
+            Ok(price_data) => {
+                info!(
+                    "Fetched fresh {} price: ${}",
+                    asset.pair(),
+                    price_data.price
+                );
+                Ok(price_data)
             }
             Err(e) => {
-                error!("Failed to fetch price from Chainlink: {}", e);
+                error!("Failed to fetch {} price: {}", asset.pair(), e);
 
-                let cache = self.cached_price.read().await;
-                if let Some(price_data) = cache.as_ref() {
+                let cache = self.cached_prices.read().await;
+                if let Some(price_data) = cache.get(asset.symbol) {
                     warn!(
-                        "Using stale cached price as fallback: ${}",
+                        "Using stale cached price as fallback (degraded): ${}",
                         price_data.price
                     );
-                    return Ok(price_data.price);
+                    return Ok(PriceData {
+                        degraded: true,
+                        ..price_data.clone()
+                    });
                 }
 
-                Err(anyhow!("Failed to fetch APT/USD price: {}", e))
+                Err(anyhow!("Failed to fetch {} price: {}", asset.pair(), e))
             }
         }
     }
 
-    async fn fetch_price_from_chainlink(&self) -> Result<PriceData> {
+    #[allow(dead_code)]
+    pub async fn get_cached_price(&self) -> Option<PriceData> {
+        let cache = self.cached_prices.read().await;
+        cache.get("APT").cloned()
+    }
+
+    #[allow(dead_code)]
+    pub async fn refresh_price(&self) -> Result<PriceData> {
+        let asset = self.configured_asset("APT")?;
+        self.refresh(asset).await
+    }
+
+    /// Fetches a fresh aggregated price, bypassing the cache, and records it.
+    pub async fn refresh(&self, asset: &'static AssetSpec) -> Result<PriceData> {
+        let (price_data, prices) = self.fetch_aggregated_price(asset).await?;
+
+        if let Err(e) = self.record_sample(asset, &price_data, &prices).await {
+            warn!("Failed to record {} price sample: {}", asset.pair(), e);
+        }
+
+        self.cached_prices
+            .write()
+            .await
+            .insert(asset.symbol, price_data.clone());
+
+        Ok(price_data)
+    }
+
+    /// Refreshes every configured pair; used by the scheduler to keep `price_history` dense.
+    pub async fn sample_all(&self) -> Vec<(String, Result<PriceData>)> {
+        let mut results = Vec::new();
+        for asset in &self.pairs {
+            results.push((asset.pair(), self.refresh(asset).await));
+        }
+        results
+    }
+
+    async fn fetch_aggregated_price(
+        &self,
+        asset: &'static AssetSpec,
+    ) -> Result<(PriceData, Vec<(String, f64)>)> {
+        let pair = asset.pair();
+        let now = chrono::Utc::now().timestamp();
+
+        let all_sources = sources_for(asset);
+        let (sources, skipped): (Vec<_>, Vec<_>) = all_sources.into_iter().partition(|source| {
+            self.health
+                .should_attempt(source.name, &pair, now, self.circuit_breaker)
+        });
+        for source in &skipped {
+            warn!("⏭️  {}: circuit open for {}, skipping", source.name, pair);
+        }
+
         info!(
-            "🔄 Fetching APT/USD price from {} sources...",
-            PRICE_SOURCES.len()
+            "🔄 Fetching {} price from {} sources ({} skipped)...",
+            pair,
+            sources.len(),
+            skipped.len()
         );
 
         let mut tasks = Vec::new();
-        for source in PRICE_SOURCES {
-            let source_clone = source.clone();
+        for source in sources.iter().cloned() {
+            let timeout_secs = self.request_timeout_secs;
             let task = tokio::spawn(async move {
-                match fetch_from_source_static(&source_clone).await {
-                    Ok(price) => Some((source_clone.name, price)),
-                    Err(e) => {
-                        warn!("❌ {}: {}", source_clone.name, e);
-                        None
-                    }
-                }
+                let started = Instant::now();
+                let result = fetch_from_source_static(&source, asset, timeout_secs).await;
+                (source.name, result, started.elapsed().as_millis() as u64)
             });
             tasks.push(task);
         }
 
         let results = futures::future::join_all(tasks).await;
 
+        let now = chrono::Utc::now().timestamp();
         let mut prices: Vec<(String, f64)> = Vec::new();
-        for result in results {
-            if let Ok(Some((name, price))) = result {
-                info!("✅ {}: ${:.4}", name, price);
-                prices.push((name.to_string(), price));
+        let mut latencies: HashMap<String, u64> = HashMap::new();
+        for (name, result, latency_ms) in results.into_iter().flatten() {
+            match result {
+                Ok(price) => {
+                    info!("✅ {}: ${:.4} ({}ms)", name, price, latency_ms);
+                    prices.push((name.to_string(), price));
+                    latencies.insert(name.to_string(), latency_ms);
+                }
+                Err(e) => {
+                    warn!("❌ {}: {}", name, e);
+                    self.health.record_failure(
+                        name,
+                        &pair,
+                        e.to_string(),
+                        latency_ms,
+                        now,
+                        self.circuit_breaker,
+                    );
+                }
             }
         }
 
         if prices.is_empty() {
             error!(
-                "⚠️  All {} price sources failed, using fallback",
-                PRICE_SOURCES.len()
+                "⚠️  No price sources available for {} ({} failed, {} skipped)",
+                pair,
+                sources.len(),
+                skipped.len()
+            );
+            return Err(anyhow!("No price sources available for {}", pair));
+        }
+
+        // Health is recorded only once outliers are known, so a source that is always an
+        // outlier builds up a failure streak instead of having it reset by its response.
+        let (accepted, rejected) = reject_outliers(prices, self.max_deviation_pct);
+        let latency = |name: &str| latencies.get(name).copied().unwrap_or(0);
+        for (name, price) in &accepted {
+            self.health
+                .record_success(name, &pair, *price, latency(name), now);
+        }
+        for (name, price, deviation_pct) in &rejected {
+            warn!(
+                "🚫 {}: ${:.4} rejected as outlier ({:.2}% from median)",
+                name, price, deviation_pct
+            );
+            self.health.record_outlier(
+                name,
+                &pair,
+                *price,
+                *deviation_pct,
+                latency(name),
+                now,
+                self.circuit_breaker,
             );
-            return Ok(self.get_fallback_price());
         }
 
-        let median_price = self.calculate_median_price(&prices);
-        let source_count = prices.len();
+        let median_price = self.calculate_median_price(&accepted);
+        let source_count = accepted.len();
+        let degraded = source_count < self.min_sources;
+
+        if degraded {
+            warn!(
+                "⚠️  {} price is degraded: {} sources agree, {} required",
+                pair, source_count, self.min_sources
+            );
+        }
 
         info!(
-            "📊 Aggregated price from {}/{} sources: ${:.4} (median)",
+            "📊 Aggregated {} price from {}/{} sources: ${:.4} (median{})",
+            pair,
             source_count,
-            PRICE_SOURCES.len(),
-            median_price
+            sources.len() + skipped.len(),
+            median_price,
+            if degraded { ", degraded" } else { "" }
         );
 
-        Ok(PriceData {
-            price: median_price,
-            decimals: 8,
-            timestamp: chrono::Utc::now().timestamp(),
-            round_id: source_count as u64,
-        })
+        Ok((
+            PriceData {
+                price: median_price,
+                decimals: 8,
+                timestamp: now,
+                round_id: source_count as u64,
+                degraded,
+            },
+            accepted,
+        ))
     }
 
     fn calculate_median_price(&self, prices: &[(String, f64)]) -> f64 {
-        if prices.is_empty() {
-            return 0.0;
-        }
+        let values: Vec<f64> = prices.iter().map(|(_, price)| *price).collect();
+        median(&values)
+    }
 
-        let mut values: Vec<f64> = prices.iter().map(|(_, price)| *price).collect();
-        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    fn history_pool(&self) -> Result<&PgPool> {
+        self.history
+            .as_ref()
+            .ok_or_else(|| anyhow!("Price history is not available for this price feed"))
+    }
 
-        let len = values.len();
-        if len % 2 == 0 {
-            (values[len / 2 - 1] + values[len / 2]) / 2.0
-        } else {
-            values[len / 2]
-        }
+    async fn record_sample(
+        &self,
+        asset: &AssetSpec,
+        price_data: &PriceData,
+        prices: &[(String, f64)],
+    ) -> Result<()> {
+        let Some(pool) = self.history.as_ref() else {
+            return Ok(());
+        };
+
+        let sources: serde_json::Map<String, Value> = prices
+            .iter()
+            .map(|(name, price)| (name.clone(), serde_json::json!(price)))
+            .collect();
+        let recorded_at = chrono::DateTime::from_timestamp(price_data.timestamp, 0)
+            .unwrap_or_else(chrono::Utc::now)
+            .naive_utc();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO price_history (pair, price, "sourceCount", sources, degraded, "recordedAt")
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            asset.pair(),
+            price_data.price,
+            price_data.round_id as i32,
+            Value::Object(sources),
+            price_data.degraded,
+            recorded_at
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
     }
 
-    fn get_fallback_price(&self) -> PriceData {
-        info!("💰 Using fallback price: APT/USD = $12.00");
-        PriceData {
-            price: 12.0,
-            decimals: 8,
-            timestamp: chrono::Utc::now().timestamp(),
-            round_id: 0,
-        }
+    /// Newest non-degraded sample of a pair, or `None` without history or samples.
+    pub async fn latest_sample(&self, asset: &AssetSpec) -> Result<Option<PricePoint>> {
+        let Some(pool) = self.history.as_ref() else {
+            return Ok(None);
+        };
+
+        let row = sqlx::query!(
+            r#"
+            SELECT price, "sourceCount" as source_count, degraded, "recordedAt" as recorded_at
+            FROM price_history
+            WHERE pair = $1 AND NOT degraded
+            ORDER BY "recordedAt" DESC
+            LIMIT 1
+            "#,
+            asset.pair()
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(|r| PricePoint {
+            price: r.price,
+            source_count: r.source_count,
+            degraded: r.degraded,
+            timestamp: r.recorded_at.and_utc().timestamp(),
+        }))
     }
 
-    pub async fn get_cached_price(&self) -> Option<PriceData> {
-        let cache = self.cached_price.read().await;
-        cache.clone()
+    /// Samples for a pair between `from` and `to` (unix seconds), oldest first.
+    pub async fn history(
+        &self,
+        asset: &AssetSpec,
+        from: i64,
+        to: i64,
+        limit: i64,
+    ) -> Result<Vec<PricePoint>> {
+        let pool = self.history_pool()?;
+        let from = to_naive(from)?;
+        let to = to_naive(to)?;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT price, "sourceCount" as source_count, degraded, "recordedAt" as recorded_at
+            FROM (
+                SELECT price, "sourceCount", degraded, "recordedAt"
+                FROM price_history
+                WHERE pair = $1 AND "recordedAt" >= $2 AND "recordedAt" <= $3
+                ORDER BY "recordedAt" DESC
+                LIMIT $4
+            ) recent
+            ORDER BY "recordedAt" ASC
+            "#,
+            asset.pair(),
+            from,
+            to,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| PricePoint {
+                price: r.price,
+                source_count: r.source_count,
+                degraded: r.degraded,
+                timestamp: r.recorded_at.and_utc().timestamp(),
+            })
+            .collect())
     }
 
-    pub async fn refresh_price(&self) -> Result<PriceData> {
-        let price_data = self.fetch_price_from_chainlink().await?;
+    /// Time-weighted average price over the last `window_secs`.
+    pub async fn twap(&self, asset: &AssetSpec, window_secs: i64) -> Result<TwapData> {
+        if window_secs <= 0 {
+            return Err(anyhow!("TWAP window must be greater than 0"));
+        }
 
-        {
-            let mut cache = self.cached_price.write().await;
-            *cache = Some(price_data.clone());
+        let pool = self.history_pool()?;
+        let to = chrono::Utc::now().timestamp();
+        let from = to - window_secs;
+
+        // The last sample before the window sets the price at the window start.
+        // Degraded samples are left out so a single thin quote cannot skew the average.
+        let opening = sqlx::query!(
+            r#"
+            SELECT price, "recordedAt" as recorded_at
+            FROM price_history
+            WHERE pair = $1 AND "recordedAt" < $2 AND NOT degraded
+            ORDER BY "recordedAt" DESC
+            LIMIT 1
+            "#,
+            asset.pair(),
+            to_naive(from)?
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        let in_window = sqlx::query!(
+            r#"
+            SELECT price, "recordedAt" as recorded_at
+            FROM price_history
+            WHERE pair = $1 AND "recordedAt" >= $2 AND "recordedAt" <= $3 AND NOT degraded
+            ORDER BY "recordedAt" ASC
+            "#,
+            asset.pair(),
+            to_naive(from)?,
+            to_naive(to)?
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let sample_count = in_window.len();
+        let samples: Vec<(i64, f64)> = opening
+            .into_iter()
+            .map(|r| (r.recorded_at.and_utc().timestamp(), r.price))
+            .chain(
+                in_window
+                    .into_iter()
+                    .map(|r| (r.recorded_at.and_utc().timestamp(), r.price)),
+            )
+            .collect();
+
+        let twap = time_weighted_average(&samples, from, to).ok_or_else(|| {
+            anyhow!(
+                "No {} price samples available for a {}s TWAP",
+                asset.pair(),
+                window_secs
+            )
+        })?;
+
+        Ok(TwapData {
+            pair: asset.pair(),
+            twap,
+            window_secs,
+            from,
+            to,
+            sample_count,
+        })
+    }
+
+    pub async fn price_for_conversion(&self, asset: &str, method: PriceMethod) -> Result<f64> {
+        let asset = self.configured_asset(asset)?;
+        match method {
+            PriceMethod::Spot => Ok(self.get_price_data(asset).await?.price),
+            PriceMethod::Twap => Ok(self.twap(asset, self.twap_window_secs).await?.twap),
         }
+    }
 
-        Ok(price_data)
+    pub async fn convert_to_usd(
+        &self,
+        asset: &str,
+        amount: f64,
+        method: PriceMethod,
+    ) -> Result<f64> {
+        let price = self.price_for_conversion(asset, method).await?;
+        Ok(amount * price)
+    }
+
+    pub async fn convert_from_usd(
+        &self,
+        asset: &str,
+        usd_amount: f64,
+        method: PriceMethod,
+    ) -> Result<f64> {
+        let price = self.price_for_conversion(asset, method).await?;
+        if price == 0.0 {
+            return Err(anyhow!("Invalid price: 0"));
+        }
+        Ok(usd_amount / price)
     }
 
     #[allow(dead_code)]
     pub async fn apt_to_usd(&self, apt_amount: f64) -> Result<f64> {
-        let price = self.get_apt_usd_price().await?;
-        Ok(apt_amount * price)
+        self.convert_to_usd("APT", apt_amount, self.conversion_method)
+            .await
     }
 
     #[allow(dead_code)]
     pub async fn usd_to_apt(&self, usd_amount: f64) -> Result<f64> {
-        let price = self.get_apt_usd_price().await?;
-        if price == 0.0 {
-            return Err(anyhow!("Invalid price: 0"));
+        self.convert_from_usd("APT", usd_amount, self.conversion_method)
+            .await
+    }
+}
+
+fn to_naive(timestamp: i64) -> Result<chrono::NaiveDateTime> {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|dt| dt.naive_utc())
+        .ok_or_else(|| anyhow!("Invalid timestamp: {}", timestamp))
+}
+
+/// `(source, price, deviation_pct)` of a quote dropped by [`reject_outliers`].
+pub type RejectedQuote = (String, f64, f64);
+
+fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    let mut values = values.to_vec();
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let len = values.len();
+    if len % 2 == 0 {
+        (values[len / 2 - 1] + values[len / 2]) / 2.0
+    } else {
+        values[len / 2]
+    }
+}
+
+/// Splits quotes into those within `max_deviation_pct` of the median and the rejected rest.
+///
+/// Rejected entries carry their deviation in percent. With two quotes or fewer there is no
+/// majority to compare against, so nothing is rejected.
+pub fn reject_outliers(
+    prices: Vec<(String, f64)>,
+    max_deviation_pct: f64,
+) -> (Vec<(String, f64)>, Vec<RejectedQuote>) {
+    if prices.len() <= 2 {
+        return (prices, Vec::new());
+    }
+
+    let values: Vec<f64> = prices.iter().map(|(_, price)| *price).collect();
+    let reference = median(&values);
+    if reference <= 0.0 {
+        return (prices, Vec::new());
+    }
+
+    let mut accepted = Vec::new();
+    let mut rejected = Vec::new();
+    for (name, price) in prices {
+        let deviation_pct = (price - reference).abs() / reference * 100.0;
+        if deviation_pct > max_deviation_pct {
+            rejected.push((name, price, deviation_pct));
+        } else {
+            accepted.push((name, price));
         }
-        Ok(usd_amount / price)
+    }
+
+    (accepted, rejected)
+}
+
+fn parse_source_price(source: &str, asset: &AssetSpec, data: &Value) -> Option<f64> {
+    let as_f64 = |v: &Value| v.as_str().and_then(|s| s.parse::<f64>().ok());
+
+    match source {
+        "Binance" | "Coinbase" => as_f64(&data["price"]),
+        // Kraken keys results by its own pair name (e.g. XXBTZUSD), so take the only entry.
+        "Kraken" => data["result"]
+            .as_object()
+            .and_then(|result| result.values().next())
+            .and_then(|ticker| as_f64(&ticker["c"][0])),
+        "Bybit" => as_f64(&data["result"]["list"][0]["lastPrice"]),
+        "OKX" => as_f64(&data["data"][0]["last"]),
+        "CoinGecko" => data[asset.coingecko?]["usd"].as_f64(),
+        "CoinPaprika" => data["quotes"]["USD"]["price"].as_f64(),
+        _ => None,
     }
 }
 
-async fn fetch_from_source_static(source: &PriceSource) -> Result<f64> {
+async fn fetch_from_source_static(
+    source: &PriceSource,
+    asset: &AssetSpec,
+    timeout_secs: u64,
+) -> Result<f64> {
     let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
+        .timeout(std::time::Duration::from_secs(timeout_secs))
         .build()?;
 
-    let response = client.get(source.url).send().await?;
+    let response = client.get(&source.url).send().await?;
 
     if !response.status().is_success() {
         return Err(anyhow!("HTTP {}", response.status()));
@@ -232,37 +837,10 @@ async fn fetch_from_source_static(source: &PriceSource) -> Result<f64> {
 
     let data: Value = response.json().await?;
 
-    let price = match source.name {
-        "Binance" => data["price"]
-            .as_str()
-            .and_then(|s| s.parse::<f64>().ok())
-            .ok_or_else(|| anyhow!("Invalid format"))?,
-        "Coinbase" => data["price"]
-            .as_str()
-            .and_then(|s| s.parse::<f64>().ok())
-            .ok_or_else(|| anyhow!("Invalid format"))?,
-        "Kraken" => data["result"]["APTUSD"]["c"][0]
-            .as_str()
-            .and_then(|s| s.parse::<f64>().ok())
-            .ok_or_else(|| anyhow!("Invalid format"))?,
-        "Bybit" => data["result"]["list"][0]["lastPrice"]
-            .as_str()
-            .and_then(|s| s.parse::<f64>().ok())
-            .ok_or_else(|| anyhow!("Invalid format"))?,
-        "OKX" => data["data"][0]["last"]
-            .as_str()
-            .and_then(|s| s.parse::<f64>().ok())
-            .ok_or_else(|| anyhow!("Invalid format"))?,
-        "CoinGecko" => data["aptos"]["usd"]
-            .as_f64()
-            .ok_or_else(|| anyhow!("Invalid format"))?,
-        "CoinPaprika" => data["quotes"]["USD"]["price"]
-            .as_f64()
-            .ok_or_else(|| anyhow!("Invalid format"))?,
-        _ => return Err(anyhow!("Unknown source")),
-    };
-
-    if price <= 0.0 || price > 10000.0 {
+    let price =
+        parse_source_price(source.name, asset, &data).ok_or_else(|| anyhow!("Invalid format"))?;
+
+    if price <= 0.0 || price > asset.max_price {
         return Err(anyhow!("Invalid price range: {}", price));
     }
 
@@ -271,8 +849,19 @@ async fn fetch_from_source_static(source: &PriceSource) -> Result<f64> {
 
 impl Default for ChainlinkPriceFeed {
     fn default() -> Self {
-        Self::new().unwrap_or_else(|_| Self {
-            cached_price: Arc::new(RwLock::new(None)),
+        let config = PriceFeedConfig::default();
+        Self::new(&config).unwrap_or_else(|_| Self {
+            cached_prices: Arc::new(RwLock::new(HashMap::new())),
+            pairs: vec![&SUPPORTED_ASSETS[0]],
+            history: None,
+            cache_ttl_secs: config.cache_ttl_secs,
+            request_timeout_secs: config.request_timeout_secs,
+            twap_window_secs: config.twap_window_secs,
+            conversion_method: PriceMethod::Spot,
+            min_sources: config.min_sources,
+            max_deviation_pct: config.max_deviation_pct,
+            circuit_breaker: CircuitBreakerPolicy::from(&config),
+            health: SOURCE_HEALTH.clone(),
         })
     }
 }
@@ -283,22 +872,26 @@ mod tests {
 
     #[tokio::test]
     async fn test_price_feed_creation() {
-        let feed = ChainlinkPriceFeed::new();
+        let feed = ChainlinkPriceFeed::new(&PriceFeedConfig::default());
         assert!(feed.is_ok());
     }
 
     #[tokio::test]
     async fn test_apt_to_usd_conversion() {
-        let feed = ChainlinkPriceFeed::new().unwrap();
+        let feed = ChainlinkPriceFeed::new(&PriceFeedConfig::default()).unwrap();
 
         {
-            let mut cache = feed.cached_price.write().await;
-            *cache = Some(PriceData {
-                price: 12.0,
-                decimals: 8,
-                timestamp: chrono::Utc::now().timestamp(),
-                round_id: 1,
-            });
+            let mut cache = feed.cached_prices.write().await;
+            cache.insert(
+                "APT",
+                PriceData {
+                    price: 12.0,
+                    decimals: 8,
+                    timestamp: chrono::Utc::now().timestamp(),
+                    round_id: 1,
+                    degraded: false,
+                },
+            );
         }
 
         let result = feed.apt_to_usd(10.0).await;
@@ -308,20 +901,81 @@ mod tests {
 
     #[tokio::test]
     async fn test_usd_to_apt_conversion() {
-        let feed = ChainlinkPriceFeed::new().unwrap();
+        let feed = ChainlinkPriceFeed::new(&PriceFeedConfig::default()).unwrap();
 
         {
-            let mut cache = feed.cached_price.write().await;
-            *cache = Some(PriceData {
-                price: 12.0,
-                decimals: 8,
-                timestamp: chrono::Utc::now().timestamp(),
-                round_id: 1,
-            });
+            let mut cache = feed.cached_prices.write().await;
+            cache.insert(
+                "APT",
+                PriceData {
+                    price: 12.0,
+                    decimals: 8,
+                    timestamp: chrono::Utc::now().timestamp(),
+                    round_id: 1,
+                    degraded: false,
+                },
+            );
         }
 
         let result = feed.usd_to_apt(120.0).await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), 10.0);
     }
+
+    #[test]
+    fn test_find_asset_accepts_symbols_and_pairs() {
+        assert_eq!(find_asset("btc").unwrap().symbol, "BTC");
+        assert_eq!(find_asset("ETH/USD").unwrap().symbol, "ETH");
+        assert_eq!(find_asset("apt-usd").unwrap().symbol, "APT");
+        assert!(find_asset("DOGE").is_none());
+
+        let config = PriceFeedConfig {
+            pairs: vec!["APT".to_string(), "DOGE".to_string()],
+            ..Default::default()
+        };
+        assert!(ChainlinkPriceFeed::new(&config).is_err());
+    }
+
+    #[test]
+    fn test_parse_source_price_per_exchange() {
+        let btc = find_asset("BTC").unwrap();
+
+        let kraken = serde_json::json!({
+            "error": [],
+            "result": { "XXBTZUSD": { "c": ["64000.10", "0.01"] } }
+        });
+        assert_eq!(parse_source_price("Kraken", btc, &kraken), Some(64000.10));
+
+        let coingecko = serde_json::json!({ "bitcoin": { "usd": 64010.0 } });
+        assert_eq!(
+            parse_source_price("CoinGecko", btc, &coingecko),
+            Some(64010.0)
+        );
+
+        let binance = serde_json::json!({ "symbol": "BTCUSDT", "price": "64005.5" });
+        assert_eq!(parse_source_price("Binance", btc, &binance), Some(64005.5));
+        assert_eq!(parse_source_price("Unknown", btc, &binance), None);
+    }
+
+    #[test]
+    fn test_reject_outliers_uses_median_deviation() {
+        let prices = vec![
+            ("Binance".to_string(), 8.00),
+            ("Coinbase".to_string(), 8.02),
+            ("Kraken".to_string(), 7.98),
+            ("OKX".to_string(), 9.10),
+        ];
+
+        let (accepted, rejected) = reject_outliers(prices, 2.0);
+        assert_eq!(accepted.len(), 3);
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].0, "OKX");
+        assert!(rejected[0].2 > 2.0);
+
+        // Two quotes cannot outvote each other.
+        let (accepted, rejected) =
+            reject_outliers(vec![("A".to_string(), 1.0), ("B".to_string(), 2.0)], 2.0);
+        assert_eq!(accepted.len(), 2);
+        assert!(rejected.is_empty());
+    }
 }
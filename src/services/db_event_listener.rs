@@ -1,10 +1,50 @@
 use anyhow::Result;
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::{PgListener, PgPool};
 use std::time::Instant;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
 use super::blockchain_sync::BlockchainSyncService;
+use super::job_queue::backoff_delay;
+use super::response_cache::publish_invalidation;
+
+/// Row of this listener in `event_listener_state`.
+const LISTENER_STATE_ID: &str = "db_event_listener";
+
+const CHANNELS: [&str; 9] = [
+    "bet_event",
+    "market_event",
+    "new_bet_event",
+    "new_market_event",
+    "market_resolution_event",
+    "winnings_claim_event",
+    "yield_deposit_event",
+    "protocol_fee_event",
+    "blockchain_event",
+];
+
+/// Indexer streams the listener keeps a catch-up cursor for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EventStream {
+    Markets,
+    Bets,
+    Resolutions,
+    Claims,
+}
+
+impl EventStream {
+    fn for_channel(channel: &str) -> Option<Self> {
+        match channel {
+            "market_event" | "new_market_event" => Some(EventStream::Markets),
+            "bet_event" | "new_bet_event" => Some(EventStream::Bets),
+            "market_resolution_event" => Some(EventStream::Resolutions),
+            "winnings_claim_event" => Some(EventStream::Claims),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(tag = "type")]
@@ -90,9 +130,59 @@ pub struct GenericEventData {
     pub created_at: Option<String>,
 }
 
+/// The market an event payload belongs to. Claims carry only a bet id, so like any payload
+/// without a `market_id` they are treated as touching every market.
+fn payload_market_id(payload: &str) -> Option<i64> {
+    serde_json::from_str::<serde_json::Value>(payload)
+        .ok()?
+        .get("market_id")?
+        .as_i64()
+}
+
+/// Connection health and catch-up cursors of the listener, from `event_listener_state`.
+///
+/// `connected` is what the listener last recorded; if its process died it stays true, so
+/// check the `event_listener` leader as well.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenerState {
+    pub connected: bool,
+    pub connected_at: Option<NaiveDateTime>,
+    pub disconnected_at: Option<NaiveDateTime>,
+    pub last_notification_at: Option<NaiveDateTime>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<NaiveDateTime>,
+    pub reconnects: i32,
+    pub bet_version: i64,
+    pub claim_version: i64,
+    pub market_version: i64,
+    pub resolution_version: i64,
+    pub last_catch_up_at: Option<NaiveDateTime>,
+    pub last_catch_up_events: Option<i32>,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Indexer rows applied by one catch-up pass.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatchUpSummary {
+    pub markets: i64,
+    pub bets: i64,
+    pub resolutions: i64,
+    pub claims: i64,
+}
+
+impl CatchUpSummary {
+    pub fn total(&self) -> i64 {
+        self.markets + self.bets + self.resolutions + self.claims
+    }
+}
+
 pub struct DbEventListener {
     pool: PgPool,
     blockchain_sync: BlockchainSyncService,
+    reconnect_base_secs: u64,
+    reconnect_max_secs: u64,
 }
 
 impl DbEventListener {
@@ -101,80 +191,426 @@ impl DbEventListener {
         Self {
             pool,
             blockchain_sync,
+            reconnect_base_secs: 1,
+            reconnect_max_secs: 60,
         }
     }
 
-    pub async fn start_listening(self) -> Result<()> {
+    /// Sets the delay before reconnecting: `base_secs`, doubled after every failed attempt
+    /// up to `max_secs`.
+    pub fn with_reconnect_backoff(mut self, base_secs: u64, max_secs: u64) -> Self {
+        self.reconnect_base_secs = base_secs;
+        self.reconnect_max_secs = max_secs;
+        self
+    }
+
+    /// Processes notifications until `shutdown` is cancelled.
+    ///
+    /// Connections are managed here rather than by `PgListener`, so that every (re)connect
+    /// is followed by a catch-up pass over the indexer tables: notifications sent while the
+    /// listener was down are lost, but the rows behind them are still applied. Failed
+    /// connection attempts back off exponentially. On shutdown an event already being
+    /// processed is finished first; then the channels are unsubscribed.
+    pub async fn start_listening(self, shutdown: CancellationToken) -> Result<()> {
         info!("🎧 Starting database event listener...");
 
-        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let mut failures = 0;
+        let mut connections = 0u32;
+        while !shutdown.is_cancelled() {
+            let mut listener = match self.subscribe().await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    failures += 1;
+                    let delay =
+                        backoff_delay(failures, self.reconnect_base_secs, self.reconnect_max_secs);
+                    error!(
+                        "❌ Event listener connection failed (attempt {}), retrying in {}s: {:#}",
+                        failures,
+                        delay.as_secs(),
+                        e
+                    );
+                    self.record_disconnected(Some(&format!("{:#}", e))).await;
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = shutdown.cancelled() => break,
+                    }
+                    continue;
+                }
+            };
+            failures = 0;
+            connections += 1;
+            if let Err(e) = self.record_connected(connections > 1).await {
+                warn!("Failed to record listener health: {}", e);
+            }
+
+            if connections == 1 {
+                info!("✅ Database event listener started and subscribed to all channels");
+                info!("👂 Listening for INSERTUPDATE events on: bets, markets, resolutions, claims, yields, fees");
+                info!(
+                    "⚡ Real-time updates will be processed immediately when indexer adds OR modifies data"
+                );
+            } else {
+                info!("🔌 Database event listener reconnected and resubscribed");
+            }
+
+            match self.catch_up().await {
+                Ok(summary) if summary.total() > 0 => {
+                    info!(
+                        "⏩ Caught up on missed events: {} markets, {} bets, {} resolutions, {} claims",
+                        summary.markets, summary.bets, summary.resolutions, summary.claims
+                    );
+                    self.invalidate_cache(None).await;
+                }
+                Ok(_) => {}
+                Err(e) => error!("❌ Event catch-up failed: {:#}", e),
+            }
 
-        let mut listener = PgListener::connect(&database_url).await?;
+            let lost = loop {
+                let received = tokio::select! {
+                    received = listener.try_recv() => received,
+                    _ = shutdown.cancelled() => break None,
+                };
+                match received {
+                    Ok(Some(notification)) => {
+                        let channel = notification.channel();
+                        info!("📨 Received event on channel: {}", channel);
 
-        listener.listen("bet_event").await?;
-        listener.listen("market_event").await?;
+                        let _ = self.process_and_log(channel, notification.payload()).await;
+                    }
+                    Ok(None) => break Some("connection lost".to_string()),
+                    Err(e) => break Some(e.to_string()),
+                }
+            };
 
-        listener.listen("new_bet_event").await?;
-        listener.listen("new_market_event").await?;
+            match lost {
+                Some(reason) => {
+                    warn!("⚠️  Database event listener disconnected: {}", reason);
+                    self.record_disconnected(Some(&reason)).await;
+                    let delay = backoff_delay(1, self.reconnect_base_secs, self.reconnect_max_secs);
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = shutdown.cancelled() => break,
+                    }
+                }
+                None => {
+                    listener.unlisten_all().await?;
+                    info!("🔇 Database event listener unsubscribed from all channels");
+                    break;
+                }
+            }
+        }
 
-        listener.listen("market_resolution_event").await?;
-        listener.listen("winnings_claim_event").await?;
-        listener.listen("yield_deposit_event").await?;
-        listener.listen("protocol_fee_event").await?;
-        listener.listen("blockchain_event").await?;
+        self.record_disconnected(None).await;
+        Ok(())
+    }
 
-        info!("✅ Database event listener started and subscribed to all channels");
-        info!("👂 Listening for INSERTUPDATE events on: bets, markets, resolutions, claims, yields, fees");
-        info!(
-            "⚡ Real-time updates will be processed immediately when indexer adds OR modifies data"
-        );
+    async fn subscribe(&self) -> Result<PgListener> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        // Report a lost connection instead of silently reconnecting, so it can be caught up.
+        listener.eager_reconnect(false);
+        listener.listen_all(CHANNELS).await?;
+        Ok(listener)
+    }
+
+    /// Applies indexer rows above each stream's cursor that have not been applied yet, then
+    /// moves the cursors up.
+    pub async fn catch_up(&self) -> Result<CatchUpSummary> {
+        let cursors = self.cursors().await?;
+        let mut summary = CatchUpSummary::default();
 
         loop {
-            match listener.recv().await {
-                Ok(notification) => {
-                    let start = Instant::now();
-                    let channel = notification.channel();
-                    let payload = notification.payload();
-
-                    info!("📨 Received event on channel: {}", channel);
-
-                    match self.process_notification(channel, payload).await {
-                        Ok(_) => {
-                            let duration = start.elapsed().as_millis() as i32;
-                            info!("✅ Event processed successfully in {}ms", duration);
-
-                            if let Err(e) = self
-                                .log_event_processing(channel, payload, "success", None, duration)
-                                .await
-                            {
-                                warn!("Failed to log event processing: {}", e);
-                            }
-                        }
-                        Err(e) => {
-                            let duration = start.elapsed().as_millis() as i32;
-                            error!("❌ Failed to process event: {}", e);
-
-                            if let Err(log_err) = self
-                                .log_event_processing(
-                                    channel,
-                                    payload,
-                                    "error",
-                                    Some(&e.to_string()),
-                                    duration,
-                                )
-                                .await
-                            {
-                                warn!("Failed to log event error: {}", log_err);
-                            }
-                        }
+            let result = self.blockchain_sync.sync_markets().await?;
+            summary.markets += result.new_events;
+            if result.new_events == 0 || result.errors > 0 {
+                break;
+            }
+        }
+        self.advance_cursor(EventStream::Markets).await?;
+
+        let mut touched = Vec::new();
+        loop {
+            let result = self.blockchain_sync.sync_bets().await?;
+            summary.bets += result.new_events;
+            touched.extend(result.touched_markets);
+            if result.new_events == 0 || result.errors > 0 {
+                break;
+            }
+        }
+        touched.sort_unstable();
+        touched.dedup();
+        self.blockchain_sync
+            .update_market_stats_for_markets(&touched)
+            .await?;
+        self.advance_cursor(EventStream::Bets).await?;
+
+        let resolutions = sqlx::query!(
+            r#"
+            SELECT m.market_id, m.outcome AS "outcome!"
+            FROM markets m
+            JOIN markets_extended me ON me."blockchainMarketId" = m.market_id
+            WHERE m.resolved AND m.outcome IS NOT NULL AND me.status <> 'resolved'
+              AND COALESCE(m.resolution_transaction_version, m.transaction_version) > $1
+            ORDER BY COALESCE(m.resolution_transaction_version, m.transaction_version)
+            "#,
+            cursors.resolution_version
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        for resolution in resolutions {
+            self.apply_market_resolution(resolution.market_id, resolution.outcome)
+                .await?;
+            summary.resolutions += 1;
+        }
+        self.advance_cursor(EventStream::Resolutions).await?;
+
+        let claims = sqlx::query!(
+            r#"
+            SELECT b.bet_id, b.winning_amount, b.yield_share
+            FROM bets b
+            JOIN bets_extended be ON be."blockchainBetId" = b.bet_id
+            WHERE b.claimed AND be.status <> 'claimed'
+              AND COALESCE(b.claim_transaction_version, b.transaction_version) > $1
+            ORDER BY COALESCE(b.claim_transaction_version, b.transaction_version)
+            "#,
+            cursors.claim_version
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        for claim in claims {
+            self.apply_winnings_claim(
+                claim.bet_id,
+                claim.winning_amount.unwrap_or(0),
+                claim.yield_share.unwrap_or(0),
+            )
+            .await?;
+            summary.claims += 1;
+        }
+        self.advance_cursor(EventStream::Claims).await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE event_listener_state
+            SET "lastCatchUpAt" = NOW(), "lastCatchUpEvents" = $2, "updatedAt" = NOW()
+            WHERE id = $1
+            "#,
+            LISTENER_STATE_ID,
+            summary.total() as i32
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(summary)
+    }
+
+    /// Moves a stream's cursor to the highest transaction version at or below which every
+    /// indexer row has been applied. A row that could not be applied yet (say, a bet whose
+    /// market is not synced) holds the cursor back so the next catch-up retries it.
+    async fn advance_cursor(&self, stream: EventStream) -> Result<()> {
+        let query = match stream {
+            EventStream::Markets => sqlx::query!(
+                r#"
+                UPDATE event_listener_state s
+                SET "marketVersion" = COALESCE(
+                        (SELECT MIN(m.transaction_version) - 1
+                         FROM markets m
+                         LEFT JOIN markets_extended me ON me."blockchainMarketId" = m.market_id
+                         WHERE me.id IS NULL AND m.transaction_version > s."marketVersion"),
+                        (SELECT MAX(m.transaction_version)
+                         FROM markets m WHERE m.transaction_version > s."marketVersion"),
+                        s."marketVersion"),
+                    "updatedAt" = NOW()
+                WHERE s.id = $1
+                "#,
+                LISTENER_STATE_ID
+            ),
+            EventStream::Bets => sqlx::query!(
+                r#"
+                UPDATE event_listener_state s
+                SET "betVersion" = COALESCE(
+                        (SELECT MIN(b.transaction_version) - 1
+                         FROM bets b
+                         LEFT JOIN bets_extended be ON be."blockchainBetId" = b.bet_id
+                         WHERE be.id IS NULL AND b.transaction_version > s."betVersion"),
+                        (SELECT MAX(b.transaction_version)
+                         FROM bets b WHERE b.transaction_version > s."betVersion"),
+                        s."betVersion"),
+                    "updatedAt" = NOW()
+                WHERE s.id = $1
+                "#,
+                LISTENER_STATE_ID
+            ),
+            EventStream::Resolutions => sqlx::query!(
+                r#"
+                UPDATE event_listener_state s
+                SET "resolutionVersion" = COALESCE(
+                        (SELECT MIN(COALESCE(m.resolution_transaction_version, m.transaction_version)) - 1
+                         FROM markets m
+                         LEFT JOIN markets_extended me ON me."blockchainMarketId" = m.market_id
+                         WHERE m.resolved AND m.outcome IS NOT NULL
+                           AND (me.id IS NULL OR me.status <> 'resolved')
+                           AND COALESCE(m.resolution_transaction_version, m.transaction_version)
+                               > s."resolutionVersion"),
+                        (SELECT MAX(COALESCE(m.resolution_transaction_version, m.transaction_version))
+                         FROM markets m
+                         WHERE m.resolved
+                           AND COALESCE(m.resolution_transaction_version, m.transaction_version)
+                               > s."resolutionVersion"),
+                        s."resolutionVersion"),
+                    "updatedAt" = NOW()
+                WHERE s.id = $1
+                "#,
+                LISTENER_STATE_ID
+            ),
+            EventStream::Claims => sqlx::query!(
+                r#"
+                UPDATE event_listener_state s
+                SET "claimVersion" = COALESCE(
+                        (SELECT MIN(COALESCE(b.claim_transaction_version, b.transaction_version)) - 1
+                         FROM bets b
+                         LEFT JOIN bets_extended be ON be."blockchainBetId" = b.bet_id
+                         WHERE b.claimed AND (be.id IS NULL OR be.status <> 'claimed')
+                           AND COALESCE(b.claim_transaction_version, b.transaction_version)
+                               > s."claimVersion"),
+                        (SELECT MAX(COALESCE(b.claim_transaction_version, b.transaction_version))
+                         FROM bets b
+                         WHERE b.claimed
+                           AND COALESCE(b.claim_transaction_version, b.transaction_version)
+                               > s."claimVersion"),
+                        s."claimVersion"),
+                    "updatedAt" = NOW()
+                WHERE s.id = $1
+                "#,
+                LISTENER_STATE_ID
+            ),
+        };
+        query.execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn cursors(&self) -> Result<ListenerState> {
+        self.state()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("event_listener_state has no listener row"))
+    }
+
+    /// Connection health and catch-up cursors, or `None` if the listener has never run.
+    pub async fn state(&self) -> Result<Option<ListenerState>> {
+        let state = sqlx::query_as!(
+            ListenerState,
+            r#"
+            SELECT connected, "connectedAt" AS connected_at, "disconnectedAt" AS disconnected_at,
+                   "lastNotificationAt" AS last_notification_at, "lastError" AS last_error,
+                   "lastErrorAt" AS last_error_at, reconnects, "betVersion" AS bet_version,
+                   "claimVersion" AS claim_version, "marketVersion" AS market_version,
+                   "resolutionVersion" AS resolution_version, "lastCatchUpAt" AS last_catch_up_at,
+                   "lastCatchUpEvents" AS last_catch_up_events, "updatedAt" AS updated_at
+            FROM event_listener_state
+            WHERE id = $1
+            "#,
+            LISTENER_STATE_ID
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(state)
+    }
+
+    async fn record_connected(&self, reconnect: bool) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO event_listener_state (id, connected, "connectedAt")
+            VALUES ($1, TRUE, NOW())
+            ON CONFLICT (id) DO UPDATE SET
+                connected = TRUE,
+                "connectedAt" = NOW(),
+                reconnects = event_listener_state.reconnects + CASE WHEN $2 THEN 1 ELSE 0 END,
+                "updatedAt" = NOW()
+            "#,
+            LISTENER_STATE_ID,
+            reconnect
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Best effort: the database may well be what the listener lost.
+    async fn record_disconnected(&self, error: Option<&str>) {
+        let recorded = sqlx::query!(
+            r#"
+            UPDATE event_listener_state
+            SET connected = FALSE,
+                "disconnectedAt" = CASE WHEN connected THEN NOW() ELSE "disconnectedAt" END,
+                "lastError" = COALESCE($2, "lastError"),
+                "lastErrorAt" = CASE WHEN $2 IS NULL THEN "lastErrorAt" ELSE NOW() END,
+                "updatedAt" = NOW()
+            WHERE id = $1
+            "#,
+            LISTENER_STATE_ID,
+            error
+        )
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = recorded {
+            warn!("Failed to record listener health: {}", e);
+        }
+    }
+
+    /// Tells every replica's response cache that `market` (or, for `None`, any market)
+    /// changed.
+    async fn invalidate_cache(&self, market: Option<i64>) {
+        if let Err(e) = publish_invalidation(&self.pool, market).await {
+            warn!("Failed to publish cache invalidation: {}", e);
+        }
+    }
+
+    /// Processes one event and records the outcome in `event_processing_log`.
+    async fn process_and_log(&self, channel: &str, payload: &str) -> Result<()> {
+        let start = Instant::now();
+        let result = self.process_notification(channel, payload).await;
+        let duration = start.elapsed().as_millis() as i32;
+
+        if let Err(e) = sqlx::query!(
+            r#"UPDATE event_listener_state SET "lastNotificationAt" = NOW() WHERE id = $1"#,
+            LISTENER_STATE_ID
+        )
+        .execute(&self.pool)
+        .await
+        {
+            warn!("Failed to record listener health: {}", e);
+        }
+
+        match &result {
+            Ok(_) => {
+                info!("✅ Event processed successfully in {}ms", duration);
+
+                if let Some(stream) = EventStream::for_channel(channel) {
+                    if let Err(e) = self.advance_cursor(stream).await {
+                        warn!("Failed to advance the {:?} catch-up cursor: {}", stream, e);
                     }
                 }
-                Err(e) => {
-                    error!("Error receiving notification: {}", e);
-                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+
+                self.invalidate_cache(payload_market_id(payload)).await;
+
+                if let Err(e) = self
+                    .log_event_processing(channel, payload, "success", None, duration)
+                    .await
+                {
+                    warn!("Failed to log event processing: {}", e);
+                }
+            }
+            Err(e) => {
+                error!("❌ Failed to process event: {}", e);
+
+                if let Err(log_err) = self
+                    .log_event_processing(channel, payload, "error", Some(&e.to_string()), duration)
+                    .await
+                {
+                    warn!("Failed to log event error: {}", log_err);
                 }
             }
         }
+
+        result
     }
 
     async fn process_notification(&self, channel: &str, payload: &str) -> Result<()> {
@@ -277,6 +713,14 @@ impl DbEventListener {
             event.market_id, event.outcome
         );
 
+        self.apply_market_resolution(event.market_id, event.outcome)
+            .await?;
+
+        info!("✅ Market resolution processed");
+        Ok(())
+    }
+
+    pub async fn apply_market_resolution(&self, market_id: i64, outcome: bool) -> Result<()> {
         sqlx::query!(
             r#"
             UPDATE markets_extended
@@ -286,8 +730,8 @@ impl DbEventListener {
                 "updatedAt" = NOW()
             WHERE "blockchainMarketId" = $2
             "#,
-            event.outcome,
-            event.market_id
+            outcome,
+            market_id
         )
         .execute(&self.pool)
         .await?;
@@ -302,8 +746,8 @@ impl DbEventListener {
             AND position != $2
             AND status = 'active'
             "#,
-            event.market_id,
-            event.outcome
+            market_id,
+            outcome
         )
         .execute(&self.pool)
         .await?;
@@ -318,13 +762,12 @@ impl DbEventListener {
             AND position = $2
             AND status = 'active'
             "#,
-            event.market_id,
-            event.outcome
+            market_id,
+            outcome
         )
         .execute(&self.pool)
         .await?;
 
-        info!("✅ Market resolution processed");
         Ok(())
     }
 
@@ -334,7 +777,20 @@ impl DbEventListener {
             event.bet_id, event.user_addr, event.winning_amount
         );
 
-        let total_payout = event.winning_amount + event.yield_share;
+        self.apply_winnings_claim(event.bet_id, event.winning_amount, event.yield_share)
+            .await?;
+
+        info!("✅ Winnings claim processed");
+        Ok(())
+    }
+
+    async fn apply_winnings_claim(
+        &self,
+        bet_id: i64,
+        winning_amount: i64,
+        yield_share: i64,
+    ) -> Result<()> {
+        let total_payout = winning_amount + yield_share;
         let total_payout_decimal = sqlx::types::BigDecimal::from(total_payout);
 
         sqlx::query!(
@@ -346,12 +802,11 @@ impl DbEventListener {
             WHERE "blockchainBetId" = $2
             "#,
             total_payout_decimal,
-            event.bet_id
+            bet_id
         )
         .execute(&self.pool)
         .await?;
 
-        info!("✅ Winnings claim processed");
         Ok(())
     }
 
@@ -361,20 +816,8 @@ impl DbEventListener {
             event.market_id, event.amount
         );
 
-        let amount_decimal = sqlx::types::BigDecimal::from(event.amount);
-
-        sqlx::query!(
-            r#"
-            UPDATE markets_extended
-            SET "totalYieldEarned" = "totalYieldEarned" + $1,
-                "updatedAt" = NOW()
-            WHERE "blockchainMarketId" = $2
-            "#,
-            amount_decimal,
-            event.market_id
-        )
-        .execute(&self.pool)
-        .await?;
+        // "totalYieldEarned" is derived from the yield_records ledger by the accrual job;
+        // on-chain deposits are only logged here so they are not counted twice.
 
         info!("✅ Yield deposit processed");
         Ok(())
@@ -431,6 +874,78 @@ impl DbEventListener {
         Ok(())
     }
 
+    #[allow(dead_code)]
+    pub async fn list_failed_events(&self, limit: i64) -> Result<Vec<EventLogEntry>> {
+        let entries = sqlx::query_as!(
+            EventLogEntry,
+            r#"
+            SELECT
+                id,
+                event_type,
+                event_data,
+                transaction_version,
+                processing_status,
+                error_message,
+                processing_duration_ms,
+                created_at
+            FROM event_processing_log
+            WHERE processing_status = 'error'
+            ORDER BY created_at DESC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Re-runs a logged event through the same handlers as a live notification.
+    ///
+    /// The retry is logged as a new entry; on success the original entry is marked `retried`
+    /// so it no longer shows up in [`Self::list_failed_events`].
+    #[allow(dead_code)]
+    pub async fn retry_logged_event(&self, id: i32) -> Result<EventRetryResult> {
+        let entry = sqlx::query!(
+            r#"
+            SELECT event_type, event_data, processing_status
+            FROM event_processing_log
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Event log entry {} not found", id))?;
+
+        let payload = match entry.event_data.get("raw").and_then(|v| v.as_str()) {
+            Some(raw) => raw.to_string(),
+            None => entry.event_data.to_string(),
+        };
+
+        info!("🔁 Retrying event {} on channel {}", id, entry.event_type);
+
+        let result = self.process_and_log(&entry.event_type, &payload).await;
+
+        if result.is_ok() {
+            sqlx::query!(
+                "UPDATE event_processing_log SET processing_status = 'retried' WHERE id = $1",
+                id
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(EventRetryResult {
+            id,
+            event_type: entry.event_type,
+            previous_status: entry.processing_status,
+            success: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+        })
+    }
+
     pub async fn get_event_stats(&self) -> Result<Vec<EventStats>> {
         let stats = sqlx::query_as!(
             EventStats,
@@ -461,3 +976,174 @@ pub struct EventStats {
     pub avg_duration_ms: Option<sqlx::types::BigDecimal>,
     pub last_processed_at: Option<chrono::NaiveDateTime>,
 }
+
+#[allow(dead_code)]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventLogEntry {
+    pub id: i32,
+    pub event_type: String,
+    pub event_data: serde_json::Value,
+    pub transaction_version: Option<i64>,
+    pub processing_status: String,
+    pub error_message: Option<String>,
+    pub processing_duration_ms: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventRetryResult {
+    pub id: i32,
+    pub event_type: String,
+    pub previous_status: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn indexer_market(pool: &PgPool, market_id: i64, version: i64) {
+        sqlx::query!(
+            r#"
+            INSERT INTO markets (market_id, question, end_time, yield_protocol_addr,
+                                 transaction_version, transaction_block_height)
+            VALUES ($1, 'Will it rain?', EXTRACT(EPOCH FROM NOW() + INTERVAL '1 day')::bigint,
+                    '0xprotocol', $2, $2)
+            "#,
+            market_id,
+            version
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn indexer_bet(pool: &PgPool, bet_id: i64, market_id: i64, version: i64) {
+        sqlx::query!(
+            r#"
+            INSERT INTO bets (bet_id, market_id, user_addr, position, amount,
+                              transaction_version, transaction_block_height)
+            VALUES ($1, $2, '0xu1', true, 100, $3, $3)
+            "#,
+            bet_id,
+            market_id,
+            version
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn listener(pool: &PgPool) -> DbEventListener {
+        let listener = DbEventListener::new(pool.clone());
+        listener.record_connected(false).await.unwrap();
+        listener
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_bet_placed_while_disconnected_is_caught_up(pool: PgPool) {
+        let listener = listener(&pool).await;
+        indexer_market(&pool, 1, 1).await;
+        assert_eq!(listener.catch_up().await.unwrap().markets, 1);
+
+        // No notification reaches the listener for this bet.
+        listener.record_disconnected(Some("connection reset")).await;
+        indexer_bet(&pool, 10, 1, 2).await;
+
+        let summary = listener.catch_up().await.unwrap();
+        assert_eq!((summary.markets, summary.bets), (0, 1));
+        let synced = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM bets_extended WHERE "blockchainBetId" = 10"#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(synced, 1);
+
+        let state = listener.cursors().await.unwrap();
+        assert_eq!((state.market_version, state.bet_version), (1, 2));
+        assert_eq!(state.last_catch_up_events, Some(1));
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_resolution_and_claim_are_applied_on_catch_up(pool: PgPool) {
+        let listener = listener(&pool).await;
+        indexer_market(&pool, 1, 1).await;
+        indexer_bet(&pool, 10, 1, 2).await;
+        listener.catch_up().await.unwrap();
+
+        sqlx::query!(
+            r#"
+            UPDATE markets SET resolved = TRUE, outcome = TRUE, resolution_transaction_version = 5
+            WHERE market_id = 1
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            UPDATE bets
+            SET claimed = TRUE, winning_amount = 150, yield_share = 10,
+                claim_transaction_version = 6
+            WHERE bet_id = 10
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let summary = listener.catch_up().await.unwrap();
+        assert_eq!((summary.resolutions, summary.claims), (1, 1));
+
+        let market = sqlx::query!(
+            r#"SELECT status, result FROM markets_extended WHERE "blockchainMarketId" = 1"#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            (market.status.as_str(), market.result),
+            ("resolved", Some(true))
+        );
+        let bet = sqlx::query!(
+            r#"
+            SELECT status, payout::int AS "payout!"
+            FROM bets_extended WHERE "blockchainBetId" = 10
+            "#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((bet.status.as_str(), bet.payout), ("claimed", 160));
+
+        let state = listener.cursors().await.unwrap();
+        assert_eq!((state.resolution_version, state.claim_version), (5, 6));
+
+        // Already applied: a second pass finds nothing to do.
+        assert_eq!(listener.catch_up().await.unwrap().total(), 0);
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_unsynced_market_holds_the_bet_cursor_back(pool: PgPool) {
+        let listener = listener(&pool).await;
+        indexer_market(&pool, 1, 1).await;
+        // Bet 10's market has not reached the indexer yet; bet 11's has.
+        indexer_bet(&pool, 10, 2, 3).await;
+        indexer_bet(&pool, 11, 1, 4).await;
+
+        let summary = listener.catch_up().await.unwrap();
+        assert_eq!(summary.bets, 1);
+        assert_eq!(listener.cursors().await.unwrap().bet_version, 2);
+
+        indexer_market(&pool, 2, 5).await;
+        let summary = listener.catch_up().await.unwrap();
+        assert_eq!((summary.markets, summary.bets), (1, 1));
+        let state = listener.cursors().await.unwrap();
+        assert_eq!((state.market_version, state.bet_version), (5, 4));
+    }
+}
@@ -4,6 +4,8 @@ use sqlx::PgPool;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use crate::config::ChainConfig;
+
 #[derive(Debug, Deserialize, Serialize)]
 #[allow(dead_code)]
 pub struct MarketCreatedEvent {
@@ -65,17 +67,11 @@ pub struct EventIndexer {
 
 #[allow(dead_code)]
 impl EventIndexer {
-    pub fn new(pool: PgPool) -> Result<Self> {
-        let node_url = std::env::var("APTOS_NODE_URL")
-            .unwrap_or_else(|_| "https://fullnode.testnet.aptoslabs.com/v1".to_string());
-
-        let module_address = std::env::var("APTOS_MODULE_ADDRESS")
-            .map_err(|_| anyhow!("APTOS_MODULE_ADDRESS environment variable is required"))?;
-
+    pub fn new(pool: PgPool, chain: &ChainConfig) -> Result<Self> {
         Ok(Self {
             pool,
-            node_url,
-            module_address,
+            node_url: chain.node_url.clone(),
+            module_address: chain.require_module_address()?.to_string(),
             last_processed_version: 0,
         })
     }
@@ -318,20 +314,21 @@ impl EventIndexer {
         );
 
         let market_id: i64 = data.market_id.parse()?;
-        let yield_earned = data.total_yield_earned.parse::<sqlx::types::BigDecimal>()?;
+        info!(
+            "Market {} reported {} yield on-chain; ledger total is kept by the accrual job",
+            market_id, data.total_yield_earned
+        );
 
         sqlx::query!(
             r#"
             UPDATE markets_extended
             SET status = 'resolved',
                 result = $1,
-                "totalYieldEarned" = $2,
                 "resolutionDate" = NOW(),
                 "updatedAt" = NOW()
-            WHERE "blockchainMarketId" = $3
+            WHERE "blockchainMarketId" = $2
             "#,
             data.outcome,
-            yield_earned,
             market_id
         )
         .execute(&self.pool)
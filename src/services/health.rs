@@ -0,0 +1,446 @@
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::db::ReplicaStatus;
+
+use super::chainlink_price_feed::ChainlinkPriceFeed;
+use super::db_event_listener::DbEventListener;
+use super::job_queue::{JobKind, JobQueue};
+use super::leader_election::{job_leaders, SchedulerJob};
+
+/// Outcome of a check, and of readiness as a whole (the worst of its checks).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Unhealthy,
+}
+
+impl HealthStatus {
+    /// Grades `value` against a degraded and an unhealthy threshold.
+    pub fn grade(value: i64, degraded: i64, unhealthy: i64) -> Self {
+        if value >= unhealthy {
+            HealthStatus::Unhealthy
+        } else if value >= degraded {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Ok
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheck {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// What was observed, with the thresholds it was graded against.
+    pub details: Value,
+}
+
+impl HealthCheck {
+    fn new(status: HealthStatus, details: Value) -> Self {
+        Self {
+            status,
+            message: None,
+            details,
+        }
+    }
+
+    fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    fn failed(message: String) -> Self {
+        Self::new(HealthStatus::Unhealthy, Value::Null).with_message(message)
+    }
+
+    fn disabled() -> Self {
+        Self::new(HealthStatus::Ok, Value::Null).with_message("disabled")
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Readiness {
+    pub status: HealthStatus,
+    pub checks: BTreeMap<&'static str, HealthCheck>,
+}
+
+/// Dependency checks behind `/health/ready`, graded against `[health]`.
+pub struct HealthService {
+    pool: PgPool,
+    config: Arc<Config>,
+    replica: Option<ReplicaStatus>,
+}
+
+impl HealthService {
+    pub fn new(pool: PgPool, config: Arc<Config>) -> Self {
+        Self {
+            pool,
+            config,
+            replica: None,
+        }
+    }
+
+    /// Reports the read replica's last lag check; without one the check is disabled.
+    pub fn with_replica(mut self, replica: Option<ReplicaStatus>) -> Self {
+        self.replica = replica;
+        self
+    }
+
+    pub async fn readiness(&self) -> Readiness {
+        // Read the pool before the other checks borrow connections from it.
+        let pool = self.check_pool();
+        let (database, (indexer_lag, event_listener), last_sync, price_freshness) = tokio::join!(
+            self.timed(self.check_database()),
+            self.check_indexer(),
+            self.timed(self.check_last_sync()),
+            self.timed(self.check_prices()),
+        );
+
+        let checks = BTreeMap::from([
+            ("database", database),
+            ("pool", pool),
+            ("indexerLag", indexer_lag),
+            ("eventListener", event_listener),
+            ("lastSync", last_sync),
+            ("priceFreshness", price_freshness),
+            ("replica", self.check_replica()),
+        ]);
+        let status = checks
+            .values()
+            .map(|c| c.status)
+            .max()
+            .unwrap_or(HealthStatus::Ok);
+        Readiness { status, checks }
+    }
+
+    /// Runs a check under `health.check_timeout_ms`; errors and timeouts are unhealthy.
+    async fn timed(&self, check: impl Future<Output = Result<HealthCheck>>) -> HealthCheck {
+        let timeout = Duration::from_millis(self.config.health.check_timeout_ms);
+        match tokio::time::timeout(timeout, check).await {
+            Ok(Ok(check)) => check,
+            Ok(Err(e)) => HealthCheck::failed(format!("{:#}", e)),
+            Err(_) => HealthCheck::failed(format!("timed out after {}ms", timeout.as_millis())),
+        }
+    }
+
+    async fn check_database(&self) -> Result<HealthCheck> {
+        sqlx::query_scalar!("SELECT 1 AS \"one!\"")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(HealthCheck::new(HealthStatus::Ok, Value::Null))
+    }
+
+    fn check_pool(&self) -> HealthCheck {
+        let max = self.config.database.max_connections.max(1);
+        let in_use = self.pool.size().saturating_sub(self.pool.num_idle() as u32);
+        let used_pct = in_use * 100 / max;
+        let health = &self.config.health;
+        HealthCheck::new(
+            HealthStatus::grade(
+                used_pct as i64,
+                health.pool_degraded_pct as i64,
+                health.pool_unhealthy_pct as i64,
+            ),
+            json!({
+                "inUse": in_use,
+                "idle": self.pool.num_idle(),
+                "maxConnections": max,
+                "usedPct": used_pct,
+                "degradedPct": health.pool_degraded_pct,
+                "unhealthyPct": health.pool_unhealthy_pct,
+            }),
+        )
+    }
+
+    /// Indexer lag and listener health both read the listener's state, so they share it.
+    async fn check_indexer(&self) -> (HealthCheck, HealthCheck) {
+        let timeout = Duration::from_millis(self.config.health.check_timeout_ms);
+        let state = tokio::time::timeout(timeout, DbEventListener::new(self.pool.clone()).state())
+            .await
+            .map_err(|_| anyhow::anyhow!("timed out after {}ms", timeout.as_millis()))
+            .and_then(|state| state);
+        let state = match state {
+            Ok(state) => state,
+            Err(e) => {
+                let message = format!("{:#}", e);
+                return (
+                    HealthCheck::failed(message.clone()),
+                    HealthCheck::failed(message),
+                );
+            }
+        };
+
+        let applied = state.as_ref().map(|s| s.bet_version);
+        let lag = self.timed(self.check_indexer_lag(applied));
+        let listener = self.timed(self.check_listener(state.as_ref().map(|s| s.connected)));
+        tokio::join!(lag, listener)
+    }
+
+    /// Versions between the newest bet in the indexer's `bets` table and the newest one the
+    /// backend has applied, per the event listener's cursor or `indexer_state`.
+    async fn check_indexer_lag(&self, listener_version: Option<i64>) -> Result<HealthCheck> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                (SELECT MAX(transaction_version) FROM bets) AS latest,
+                (SELECT MAX(last_processed_version) FROM indexer_state) AS processed
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let applied = row.processed.max(listener_version).unwrap_or(0);
+        let lag = row
+            .latest
+            .map(|latest| (latest - applied).max(0))
+            .unwrap_or(0);
+        let health = &self.config.health;
+        Ok(HealthCheck::new(
+            HealthStatus::grade(
+                lag,
+                health.indexer_lag_degraded_versions,
+                health.indexer_lag_unhealthy_versions,
+            ),
+            json!({
+                "latestVersion": row.latest,
+                "appliedVersion": applied,
+                "lagVersions": lag,
+                "degradedVersions": health.indexer_lag_degraded_versions,
+                "unhealthyVersions": health.indexer_lag_unhealthy_versions,
+            }),
+        ))
+    }
+
+    /// The listener runs on one replica; with leader election on, its recorded connection
+    /// only counts while that replica still holds the listener's lock.
+    async fn check_listener(&self, connected: Option<bool>) -> Result<HealthCheck> {
+        let Some(connected) = connected else {
+            return Ok(HealthCheck::new(HealthStatus::Degraded, Value::Null)
+                .with_message("event listener has not connected yet"));
+        };
+
+        let leader = if self.config.scheduler.enable_leader_election {
+            job_leaders(&self.pool)
+                .await?
+                .into_iter()
+                .find(|l| l.job == SchedulerJob::EventListener.as_str())
+        } else {
+            None
+        };
+        let leader_active = leader.as_ref().map(|l| l.active);
+        let details = json!({
+            "connected": connected,
+            "leader": leader.as_ref().map(|l| &l.instance_id),
+            "leaderActive": leader_active,
+        });
+
+        if !connected {
+            return Ok(HealthCheck::new(HealthStatus::Degraded, details)
+                .with_message("event listener is disconnected"));
+        }
+        if leader_active == Some(false) {
+            return Ok(HealthCheck::new(HealthStatus::Degraded, details)
+                .with_message("event listener's instance is gone"));
+        }
+        Ok(HealthCheck::new(HealthStatus::Ok, details))
+    }
+
+    async fn check_last_sync(&self) -> Result<HealthCheck> {
+        if !self.config.scheduler.enable_indexer_sync {
+            return Ok(HealthCheck::disabled());
+        }
+
+        let finished_at = JobQueue::new(self.pool.clone(), &self.config.jobs)
+            .last_succeeded(JobKind::IndexerSync)
+            .await?;
+        let health = &self.config.health;
+        let Some(finished_at) = finished_at else {
+            return Ok(HealthCheck::new(HealthStatus::Degraded, Value::Null)
+                .with_message("no indexer sync has succeeded yet"));
+        };
+
+        let age = age_secs(finished_at);
+        Ok(HealthCheck::new(
+            HealthStatus::grade(age, health.sync_degraded_secs, health.sync_unhealthy_secs),
+            json!({
+                "lastSucceededAt": finished_at,
+                "ageSecs": age,
+                "degradedSecs": health.sync_degraded_secs,
+                "unhealthySecs": health.sync_unhealthy_secs,
+            }),
+        ))
+    }
+
+    /// An unusable replica only degrades readiness: its reads fall back to the primary.
+    fn check_replica(&self) -> HealthCheck {
+        let Some(replica) = &self.replica else {
+            return HealthCheck::disabled();
+        };
+        let details = serde_json::to_value(replica).unwrap_or(Value::Null);
+        if replica.usable {
+            return HealthCheck::new(HealthStatus::Ok, details);
+        }
+        let message = match (&replica.last_error, replica.last_checked_at) {
+            (_, None) => "read replica has not been checked yet".to_string(),
+            (Some(error), _) => format!("reading from the primary: {}", error),
+            (None, _) => "reading from the primary: replica lag is over the limit".to_string(),
+        };
+        HealthCheck::new(HealthStatus::Degraded, details).with_message(message)
+    }
+
+    /// Graded on the stalest configured pair.
+    async fn check_prices(&self) -> Result<HealthCheck> {
+        if !self.config.scheduler.enable_price_sampling {
+            return Ok(HealthCheck::disabled());
+        }
+
+        let feed =
+            ChainlinkPriceFeed::new(&self.config.price_feed)?.with_history(self.pool.clone());
+        let health = &self.config.health;
+        let mut status = HealthStatus::Ok;
+        let mut missing = Vec::new();
+        let mut pairs = serde_json::Map::new();
+        for asset in feed.pairs() {
+            let pair = asset.pair();
+            match feed.latest_sample(asset).await? {
+                Some(sample) => {
+                    let age = Utc::now().timestamp() - sample.timestamp;
+                    status = status.max(HealthStatus::grade(
+                        age,
+                        health.price_degraded_secs,
+                        health.price_unhealthy_secs,
+                    ));
+                    pairs.insert(pair, json!({ "price": sample.price, "ageSecs": age }));
+                }
+                None => {
+                    status = status.max(HealthStatus::Degraded);
+                    pairs.insert(pair.clone(), Value::Null);
+                    missing.push(pair);
+                }
+            }
+        }
+
+        let check = HealthCheck::new(
+            status,
+            json!({
+                "pairs": pairs,
+                "degradedSecs": health.price_degraded_secs,
+                "unhealthySecs": health.price_unhealthy_secs,
+            }),
+        );
+        Ok(if missing.is_empty() {
+            check
+        } else {
+            check.with_message(format!("no price samples for {}", missing.join(", ")))
+        })
+    }
+}
+
+fn age_secs(at: NaiveDateTime) -> i64 {
+    (Utc::now().naive_utc() - at).num_seconds().max(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grade_uses_inclusive_thresholds() {
+        assert_eq!(HealthStatus::grade(79, 80, 100), HealthStatus::Ok);
+        assert_eq!(HealthStatus::grade(80, 80, 100), HealthStatus::Degraded);
+        assert_eq!(HealthStatus::grade(100, 80, 100), HealthStatus::Unhealthy);
+    }
+
+    fn service(pool: PgPool, configure: impl FnOnce(&mut Config)) -> HealthService {
+        let mut config = Config::default();
+        config.health.indexer_lag_degraded_versions = 100;
+        config.health.indexer_lag_unhealthy_versions = 500;
+        configure(&mut config);
+        HealthService::new(pool, Arc::new(config))
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_indexer_lag_is_graded_from_the_furthest_applied_version(pool: PgPool) {
+        sqlx::query!(
+            r#"
+            INSERT INTO bets (bet_id, market_id, user_addr, position, amount,
+                              transaction_version, transaction_block_height)
+            VALUES (1, 1, '0xu1', true, 100, 1000, 1000)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let health = service(pool.clone(), |_| {});
+
+        // Nothing applied yet.
+        let check = health.check_indexer_lag(None).await.unwrap();
+        assert_eq!(check.status, HealthStatus::Unhealthy);
+        assert_eq!(check.details["lagVersions"], 1000);
+
+        // The listener's cursor alone.
+        let check = health.check_indexer_lag(Some(950)).await.unwrap();
+        assert_eq!(check.status, HealthStatus::Ok);
+        assert_eq!(check.details["appliedVersion"], 950);
+
+        // indexer_state is ahead of a lagging listener.
+        sqlx::query!(
+            "INSERT INTO indexer_state (indexer_name, last_processed_version) VALUES ('kizo', 800)"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let check = health.check_indexer_lag(Some(300)).await.unwrap();
+        assert_eq!(check.status, HealthStatus::Degraded);
+        assert_eq!(check.details["lagVersions"], 200);
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_listener_on_a_gone_instance_is_degraded(pool: PgPool) {
+        // The recorded leader's session holds no lock: its replica died.
+        sqlx::query!(
+            r#"
+            INSERT INTO scheduler_leaders (job, "instanceId", "backendPid")
+            VALUES ('event_listener', 'replica-a', 0)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let health = service(pool.clone(), |c| c.scheduler.enable_leader_election = true);
+        let check = health.check_listener(Some(true)).await.unwrap();
+        assert_eq!(check.status, HealthStatus::Degraded);
+        assert_eq!(
+            check.message.as_deref(),
+            Some("event listener's instance is gone")
+        );
+        assert_eq!(check.details["leader"], "replica-a");
+        assert_eq!(check.details["leaderActive"], false);
+
+        // Without leader election the recorded connection is all there is to go on.
+        let health = service(pool, |c| c.scheduler.enable_leader_election = false);
+        assert_eq!(
+            health.check_listener(Some(true)).await.unwrap().status,
+            HealthStatus::Ok
+        );
+        assert_eq!(
+            health.check_listener(Some(false)).await.unwrap().status,
+            HealthStatus::Degraded
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+/// An in-progress key older than this is assumed to belong to a request that died
+/// (e.g. a restart) and can be taken over by a retry.
+const IN_PROGRESS_TIMEOUT_SECS: f64 = 300.0;
+
+/// Outcome of presenting an `Idempotency-Key` with a request.
+#[derive(Debug, PartialEq)]
+pub enum KeyClaim {
+    /// First use of the key (or it expired): run the request.
+    New,
+    /// The key already completed with the same request: replay its response.
+    Replay(StoredResponse),
+    /// The key was first used with a different request.
+    Mismatch,
+    /// The original request with this key is still running.
+    InProgress,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub body: String,
+    pub content_type: Option<String>,
+}
+
+/// Stored form of a client's key: keys are scoped to the caller that sent them, so two
+/// callers picking the same key never see each other's responses.
+pub fn caller_key(caller: &str, key: &str) -> String {
+    format!("{}|{}", caller, key)
+}
+
+/// SHA-256 over method, path and body, identifying what a key was first used for.
+pub fn request_hash(method: &str, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+/// Stored idempotency keys and the responses they produced.
+pub struct IdempotencyStore {
+    pool: PgPool,
+}
+
+impl IdempotencyStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Claims `key` for a request with `request_hash`, keeping it for `retention_secs`.
+    /// Expired keys are purged first, so a key can be reused once its window has passed.
+    pub async fn claim(
+        &self,
+        key: &str,
+        request_hash: &str,
+        retention_secs: i64,
+    ) -> Result<KeyClaim> {
+        sqlx::query!(r#"DELETE FROM idempotency_keys WHERE "expiresAt" < NOW()"#)
+            .execute(&self.pool)
+            .await?;
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO idempotency_keys (key, "requestHash", "expiresAt")
+            VALUES ($1, $2, NOW() + make_interval(secs => $3))
+            ON CONFLICT (key) DO NOTHING
+            "#,
+            key,
+            request_hash,
+            retention_secs as f64
+        )
+        .execute(&self.pool)
+        .await?;
+        if inserted.rows_affected() == 1 {
+            return Ok(KeyClaim::New);
+        }
+
+        let Some(existing) = sqlx::query!(
+            r#"
+            SELECT "requestHash" as request_hash, status,
+                   "responseStatus" as response_status, "responseBody" as response_body,
+                   "responseContentType" as response_content_type
+            FROM idempotency_keys
+            WHERE key = $1
+            "#,
+            key
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        else {
+            // Expired and purged by a concurrent request; let the client retry.
+            return Ok(KeyClaim::InProgress);
+        };
+
+        if existing.request_hash != request_hash {
+            return Ok(KeyClaim::Mismatch);
+        }
+
+        if existing.status == "completed" {
+            return Ok(KeyClaim::Replay(StoredResponse {
+                status: existing.response_status.unwrap_or(200) as u16,
+                body: existing.response_body.unwrap_or_default(),
+                content_type: existing.response_content_type,
+            }));
+        }
+
+        let taken_over = sqlx::query!(
+            r#"
+            UPDATE idempotency_keys
+            SET "createdAt" = NOW()
+            WHERE key = $1
+              AND status = 'in_progress'
+              AND "createdAt" < NOW() - make_interval(secs => $2)
+            "#,
+            key,
+            IN_PROGRESS_TIMEOUT_SECS
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if taken_over.rows_affected() == 1 {
+            Ok(KeyClaim::New)
+        } else {
+            Ok(KeyClaim::InProgress)
+        }
+    }
+
+    /// Stores the response of the request that claimed `key`, to be replayed on retries.
+    pub async fn complete(&self, key: &str, response: &StoredResponse) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE idempotency_keys
+            SET status = 'completed',
+                "responseStatus" = $2,
+                "responseBody" = $3,
+                "responseContentType" = $4,
+                "completedAt" = NOW()
+            WHERE key = $1
+            "#,
+            key,
+            response.status as i32,
+            response.body,
+            response.content_type
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_hash_covers_method_path_and_body() {
+        let hash = request_hash("POST", "/api/bets", br#"{"amount":"100"}"#);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(
+            hash,
+            request_hash("POST", "/api/bets", br#"{"amount":"100"}"#)
+        );
+        assert_ne!(
+            hash,
+            request_hash("POST", "/api/bets", br#"{"amount":"200"}"#)
+        );
+        assert_ne!(
+            hash,
+            request_hash("POST", "/api/bets/claim", br#"{"amount":"100"}"#)
+        );
+        assert_ne!(
+            request_hash("POST", "/a", b"b/c"),
+            request_hash("POST", "/a/b", b"c")
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_server_errors_are_replayed(pool: PgPool) {
+        let store = IdempotencyStore::new(pool);
+        let key = caller_key("api-key:0123456789ab", "retry-me");
+        let hash = request_hash("POST", "/api/bets", b"{}");
+
+        assert_eq!(store.claim(&key, &hash, 60).await.unwrap(), KeyClaim::New);
+        let failed = StoredResponse {
+            status: 500,
+            body: r#"{"success":false}"#.to_string(),
+            content_type: Some("application/json".to_string()),
+        };
+        store.complete(&key, &failed).await.unwrap();
+
+        assert_eq!(
+            store.claim(&key, &hash, 60).await.unwrap(),
+            KeyClaim::Replay(failed)
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_keys_are_scoped_to_the_caller(pool: PgPool) {
+        let store = IdempotencyStore::new(pool);
+        let hash = request_hash("POST", "/api/bets", b"{}");
+
+        let first = caller_key("api-key:0123456789ab", "shared");
+        let second = caller_key("anonymous", "shared");
+        assert_eq!(store.claim(&first, &hash, 60).await.unwrap(), KeyClaim::New);
+        assert_eq!(
+            store.claim(&second, &hash, 60).await.unwrap(),
+            KeyClaim::New
+        );
+    }
+}
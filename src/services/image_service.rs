@@ -3,6 +3,8 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
+use crate::config::ImagesConfig;
+
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct PexelsSearchResponse {
@@ -46,8 +48,8 @@ pub struct ImageService {
 }
 
 impl ImageService {
-    pub fn new() -> Result<Self> {
-        let pexels_api_key = std::env::var("PEXELS_API_KEY").unwrap_or_else(|_| String::new());
+    pub fn new(config: &ImagesConfig) -> Result<Self> {
+        let pexels_api_key = config.pexels_api_key.clone().unwrap_or_default();
 
         if pexels_api_key.is_empty() {
             warn!("PEXELS_API_KEY not set, will use fallback images only");
@@ -325,7 +327,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_image_service_creation() {
-        let service = ImageService::new();
+        let service = ImageService::new(&ImagesConfig::default());
         assert!(service.is_ok());
     }
 }
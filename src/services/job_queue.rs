@@ -0,0 +1,801 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::config::JobsConfig;
+
+/// First key of the per-kind enqueue locks (`pg_advisory_xact_lock(int, int)`), next to the
+/// job locks' 0x4b5a and the bet locks' 0x4b5b namespaces.
+const ENQUEUE_LOCK_NAMESPACE: i32 = 0x4b5c;
+
+/// Work the queue knows how to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    IndexerSync,
+    YieldCalc,
+    YieldAccrual,
+    ApyRefresh,
+    Reconciliation,
+}
+
+impl JobKind {
+    pub const ALL: [JobKind; 5] = [
+        JobKind::IndexerSync,
+        JobKind::YieldCalc,
+        JobKind::YieldAccrual,
+        JobKind::ApyRefresh,
+        JobKind::Reconciliation,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::IndexerSync => "indexer_sync",
+            JobKind::YieldCalc => "yield_calc",
+            JobKind::YieldAccrual => "yield_accrual",
+            JobKind::ApyRefresh => "apy_refresh",
+            JobKind::Reconciliation => "reconciliation",
+        }
+    }
+}
+
+impl FromStr for JobKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        JobKind::ALL
+            .into_iter()
+            .find(|k| k.as_str() == s)
+            .ok_or_else(|| anyhow!("Unknown job kind: {}", s))
+    }
+}
+
+impl fmt::Display for JobKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Succeeded => "succeeded",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl FromStr for JobState {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "queued" => Ok(JobState::Queued),
+            "running" => Ok(JobState::Running),
+            "succeeded" => Ok(JobState::Succeeded),
+            "failed" => Ok(JobState::Failed),
+            "cancelled" => Ok(JobState::Cancelled),
+            other => Err(anyhow!("Unknown job state: {}", other)),
+        }
+    }
+}
+
+/// When a recurring job is due: a fixed interval, or a cron expression evaluated in UTC
+/// (`sec min hour day-of-month month day-of-week [year]`).
+#[derive(Debug, Clone)]
+pub enum JobSchedule {
+    Every(Duration),
+    Cron(Box<cron::Schedule>),
+}
+
+impl JobSchedule {
+    pub fn cron(expression: &str) -> Result<Self> {
+        let schedule = cron::Schedule::from_str(expression)
+            .map_err(|e| anyhow!("Invalid cron expression '{}': {}", expression, e))?;
+        Ok(JobSchedule::Cron(Box::new(schedule)))
+    }
+
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            JobSchedule::Every(interval) => {
+                Some(after + chrono::Duration::from_std(*interval).ok()?)
+            }
+            JobSchedule::Cron(schedule) => schedule.after(&after).next(),
+        }
+    }
+}
+
+impl fmt::Display for JobSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobSchedule::Every(interval) => write!(f, "every {}s", interval.as_secs()),
+            JobSchedule::Cron(schedule) => write!(f, "cron {}", schedule),
+        }
+    }
+}
+
+/// A row of `jobs`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: String,
+    pub kind: String,
+    pub payload: Value,
+    pub state: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub next_run_at: NaiveDateTime,
+    pub last_error: Option<String>,
+    pub result: Option<Value>,
+    pub locked_by: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub updated_at: NaiveDateTime,
+}
+
+/// A row of `job_schedules`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledJob {
+    pub kind: String,
+    pub schedule: String,
+    pub paused: bool,
+    pub next_run_at: NaiveDateTime,
+    pub last_enqueued_at: Option<NaiveDateTime>,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NewJob {
+    pub kind: JobKind,
+    /// Kind-specific options, e.g. `{"marketId": "..."}` for `yield_accrual` or
+    /// `{"repair": true}` for `reconciliation`
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub payload: Option<Value>,
+    /// Delay before the first attempt
+    #[serde(default)]
+    pub delay_secs: Option<u64>,
+    /// Attempts before the job is marked failed; defaults to `jobs.max_attempts`
+    #[serde(default)]
+    pub max_attempts: Option<i32>,
+}
+
+#[derive(Debug, Default)]
+pub struct JobFilter {
+    pub state: Option<JobState>,
+    pub kind: Option<JobKind>,
+    pub limit: i64,
+}
+
+/// What happened to a job that returned an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureOutcome {
+    /// Queued again after the given backoff.
+    Retrying(Duration),
+    /// Out of attempts; the job is now `failed`.
+    Failed,
+    /// The job was cancelled or changed while it ran; the result was dropped.
+    Dropped,
+}
+
+/// Delay before attempt `attempts + 1`: `base * 2^(attempts - 1)`, capped at `max`.
+pub fn backoff_delay(attempts: i32, base_secs: u64, max_secs: u64) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 32) as u32;
+    let secs = base_secs.saturating_mul(1u64 << exponent).min(max_secs);
+    Duration::from_secs(secs)
+}
+
+pub struct JobQueue {
+    pool: PgPool,
+    config: JobsConfig,
+}
+
+impl JobQueue {
+    pub fn new(pool: PgPool, config: &JobsConfig) -> Self {
+        Self {
+            pool,
+            config: config.clone(),
+        }
+    }
+
+    pub async fn enqueue(&self, job: NewJob) -> Result<Job> {
+        self.insert(&self.pool, job).await
+    }
+
+    async fn insert<'e>(&self, executor: impl PgExecutor<'e>, job: NewJob) -> Result<Job> {
+        let max_attempts = job.max_attempts.unwrap_or(self.config.max_attempts);
+        if max_attempts < 1 {
+            return Err(anyhow!("maxAttempts must be at least 1"));
+        }
+        let delay_secs = job.delay_secs.unwrap_or(0) as f64;
+
+        let job = sqlx::query_as!(
+            Job,
+            r#"
+            INSERT INTO jobs (id, kind, payload, "maxAttempts", "nextRunAt")
+            VALUES ($1, $2, $3, $4, NOW() + make_interval(secs => $5))
+            RETURNING id, kind, payload, state, attempts, "maxAttempts" as max_attempts,
+                      "nextRunAt" as next_run_at, "lastError" as last_error, result,
+                      "lockedBy" as locked_by, "createdAt" as created_at,
+                      "startedAt" as started_at, "finishedAt" as finished_at,
+                      "updatedAt" as updated_at
+            "#,
+            Uuid::new_v4().to_string(),
+            job.kind.as_str(),
+            job.payload
+                .unwrap_or_else(|| Value::Object(Default::default())),
+            max_attempts,
+            delay_secs
+        )
+        .fetch_one(executor)
+        .await
+        .context("Failed to enqueue job")?;
+        Ok(job)
+    }
+
+    /// Returns the queued or running job of `kind` if there is one, otherwise enqueues a
+    /// new one. The flag is true when a job was enqueued. Concurrent callers, and schedules
+    /// coming due, take the kind's enqueue lock, so only one of them enqueues.
+    pub async fn enqueue_unless_pending(&self, kind: JobKind) -> Result<(Job, bool)> {
+        let mut tx = self.pool.begin().await?;
+        lock_kind(&mut tx, kind).await?;
+        let pending = sqlx::query_as!(
+            Job,
+            r#"
+            SELECT id, kind, payload, state, attempts, "maxAttempts" as max_attempts,
+                   "nextRunAt" as next_run_at, "lastError" as last_error, result,
+                   "lockedBy" as locked_by, "createdAt" as created_at,
+                   "startedAt" as started_at, "finishedAt" as finished_at,
+                   "updatedAt" as updated_at
+            FROM jobs
+            WHERE kind = $1 AND state IN ('queued', 'running')
+            ORDER BY "createdAt"
+            LIMIT 1
+            "#,
+            kind.as_str()
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(job) = pending {
+            return Ok((job, false));
+        }
+
+        let job = self
+            .insert(
+                &mut *tx,
+                NewJob {
+                    kind,
+                    payload: None,
+                    delay_secs: None,
+                    max_attempts: None,
+                },
+            )
+            .await?;
+        tx.commit().await?;
+        Ok((job, true))
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<Job>> {
+        let job = sqlx::query_as!(
+            Job,
+            r#"
+            SELECT id, kind, payload, state, attempts, "maxAttempts" as max_attempts,
+                   "nextRunAt" as next_run_at, "lastError" as last_error, result,
+                   "lockedBy" as locked_by, "createdAt" as created_at,
+                   "startedAt" as started_at, "finishedAt" as finished_at,
+                   "updatedAt" as updated_at
+            FROM jobs
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(job)
+    }
+
+    /// Newest jobs first.
+    pub async fn list(&self, filter: &JobFilter) -> Result<Vec<Job>> {
+        let jobs = sqlx::query_as!(
+            Job,
+            r#"
+            SELECT id, kind, payload, state, attempts, "maxAttempts" as max_attempts,
+                   "nextRunAt" as next_run_at, "lastError" as last_error, result,
+                   "lockedBy" as locked_by, "createdAt" as created_at,
+                   "startedAt" as started_at, "finishedAt" as finished_at,
+                   "updatedAt" as updated_at
+            FROM jobs
+            WHERE ($1::text IS NULL OR state = $1)
+              AND ($2::text IS NULL OR kind = $2)
+            ORDER BY "createdAt" DESC
+            LIMIT $3
+            "#,
+            filter.state.map(|s| s.as_str()),
+            filter.kind.map(|k| k.as_str()),
+            filter.limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(jobs)
+    }
+
+    /// Claims the next due job for `worker`. Jobs of a paused kind are left queued.
+    pub async fn claim(&self, worker: &str) -> Result<Option<Job>> {
+        let job = sqlx::query_as!(
+            Job,
+            r#"
+            UPDATE jobs
+            SET state = 'running',
+                attempts = attempts + 1,
+                "lockedBy" = $1,
+                "startedAt" = NOW(),
+                "updatedAt" = NOW()
+            WHERE id = (
+                SELECT j.id FROM jobs j
+                WHERE j.state = 'queued'
+                  AND j."nextRunAt" <= NOW()
+                  AND NOT EXISTS (
+                      SELECT 1 FROM job_schedules s WHERE s.kind = j.kind AND s.paused
+                  )
+                ORDER BY j."nextRunAt"
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, kind, payload, state, attempts, "maxAttempts" as max_attempts,
+                      "nextRunAt" as next_run_at, "lastError" as last_error, result,
+                      "lockedBy" as locked_by, "createdAt" as created_at,
+                      "startedAt" as started_at, "finishedAt" as finished_at,
+                      "updatedAt" as updated_at
+            "#,
+            worker
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(job)
+    }
+
+    /// Marks a claimed job succeeded. Returns false if it was cancelled while running, or
+    /// taken over by another claim.
+    ///
+    /// `complete`, `release`, `fail` and `heartbeat` only touch the job while it is still
+    /// held by the claim that returned `job`: the same worker (`lockedBy`) and claim time
+    /// (`startedAt`). Attempt counts cannot fence a claim, as `retry` resets them.
+    pub async fn complete(&self, job: &Job, result: &Value) -> Result<bool> {
+        let updated = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET state = 'succeeded', result = $4, "lastError" = NULL,
+                "finishedAt" = NOW(), "updatedAt" = NOW()
+            WHERE id = $1 AND state = 'running' AND "lockedBy" = $2 AND "startedAt" = $3
+            "#,
+            job.id,
+            job.locked_by,
+            job.started_at,
+            result
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(updated.rows_affected() == 1)
+    }
+
+    /// Puts a job interrupted by shutdown back in the queue without counting the attempt.
+    /// Returns false if it was cancelled while running.
+    pub async fn release(&self, job: &Job) -> Result<bool> {
+        let updated = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET state = 'queued', attempts = attempts - 1, "lockedBy" = NULL,
+                "lastError" = 'Interrupted by shutdown', "nextRunAt" = NOW(), "updatedAt" = NOW()
+            WHERE id = $1 AND state = 'running' AND "lockedBy" = $2 AND "startedAt" = $3
+            "#,
+            job.id,
+            job.locked_by,
+            job.started_at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(updated.rows_affected() == 1)
+    }
+
+    /// Records a failed attempt: the job is queued again with exponential backoff, or marked
+    /// failed once it has used all its attempts.
+    pub async fn fail(&self, job: &Job, error: &str) -> Result<FailureOutcome> {
+        let (state, delay) = if job.attempts < job.max_attempts {
+            let delay = backoff_delay(
+                job.attempts,
+                self.config.backoff_base_secs,
+                self.config.backoff_max_secs,
+            );
+            (JobState::Queued, Some(delay))
+        } else {
+            (JobState::Failed, None)
+        };
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET state = $4,
+                "lastError" = $5,
+                "nextRunAt" = NOW() + make_interval(secs => $6),
+                "finishedAt" = CASE WHEN $4 = 'failed' THEN NOW() END,
+                "updatedAt" = NOW()
+            WHERE id = $1 AND state = 'running' AND "lockedBy" = $2 AND "startedAt" = $3
+            "#,
+            job.id,
+            job.locked_by,
+            job.started_at,
+            state.as_str(),
+            error,
+            delay.map(|d| d.as_secs_f64()).unwrap_or(0.0)
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(match (updated.rows_affected(), delay) {
+            (0, _) => FailureOutcome::Dropped,
+            (_, Some(delay)) => FailureOutcome::Retrying(delay),
+            (_, None) => FailureOutcome::Failed,
+        })
+    }
+
+    /// Queues a failed or cancelled job again with a fresh set of attempts.
+    pub async fn retry(&self, id: &str) -> Result<Option<Job>> {
+        let job = sqlx::query_as!(
+            Job,
+            r#"
+            UPDATE jobs
+            SET state = 'queued', attempts = 0, "nextRunAt" = NOW(), "lockedBy" = NULL,
+                "finishedAt" = NULL, "updatedAt" = NOW()
+            WHERE id = $1 AND state IN ('failed', 'cancelled')
+            RETURNING id, kind, payload, state, attempts, "maxAttempts" as max_attempts,
+                      "nextRunAt" as next_run_at, "lastError" as last_error, result,
+                      "lockedBy" as locked_by, "createdAt" as created_at,
+                      "startedAt" as started_at, "finishedAt" as finished_at,
+                      "updatedAt" as updated_at
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(job)
+    }
+
+    /// Cancels a queued or running job. A running job is not interrupted, but its outcome
+    /// is discarded and it is not retried.
+    pub async fn cancel(&self, id: &str) -> Result<Option<Job>> {
+        let job = sqlx::query_as!(
+            Job,
+            r#"
+            UPDATE jobs
+            SET state = 'cancelled', "finishedAt" = NOW(), "updatedAt" = NOW()
+            WHERE id = $1 AND state IN ('queued', 'running')
+            RETURNING id, kind, payload, state, attempts, "maxAttempts" as max_attempts,
+                      "nextRunAt" as next_run_at, "lastError" as last_error, result,
+                      "lockedBy" as locked_by, "createdAt" as created_at,
+                      "startedAt" as started_at, "finishedAt" as finished_at,
+                      "updatedAt" as updated_at
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(job)
+    }
+
+    /// Makes `job_schedules` match the configured schedules. A new schedule is due at once;
+    /// a changed one from its next run after now. Pause flags of schedules that stay are
+    /// kept, and so is their next run, so restarts do not re-run every job.
+    pub async fn sync_schedules(&self, schedules: &[(JobKind, JobSchedule)]) -> Result<()> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        for (kind, schedule) in schedules {
+            let next_run = schedule
+                .next_after(now)
+                .ok_or_else(|| anyhow!("Schedule for {} never fires", kind))?;
+            sqlx::query!(
+                r#"
+                INSERT INTO job_schedules (kind, schedule, "nextRunAt")
+                VALUES ($1, $2, $3)
+                ON CONFLICT (kind) DO UPDATE SET
+                    schedule = EXCLUDED.schedule,
+                    "nextRunAt" = $4,
+                    "updatedAt" = NOW()
+                WHERE job_schedules.schedule <> EXCLUDED.schedule
+                "#,
+                kind.as_str(),
+                schedule.to_string(),
+                now.naive_utc(),
+                next_run.naive_utc()
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let kinds: Vec<String> = schedules
+            .iter()
+            .map(|(k, _)| k.as_str().to_string())
+            .collect();
+        sqlx::query!("DELETE FROM job_schedules WHERE kind <> ALL($1)", &kinds)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn schedules(&self) -> Result<Vec<ScheduledJob>> {
+        let schedules = sqlx::query_as!(
+            ScheduledJob,
+            r#"
+            SELECT kind, schedule, paused, "nextRunAt" as next_run_at,
+                   "lastEnqueuedAt" as last_enqueued_at, "updatedAt" as updated_at
+            FROM job_schedules
+            ORDER BY kind
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(schedules)
+    }
+
+    /// Pauses or resumes a kind. Returns None if the kind has no schedule.
+    pub async fn set_paused(&self, kind: JobKind, paused: bool) -> Result<Option<ScheduledJob>> {
+        let schedule = sqlx::query_as!(
+            ScheduledJob,
+            r#"
+            UPDATE job_schedules SET paused = $2, "updatedAt" = NOW()
+            WHERE kind = $1
+            RETURNING kind, schedule, paused, "nextRunAt" as next_run_at,
+                      "lastEnqueuedAt" as last_enqueued_at, "updatedAt" as updated_at
+            "#,
+            kind.as_str(),
+            paused
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(schedule)
+    }
+
+    /// Enqueues a job for every due, unpaused schedule and moves the schedule to its next
+    /// run. A kind that still has a queued or running job is not enqueued twice; its
+    /// schedule just moves on. Returns the kinds that were enqueued.
+    pub async fn enqueue_due(&self, schedules: &[(JobKind, JobSchedule)]) -> Result<Vec<JobKind>> {
+        let now = Utc::now();
+        let mut enqueued = Vec::new();
+
+        for (kind, schedule) in schedules {
+            let Some(next_run) = schedule.next_after(now) else {
+                continue;
+            };
+            let mut tx = self.pool.begin().await?;
+            let due = sqlx::query!(
+                r#"
+                UPDATE job_schedules
+                SET "nextRunAt" = $3, "updatedAt" = NOW()
+                WHERE kind = $1 AND NOT paused AND "nextRunAt" <= $2
+                "#,
+                kind.as_str(),
+                now.naive_utc(),
+                next_run.naive_utc()
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+                == 1;
+            if !due {
+                continue;
+            }
+
+            lock_kind(&mut tx, *kind).await?;
+            let inserted = sqlx::query!(
+                r#"
+                INSERT INTO jobs (id, kind, "maxAttempts")
+                SELECT $1, $2, $3
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM jobs WHERE kind = $2 AND state IN ('queued', 'running')
+                )
+                "#,
+                Uuid::new_v4().to_string(),
+                kind.as_str(),
+                self.config.max_attempts
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+                == 1;
+            if inserted {
+                sqlx::query!(
+                    r#"UPDATE job_schedules SET "lastEnqueuedAt" = NOW() WHERE kind = $1"#,
+                    kind.as_str()
+                )
+                .execute(&mut *tx)
+                .await?;
+                enqueued.push(*kind);
+            }
+            tx.commit().await?;
+        }
+        Ok(enqueued)
+    }
+
+    /// Puts jobs whose worker stopped heartbeating back in the queue (or fails them when they
+    /// are out of attempts). Returns how many were recovered.
+    pub async fn requeue_stale(&self) -> Result<u64> {
+        let recovered = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET state = CASE WHEN attempts < "maxAttempts" THEN 'queued' ELSE 'failed' END,
+                "lastError" = 'Worker stopped before finishing the job',
+                "nextRunAt" = NOW(),
+                "finishedAt" = CASE WHEN attempts < "maxAttempts" THEN NULL ELSE NOW() END,
+                "updatedAt" = NOW()
+            WHERE state = 'running' AND "updatedAt" < NOW() - make_interval(secs => $1)
+            "#,
+            self.config.stale_after_secs as f64
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(recovered.rows_affected())
+    }
+
+    /// When a job of `kind` last finished successfully.
+    pub async fn last_succeeded(&self, kind: JobKind) -> Result<Option<NaiveDateTime>> {
+        let finished_at = sqlx::query_scalar!(
+            r#"
+            SELECT MAX("finishedAt") FROM jobs
+            WHERE kind = $1 AND state = 'succeeded'
+            "#,
+            kind.as_str()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(finished_at)
+    }
+
+    /// Bumps `updatedAt` of a running job so it is not taken for stale.
+    pub async fn heartbeat(&self, job: &Job) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE jobs SET "updatedAt" = NOW()
+            WHERE id = $1 AND state = 'running' AND "lockedBy" = $2 AND "startedAt" = $3
+            "#,
+            job.id,
+            job.locked_by,
+            job.started_at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes finished jobs older than `jobs.retention_days`.
+    pub async fn prune(&self) -> Result<u64> {
+        let deleted = sqlx::query!(
+            r#"
+            DELETE FROM jobs
+            WHERE state IN ('succeeded', 'failed', 'cancelled')
+              AND "finishedAt" < NOW() - make_interval(days => $1)
+            "#,
+            self.config.retention_days as i32
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(deleted.rows_affected())
+    }
+}
+
+/// Takes the enqueue lock of `kind` until `conn`'s transaction ends.
+async fn lock_kind(conn: &mut PgConnection, kind: JobKind) -> Result<()> {
+    sqlx::query!(
+        "SELECT pg_advisory_xact_lock($1, hashtext($2))",
+        ENQUEUE_LOCK_NAMESPACE,
+        kind.as_str()
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        assert_eq!(backoff_delay(1, 30, 3600), Duration::from_secs(30));
+        assert_eq!(backoff_delay(2, 30, 3600), Duration::from_secs(60));
+        assert_eq!(backoff_delay(4, 30, 3600), Duration::from_secs(240));
+        assert_eq!(backoff_delay(10, 30, 3600), Duration::from_secs(3600));
+        assert_eq!(backoff_delay(100, 30, 3600), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_schedule_next_run() {
+        let now = DateTime::parse_from_rfc3339("2026-01-01T10:07:30Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let every = JobSchedule::Every(Duration::from_secs(300));
+        assert_eq!(
+            every.next_after(now).unwrap().to_rfc3339(),
+            "2026-01-01T10:12:30+00:00"
+        );
+
+        let cron = JobSchedule::cron("0 */15 * * * *").unwrap();
+        assert_eq!(
+            cron.next_after(now).unwrap().to_rfc3339(),
+            "2026-01-01T10:15:00+00:00"
+        );
+        assert!(JobSchedule::cron("every five minutes").is_err());
+    }
+
+    #[test]
+    fn test_job_kind_round_trip() {
+        for kind in JobKind::ALL {
+            assert_eq!(kind.as_str().parse::<JobKind>().unwrap(), kind);
+        }
+        assert!("unknown".parse::<JobKind>().is_err());
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_claim_from_before_a_retry_cannot_finish_the_job(pool: PgPool) {
+        let queue = JobQueue::new(pool, &JobsConfig::default());
+        let job = queue
+            .enqueue(NewJob {
+                kind: JobKind::Reconciliation,
+                payload: None,
+                delay_secs: None,
+                max_attempts: None,
+            })
+            .await
+            .unwrap();
+
+        // Cancelled while running and retried: the new claim is on attempt 1 again.
+        let stale = queue.claim("worker-a").await.unwrap().unwrap();
+        queue.cancel(&job.id).await.unwrap();
+        queue.retry(&job.id).await.unwrap().unwrap();
+        let current = queue.claim("worker-b").await.unwrap().unwrap();
+        assert_eq!(stale.attempts, current.attempts);
+
+        assert!(!queue.complete(&stale, &Value::Null).await.unwrap());
+        assert!(!queue.release(&stale).await.unwrap());
+        assert_eq!(
+            queue.fail(&stale, "late").await.unwrap(),
+            FailureOutcome::Dropped
+        );
+        assert!(queue.complete(&current, &Value::Null).await.unwrap());
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_concurrent_enqueue_unless_pending_enqueues_once(pool: PgPool) {
+        let queue = JobQueue::new(pool, &JobsConfig::default());
+        let results = futures::future::join_all(
+            (0..8).map(|_| queue.enqueue_unless_pending(JobKind::IndexerSync)),
+        )
+        .await;
+
+        let jobs: Vec<(Job, bool)> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(jobs.iter().filter(|(_, enqueued)| *enqueued).count(), 1);
+        assert!(jobs.iter().all(|(job, _)| job.id == jobs[0].0.id));
+    }
+}
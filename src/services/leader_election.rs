@@ -0,0 +1,295 @@
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{Connection, PgConnection, PgPool};
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// First key of every job lock (`pg_try_advisory_lock(int, int)`), keeping them apart from
+/// any other advisory locks taken on the database. 0x4b5a is "KZ".
+const LOCK_NAMESPACE: i32 = 0x4b5a;
+
+/// Background jobs that must run on exactly one replica at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchedulerJob {
+    /// Enqueues due recurring jobs; the queued jobs themselves run on any replica.
+    JobScheduler,
+    PriceSampling,
+    TxConfirmation,
+    EventListener,
+}
+
+impl SchedulerJob {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SchedulerJob::JobScheduler => "job_scheduler",
+            SchedulerJob::PriceSampling => "price_sampling",
+            SchedulerJob::TxConfirmation => "tx_confirmation",
+            SchedulerJob::EventListener => "event_listener",
+        }
+    }
+
+    /// Second key of the job's advisory lock. Never reuse a value for a different job;
+    /// 1-3 belonged to the sync and yield loops that moved to the job queue.
+    fn lock_key(&self) -> i32 {
+        match self {
+            SchedulerJob::JobScheduler => 7,
+            SchedulerJob::PriceSampling => 4,
+            SchedulerJob::TxConfirmation => 5,
+            SchedulerJob::EventListener => 6,
+        }
+    }
+}
+
+impl fmt::Display for SchedulerJob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The replica recorded as leading a job. `active` is false when the recorded session no
+/// longer holds the lock, i.e. the leader died and no replica has taken over yet.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobLeader {
+    pub job: String,
+    pub instance_id: String,
+    pub acquired_at: NaiveDateTime,
+    pub heartbeat_at: NaiveDateTime,
+    pub active: bool,
+}
+
+/// Identifies this process in `scheduler_leaders` when `scheduler.instance_id` is not set.
+pub fn default_instance_id() -> String {
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .filter(|h| !h.is_empty())
+        .or_else(|| {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|h| h.trim().to_string())
+                .filter(|h| !h.is_empty())
+        })
+        .unwrap_or_else(|| "localhost".to_string());
+    format!("{}:{}", host, std::process::id())
+}
+
+/// Advisory-lock leader election, one lock per job.
+///
+/// Each job keeps its own connection outside the pool. The lock is tied to that session,
+/// so when the leader exits or its connection drops Postgres releases the lock and a
+/// standby replica picks the job up on its next attempt.
+#[derive(Clone)]
+pub struct LeaderElection {
+    pool: PgPool,
+    instance_id: String,
+    retry_interval: Duration,
+}
+
+impl LeaderElection {
+    pub fn new(pool: PgPool, instance_id: impl Into<String>, retry_interval: Duration) -> Self {
+        Self {
+            pool,
+            instance_id: instance_id.into(),
+            retry_interval,
+        }
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Runs `work` while this instance leads `job`, until `shutdown` is cancelled. Standbys
+    /// try for the lock every retry interval. The leader heartbeats on the same interval and
+    /// stops `work` as soon as its lock connection fails; if `work` finishes on its own the
+    /// lock is released and contested again. `work` is expected to watch `shutdown` itself,
+    /// so on shutdown the leader waits for it to wind down before releasing the lock.
+    pub async fn run<F, Fut>(&self, job: SchedulerJob, shutdown: &CancellationToken, mut work: F)
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let mut conn: Option<PgConnection> = None;
+        let mut standing_by = false;
+
+        while !shutdown.is_cancelled() {
+            if conn.is_none() {
+                match PgConnection::connect_with(&self.pool.connect_options()).await {
+                    Ok(c) => conn = Some(c),
+                    Err(e) => error!("❌ [{}] Leader election connection failed: {}", job, e),
+                }
+            }
+
+            let mut reconnect = false;
+            if let Some(c) = conn.as_mut() {
+                match self.try_acquire(job, c).await {
+                    Ok(true) => {
+                        info!("👑 [{}] Leadership acquired by {}", job, self.instance_id);
+                        standing_by = false;
+                        tokio::select! {
+                            _ = work() => {
+                                if !shutdown.is_cancelled() {
+                                    warn!("⚠️  [{}] Job stopped; releasing leadership", job);
+                                }
+                                reconnect = self.release(job, c).await.is_err();
+                            }
+                            e = self.hold(job, c) => {
+                                warn!("👋 [{}] Leadership lost by {}: {:#}", job, self.instance_id, e);
+                                reconnect = true;
+                            }
+                        }
+                    }
+                    Ok(false) => {
+                        if !standing_by {
+                            info!("⏳ [{}] Led by another instance; standing by", job);
+                            standing_by = true;
+                        }
+                    }
+                    Err(e) => {
+                        error!("❌ [{}] Leader election failed: {:#}", job, e);
+                        reconnect = true;
+                    }
+                }
+            }
+            if reconnect {
+                conn = None;
+            }
+
+            tokio::select! {
+                _ = time::sleep(self.retry_interval) => {}
+                _ = shutdown.cancelled() => {}
+            }
+        }
+
+        if let Some(c) = conn {
+            if let Err(e) = c.close().await {
+                warn!(
+                    "⚠️  [{}] Failed to close the leader election connection: {}",
+                    job, e
+                );
+            }
+        }
+    }
+
+    async fn try_acquire(&self, job: SchedulerJob, conn: &mut PgConnection) -> Result<bool> {
+        let acquired = sqlx::query_scalar!(
+            r#"SELECT pg_try_advisory_lock($1, $2) AS "acquired!""#,
+            LOCK_NAMESPACE,
+            job.lock_key()
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .context("Failed to try the job lock")?;
+        if !acquired {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO scheduler_leaders (job, "instanceId", "backendPid")
+            VALUES ($1, $2, pg_backend_pid())
+            ON CONFLICT (job) DO UPDATE SET
+                "instanceId" = EXCLUDED."instanceId",
+                "backendPid" = EXCLUDED."backendPid",
+                "acquiredAt" = CURRENT_TIMESTAMP,
+                "heartbeatAt" = CURRENT_TIMESTAMP
+            "#,
+            job.as_str(),
+            self.instance_id
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to record leadership")?;
+        Ok(true)
+    }
+
+    /// Heartbeats on the lock connection until it fails, then returns the error.
+    async fn hold(&self, job: SchedulerJob, conn: &mut PgConnection) -> anyhow::Error {
+        loop {
+            time::sleep(self.retry_interval).await;
+            let updated = sqlx::query!(
+                r#"
+                UPDATE scheduler_leaders SET "heartbeatAt" = CURRENT_TIMESTAMP
+                WHERE job = $1 AND "backendPid" = pg_backend_pid()
+                "#,
+                job.as_str()
+            )
+            .execute(&mut *conn)
+            .await;
+            match updated {
+                Ok(result) if result.rows_affected() == 1 => {}
+                Ok(_) => return anyhow::anyhow!("leader record was replaced"),
+                Err(e) => return anyhow::Error::new(e).context("heartbeat failed"),
+            }
+        }
+    }
+
+    async fn release(&self, job: SchedulerJob, conn: &mut PgConnection) -> Result<()> {
+        let released = sqlx::query_scalar!(
+            r#"SELECT pg_advisory_unlock($1, $2) AS "released!""#,
+            LOCK_NAMESPACE,
+            job.lock_key()
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        if !released {
+            bail!("lock was not held");
+        }
+        Ok(())
+    }
+
+    /// Recorded leader of every job that has had one, checked against `pg_locks`.
+    pub async fn leaders(&self) -> Result<Vec<JobLeader>> {
+        job_leaders(&self.pool).await
+    }
+}
+
+/// Recorded leader of every job that has had one, checked against `pg_locks`.
+pub async fn job_leaders(pool: &PgPool) -> Result<Vec<JobLeader>> {
+    let leaders = sqlx::query_as!(
+        JobLeader,
+        r#"
+        SELECT
+            l.job,
+            l."instanceId" AS instance_id,
+            l."acquiredAt" AS acquired_at,
+            l."heartbeatAt" AS heartbeat_at,
+            EXISTS (
+                SELECT 1 FROM pg_locks k
+                WHERE k.locktype = 'advisory'
+                  AND k.granted
+                  AND k.pid = l."backendPid"
+                  AND k.classid::bigint = $1
+            ) AS "active!"
+        FROM scheduler_leaders l
+        ORDER BY l.job
+        "#,
+        LOCK_NAMESPACE as i64
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(leaders)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_lock_keys_are_unique() {
+        let jobs = [
+            SchedulerJob::JobScheduler,
+            SchedulerJob::PriceSampling,
+            SchedulerJob::TxConfirmation,
+            SchedulerJob::EventListener,
+        ];
+        let keys: std::collections::HashSet<_> = jobs.iter().map(|j| j.lock_key()).collect();
+        let names: std::collections::HashSet<_> = jobs.iter().map(|j| j.as_str()).collect();
+        assert_eq!(keys.len(), jobs.len());
+        assert_eq!(names.len(), jobs.len());
+    }
+}
@@ -0,0 +1,513 @@
+use anyhow::{anyhow, Result};
+use bigdecimal::BigDecimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::config::ChainConfig;
+
+use super::aptos_contract::{AptosContractService, CreateMarketParams};
+use super::chain_transactions::{
+    ChainTransactionService, NewChainTransaction, TxKind, ROW_PENDING,
+};
+use super::market_outcomes::{parimutuel_payout, MarketType, NO_OUTCOME, YES_OUTCOME};
+use super::response_cache::publish_invalidation;
+use super::signer::SignerPool;
+
+/// Operator actions on markets shared by the admin routes and the `kizo-admin` CLI.
+pub struct MarketAdminService {
+    pool: PgPool,
+    chain: ChainConfig,
+    signers: Option<Arc<SignerPool>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncMarketsData {
+    pub total_found: usize,
+    pub synced: usize,
+    pub failed: usize,
+    pub markets: Vec<SyncedMarket>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncedMarket {
+    pub market_id: String,
+    pub question: String,
+    pub blockchain_market_id: Option<i64>,
+    pub status: String,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketStatusChange {
+    pub market_id: String,
+    pub blockchain_market_id: Option<i64>,
+    pub status: String,
+    pub result: Option<bool>,
+    /// Winning outcome index of a categorical market.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winning_outcome: Option<i32>,
+    pub bets_updated: u64,
+}
+
+impl MarketAdminService {
+    pub fn new(pool: PgPool, chain: &ChainConfig) -> Self {
+        Self {
+            pool,
+            chain: chain.clone(),
+            signers: None,
+        }
+    }
+
+    /// Accounts `push_pending_markets` signs with.
+    pub fn with_signers(mut self, signers: Arc<SignerPool>) -> Self {
+        self.signers = Some(signers);
+        self
+    }
+
+    /// Creates on-chain markets for active database markets that have no blockchain ID yet.
+    pub async fn push_pending_markets(&self, limit: usize) -> Result<SyncMarketsData> {
+        let signers = self
+            .signers
+            .clone()
+            .ok_or_else(|| anyhow!("No signer available for market creation"))?;
+        let aptos_service = AptosContractService::new(&self.chain)?.with_signers(signers);
+        let protocol_selector_addr = self.chain.protocol_selector_addr()?.to_string();
+        let tx_service = ChainTransactionService::new(self.pool.clone(), &self.chain.node_url);
+
+        let markets = sqlx::query!(
+            r#"
+            SELECT id, "marketId", question, description, "endDate"
+            FROM markets_extended
+            WHERE "blockchainMarketId" IS NULL
+            AND status = 'active'
+            ORDER BY "createdAt" DESC
+            LIMIT $1
+            "#,
+            limit as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total_found = markets.len();
+        info!("Found {} markets without blockchain ID", total_found);
+
+        let mut synced = 0;
+        let mut failed = 0;
+        let mut synced_markets = Vec::new();
+
+        for market in markets {
+            let market_id_str = market.marketId.clone().unwrap_or_default();
+            info!(
+                "Processing market: {} - {}",
+                market_id_str,
+                market.question.as_deref().unwrap_or("No question")
+            );
+
+            let now = chrono::Utc::now();
+            let end_utc = market.endDate.and_utc();
+            let duration = end_utc.signed_duration_since(now);
+            let duration_seconds = duration.num_seconds().max(0) as u64;
+
+            let params = CreateMarketParams {
+                question: market
+                    .question
+                    .clone()
+                    .unwrap_or_else(|| "Untitled Market".to_string()),
+                description: market
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| "No description".to_string()),
+                duration_seconds,
+                token_type: self.chain.token_type.clone(),
+                protocol_selector_addr: protocol_selector_addr.clone(),
+            };
+
+            match aptos_service.create_market(params).await {
+                Ok(result) => {
+                    info!(
+                        "Created market on blockchain: ID {}, TX: {}",
+                        result.market_id, result.tx_hash
+                    );
+
+                    match sqlx::query!(
+                        r#"
+                        UPDATE markets_extended
+                        SET "blockchainMarketId" = $1, "txHash" = $3, "txStatus" = $4
+                        WHERE id = $2
+                        "#,
+                        result.market_id,
+                        market.id,
+                        result.tx_hash,
+                        ROW_PENDING
+                    )
+                    .execute(&self.pool)
+                    .await
+                    {
+                        Ok(_) => {
+                            if let Err(e) = tx_service
+                                .record(
+                                    NewChainTransaction {
+                                        kind: TxKind::CreateMarket,
+                                        sender: &result.sender,
+                                        payload: &result.payload,
+                                        hash: &result.tx_hash,
+                                        market_id: Some(&market.id),
+                                        bet_id: None,
+                                    },
+                                    self.chain.tx_expiration_secs,
+                                )
+                                .await
+                            {
+                                error!("Failed to record transaction {}: {}", result.tx_hash, e);
+                            }
+
+                            synced += 1;
+                            synced_markets.push(SyncedMarket {
+                                market_id: market_id_str,
+                                question: market.question.unwrap_or_default(),
+                                blockchain_market_id: Some(result.market_id),
+                                status: "synced".to_string(),
+                            });
+                        }
+                        Err(e) => {
+                            error!("Failed to update database for market {}: {}", market.id, e);
+                            failed += 1;
+                            synced_markets.push(SyncedMarket {
+                                market_id: market_id_str,
+                                question: market.question.unwrap_or_default(),
+                                blockchain_market_id: Some(result.market_id),
+                                status: format!("blockchain_created_but_db_update_failed: {}", e),
+                            });
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to create market on blockchain: {}", e);
+                    failed += 1;
+                    synced_markets.push(SyncedMarket {
+                        market_id: market_id_str,
+                        question: market.question.unwrap_or_default(),
+                        blockchain_market_id: None,
+                        status: format!("failed: {}", e),
+                    });
+                }
+            }
+        }
+
+        Ok(SyncMarketsData {
+            total_found,
+            synced,
+            failed,
+            markets: synced_markets,
+        })
+    }
+
+    /// Marks an active market resolved and settles its active bets as won or lost.
+    #[allow(dead_code)]
+    pub async fn resolve_market(
+        &self,
+        identifier: &str,
+        outcome: bool,
+    ) -> Result<MarketStatusChange> {
+        let mut tx = self.pool.begin().await?;
+        let (market_id, blockchain_market_id) = self.find_market(&mut tx, identifier).await?;
+
+        let market = sqlx::query!(
+            r#"SELECT "marketType" as market_type, status FROM markets_extended WHERE id = $1"#,
+            market_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if market.status != "active" {
+            return Err(anyhow!("Market {} is already {}", market_id, market.status));
+        }
+        if market.market_type.parse::<MarketType>()? == MarketType::Categorical {
+            return Err(anyhow!(
+                "Market {} is categorical; resolve it by outcome index",
+                market_id
+            ));
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE markets_extended
+            SET status = 'resolved',
+                result = $1,
+                "resolutionDate" = NOW(),
+                "updatedAt" = NOW()
+            WHERE id = $2
+            "#,
+            outcome,
+            market_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let bets = sqlx::query!(
+            r#"
+            UPDATE bets_extended
+            SET status = CASE WHEN position = $2 THEN 'won' ELSE 'lost' END,
+                "updatedAt" = NOW()
+            WHERE "marketId" = $1 AND status = 'active'
+            "#,
+            market_id,
+            outcome
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        self.invalidate_cache(blockchain_market_id).await;
+
+        info!(
+            "Resolved market {} as {} ({} bets settled)",
+            market_id,
+            if outcome { "YES" } else { "NO" },
+            bets.rows_affected()
+        );
+
+        Ok(MarketStatusChange {
+            market_id,
+            blockchain_market_id,
+            status: "resolved".to_string(),
+            result: Some(outcome),
+            winning_outcome: None,
+            bets_updated: bets.rows_affected(),
+        })
+    }
+
+    /// Resolves a market by outcome index. Binary markets map 0 to YES and 1 to NO; categorical
+    /// markets settle parimutuel, each winning bet's payout being its share of the winning
+    /// outcome's pool applied to the whole pool.
+    #[allow(dead_code)]
+    pub async fn resolve_outcome(
+        &self,
+        identifier: &str,
+        outcome_index: i32,
+    ) -> Result<MarketStatusChange> {
+        let mut tx = self.pool.begin().await?;
+        let (market_id, blockchain_market_id) = self.find_market(&mut tx, identifier).await?;
+
+        let market = sqlx::query!(
+            r#"SELECT "marketType" as market_type, status FROM markets_extended WHERE id = $1"#,
+            market_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if market.status != "active" {
+            return Err(anyhow!("Market {} is already {}", market_id, market.status));
+        }
+
+        if market.market_type.parse::<MarketType>()? == MarketType::Binary {
+            drop(tx);
+            return match outcome_index {
+                YES_OUTCOME => self.resolve_market(&market_id, true).await,
+                NO_OUTCOME => self.resolve_market(&market_id, false).await,
+                other => Err(anyhow!(
+                    "Binary markets resolve to outcome {} (YES) or {} (NO), got {}",
+                    YES_OUTCOME,
+                    NO_OUTCOME,
+                    other
+                )),
+            };
+        }
+
+        let outcome_exists = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM market_outcomes WHERE "marketId" = $1 AND "outcomeIndex" = $2
+            ) as "exists!"
+            "#,
+            market_id,
+            outcome_index
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if !outcome_exists {
+            return Err(anyhow!(
+                "Market {} has no outcome {}",
+                market_id,
+                outcome_index
+            ));
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE markets_extended
+            SET status = 'resolved',
+                result = NULL,
+                "winningOutcome" = $1,
+                "resolutionDate" = NOW(),
+                "updatedAt" = NOW()
+            WHERE id = $2
+            "#,
+            outcome_index,
+            market_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let bets = sqlx::query!(
+            r#"
+            SELECT id, "outcomeIndex" as outcome_index, COALESCE(amount, 0) as "amount!"
+            FROM bets_extended
+            WHERE "marketId" = $1 AND status = 'active'
+            FOR UPDATE
+            "#,
+            market_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let total_pool: BigDecimal = bets.iter().map(|b| &b.amount).sum();
+        let winning_pool: BigDecimal = bets
+            .iter()
+            .filter(|b| b.outcome_index == Some(outcome_index))
+            .map(|b| &b.amount)
+            .sum();
+
+        let mut ids = Vec::with_capacity(bets.len());
+        let mut statuses = Vec::with_capacity(bets.len());
+        let mut payouts = Vec::with_capacity(bets.len());
+        for bet in &bets {
+            let won = bet.outcome_index == Some(outcome_index);
+            ids.push(bet.id.clone());
+            statuses.push(if won { "won" } else { "lost" }.to_string());
+            payouts.push(if won {
+                parimutuel_payout(&bet.amount, &winning_pool, &total_pool)
+            } else {
+                BigDecimal::from(0)
+            });
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE bets_extended b
+            SET status = s.status,
+                payout = s.payout,
+                "updatedAt" = NOW()
+            FROM UNNEST($1::text[], $2::text[], $3::numeric[]) AS s(id, status, payout)
+            WHERE b.id = s.id
+            "#,
+            &ids,
+            &statuses,
+            &payouts
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        self.invalidate_cache(blockchain_market_id).await;
+
+        info!(
+            "Resolved categorical market {} to outcome {} ({} bets settled, winning pool {} of {})",
+            market_id,
+            outcome_index,
+            bets.len(),
+            winning_pool,
+            total_pool
+        );
+
+        Ok(MarketStatusChange {
+            market_id,
+            blockchain_market_id,
+            status: "resolved".to_string(),
+            result: None,
+            winning_outcome: Some(outcome_index),
+            bets_updated: bets.len() as u64,
+        })
+    }
+
+    /// Cancels an active market and marks its active bets as cancelled.
+    #[allow(dead_code)]
+    pub async fn cancel_market(&self, identifier: &str) -> Result<MarketStatusChange> {
+        let mut tx = self.pool.begin().await?;
+        let (market_id, blockchain_market_id) = self.find_market(&mut tx, identifier).await?;
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE markets_extended
+            SET status = 'cancelled', "updatedAt" = NOW()
+            WHERE id = $1 AND status = 'active'
+            "#,
+            market_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            let status = sqlx::query_scalar!(
+                r#"SELECT status FROM markets_extended WHERE id = $1"#,
+                market_id
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            return Err(anyhow!("Market {} is already {}", market_id, status));
+        }
+
+        let bets = sqlx::query!(
+            r#"
+            UPDATE bets_extended
+            SET status = 'cancelled', "updatedAt" = NOW()
+            WHERE "marketId" = $1 AND status = 'active'
+            "#,
+            market_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        self.invalidate_cache(blockchain_market_id).await;
+
+        info!(
+            "Cancelled market {} ({} bets cancelled)",
+            market_id,
+            bets.rows_affected()
+        );
+
+        Ok(MarketStatusChange {
+            market_id,
+            blockchain_market_id,
+            status: "cancelled".to_string(),
+            result: None,
+            winning_outcome: None,
+            bets_updated: bets.rows_affected(),
+        })
+    }
+
+    /// Tells every replica's response cache that the market changed; a market not on chain
+    /// yet drops every entry.
+    #[allow(dead_code)]
+    async fn invalidate_cache(&self, blockchain_market_id: Option<i64>) {
+        if let Err(e) = publish_invalidation(&self.pool, blockchain_market_id).await {
+            warn!("Failed to publish cache invalidation: {}", e);
+        }
+    }
+
+    #[allow(dead_code)]
+    async fn find_market(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        identifier: &str,
+    ) -> Result<(String, Option<i64>)> {
+        let market = sqlx::query!(
+            r#"
+            SELECT id, "blockchainMarketId" as blockchain_market_id
+            FROM markets_extended
+            WHERE id = $1 OR "marketId" = $1 OR "adjTicker" = $1
+                OR "blockchainMarketId"::text = $1
+            LIMIT 1
+            FOR UPDATE
+            "#,
+            identifier
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| anyhow!("Market not found: {}", identifier))?;
+
+        Ok((market.id, market.blockchain_market_id))
+    }
+}
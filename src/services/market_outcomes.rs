@@ -0,0 +1,463 @@
+use anyhow::{anyhow, Result};
+use bigdecimal::{BigDecimal, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use tracing::info;
+use uuid::Uuid;
+
+/// Outcome index of YES when a binary market is listed as outcomes.
+pub const YES_OUTCOME: i32 = 0;
+/// Outcome index of NO when a binary market is listed as outcomes.
+pub const NO_OUTCOME: i32 = 1;
+
+const MAX_OUTCOMES: usize = 32;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarketType {
+    /// YES/NO market using `position`, `yesPoolSize`/`noPoolSize` and `result`.
+    #[default]
+    Binary,
+    /// N named outcomes in `market_outcomes`, bets reference one by `outcomeIndex`.
+    Categorical,
+}
+
+impl MarketType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketType::Binary => "binary",
+            MarketType::Categorical => "categorical",
+        }
+    }
+}
+
+impl FromStr for MarketType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "binary" => Ok(MarketType::Binary),
+            "categorical" => Ok(MarketType::Categorical),
+            other => Err(anyhow!("Unknown market type: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutcomePool {
+    pub index: i32,
+    pub label: String,
+    pub pool_size: BigDecimal,
+    pub bet_count: i32,
+    /// Share of the market's pool on this outcome, between 0 and 1.
+    pub probability: f64,
+}
+
+/// Binary market pools listed as YES/NO outcomes, so every market can be rendered the same way.
+/// Probabilities come from the market's stored `probability` percentage.
+pub fn binary_outcomes(
+    yes_pool: &BigDecimal,
+    no_pool: &BigDecimal,
+    count_yes: i32,
+    count_no: i32,
+    probability: i32,
+) -> Vec<OutcomePool> {
+    let yes_probability = (probability as f64 / 100.0).clamp(0.0, 1.0);
+    vec![
+        OutcomePool {
+            index: YES_OUTCOME,
+            label: "Yes".to_string(),
+            pool_size: yes_pool.clone(),
+            bet_count: count_yes,
+            probability: yes_probability,
+        },
+        OutcomePool {
+            index: NO_OUTCOME,
+            label: "No".to_string(),
+            pool_size: no_pool.clone(),
+            bet_count: count_no,
+            probability: 1.0 - yes_probability,
+        },
+    ]
+}
+
+/// Implied probability of each outcome from its share of the pool; equal while nothing is staked.
+pub fn outcome_probabilities(pools: &[f64]) -> Vec<f64> {
+    let total: f64 = pools.iter().sum();
+    if total <= 0.0 {
+        let n = pools.len().max(1) as f64;
+        return vec![1.0 / n; pools.len()];
+    }
+    pools.iter().map(|pool| pool / total).collect()
+}
+
+/// Decimal odds for staking `amount` on outcome `index`, counting the stake itself.
+pub fn outcome_odds(pools: &[f64], index: usize, amount: f64) -> f64 {
+    let total: f64 = pools.iter().sum::<f64>() + amount;
+    let outcome_pool = pools.get(index).copied().unwrap_or(0.0) + amount;
+    if outcome_pool > 0.0 {
+        (total / outcome_pool).max(1.0)
+    } else {
+        1.0
+    }
+}
+
+/// Parimutuel payout of a winning stake: its share of the winning pool applied to the whole pool.
+/// With nothing on the winning outcome the stake is returned as is.
+pub fn parimutuel_payout(
+    stake: &BigDecimal,
+    winning_pool: &BigDecimal,
+    total_pool: &BigDecimal,
+) -> BigDecimal {
+    if winning_pool.is_zero() {
+        return stake.clone();
+    }
+    (stake * total_pool / winning_pool).round(18)
+}
+
+/// Outcome definitions and pools of categorical markets.
+pub struct MarketOutcomeService {
+    pool: PgPool,
+}
+
+impl MarketOutcomeService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Outcomes of each categorical market in `market_ids`; binary markets are not included.
+    pub async fn outcomes_for_markets(
+        &self,
+        market_ids: &[String],
+    ) -> Result<HashMap<String, Vec<OutcomePool>>> {
+        Self::load_outcomes(&self.pool, market_ids).await
+    }
+
+    async fn load_outcomes<'e>(
+        executor: impl PgExecutor<'e>,
+        market_ids: &[String],
+    ) -> Result<HashMap<String, Vec<OutcomePool>>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT "marketId" as market_id, "outcomeIndex" as outcome_index, label,
+                   "poolSize" as pool_size, "betCount" as bet_count
+            FROM market_outcomes
+            WHERE "marketId" = ANY($1)
+            ORDER BY "marketId", "outcomeIndex"
+            "#,
+            market_ids
+        )
+        .fetch_all(executor)
+        .await?;
+
+        let mut outcomes: HashMap<String, Vec<OutcomePool>> = HashMap::new();
+        for row in rows {
+            outcomes
+                .entry(row.market_id)
+                .or_default()
+                .push(OutcomePool {
+                    index: row.outcome_index,
+                    label: row.label,
+                    pool_size: row.pool_size,
+                    bet_count: row.bet_count,
+                    probability: 0.0,
+                });
+        }
+
+        for market_outcomes in outcomes.values_mut() {
+            let pools: Vec<f64> = market_outcomes
+                .iter()
+                .map(|o| o.pool_size.to_f64().unwrap_or(0.0))
+                .collect();
+            for (outcome, probability) in market_outcomes
+                .iter_mut()
+                .zip(outcome_probabilities(&pools))
+            {
+                outcome.probability = probability;
+            }
+        }
+
+        Ok(outcomes)
+    }
+
+    pub async fn outcomes(&self, market_id: &str) -> Result<Vec<OutcomePool>> {
+        Self::outcomes_in(&self.pool, market_id).await
+    }
+
+    /// [`Self::outcomes`], read through `executor`, e.g. a transaction holding the bet lock.
+    pub async fn outcomes_in<'e>(
+        executor: impl PgExecutor<'e>,
+        market_id: &str,
+    ) -> Result<Vec<OutcomePool>> {
+        Ok(Self::load_outcomes(executor, &[market_id.to_string()])
+            .await?
+            .remove(market_id)
+            .unwrap_or_default())
+    }
+
+    /// Turns a market into a categorical market with the given outcome labels, in order.
+    /// Only allowed before any bet has been placed on it.
+    pub async fn define_outcomes(
+        &self,
+        market_id: &str,
+        labels: &[String],
+    ) -> Result<Vec<OutcomePool>> {
+        let labels = validate_labels(labels)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let bet_count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM bets_extended WHERE "marketId" = $1"#,
+            market_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if bet_count > 0 {
+            return Err(anyhow!(
+                "Market {} already has bets; its outcomes cannot change",
+                market_id
+            ));
+        }
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE markets_extended
+            SET "marketType" = 'categorical',
+                probability = ROUND(100.0 / $2)::int,
+                "updatedAt" = NOW()
+            WHERE id = $1
+            "#,
+            market_id,
+            labels.len() as f64
+        )
+        .execute(&mut *tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(anyhow!("Market not found: {}", market_id));
+        }
+
+        sqlx::query!(
+            r#"DELETE FROM market_outcomes WHERE "marketId" = $1"#,
+            market_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for (index, label) in labels.iter().enumerate() {
+            sqlx::query!(
+                r#"
+                INSERT INTO market_outcomes (id, "marketId", "outcomeIndex", label)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                Uuid::new_v4().to_string(),
+                market_id,
+                index as i32,
+                label
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        info!(
+            "Market {} is now categorical with {} outcomes",
+            market_id,
+            labels.len()
+        );
+
+        self.outcomes(market_id).await
+    }
+
+    /// Recomputes outcome pools and counts from every bet that was not cancelled or failed, so
+    /// settled bets of resolved markets still count, and sets each categorical market's
+    /// `probability` to its leading outcome's share. Limited to one market when `market_id` is set.
+    pub async fn refresh_pools(&self, market_id: Option<&str>) -> Result<u64> {
+        Self::refresh_pools_in(&mut *self.pool.acquire().await?, market_id).await
+    }
+
+    /// [`Self::refresh_pools`] on `conn`, e.g. inside a transaction holding the bet lock.
+    pub async fn refresh_pools_in(conn: &mut PgConnection, market_id: Option<&str>) -> Result<u64> {
+        let updated = sqlx::query!(
+            r#"
+            UPDATE market_outcomes mo
+            SET "poolSize" = COALESCE(subq.pool, 0),
+                "betCount" = COALESCE(subq.bets, 0),
+                "updatedAt" = NOW()
+            FROM market_outcomes mo2
+            LEFT JOIN (
+                SELECT "marketId", "outcomeIndex", SUM(amount) as pool, COUNT(*)::int as bets
+                FROM bets_extended
+                WHERE status NOT IN ('cancelled', 'failed') AND "outcomeIndex" IS NOT NULL
+                GROUP BY "marketId", "outcomeIndex"
+            ) subq ON subq."marketId" = mo2."marketId" AND subq."outcomeIndex" = mo2."outcomeIndex"
+            WHERE mo.id = mo2.id
+              AND ($1::text IS NULL OR mo."marketId" = $1)
+            "#,
+            market_id
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Self::refresh_probability(&mut *conn, market_id).await?;
+
+        Ok(updated.rows_affected())
+    }
+
+    /// Recomputes the probability of categorical markets (all, or just `market_id`) from
+    /// their outcome pools.
+    pub async fn refresh_probability<'e>(
+        executor: impl PgExecutor<'e>,
+        market_id: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE markets_extended me
+            SET probability = CASE
+                    WHEN subq.total > 0 THEN ROUND(subq.leading / subq.total * 100)::int
+                    ELSE ROUND(100.0 / subq.outcomes)::int
+                END
+            FROM (
+                SELECT "marketId", SUM("poolSize") as total, MAX("poolSize") as leading,
+                       COUNT(*) as outcomes
+                FROM market_outcomes
+                GROUP BY "marketId"
+            ) subq
+            WHERE me.id = subq."marketId"
+              AND me."marketType" = 'categorical'
+              AND ($1::text IS NULL OR me.id = $1)
+            "#,
+            market_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+fn validate_labels(labels: &[String]) -> Result<Vec<String>> {
+    let labels: Vec<String> = labels.iter().map(|l| l.trim().to_string()).collect();
+
+    if labels.len() < 2 || labels.len() > MAX_OUTCOMES {
+        return Err(anyhow!(
+            "A categorical market needs between 2 and {} outcomes, got {}",
+            MAX_OUTCOMES,
+            labels.len()
+        ));
+    }
+    if labels.iter().any(|l| l.is_empty()) {
+        return Err(anyhow!("Outcome labels cannot be empty"));
+    }
+    let mut seen = HashSet::new();
+    if let Some(duplicate) = labels.iter().find(|l| !seen.insert(l.to_lowercase())) {
+        return Err(anyhow!("Duplicate outcome label: {}", duplicate));
+    }
+
+    Ok(labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probabilities_and_odds() {
+        assert_eq!(outcome_probabilities(&[0.0, 0.0, 0.0, 0.0]), vec![0.25; 4]);
+        assert_eq!(
+            outcome_probabilities(&[1.0, 3.0, 0.0]),
+            vec![0.25, 0.75, 0.0]
+        );
+
+        // 100 staked on an outcome holding 100 of a 400 pool: (400 + 100) / (100 + 100).
+        assert_eq!(outcome_odds(&[100.0, 200.0, 100.0], 2, 100.0), 2.5);
+        assert_eq!(outcome_odds(&[0.0, 0.0], 0, 10.0), 1.0);
+    }
+
+    #[test]
+    fn test_parimutuel_payout_distributes_whole_pool() {
+        let total = BigDecimal::from(1000);
+        let winning = BigDecimal::from(300);
+        let payouts: Vec<BigDecimal> = [100, 200]
+            .iter()
+            .map(|stake| parimutuel_payout(&BigDecimal::from(*stake), &winning, &total))
+            .collect();
+
+        assert_eq!(payouts[0].to_f64().unwrap(), 1000.0 / 3.0);
+        let error = (payouts.iter().sum::<BigDecimal>() - &total).abs();
+        assert!(error.to_f64().unwrap() < 1e-9);
+        assert_eq!(
+            parimutuel_payout(&BigDecimal::from(5), &BigDecimal::from(0), &total),
+            BigDecimal::from(5)
+        );
+    }
+
+    #[test]
+    fn test_validate_labels() {
+        let labels = |ls: &[&str]| ls.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        assert!(validate_labels(&labels(&["Only"])).is_err());
+        assert!(validate_labels(&labels(&["A", " a "])).is_err());
+        assert!(validate_labels(&labels(&["A", ""])).is_err());
+        assert_eq!(
+            validate_labels(&labels(&[" Red ", "Blue", "Green"])).unwrap(),
+            labels(&["Red", "Blue", "Green"])
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_refresh_keeps_settled_bets_of_resolved_markets(pool: PgPool) {
+        sqlx::query!("INSERT INTO users (id, address) VALUES ('u-1', '0x1')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query!(
+            r#"INSERT INTO markets_extended (id, "endDate") VALUES ('m-1', NOW() + INTERVAL '1 day')"#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let service = MarketOutcomeService::new(pool.clone());
+        service
+            .define_outcomes("m-1", &["Red".to_string(), "Blue".to_string()])
+            .await
+            .unwrap();
+
+        for (id, outcome, amount, status) in [
+            ("b-1", 0, 30, "won"),
+            ("b-2", 1, 10, "lost"),
+            ("b-3", 1, 50, "cancelled"),
+        ] {
+            sqlx::query!(
+                r#"
+                INSERT INTO bets_extended
+                    (id, "blockchainBetId", "userId", "marketId", "outcomeIndex", amount, odds, status)
+                VALUES ($1, $2, 'u-1', 'm-1', $3, $4, 1, $5)
+                "#,
+                id,
+                id[2..].parse::<i64>().unwrap(),
+                outcome,
+                BigDecimal::from(amount),
+                status
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        sqlx::query!(r#"UPDATE markets_extended SET status = 'resolved', "winningOutcome" = 0"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        service.refresh_pools(Some("m-1")).await.unwrap();
+
+        let outcomes = service.outcomes("m-1").await.unwrap();
+        assert_eq!(outcomes[0].pool_size, BigDecimal::from(30));
+        assert_eq!(outcomes[1].pool_size, BigDecimal::from(10));
+        assert_eq!(outcomes[1].bet_count, 1);
+        assert_eq!(outcomes[0].probability, 0.75);
+    }
+}
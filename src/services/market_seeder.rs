@@ -1,9 +1,12 @@
 use anyhow::Result;
+use serde::Serialize;
 use sqlx::types::BigDecimal;
 use sqlx::PgPool;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use crate::config::{AdjacentConfig, ImagesConfig};
+
 use super::adjacent::{AdjacentMarket, AdjacentService};
 use super::image_service::ImageService;
 
@@ -15,9 +18,9 @@ pub struct MarketSeeder {
 
 #[allow(dead_code)]
 impl MarketSeeder {
-    pub fn new(pool: PgPool, api_key: String) -> Result<Self> {
-        let adjacent_service = AdjacentService::new(api_key)?;
-        let image_service = ImageService::new()?;
+    pub fn new(pool: PgPool, adjacent: &AdjacentConfig, images: &ImagesConfig) -> Result<Self> {
+        let adjacent_service = AdjacentService::new(adjacent)?;
+        let image_service = ImageService::new(images)?;
 
         info!("ℹ️  Market seeder will create markets in database only.");
         info!("ℹ️  Use the deploy_markets.sh script to deploy to blockchain.");
@@ -290,7 +293,8 @@ impl MarketSeeder {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SeedResult {
     pub total_requested: usize,
     pub fetched_from_api: usize,
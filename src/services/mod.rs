@@ -1,16 +1,32 @@
 pub mod adjacent;
 pub mod aptos_contract;
+pub mod audit;
+pub mod bet_quote;
 pub mod betting_service;
 pub mod blockchain_sync;
+pub mod chain_transactions;
 pub mod chainlink_price_feed;
 pub mod db_event_listener;
 pub mod event_indexer;
+pub mod health;
+pub mod idempotency;
 pub mod image_service;
+pub mod job_queue;
+pub mod leader_election;
+pub mod market_admin;
+pub mod market_outcomes;
 pub mod market_seeder;
+pub mod price_source_health;
 pub mod realtime_sync;
+pub mod reconciliation;
+pub mod response_cache;
+pub mod risk_limits;
 pub mod scheduler;
+pub mod signer;
 pub mod user_service;
 pub mod user_yield_calculator;
+pub mod yield_accrual;
+pub mod yield_allocation;
 pub mod yield_calculator;
 pub mod yield_service;
 
@@ -0,0 +1,317 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::config::PriceFeedConfig;
+
+/// Process-wide health state, shared by every price feed instance (routes and scheduler).
+pub static SOURCE_HEALTH: Lazy<Arc<SourceHealthRegistry>> =
+    Lazy::new(|| Arc::new(SourceHealthRegistry::default()));
+
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerPolicy {
+    pub failure_threshold: u32,
+    pub cooldown_secs: i64,
+}
+
+impl From<&PriceFeedConfig> for CircuitBreakerPolicy {
+    fn from(config: &PriceFeedConfig) -> Self {
+        Self {
+            failure_threshold: config.circuit_breaker_threshold,
+            cooldown_secs: config.circuit_breaker_cooldown_secs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests flow normally.
+    Closed,
+    /// Too many consecutive failures; the source is skipped until the cooldown ends.
+    Open,
+    /// Cooldown elapsed; a single probe request decides whether the circuit closes again.
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Default)]
+struct SourceHealth {
+    total_requests: u64,
+    successes: u64,
+    failures: u64,
+    outliers_rejected: u64,
+    consecutive_failures: u32,
+    total_latency_ms: u64,
+    last_latency_ms: Option<u64>,
+    last_price: Option<f64>,
+    last_error: Option<String>,
+    last_success_at: Option<i64>,
+    last_failure_at: Option<i64>,
+    circuit_open_until: Option<i64>,
+    /// While half-open, when the in-flight probe is given up on if it never reports back.
+    probe_until: Option<i64>,
+}
+
+impl SourceHealth {
+    fn state(&self, now: i64) -> CircuitState {
+        match self.circuit_open_until {
+            Some(until) if now < until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+            None => CircuitState::Closed,
+        }
+    }
+
+    fn fail(&mut self, error: String, now: i64, policy: CircuitBreakerPolicy) {
+        self.failures += 1;
+        self.consecutive_failures += 1;
+        self.last_error = Some(error);
+        self.last_failure_at = Some(now);
+        self.probe_until = None;
+
+        // A failed probe while half-open re-opens immediately.
+        if self.consecutive_failures >= policy.failure_threshold
+            || self.circuit_open_until.is_some()
+        {
+            self.circuit_open_until = Some(now + policy.cooldown_secs);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceHealthSnapshot {
+    pub source: String,
+    pub pair: String,
+    pub state: CircuitState,
+    pub success_rate: Option<f64>,
+    pub total_requests: u64,
+    pub successes: u64,
+    pub failures: u64,
+    pub outliers_rejected: u64,
+    pub consecutive_failures: u32,
+    pub avg_latency_ms: Option<f64>,
+    pub last_latency_ms: Option<u64>,
+    pub last_price: Option<f64>,
+    pub last_error: Option<String>,
+    pub last_success_at: Option<i64>,
+    pub last_failure_at: Option<i64>,
+    pub circuit_open_until: Option<i64>,
+}
+
+/// Per (source, pair) request statistics and circuit breaker state.
+#[derive(Debug, Default)]
+pub struct SourceHealthRegistry {
+    sources: Mutex<BTreeMap<(String, String), SourceHealth>>,
+}
+
+impl SourceHealthRegistry {
+    fn with_entry<T>(&self, source: &str, pair: &str, f: impl FnOnce(&mut SourceHealth) -> T) -> T {
+        let mut sources = self.sources.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = sources
+            .entry((source.to_string(), pair.to_string()))
+            .or_default();
+        f(entry)
+    }
+
+    /// Whether the source should be queried now. Open circuits are skipped, and a half-open
+    /// circuit lets through one probe at a time; a probe that never reports back frees its slot
+    /// after another cooldown.
+    pub fn should_attempt(
+        &self,
+        source: &str,
+        pair: &str,
+        now: i64,
+        policy: CircuitBreakerPolicy,
+    ) -> bool {
+        self.with_entry(source, pair, |h| match h.state(now) {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => {
+                if h.probe_until.is_some_and(|until| now < until) {
+                    return false;
+                }
+                h.probe_until = Some(now + policy.cooldown_secs);
+                true
+            }
+        })
+    }
+
+    pub fn record_success(&self, source: &str, pair: &str, price: f64, latency_ms: u64, now: i64) {
+        self.with_entry(source, pair, |h| {
+            h.total_requests += 1;
+            h.successes += 1;
+            h.consecutive_failures = 0;
+            h.circuit_open_until = None;
+            h.probe_until = None;
+            h.total_latency_ms += latency_ms;
+            h.last_latency_ms = Some(latency_ms);
+            h.last_price = Some(price);
+            h.last_success_at = Some(now);
+        })
+    }
+
+    pub fn record_failure(
+        &self,
+        source: &str,
+        pair: &str,
+        error: String,
+        latency_ms: u64,
+        now: i64,
+        policy: CircuitBreakerPolicy,
+    ) {
+        self.with_entry(source, pair, |h| {
+            h.total_requests += 1;
+            h.total_latency_ms += latency_ms;
+            h.last_latency_ms = Some(latency_ms);
+            h.fail(error, now, policy);
+        })
+    }
+
+    /// Records a response whose quote was rejected as an outlier, as a failure.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_outlier(
+        &self,
+        source: &str,
+        pair: &str,
+        price: f64,
+        deviation_pct: f64,
+        latency_ms: u64,
+        now: i64,
+        policy: CircuitBreakerPolicy,
+    ) {
+        self.with_entry(source, pair, |h| {
+            h.total_requests += 1;
+            h.total_latency_ms += latency_ms;
+            h.last_latency_ms = Some(latency_ms);
+            h.outliers_rejected += 1;
+            h.last_price = Some(price);
+            h.fail(
+                format!(
+                    "outlier: ${:.4} deviates {:.2}% from median",
+                    price, deviation_pct
+                ),
+                now,
+                policy,
+            );
+        })
+    }
+
+    pub fn snapshot(&self, now: i64) -> Vec<SourceHealthSnapshot> {
+        let sources = self.sources.lock().unwrap_or_else(|e| e.into_inner());
+        sources
+            .iter()
+            .map(|((source, pair), h)| SourceHealthSnapshot {
+                source: source.clone(),
+                pair: pair.clone(),
+                state: h.state(now),
+                success_rate: (h.total_requests > 0)
+                    .then(|| h.successes as f64 / h.total_requests as f64),
+                total_requests: h.total_requests,
+                successes: h.successes,
+                failures: h.failures,
+                outliers_rejected: h.outliers_rejected,
+                consecutive_failures: h.consecutive_failures,
+                avg_latency_ms: (h.total_requests > 0)
+                    .then(|| h.total_latency_ms as f64 / h.total_requests as f64),
+                last_latency_ms: h.last_latency_ms,
+                last_price: h.last_price,
+                last_error: h.last_error.clone(),
+                last_success_at: h.last_success_at,
+                last_failure_at: h.last_failure_at,
+                circuit_open_until: h.circuit_open_until.filter(|until| *until > now),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: CircuitBreakerPolicy = CircuitBreakerPolicy {
+        failure_threshold: 2,
+        cooldown_secs: 60,
+    };
+
+    fn state_of(registry: &SourceHealthRegistry, now: i64) -> CircuitState {
+        registry.snapshot(now)[0].state
+    }
+
+    #[test]
+    fn test_circuit_opens_after_threshold_and_recovers() {
+        let registry = SourceHealthRegistry::default();
+
+        registry.record_failure("Kraken", "APT/USD", "HTTP 502".into(), 40, 1_000, POLICY);
+        assert!(registry.should_attempt("Kraken", "APT/USD", 1_001, POLICY));
+
+        registry.record_failure("Kraken", "APT/USD", "HTTP 502".into(), 40, 1_010, POLICY);
+        assert_eq!(state_of(&registry, 1_011), CircuitState::Open);
+        assert!(!registry.should_attempt("Kraken", "APT/USD", 1_011, POLICY));
+
+        // Cooldown elapsed: one probe is allowed and a failure re-opens straight away.
+        assert!(registry.should_attempt("Kraken", "APT/USD", 1_070, POLICY));
+        assert!(!registry.should_attempt("Kraken", "APT/USD", 1_070, POLICY));
+        registry.record_failure("Kraken", "APT/USD", "timeout".into(), 5_000, 1_070, POLICY);
+        assert!(!registry.should_attempt("Kraken", "APT/USD", 1_071, POLICY));
+
+        registry.record_success("Kraken", "APT/USD", 8.5, 30, 1_200);
+        let snapshot = &registry.snapshot(1_200)[0];
+        assert_eq!(snapshot.state, CircuitState::Closed);
+        assert_eq!(snapshot.consecutive_failures, 0);
+        assert_eq!(snapshot.total_requests, 4);
+        assert_eq!(snapshot.success_rate, Some(0.25));
+        assert_eq!(snapshot.last_error.as_deref(), Some("timeout"));
+    }
+
+    #[test]
+    fn test_half_open_allows_one_probe_at_a_time() {
+        let registry = SourceHealthRegistry::default();
+        registry.record_failure("OKX", "APT/USD", "HTTP 503".into(), 40, 1_000, POLICY);
+        registry.record_failure("OKX", "APT/USD", "HTTP 503".into(), 40, 1_000, POLICY);
+
+        assert!(registry.should_attempt("OKX", "APT/USD", 1_060, POLICY));
+        assert!(!registry.should_attempt("OKX", "APT/USD", 1_061, POLICY));
+        assert_eq!(state_of(&registry, 1_061), CircuitState::HalfOpen);
+
+        // The probe never reported back: its slot is freed after another cooldown.
+        assert!(!registry.should_attempt("OKX", "APT/USD", 1_119, POLICY));
+        assert!(registry.should_attempt("OKX", "APT/USD", 1_120, POLICY));
+
+        registry.record_success("OKX", "APT/USD", 8.5, 30, 1_121);
+        assert!(registry.should_attempt("OKX", "APT/USD", 1_121, POLICY));
+        assert!(registry.should_attempt("OKX", "APT/USD", 1_121, POLICY));
+    }
+
+    #[test]
+    fn test_outlier_counts_as_failure() {
+        let registry = SourceHealthRegistry::default();
+
+        registry.record_outlier("OKX", "BTC/USD", 70_000.0, 9.5, 20, 1_000, POLICY);
+
+        let snapshot = &registry.snapshot(1_000)[0];
+        assert_eq!(snapshot.total_requests, 1);
+        assert_eq!(snapshot.successes, 0);
+        assert_eq!(snapshot.failures, 1);
+        assert_eq!(snapshot.outliers_rejected, 1);
+        assert_eq!(snapshot.state, CircuitState::Closed);
+        assert!(snapshot
+            .last_error
+            .as_deref()
+            .unwrap()
+            .starts_with("outlier"));
+    }
+
+    #[test]
+    fn test_persistent_outlier_opens_the_circuit() {
+        let registry = SourceHealthRegistry::default();
+
+        registry.record_outlier("OKX", "BTC/USD", 70_000.0, 9.5, 20, 1_000, POLICY);
+        assert!(registry.should_attempt("OKX", "BTC/USD", 1_010, POLICY));
+        registry.record_outlier("OKX", "BTC/USD", 70_100.0, 9.6, 20, 1_010, POLICY);
+
+        let snapshot = &registry.snapshot(1_011)[0];
+        assert_eq!(snapshot.consecutive_failures, 2);
+        assert_eq!(snapshot.state, CircuitState::Open);
+        assert!(!registry.should_attempt("OKX", "BTC/USD", 1_011, POLICY));
+    }
+}
@@ -6,6 +6,8 @@ use tokio::time;
 use tracing::{error, info, warn};
 
 use super::blockchain_sync::BlockchainSyncService;
+use super::yield_allocation::YieldAllocator;
+use crate::config::YieldAllocationConfig;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RealtimeSyncConfig {
@@ -30,28 +32,26 @@ pub struct RealtimeSyncService {
     pool: PgPool,
     config: RealtimeSyncConfig,
     blockchain_sync: BlockchainSyncService,
+    yield_allocation: YieldAllocationConfig,
 }
 
 impl RealtimeSyncService {
-    pub fn new(pool: PgPool) -> Self {
-        let config = RealtimeSyncConfig::default();
-        let blockchain_sync = BlockchainSyncService::new(pool.clone());
-
-        Self {
-            pool,
-            config,
-            blockchain_sync,
-        }
+    pub fn new(pool: PgPool, yield_allocation: &YieldAllocationConfig) -> Self {
+        Self::new_with_config(pool, RealtimeSyncConfig::default(), yield_allocation)
     }
 
-    #[allow(dead_code)]
-    pub fn new_with_config(pool: PgPool, config: RealtimeSyncConfig) -> Self {
+    pub fn new_with_config(
+        pool: PgPool,
+        config: RealtimeSyncConfig,
+        yield_allocation: &YieldAllocationConfig,
+    ) -> Self {
         let blockchain_sync = BlockchainSyncService::new(pool.clone());
 
         Self {
             pool,
             config,
             blockchain_sync,
+            yield_allocation: yield_allocation.clone(),
         }
     }
 
@@ -81,7 +81,10 @@ impl RealtimeSyncService {
                                 result.new_events
                             );
 
-                            if let Err(e) = sync_service.update_market_stats().await {
+                            if let Err(e) = sync_service
+                                .update_market_stats_for_markets(&result.touched_markets)
+                                .await
+                            {
                                 error!("Failed to update market stats in real-time: {}", e);
                             }
                         }
@@ -160,13 +163,17 @@ impl RealtimeSyncService {
         .await?
         .ok_or_else(|| anyhow!("Market not found: {}", market_id))?;
 
-        let yield_data = super::yield_calculator::calculate_market_yield_data(
-            &self.pool,
-            &market.totalPoolSize,
-            &market.totalPoolSize,
-            &market.endDate,
-        )
-        .await;
+        let yield_data = YieldAllocator::new(self.pool.clone(), &self.yield_allocation)
+            .rebalance_market(&market.id, &market.totalPoolSize)
+            .await
+            .map(|plan| {
+                super::yield_calculator::calculate_market_yield_data(
+                    &plan,
+                    &market.totalPoolSize,
+                    &market.totalPoolSize,
+                    &market.endDate,
+                )
+            });
 
         if let Ok(yd) = yield_data {
             let daily_yield_decimal = sqlx::types::BigDecimal::try_from(yd.daily_yield)
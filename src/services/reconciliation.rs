@@ -0,0 +1,612 @@
+use anyhow::Result;
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::blockchain_sync::BlockchainSyncService;
+use super::db_event_listener::DbEventListener;
+use super::market_outcomes::MarketOutcomeService;
+use super::response_cache::publish_invalidation;
+use super::risk_limits::RiskLimitService;
+
+/// A value of an extended row that does not match the indexer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Discrepancy {
+    pub blockchain_market_id: i64,
+    /// `markets_extended` id; `None` when the market was never synced.
+    pub market_id: Option<String>,
+    pub field: String,
+    /// Value derived from the indexer tables.
+    pub expected: Value,
+    /// Value stored in the extended tables.
+    pub actual: Value,
+    /// Whether a repair run made the field match.
+    #[serde(default)]
+    pub repaired: bool,
+}
+
+/// A row of `reconciliation_reports`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconciliationReport {
+    pub id: String,
+    pub job_id: Option<String>,
+    pub repair: bool,
+    pub markets_checked: i32,
+    pub discrepancy_count: i32,
+    pub repaired_count: i32,
+    pub discrepancies: Json<Vec<Discrepancy>>,
+    pub started_at: NaiveDateTime,
+    pub finished_at: NaiveDateTime,
+}
+
+/// An on-chain market as the indexer and the extended tables each see it.
+struct MarketComparison {
+    blockchain_market_id: i64,
+    resolved: Option<bool>,
+    outcome: Option<bool>,
+    market_id: Option<String>,
+    market_type: Option<String>,
+    status: Option<String>,
+    result: Option<bool>,
+    total_pool_size: Option<BigDecimal>,
+    yes_pool_size: Option<BigDecimal>,
+    no_pool_size: Option<BigDecimal>,
+    count_yes: Option<i32>,
+    count_no: Option<i32>,
+    indexer_bets: i64,
+    indexer_yes_pool: BigDecimal,
+    indexer_no_pool: BigDecimal,
+    indexer_yes_count: i32,
+    indexer_no_count: i32,
+    extended_bets: i64,
+    /// API bets still waiting for their transaction, which the pools already include but the
+    /// indexer does not yet.
+    pending_pool: BigDecimal,
+    pending_yes_pool: BigDecimal,
+    pending_no_pool: BigDecimal,
+    pending_yes_count: i32,
+    pending_no_count: i32,
+}
+
+impl MarketComparison {
+    fn discrepancies(&self) -> Vec<Discrepancy> {
+        let mut found = Vec::new();
+        let mut push = |field: &str, expected: Value, actual: Value| {
+            found.push(Discrepancy {
+                blockchain_market_id: self.blockchain_market_id,
+                market_id: self.market_id.clone(),
+                field: field.to_string(),
+                expected,
+                actual,
+                repaired: false,
+            });
+        };
+
+        if self.market_id.is_none() {
+            push("market", json!("present"), Value::Null);
+            return found;
+        }
+
+        if self.indexer_bets != self.extended_bets {
+            push(
+                "betCount",
+                json!(self.indexer_bets),
+                json!(self.extended_bets),
+            );
+        }
+
+        let total = &self.indexer_yes_pool + &self.indexer_no_pool + &self.pending_pool;
+        let yes_pool = &self.indexer_yes_pool + &self.pending_yes_pool;
+        let no_pool = &self.indexer_no_pool + &self.pending_no_pool;
+        let pools = [
+            ("totalPoolSize", &total, &self.total_pool_size),
+            ("yesPoolSize", &yes_pool, &self.yes_pool_size),
+            ("noPoolSize", &no_pool, &self.no_pool_size),
+        ];
+        // Categorical pools are split by outcome in market_outcomes, which the indexer's
+        // yes/no positions cannot check; only their total is compared.
+        let binary = self.market_type.as_deref() != Some("categorical");
+        for (field, expected, actual) in pools.into_iter().take(if binary { 3 } else { 1 }) {
+            if actual.as_ref() != Some(expected) {
+                push(
+                    field,
+                    json!(expected.normalized().to_string()),
+                    json!(actual.as_ref().map(|a| a.normalized().to_string())),
+                );
+            }
+        }
+        if !binary {
+            return found;
+        }
+
+        let counts = [
+            (
+                "countYes",
+                self.indexer_yes_count + self.pending_yes_count,
+                self.count_yes,
+            ),
+            (
+                "countNo",
+                self.indexer_no_count + self.pending_no_count,
+                self.count_no,
+            ),
+        ];
+        for (field, expected, actual) in counts {
+            if actual != Some(expected) {
+                push(field, json!(expected), json!(actual));
+            }
+        }
+
+        let resolved = self.status.as_deref() == Some("resolved");
+        if self.resolved == Some(true) && !resolved {
+            push("status", json!("resolved"), json!(self.status));
+        } else if self.resolved == Some(true) && self.result != self.outcome {
+            push("result", json!(self.outcome), json!(self.result));
+        }
+
+        found
+    }
+}
+
+/// Compares the indexer tables (`markets`, `bets`), the source of truth for on-chain
+/// markets, with the `markets_extended`/`bets_extended` rows derived from them.
+///
+/// Sync copies rows over in batches and bet placement bumps pools directly, so the two sides
+/// can drift. Each run stores a report; with repair on, missing rows are synced, pools and
+/// counts are recomputed from `bets`, and markets resolved on-chain are resolved.
+/// Conflicting results and extended rows the indexer does not know are only reported.
+pub struct ReconciliationService {
+    pool: PgPool,
+}
+
+impl ReconciliationService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn run(&self, repair: bool, job_id: Option<&str>) -> Result<ReconciliationReport> {
+        let started_at = Utc::now().naive_utc();
+        let markets = self.compare().await?;
+        let mut discrepancies: Vec<Discrepancy> =
+            markets.iter().flat_map(|m| m.discrepancies()).collect();
+
+        let mut repaired_count = 0;
+        if repair && !discrepancies.is_empty() {
+            self.repair(&markets, &discrepancies).await?;
+
+            let remaining: HashSet<(i64, String)> = self
+                .compare()
+                .await?
+                .iter()
+                .flat_map(|m| m.discrepancies())
+                .map(|d| (d.blockchain_market_id, d.field))
+                .collect();
+            for discrepancy in &mut discrepancies {
+                discrepancy.repaired = !remaining
+                    .contains(&(discrepancy.blockchain_market_id, discrepancy.field.clone()));
+            }
+            repaired_count = discrepancies.iter().filter(|d| d.repaired).count();
+        }
+
+        let report = sqlx::query_as!(
+            ReconciliationReport,
+            r#"
+            INSERT INTO reconciliation_reports (
+                id, "jobId", repair, "marketsChecked", "discrepancyCount", "repairedCount",
+                discrepancies, "startedAt", "finishedAt"
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW())
+            RETURNING id, "jobId" as job_id, repair, "marketsChecked" as markets_checked,
+                      "discrepancyCount" as discrepancy_count, "repairedCount" as repaired_count,
+                      discrepancies as "discrepancies: Json<Vec<Discrepancy>>",
+                      "startedAt" as started_at, "finishedAt" as finished_at
+            "#,
+            Uuid::new_v4().to_string(),
+            job_id,
+            repair,
+            markets.len() as i32,
+            discrepancies.len() as i32,
+            repaired_count as i32,
+            Json(&discrepancies) as _,
+            started_at
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        if report.discrepancy_count > 0 {
+            warn!(
+                "⚠️ Reconciliation found {} discrepancies across {} markets ({} repaired)",
+                report.discrepancy_count, report.markets_checked, report.repaired_count
+            );
+        } else {
+            info!(
+                "✅ Reconciliation found no discrepancies across {} markets",
+                report.markets_checked
+            );
+        }
+        Ok(report)
+    }
+
+    /// The newest report, if the job has run.
+    pub async fn latest(&self) -> Result<Option<ReconciliationReport>> {
+        let report = sqlx::query_as!(
+            ReconciliationReport,
+            r#"
+            SELECT id, "jobId" as job_id, repair, "marketsChecked" as markets_checked,
+                   "discrepancyCount" as discrepancy_count, "repairedCount" as repaired_count,
+                   discrepancies as "discrepancies: Json<Vec<Discrepancy>>",
+                   "startedAt" as started_at, "finishedAt" as finished_at
+            FROM reconciliation_reports
+            ORDER BY "finishedAt" DESC
+            LIMIT 1
+            "#
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(report)
+    }
+
+    async fn compare(&self) -> Result<Vec<MarketComparison>> {
+        let markets = sqlx::query_as!(
+            MarketComparison,
+            r#"
+            SELECT
+                m.market_id AS "blockchain_market_id!",
+                m.resolved AS "resolved?",
+                m.outcome AS "outcome?",
+                me.id AS "market_id?",
+                me."marketType" AS "market_type?",
+                me.status AS "status?",
+                me.result AS "result?",
+                me."totalPoolSize" AS "total_pool_size?",
+                me."yesPoolSize" AS "yes_pool_size?",
+                me."noPoolSize" AS "no_pool_size?",
+                me."countYes" AS "count_yes?",
+                me."countNo" AS "count_no?",
+                COALESCE(b.bet_count, 0) AS "indexer_bets!",
+                COALESCE(b.yes_pool, 0) AS "indexer_yes_pool!",
+                COALESCE(b.no_pool, 0) AS "indexer_no_pool!",
+                COALESCE(b.yes_count, 0) AS "indexer_yes_count!",
+                COALESCE(b.no_count, 0) AS "indexer_no_count!",
+                COALESCE(be.bet_count, 0) AS "extended_bets!",
+                COALESCE(p.pool, 0) AS "pending_pool!",
+                COALESCE(p.yes_pool, 0) AS "pending_yes_pool!",
+                COALESCE(p.no_pool, 0) AS "pending_no_pool!",
+                COALESCE(p.yes_count, 0) AS "pending_yes_count!",
+                COALESCE(p.no_count, 0) AS "pending_no_count!"
+            FROM markets m
+            LEFT JOIN markets_extended me ON me."blockchainMarketId" = m.market_id
+            LEFT JOIN (
+                SELECT
+                    market_id,
+                    COUNT(*) AS bet_count,
+                    SUM(CASE WHEN position THEN amount ELSE 0 END)::numeric AS yes_pool,
+                    SUM(CASE WHEN NOT position THEN amount ELSE 0 END)::numeric AS no_pool,
+                    (COUNT(*) FILTER (WHERE position))::int AS yes_count,
+                    (COUNT(*) FILTER (WHERE NOT position))::int AS no_count
+                FROM bets
+                GROUP BY market_id
+            ) b ON b.market_id = m.market_id
+            LEFT JOIN (
+                -- Only bets known to be on chain: indexer rows (no txStatus) and confirmed
+                -- API bets. Pending and failed API bets are not in the indexer.
+                SELECT "marketId", COUNT(*) AS bet_count
+                FROM bets_extended
+                WHERE status != 'failed' AND ("txStatus" IS NULL OR "txStatus" = 'confirmed')
+                GROUP BY "marketId"
+            ) be ON be."marketId" = me.id
+            LEFT JOIN (
+                SELECT
+                    "marketId",
+                    SUM(amount) AS pool,
+                    COALESCE(SUM(amount) FILTER (WHERE position), 0) AS yes_pool,
+                    COALESCE(SUM(amount) FILTER (WHERE NOT position), 0) AS no_pool,
+                    (COUNT(*) FILTER (WHERE position))::int AS yes_count,
+                    (COUNT(*) FILTER (WHERE NOT position))::int AS no_count
+                FROM bets_extended pb
+                WHERE "txStatus" = 'pending'
+                  AND status NOT IN ('failed', 'cancelled')
+                  AND NOT EXISTS (SELECT 1 FROM bets WHERE bet_id = pb."blockchainBetId")
+                GROUP BY "marketId"
+            ) p ON p."marketId" = me.id
+            ORDER BY m.market_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(markets)
+    }
+
+    async fn repair(
+        &self,
+        markets: &[MarketComparison],
+        discrepancies: &[Discrepancy],
+    ) -> Result<()> {
+        let missing_rows = discrepancies
+            .iter()
+            .any(|d| d.field == "market" || d.field == "betCount");
+        if missing_rows {
+            let sync = BlockchainSyncService::new(self.pool.clone());
+            loop {
+                let new_markets = sync.sync_markets().await?.new_events;
+                let new_bets = sync.sync_bets().await?.new_events;
+                if new_markets == 0 && new_bets == 0 {
+                    break;
+                }
+            }
+        }
+
+        let drifted: HashSet<i64> = discrepancies
+            .iter()
+            .map(|d| d.blockchain_market_id)
+            .collect();
+        let listener = DbEventListener::new(self.pool.clone());
+        for market in markets
+            .iter()
+            .filter(|m| drifted.contains(&m.blockchain_market_id))
+        {
+            if !self.recompute_pools(market.blockchain_market_id).await? {
+                continue;
+            }
+
+            if market.resolved == Some(true) && market.status.as_deref() != Some("resolved") {
+                if let Some(outcome) = market.outcome {
+                    listener
+                        .apply_market_resolution(market.blockchain_market_id, outcome)
+                        .await?;
+                }
+            }
+            if let Err(e) =
+                publish_invalidation(&self.pool, Some(market.blockchain_market_id)).await
+            {
+                warn!("Failed to publish cache invalidation: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets a market's pools and counts from the indexer's `bets` plus its pending API bets;
+    /// `false` when it has not been synced. Holds the market's bet lock meanwhile, so no bet
+    /// lands between the sums and the write.
+    async fn recompute_pools(&self, blockchain_market_id: i64) -> Result<bool> {
+        let market_id = sqlx::query_scalar!(
+            r#"SELECT id FROM markets_extended WHERE "blockchainMarketId" = $1"#,
+            blockchain_market_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(market_id) = market_id else {
+            return Ok(false);
+        };
+
+        let mut tx = RiskLimitService::new(self.pool.clone())
+            .lock_market(&market_id)
+            .await?;
+        sqlx::query!(
+            r#"
+            UPDATE markets_extended me
+            SET
+                "yesPoolSize" = CASE WHEN me."marketType" = 'categorical'
+                    THEN me."yesPoolSize" ELSE s.yes_pool END,
+                "noPoolSize" = CASE WHEN me."marketType" = 'categorical'
+                    THEN me."noPoolSize" ELSE s.no_pool END,
+                "countYes" = CASE WHEN me."marketType" = 'categorical'
+                    THEN me."countYes" ELSE s.yes_count END,
+                "countNo" = CASE WHEN me."marketType" = 'categorical'
+                    THEN me."countNo" ELSE s.no_count END,
+                "totalPoolSize" = s.total,
+                volume = s.total,
+                probability = CASE
+                    WHEN me."marketType" = 'categorical' THEN me.probability
+                    WHEN s.yes_pool + s.no_pool > 0 THEN
+                        ROUND((s.yes_pool / (s.yes_pool + s.no_pool) * 100)::numeric)::int
+                    ELSE 50
+                END,
+                "updatedAt" = NOW()
+            FROM (
+                SELECT
+                    COALESCE(SUM(amount), 0)::numeric AS total,
+                    COALESCE(SUM(CASE WHEN position THEN amount ELSE 0 END), 0)::numeric AS yes_pool,
+                    COALESCE(SUM(CASE WHEN NOT position THEN amount ELSE 0 END), 0)::numeric AS no_pool,
+                    (COUNT(*) FILTER (WHERE position))::int AS yes_count,
+                    (COUNT(*) FILTER (WHERE NOT position))::int AS no_count
+                FROM (
+                    SELECT position, amount::numeric AS amount FROM bets WHERE market_id = $1
+                    UNION ALL
+                    SELECT position, amount FROM bets_extended pb
+                    WHERE "marketId" = $2
+                      AND "txStatus" = 'pending'
+                      AND status NOT IN ('failed', 'cancelled')
+                      AND NOT EXISTS (SELECT 1 FROM bets WHERE bet_id = pb."blockchainBetId")
+                ) all_bets
+            ) s
+            WHERE me.id = $2
+            "#,
+            blockchain_market_id,
+            market_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        MarketOutcomeService::refresh_pools_in(&mut tx, Some(&market_id)).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_sync() -> MarketComparison {
+        MarketComparison {
+            blockchain_market_id: 7,
+            resolved: Some(false),
+            outcome: None,
+            market_id: Some("m-7".to_string()),
+            market_type: Some("binary".to_string()),
+            status: Some("active".to_string()),
+            result: None,
+            total_pool_size: Some(BigDecimal::from(300)),
+            yes_pool_size: Some(BigDecimal::from(100)),
+            no_pool_size: Some(BigDecimal::from(200)),
+            count_yes: Some(1),
+            count_no: Some(2),
+            indexer_bets: 3,
+            indexer_yes_pool: BigDecimal::from(100),
+            indexer_no_pool: BigDecimal::from(200),
+            indexer_yes_count: 1,
+            indexer_no_count: 2,
+            extended_bets: 3,
+            pending_pool: BigDecimal::from(0),
+            pending_yes_pool: BigDecimal::from(0),
+            pending_no_pool: BigDecimal::from(0),
+            pending_yes_count: 0,
+            pending_no_count: 0,
+        }
+    }
+
+    fn fields(market: &MarketComparison) -> Vec<String> {
+        market
+            .discrepancies()
+            .into_iter()
+            .map(|d| d.field)
+            .collect()
+    }
+
+    #[test]
+    fn test_matching_market_has_no_discrepancies() {
+        assert!(in_sync().discrepancies().is_empty());
+    }
+
+    #[test]
+    fn test_reports_drifted_pools_and_counts() {
+        let market = MarketComparison {
+            total_pool_size: Some(BigDecimal::from(350)),
+            yes_pool_size: Some(BigDecimal::from(150)),
+            count_yes: Some(2),
+            extended_bets: 4,
+            ..in_sync()
+        };
+        assert_eq!(
+            fields(&market),
+            ["betCount", "totalPoolSize", "yesPoolSize", "countYes"]
+        );
+    }
+
+    #[test]
+    fn test_categorical_market_compares_total_only() {
+        let market = MarketComparison {
+            market_type: Some("categorical".to_string()),
+            yes_pool_size: Some(BigDecimal::from(0)),
+            count_no: Some(0),
+            ..in_sync()
+        };
+        assert!(fields(&market).is_empty());
+    }
+
+    #[test]
+    fn test_reports_unsynced_market_and_resolution() {
+        let unsynced = MarketComparison {
+            market_id: None,
+            ..in_sync()
+        };
+        assert_eq!(fields(&unsynced), ["market"]);
+
+        let resolved = MarketComparison {
+            resolved: Some(true),
+            outcome: Some(true),
+            ..in_sync()
+        };
+        assert_eq!(fields(&resolved), ["status"]);
+
+        let wrong_result = MarketComparison {
+            status: Some("resolved".to_string()),
+            result: Some(false),
+            ..resolved
+        };
+        assert_eq!(fields(&wrong_result), ["result"]);
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_pending_api_bets_count_toward_pools_only(pool: PgPool) {
+        sqlx::query!(
+            r#"
+            INSERT INTO markets (market_id, question, end_time, yield_protocol_addr,
+                                 transaction_version, transaction_block_height)
+            VALUES (1, 'Q', 0, '0x1', 1, 1)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO bets (bet_id, market_id, user_addr, position, amount,
+                              transaction_version, transaction_block_height)
+            VALUES (10, 1, '0xu1', true, 100, 2, 2)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(r#"INSERT INTO users (id, address) VALUES ('u-1', '0xu1')"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO markets_extended (id, "endDate", "blockchainMarketId", "totalPoolSize",
+                                          "yesPoolSize", "countYes", probability)
+            VALUES ('m-1', NOW() + INTERVAL '1 day', 1, 150, 150, 2, 100)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO bets_extended (id, "blockchainBetId", "userId", "marketId", position,
+                                       amount, odds, status, "txStatus")
+            VALUES ('b-10', 10, 'u-1', 'm-1', true, 100, 1, 'active', NULL),
+                   ('b-11', 11, 'u-1', 'm-1', true, 50, 1, 'active', 'pending'),
+                   ('b-12', 12, 'u-1', 'm-1', false, 50, 1, 'failed', 'failed')
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let service = ReconciliationService::new(pool.clone());
+        let markets = service.compare().await.unwrap();
+        assert_eq!(markets.len(), 1);
+        assert_eq!(markets[0].extended_bets, 1);
+        // The pending bet is already in the pools, as placing it put it there.
+        assert!(fields(&markets[0]).is_empty());
+
+        sqlx::query!(r#"UPDATE markets_extended SET "totalPoolSize" = 999, "countYes" = 0"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let report = service.run(true, None).await.unwrap();
+        assert_eq!(report.discrepancy_count, 2);
+        assert_eq!(report.repaired_count, 2);
+
+        let market = sqlx::query!(
+            r#"SELECT "totalPoolSize", "yesPoolSize", "countYes" FROM markets_extended"#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(market.totalPoolSize, BigDecimal::from(150));
+        assert_eq!(market.yesPoolSize, BigDecimal::from(150));
+        assert_eq!(market.countYes, 2);
+    }
+}
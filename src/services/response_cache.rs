@@ -0,0 +1,493 @@
+use anyhow::Result;
+use axum::body::Bytes;
+use axum::http::HeaderMap;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::postgres::{PgListener, PgPool};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::config::CacheConfig;
+
+use super::job_queue::backoff_delay;
+
+/// Channel the event listener announces applied changes on. The payload is the blockchain
+/// market id the change belongs to, or `*` when it may touch any market.
+pub const INVALIDATION_CHANNEL: &str = "kizo_cache_invalidation";
+const ALL_MARKETS: &str = "*";
+
+/// Endpoints served through the cache, each with its own TTL and counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CachedEndpoint {
+    Markets,
+    PlatformStats,
+    MarketStats,
+}
+
+impl CachedEndpoint {
+    pub const ALL: [CachedEndpoint; 3] = [
+        CachedEndpoint::Markets,
+        CachedEndpoint::PlatformStats,
+        CachedEndpoint::MarketStats,
+    ];
+
+    fn index(self) -> usize {
+        match self {
+            CachedEndpoint::Markets => 0,
+            CachedEndpoint::PlatformStats => 1,
+            CachedEndpoint::MarketStats => 2,
+        }
+    }
+
+    pub fn ttl(self, config: &CacheConfig) -> Duration {
+        Duration::from_secs(match self {
+            CachedEndpoint::Markets => config.markets_ttl_secs,
+            CachedEndpoint::PlatformStats => config.platform_stats_ttl_secs,
+            CachedEndpoint::MarketStats => config.market_stats_ttl_secs,
+        })
+    }
+}
+
+/// Blockchain market a cached response belongs to. Handlers attach it as a response
+/// extension; responses without one cover every market.
+#[derive(Debug, Clone, Copy)]
+pub struct CachedMarket(pub i64);
+
+/// A response body and the handler's headers as served, with the `ETag` it is served under.
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub body: Bytes,
+    pub headers: HeaderMap,
+    pub etag: String,
+}
+
+impl CachedResponse {
+    pub fn new(body: Bytes, headers: HeaderMap) -> Self {
+        let etag = etag(&body);
+        Self {
+            body,
+            headers,
+            etag,
+        }
+    }
+}
+
+/// Strong `ETag` over a response body: a quoted, truncated SHA-256.
+pub fn etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    format!("\"{}\"", &hex::encode(digest)[..32])
+}
+
+struct Entry {
+    endpoint: CachedEndpoint,
+    market: Option<i64>,
+    response: CachedResponse,
+    expires_at: Instant,
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointStats {
+    pub endpoint: CachedEndpoint,
+    pub ttl_secs: u64,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Share of lookups served from the cache; `None` before the first lookup.
+    pub hit_rate: Option<f64>,
+    /// Entries dropped because the data behind them changed.
+    pub invalidations: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub enabled: bool,
+    pub entries: usize,
+    pub max_entries: usize,
+    pub endpoints: Vec<EndpointStats>,
+}
+
+/// In-process read-through cache for the hot market and stats endpoints.
+///
+/// Entries expire after their endpoint's TTL, and are dropped early when the event
+/// listener reports a change for their market. Each replica keeps its own entries and
+/// counters.
+pub struct ResponseCache {
+    config: CacheConfig,
+    entries: RwLock<HashMap<String, Entry>>,
+    counters: [Counters; 3],
+    /// Bumped by every invalidation, so a response built before one is not stored after it.
+    generation: AtomicU64,
+}
+
+impl ResponseCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        Self {
+            config: config.clone(),
+            entries: RwLock::new(HashMap::new()),
+            counters: Default::default(),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether responses of `endpoint` are cached at all.
+    pub fn caches(&self, endpoint: CachedEndpoint) -> bool {
+        self.config.enabled && !endpoint.ttl(&self.config).is_zero()
+    }
+
+    /// Looks up a live entry, counting the hit or miss.
+    pub async fn get(&self, endpoint: CachedEndpoint, key: &str) -> Option<CachedResponse> {
+        let found = self
+            .entries
+            .read()
+            .await
+            .get(key)
+            .filter(|entry| entry.expires_at > Instant::now())
+            .map(|entry| entry.response.clone());
+
+        let counters = &self.counters[endpoint.index()];
+        match found {
+            Some(_) => counters.hits.fetch_add(1, Ordering::Relaxed),
+            None => counters.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        found
+    }
+
+    /// Current invalidation generation. Read it before building a response and pass it to
+    /// [`ResponseCache::insert`].
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Stores a response for its endpoint's TTL, unless the cache was invalidated since
+    /// `generation` was read: the response may predate the change. When full, expired entries
+    /// are dropped first, then the ones closest to expiring.
+    pub async fn insert(
+        &self,
+        endpoint: CachedEndpoint,
+        key: String,
+        market: Option<i64>,
+        response: CachedResponse,
+        generation: u64,
+    ) {
+        let now = Instant::now();
+        let mut entries = self.entries.write().await;
+        if self.generation() != generation {
+            return;
+        }
+        if entries.len() >= self.config.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, entry| entry.expires_at > now);
+            while entries.len() >= self.config.max_entries {
+                let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.expires_at)
+                    .map(|(key, _)| key.clone())
+                else {
+                    break;
+                };
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            key,
+            Entry {
+                endpoint,
+                market,
+                response,
+                expires_at: now + endpoint.ttl(&self.config),
+            },
+        );
+    }
+
+    /// Drops the entries a change to `market` can affect: those of that market and those
+    /// covering every market. `None` drops everything.
+    pub async fn invalidate(&self, market: Option<i64>) -> usize {
+        let mut entries = self.entries.write().await;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        let before = entries.len();
+        entries.retain(|_, entry| {
+            let stale = match (market, entry.market) {
+                (Some(changed), Some(cached)) => changed == cached,
+                _ => true,
+            };
+            if stale {
+                self.counters[entry.endpoint.index()]
+                    .invalidations
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+            !stale
+        });
+        before - entries.len()
+    }
+
+    pub async fn stats(&self) -> CacheStats {
+        let entries = self.entries.read().await;
+        let endpoints = CachedEndpoint::ALL
+            .iter()
+            .map(|&endpoint| {
+                let counters = &self.counters[endpoint.index()];
+                let hits = counters.hits.load(Ordering::Relaxed);
+                let misses = counters.misses.load(Ordering::Relaxed);
+                EndpointStats {
+                    endpoint,
+                    ttl_secs: endpoint.ttl(&self.config).as_secs(),
+                    entries: entries.values().filter(|e| e.endpoint == endpoint).count(),
+                    hits,
+                    misses,
+                    hit_rate: (hits + misses > 0).then(|| hits as f64 / (hits + misses) as f64),
+                    invalidations: counters.invalidations.load(Ordering::Relaxed),
+                }
+            })
+            .collect();
+
+        CacheStats {
+            enabled: self.config.enabled,
+            entries: entries.len(),
+            max_entries: self.config.max_entries,
+            endpoints,
+        }
+    }
+
+    /// Applies invalidations announced on [`INVALIDATION_CHANNEL`] until `shutdown` is
+    /// cancelled. Every replica runs this, while only the one running the event listener
+    /// publishes. Announcements sent while disconnected are lost, so every (re)connect
+    /// starts from an empty cache.
+    pub async fn listen_for_invalidations(
+        self: Arc<Self>,
+        pool: PgPool,
+        reconnect_base_secs: u64,
+        reconnect_max_secs: u64,
+        shutdown: CancellationToken,
+    ) {
+        let mut failures = 0;
+        while !shutdown.is_cancelled() {
+            let mut listener = match subscribe(&pool).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    failures += 1;
+                    let delay = backoff_delay(failures, reconnect_base_secs, reconnect_max_secs);
+                    warn!(
+                        "Cache invalidation listener connection failed (attempt {}), retrying in {}s: {:#}",
+                        failures,
+                        delay.as_secs(),
+                        e
+                    );
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = shutdown.cancelled() => break,
+                    }
+                    continue;
+                }
+            };
+            failures = 0;
+            self.invalidate(None).await;
+            info!(
+                "Listening for cache invalidations on {}",
+                INVALIDATION_CHANNEL
+            );
+
+            let lost = loop {
+                let received = tokio::select! {
+                    received = listener.try_recv() => received,
+                    _ = shutdown.cancelled() => break None,
+                };
+                match received {
+                    Ok(Some(notification)) => {
+                        let market = parse_market(notification.payload());
+                        let dropped = self.invalidate(market).await;
+                        debug!(
+                            "Cache invalidation for market {}: dropped {} entries",
+                            notification.payload(),
+                            dropped
+                        );
+                    }
+                    Ok(None) => break Some("connection lost".to_string()),
+                    Err(e) => break Some(e.to_string()),
+                }
+            };
+
+            let Some(reason) = lost else { break };
+            warn!("Cache invalidation listener disconnected: {}", reason);
+            let delay = backoff_delay(1, reconnect_base_secs, reconnect_max_secs);
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.cancelled() => break,
+            }
+        }
+    }
+}
+
+async fn subscribe(pool: &PgPool) -> Result<PgListener> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.eager_reconnect(false);
+    listener.listen(INVALIDATION_CHANNEL).await?;
+    Ok(listener)
+}
+
+/// Announces a change to `market` (or to any market, for `None`) to every replica's cache.
+pub async fn publish_invalidation(pool: &PgPool, market: Option<i64>) -> Result<()> {
+    let payload = market
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| ALL_MARKETS.to_string());
+    sqlx::query!(
+        "SELECT FROM pg_notify($1, $2)",
+        INVALIDATION_CHANNEL,
+        payload
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn parse_market(payload: &str) -> Option<i64> {
+    payload.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(max_entries: usize) -> ResponseCache {
+        ResponseCache::new(&CacheConfig {
+            max_entries,
+            ..CacheConfig::default()
+        })
+    }
+
+    fn response(body: &'static str) -> CachedResponse {
+        CachedResponse::new(Bytes::from_static(body.as_bytes()), HeaderMap::new())
+    }
+
+    #[tokio::test]
+    async fn test_invalidation_keeps_other_markets() {
+        let cache = cache(10);
+        cache
+            .insert(
+                CachedEndpoint::MarketStats,
+                "a".into(),
+                Some(1),
+                response("1"),
+                0,
+            )
+            .await;
+        cache
+            .insert(
+                CachedEndpoint::MarketStats,
+                "b".into(),
+                Some(2),
+                response("2"),
+                0,
+            )
+            .await;
+        cache
+            .insert(
+                CachedEndpoint::Markets,
+                "list".into(),
+                None,
+                response("[]"),
+                0,
+            )
+            .await;
+
+        assert_eq!(cache.invalidate(Some(1)).await, 2);
+        assert!(cache.get(CachedEndpoint::MarketStats, "a").await.is_none());
+        assert!(cache.get(CachedEndpoint::MarketStats, "b").await.is_some());
+        assert!(cache.get(CachedEndpoint::Markets, "list").await.is_none());
+
+        let stats = cache.stats().await;
+        let market_stats = &stats.endpoints[CachedEndpoint::MarketStats.index()];
+        assert_eq!((market_stats.hits, market_stats.misses), (1, 1));
+        assert_eq!(market_stats.invalidations, 1);
+    }
+
+    #[tokio::test]
+    async fn test_full_cache_evicts_soonest_expiring() {
+        let cache = cache(2);
+        cache
+            .insert(
+                CachedEndpoint::Markets,
+                "list".into(),
+                None,
+                response("[]"),
+                0,
+            )
+            .await;
+        cache
+            .insert(
+                CachedEndpoint::PlatformStats,
+                "platform".into(),
+                None,
+                response("{}"),
+                0,
+            )
+            .await;
+        cache
+            .insert(
+                CachedEndpoint::MarketStats,
+                "a".into(),
+                Some(1),
+                response("1"),
+                0,
+            )
+            .await;
+
+        assert_eq!(cache.stats().await.entries, 2);
+        assert!(cache.get(CachedEndpoint::Markets, "list").await.is_none());
+        assert!(cache
+            .get(CachedEndpoint::PlatformStats, "platform")
+            .await
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_response_built_before_an_invalidation_is_not_stored() {
+        let cache = cache(10);
+        let generation = cache.generation();
+        cache.invalidate(Some(1)).await;
+        cache
+            .insert(
+                CachedEndpoint::MarketStats,
+                "a".into(),
+                Some(1),
+                response("stale"),
+                generation,
+            )
+            .await;
+        assert!(cache.get(CachedEndpoint::MarketStats, "a").await.is_none());
+
+        cache
+            .insert(
+                CachedEndpoint::MarketStats,
+                "a".into(),
+                Some(1),
+                response("fresh"),
+                cache.generation(),
+            )
+            .await;
+        assert!(cache.get(CachedEndpoint::MarketStats, "a").await.is_some());
+    }
+
+    #[test]
+    fn test_etag_is_quoted_and_follows_the_body() {
+        let tag = etag(b"{}");
+        assert!(tag.starts_with('"') && tag.ends_with('"'));
+        assert_eq!(tag, etag(b"{}"));
+        assert_ne!(tag, etag(b"[]"));
+        assert_eq!(parse_market("42"), Some(42));
+        assert_eq!(parse_market(ALL_MARKETS), None);
+    }
+}
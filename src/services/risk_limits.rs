@@ -0,0 +1,705 @@
+use anyhow::{anyhow, Result};
+use bigdecimal::{BigDecimal, Zero};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::{PgConnection, PgExecutor, PgPool, Postgres, Transaction};
+use tracing::info;
+use utoipa::ToSchema;
+
+/// Scope of the platform-wide row in `risk_limits`; market rows use the market id.
+pub const GLOBAL_SCOPE: &str = "global";
+
+/// First key of the per-market bet locks (`pg_advisory_xact_lock(int, int)`), next to the
+/// job locks' 0x4b5a namespace.
+const BET_LOCK_NAMESPACE: i32 = 0x4b5b;
+
+/// One row of `risk_limits`. `None` limits mean "no limit" on the global row and
+/// "use the global limit" on a market row.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskLimits {
+    pub scope: String,
+    pub min_bet: Option<BigDecimal>,
+    pub max_bet: Option<BigDecimal>,
+    pub max_user_market_exposure: Option<BigDecimal>,
+    pub max_market_exposure: Option<BigDecimal>,
+    pub close_cooldown_secs: Option<i32>,
+    pub betting_paused: bool,
+    pub pause_reason: Option<String>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// Partial update of a `risk_limits` row: absent fields are kept, `null` clears a limit.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RiskLimitsPatch {
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    pub min_bet: Option<Option<BigDecimal>>,
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    pub max_bet: Option<Option<BigDecimal>>,
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    pub max_user_market_exposure: Option<Option<BigDecimal>>,
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    pub max_market_exposure: Option<Option<BigDecimal>>,
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<i32>)]
+    pub close_cooldown_secs: Option<Option<i32>>,
+    pub betting_paused: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    pub pause_reason: Option<Option<String>>,
+}
+
+/// Tells an explicit `null` (`Some(None)`) apart from an absent field (`None`).
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl RiskLimits {
+    fn apply(&mut self, patch: RiskLimitsPatch) {
+        if let Some(v) = patch.min_bet {
+            self.min_bet = v;
+        }
+        if let Some(v) = patch.max_bet {
+            self.max_bet = v;
+        }
+        if let Some(v) = patch.max_user_market_exposure {
+            self.max_user_market_exposure = v;
+        }
+        if let Some(v) = patch.max_market_exposure {
+            self.max_market_exposure = v;
+        }
+        if let Some(v) = patch.close_cooldown_secs {
+            self.close_cooldown_secs = v;
+        }
+        if let Some(v) = patch.betting_paused {
+            self.betting_paused = v;
+        }
+        if let Some(v) = patch.pause_reason {
+            self.pause_reason = v;
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let amounts = [
+            ("minBet", &self.min_bet),
+            ("maxBet", &self.max_bet),
+            ("maxUserMarketExposure", &self.max_user_market_exposure),
+            ("maxMarketExposure", &self.max_market_exposure),
+        ];
+        for (name, value) in amounts {
+            if value.as_ref().is_some_and(|v| v < &BigDecimal::zero()) {
+                return Err(anyhow!("{} cannot be negative", name));
+            }
+        }
+        if let (Some(min), Some(max)) = (&self.min_bet, &self.max_bet) {
+            if min > max {
+                return Err(anyhow!("minBet cannot be above maxBet"));
+            }
+        }
+        if self.close_cooldown_secs.is_some_and(|v| v < 0) {
+            return Err(anyhow!("closeCooldownSecs cannot be negative"));
+        }
+        Ok(())
+    }
+}
+
+/// Limits that apply to one market: its own row layered over the global row.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveLimits {
+    pub min_bet: Option<BigDecimal>,
+    pub max_bet: Option<BigDecimal>,
+    pub max_user_market_exposure: Option<BigDecimal>,
+    pub max_market_exposure: Option<BigDecimal>,
+    pub close_cooldown_secs: Option<i32>,
+    pub platform_paused: bool,
+    pub market_paused: bool,
+    pub pause_reason: Option<String>,
+}
+
+impl EffectiveLimits {
+    pub fn layered(global: &RiskLimits, market: Option<&RiskLimits>) -> Self {
+        let pick = |own: Option<&Option<BigDecimal>>, fallback: &Option<BigDecimal>| {
+            own.and_then(|v| v.clone()).or_else(|| fallback.clone())
+        };
+        let market_paused = market.is_some_and(|m| m.betting_paused);
+        let pause_reason = if global.betting_paused {
+            global.pause_reason.clone()
+        } else if market_paused {
+            market.and_then(|m| m.pause_reason.clone())
+        } else {
+            None
+        };
+
+        Self {
+            min_bet: pick(market.map(|m| &m.min_bet), &global.min_bet),
+            max_bet: pick(market.map(|m| &m.max_bet), &global.max_bet),
+            max_user_market_exposure: pick(
+                market.map(|m| &m.max_user_market_exposure),
+                &global.max_user_market_exposure,
+            ),
+            max_market_exposure: pick(
+                market.map(|m| &m.max_market_exposure),
+                &global.max_market_exposure,
+            ),
+            close_cooldown_secs: market
+                .and_then(|m| m.close_cooldown_secs)
+                .or(global.close_cooldown_secs),
+            platform_paused: global.betting_paused,
+            market_paused,
+            pause_reason,
+        }
+    }
+}
+
+/// A bet refused by the risk limits. `code()` is the stable identifier returned to clients.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RiskViolation {
+    #[error("Betting is paused platform-wide{}", reason_suffix(.0))]
+    BettingPaused(Option<String>),
+    #[error("Betting is paused on this market{}", reason_suffix(.0))]
+    MarketPaused(Option<String>),
+    #[error("Betting closes {cooldown_secs}s before the market's end date")]
+    BettingClosed { cooldown_secs: i32 },
+    #[error("Bet is below the minimum of {min}")]
+    BelowMinBet { min: BigDecimal },
+    #[error("Bet is above the maximum of {max}")]
+    AboveMaxBet { max: BigDecimal },
+    #[error("Bet would take this user's stake on the market to {total}, above the cap of {cap}")]
+    UserExposureExceeded { total: BigDecimal, cap: BigDecimal },
+    #[error("Bet would take the market's pool to {total}, above the cap of {cap}")]
+    MarketExposureExceeded { total: BigDecimal, cap: BigDecimal },
+}
+
+fn reason_suffix(reason: &Option<String>) -> String {
+    reason
+        .as_deref()
+        .map(|r| format!(": {}", r))
+        .unwrap_or_default()
+}
+
+impl RiskViolation {
+    pub fn code(&self) -> &'static str {
+        match self {
+            RiskViolation::BettingPaused(_) => "BETTING_PAUSED",
+            RiskViolation::MarketPaused(_) => "MARKET_PAUSED",
+            RiskViolation::BettingClosed { .. } => "BETTING_CLOSED",
+            RiskViolation::BelowMinBet { .. } => "BET_BELOW_MINIMUM",
+            RiskViolation::AboveMaxBet { .. } => "BET_ABOVE_MAXIMUM",
+            RiskViolation::UserExposureExceeded { .. } => "USER_EXPOSURE_LIMIT",
+            RiskViolation::MarketExposureExceeded { .. } => "MARKET_EXPOSURE_LIMIT",
+        }
+    }
+}
+
+/// What a bet would change, as far as the limits are concerned.
+#[derive(Debug, Clone)]
+pub struct BetExposure {
+    pub amount: BigDecimal,
+    /// The user's active stake on the market before this bet; `None` when not known (quotes).
+    pub user_stake: Option<BigDecimal>,
+    /// The market's pool before this bet.
+    pub market_pool: BigDecimal,
+    pub seconds_to_close: i64,
+}
+
+/// Checks a bet against the limits, kill switches first.
+pub fn check_bet(limits: &EffectiveLimits, bet: &BetExposure) -> Result<(), RiskViolation> {
+    if limits.platform_paused {
+        return Err(RiskViolation::BettingPaused(limits.pause_reason.clone()));
+    }
+    if limits.market_paused {
+        return Err(RiskViolation::MarketPaused(limits.pause_reason.clone()));
+    }
+    if let Some(cooldown_secs) = limits.close_cooldown_secs {
+        if bet.seconds_to_close < cooldown_secs as i64 {
+            return Err(RiskViolation::BettingClosed { cooldown_secs });
+        }
+    }
+    if let Some(min) = &limits.min_bet {
+        if &bet.amount < min {
+            return Err(RiskViolation::BelowMinBet { min: min.clone() });
+        }
+    }
+    if let Some(max) = &limits.max_bet {
+        if &bet.amount > max {
+            return Err(RiskViolation::AboveMaxBet { max: max.clone() });
+        }
+    }
+    if let (Some(cap), Some(stake)) = (&limits.max_user_market_exposure, &bet.user_stake) {
+        let total = stake + &bet.amount;
+        if &total > cap {
+            return Err(RiskViolation::UserExposureExceeded {
+                total,
+                cap: cap.clone(),
+            });
+        }
+    }
+    if let Some(cap) = &limits.max_market_exposure {
+        let total = &bet.market_pool + &bet.amount;
+        if &total > cap {
+            return Err(RiskViolation::MarketExposureExceeded {
+                total,
+                cap: cap.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Reads, edits and enforces `risk_limits`.
+pub struct RiskLimitService {
+    pool: PgPool,
+}
+
+impl RiskLimitService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// The global row followed by every market override.
+    pub async fn list(&self) -> Result<Vec<RiskLimits>> {
+        let mut limits = sqlx::query_as!(
+            RiskLimits,
+            r#"
+            SELECT scope, "minBet" as min_bet, "maxBet" as max_bet,
+                   "maxUserMarketExposure" as max_user_market_exposure,
+                   "maxMarketExposure" as max_market_exposure,
+                   "closeCooldownSecs" as close_cooldown_secs,
+                   "bettingPaused" as betting_paused, "pauseReason" as pause_reason,
+                   "updatedAt" as "updated_at?"
+            FROM risk_limits
+            ORDER BY scope <> $1, scope
+            "#,
+            GLOBAL_SCOPE
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        if limits.first().map(|l| l.scope.as_str()) != Some(GLOBAL_SCOPE) {
+            limits.insert(0, Self::unset(GLOBAL_SCOPE));
+        }
+        Ok(limits)
+    }
+
+    /// The row for `scope`, or an empty one if none is stored.
+    pub async fn get(&self, scope: &str) -> Result<RiskLimits> {
+        Ok(self
+            .find(scope)
+            .await?
+            .unwrap_or_else(|| Self::unset(scope)))
+    }
+
+    async fn find(&self, scope: &str) -> Result<Option<RiskLimits>> {
+        Self::find_in(&self.pool, scope).await
+    }
+
+    async fn find_in<'e>(executor: impl PgExecutor<'e>, scope: &str) -> Result<Option<RiskLimits>> {
+        let limits = sqlx::query_as!(
+            RiskLimits,
+            r#"
+            SELECT scope, "minBet" as min_bet, "maxBet" as max_bet,
+                   "maxUserMarketExposure" as max_user_market_exposure,
+                   "maxMarketExposure" as max_market_exposure,
+                   "closeCooldownSecs" as close_cooldown_secs,
+                   "bettingPaused" as betting_paused, "pauseReason" as pause_reason,
+                   "updatedAt" as "updated_at?"
+            FROM risk_limits
+            WHERE scope = $1
+            "#,
+            scope
+        )
+        .fetch_optional(executor)
+        .await?;
+
+        Ok(limits)
+    }
+
+    fn unset(scope: &str) -> RiskLimits {
+        RiskLimits {
+            scope: scope.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Limits in force for a market.
+    pub async fn effective(&self, market_id: &str) -> Result<EffectiveLimits> {
+        let global = self.get(GLOBAL_SCOPE).await?;
+        let market = self.find(market_id).await?;
+        Ok(EffectiveLimits::layered(&global, market.as_ref()))
+    }
+
+    /// Applies `patch` to the row for `scope` (`global` or a market id), creating it if needed.
+    pub async fn update(&self, scope: &str, patch: RiskLimitsPatch) -> Result<RiskLimits> {
+        if scope != GLOBAL_SCOPE {
+            let exists = sqlx::query_scalar!(
+                r#"SELECT EXISTS(SELECT 1 FROM markets_extended WHERE id = $1) as "exists!""#,
+                scope
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            if !exists {
+                return Err(anyhow!("Market not found: {}", scope));
+            }
+        }
+
+        let mut limits = self.get(scope).await?;
+        limits.apply(patch);
+        limits.validate()?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO risk_limits (
+                scope, "minBet", "maxBet", "maxUserMarketExposure", "maxMarketExposure",
+                "closeCooldownSecs", "bettingPaused", "pauseReason"
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (scope) DO UPDATE SET
+                "minBet" = EXCLUDED."minBet",
+                "maxBet" = EXCLUDED."maxBet",
+                "maxUserMarketExposure" = EXCLUDED."maxUserMarketExposure",
+                "maxMarketExposure" = EXCLUDED."maxMarketExposure",
+                "closeCooldownSecs" = EXCLUDED."closeCooldownSecs",
+                "bettingPaused" = EXCLUDED."bettingPaused",
+                "pauseReason" = EXCLUDED."pauseReason",
+                "updatedAt" = NOW()
+            "#,
+            scope,
+            limits.min_bet,
+            limits.max_bet,
+            limits.max_user_market_exposure,
+            limits.max_market_exposure,
+            limits.close_cooldown_secs,
+            limits.betting_paused,
+            limits.pause_reason
+        )
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "Risk limits updated for {} (betting {})",
+            scope,
+            if limits.betting_paused {
+                "paused"
+            } else {
+                "open"
+            }
+        );
+
+        self.get(scope).await
+    }
+
+    /// Drops a market's overrides so the global limits apply again.
+    pub async fn clear_market(&self, market_id: &str) -> Result<bool> {
+        let deleted = sqlx::query!("DELETE FROM risk_limits WHERE scope = $1", market_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    /// Takes the market's bet lock until the returned transaction ends. A bet holds it from its
+    /// limit check until its stake is in the pools, so concurrent bets cannot together
+    /// exceed an exposure cap. Everything done under the lock must run on the returned
+    /// transaction: waiting for a second pooled connection while holding it can starve the
+    /// pool when many bets queue on one market.
+    pub async fn lock_market(&self, market_id: &str) -> Result<Transaction<'static, Postgres>> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "SELECT pg_advisory_xact_lock($1, hashtext($2))",
+            BET_LOCK_NAMESPACE,
+            market_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        Ok(tx)
+    }
+
+    /// Checks a bet of `amount` on a market closing at `end_date`, reading through `conn`
+    /// (the transaction holding the bet lock). The user's exposure cap is only checked when
+    /// `user_address` is given; it counts their bets whether `"userId"` holds their `users` id
+    /// or, on older API bets, the address itself, in any case.
+    pub async fn check_bet(
+        conn: &mut PgConnection,
+        market_id: &str,
+        user_address: Option<&str>,
+        amount: &BigDecimal,
+        end_date: NaiveDateTime,
+    ) -> Result<()> {
+        let global = Self::find_in(&mut *conn, GLOBAL_SCOPE)
+            .await?
+            .unwrap_or_else(|| Self::unset(GLOBAL_SCOPE));
+        let market = Self::find_in(&mut *conn, market_id).await?;
+        let limits = EffectiveLimits::layered(&global, market.as_ref());
+
+        let market_pool = sqlx::query_scalar!(
+            r#"SELECT "totalPoolSize" FROM markets_extended WHERE id = $1"#,
+            market_id
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let user_stake = match (user_address, &limits.max_user_market_exposure) {
+            (Some(user), Some(_)) => Some(
+                sqlx::query_scalar!(
+                    r#"
+                    SELECT COALESCE(SUM(amount), 0) as "stake!"
+                    FROM bets_extended
+                    WHERE "marketId" = $1
+                      AND status = 'active'
+                      AND ("userId" IN (SELECT id FROM users WHERE address = $2)
+                           OR LOWER("userId") = $2)
+                    "#,
+                    market_id,
+                    user.to_lowercase()
+                )
+                .fetch_one(&mut *conn)
+                .await?,
+            ),
+            _ => None,
+        };
+
+        let bet = BetExposure {
+            amount: amount.clone(),
+            user_stake,
+            market_pool,
+            seconds_to_close: (end_date - chrono::Utc::now().naive_utc()).num_seconds(),
+        };
+        check_bet(&limits, &bet)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(v: i64) -> BigDecimal {
+        BigDecimal::from(v)
+    }
+
+    fn bet(v: i64) -> BetExposure {
+        BetExposure {
+            amount: amount(v),
+            user_stake: Some(amount(0)),
+            market_pool: amount(0),
+            seconds_to_close: 86_400,
+        }
+    }
+
+    #[test]
+    fn test_market_limits_override_global() {
+        let global = RiskLimits {
+            scope: GLOBAL_SCOPE.to_string(),
+            min_bet: Some(amount(10)),
+            max_bet: Some(amount(1_000)),
+            close_cooldown_secs: Some(3_600),
+            ..Default::default()
+        };
+        let market = RiskLimits {
+            scope: "market-1".to_string(),
+            max_bet: Some(amount(100)),
+            betting_paused: true,
+            pause_reason: Some("oracle dispute".to_string()),
+            ..Default::default()
+        };
+
+        let limits = EffectiveLimits::layered(&global, Some(&market));
+        assert_eq!(limits.min_bet, Some(amount(10)));
+        assert_eq!(limits.max_bet, Some(amount(100)));
+        assert_eq!(limits.close_cooldown_secs, Some(3_600));
+        assert!(limits.market_paused && !limits.platform_paused);
+        assert_eq!(
+            check_bet(&limits, &bet(50)).unwrap_err().code(),
+            "MARKET_PAUSED"
+        );
+
+        let limits = EffectiveLimits::layered(&global, None);
+        assert_eq!(limits.max_bet, Some(amount(1_000)));
+        assert!(check_bet(&limits, &bet(500)).is_ok());
+    }
+
+    #[test]
+    fn test_check_bet_codes() {
+        let limits = EffectiveLimits {
+            min_bet: Some(amount(10)),
+            max_bet: Some(amount(1_000)),
+            max_user_market_exposure: Some(amount(1_500)),
+            max_market_exposure: Some(amount(10_000)),
+            close_cooldown_secs: Some(3_600),
+            ..Default::default()
+        };
+        let code = |b: BetExposure| check_bet(&limits, &b).err().map(|e| e.code());
+
+        assert_eq!(code(bet(100)), None);
+        assert_eq!(code(bet(5)), Some("BET_BELOW_MINIMUM"));
+        assert_eq!(code(bet(1_001)), Some("BET_ABOVE_MAXIMUM"));
+        assert_eq!(
+            code(BetExposure {
+                user_stake: Some(amount(1_000)),
+                ..bet(600)
+            }),
+            Some("USER_EXPOSURE_LIMIT")
+        );
+        assert_eq!(
+            code(BetExposure {
+                user_stake: None,
+                ..bet(600)
+            }),
+            None
+        );
+        assert_eq!(
+            code(BetExposure {
+                market_pool: amount(9_500),
+                ..bet(600)
+            }),
+            Some("MARKET_EXPOSURE_LIMIT")
+        );
+        assert_eq!(
+            code(BetExposure {
+                seconds_to_close: 600,
+                ..bet(100)
+            }),
+            Some("BETTING_CLOSED")
+        );
+
+        let paused = EffectiveLimits {
+            platform_paused: true,
+            market_paused: true,
+            ..limits.clone()
+        };
+        assert_eq!(
+            check_bet(&paused, &bet(100)).unwrap_err().code(),
+            "BETTING_PAUSED"
+        );
+    }
+
+    #[test]
+    fn test_patch_keeps_absent_and_clears_null() {
+        let mut limits = RiskLimits {
+            min_bet: Some(amount(10)),
+            max_bet: Some(amount(100)),
+            ..Default::default()
+        };
+        let patch: RiskLimitsPatch =
+            serde_json::from_str(r#"{"maxBet": null, "bettingPaused": true}"#).unwrap();
+        limits.apply(patch);
+
+        assert_eq!(limits.min_bet, Some(amount(10)));
+        assert_eq!(limits.max_bet, None);
+        assert!(limits.betting_paused);
+
+        limits.max_bet = Some(amount(5));
+        assert!(limits.validate().is_err());
+        assert!(serde_json::from_str::<RiskLimitsPatch>(r#"{"maxbet": 1}"#).is_err());
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_bets_on_one_market_are_serialized(pool: PgPool) {
+        let service = RiskLimitService::new(pool);
+        let wait = std::time::Duration::from_millis(200);
+
+        let held = service.lock_market("m1").await.unwrap();
+        assert!(tokio::time::timeout(wait, service.lock_market("m1"))
+            .await
+            .is_err());
+        let other = service.lock_market("m2").await.unwrap();
+
+        held.commit().await.unwrap();
+        other.commit().await.unwrap();
+        tokio::time::timeout(wait, service.lock_market("m1"))
+            .await
+            .expect("lock is released on commit")
+            .unwrap();
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_bet_checks_run_on_the_locked_connection(pool: PgPool) {
+        sqlx::query!(
+            r#"INSERT INTO markets_extended (id, "endDate") VALUES ('m1', NOW() + INTERVAL '1 day')"#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // With a single connection, any query through the pool under the lock would hang.
+        let single = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_secs(2))
+            .connect_with(pool.connect_options().as_ref().clone())
+            .await
+            .unwrap();
+        let mut locked = RiskLimitService::new(single)
+            .lock_market("m1")
+            .await
+            .unwrap();
+        let end_date = chrono::Utc::now().naive_utc() + chrono::Duration::days(1);
+        RiskLimitService::check_bet(&mut locked, "m1", Some("0xabc"), &amount(10), end_date)
+            .await
+            .unwrap();
+        locked.commit().await.unwrap();
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_user_exposure_counts_every_form_of_the_address(pool: PgPool) {
+        sqlx::query!(
+            r#"
+            INSERT INTO markets_extended (id, "endDate") VALUES ('m1', NOW() + INTERVAL '1 day');
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO users (id, address) VALUES ('u-1', '0xabc'), ('0xABC', '0xlegacy')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO bets_extended (id, "blockchainBetId", "userId", "marketId", position,
+                                       amount, odds, status)
+            VALUES ('b-1', 1, 'u-1', 'm1', true, 600, 1, 'active'),
+                   ('b-2', 2, '0xABC', 'm1', true, 600, 1, 'active')
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let service = RiskLimitService::new(pool.clone());
+        service
+            .update(
+                GLOBAL_SCOPE,
+                RiskLimitsPatch {
+                    max_user_market_exposure: Some(Some(amount(1_500))),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let end_date = chrono::Utc::now().naive_utc() + chrono::Duration::days(1);
+        let mut conn = pool.acquire().await.unwrap();
+        let err =
+            RiskLimitService::check_bet(&mut conn, "m1", Some("0xAbC"), &amount(400), end_date)
+                .await
+                .unwrap_err();
+        assert_eq!(
+            err.downcast::<RiskViolation>().unwrap().code(),
+            "USER_EXPOSURE_LIMIT"
+        );
+        RiskLimitService::check_bet(&mut conn, "m1", Some("0xAbC"), &amount(300), end_date)
+            .await
+            .unwrap();
+    }
+}
@@ -1,189 +1,522 @@
+use serde_json::{json, Value};
 use sqlx::PgPool;
+use std::future::Future;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
 use tracing::{error, info, warn};
 
+use crate::config::{Config, SchedulerConfig};
+
 use super::blockchain_sync::BlockchainSyncService;
+use super::chain_transactions::ChainTransactionService;
+use super::chainlink_price_feed::ChainlinkPriceFeed;
 use super::db_event_listener::DbEventListener;
+use super::job_queue::{FailureOutcome, Job, JobKind, JobQueue, JobSchedule, ScheduledJob};
+use super::leader_election::{default_instance_id, JobLeader, LeaderElection, SchedulerJob};
+use super::reconciliation::ReconciliationService;
+use super::yield_accrual::YieldAccrualService;
 use super::yield_service::YieldService;
 
-#[derive(Debug, Clone)]
-pub struct SchedulerConfig {
-    pub indexer_sync_interval_secs: u64,
-
-    pub yield_calc_interval_secs: u64,
-
-    pub enable_indexer_sync: bool,
+/// Pending transactions checked per confirmation run.
+const TX_CONFIRM_BATCH_SIZE: i64 = 100;
 
-    pub enable_yield_calc: bool,
-}
+/// How often the job scheduler deletes job history past `jobs.retention_days`.
+const JOB_PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
 
-impl Default for SchedulerConfig {
-    fn default() -> Self {
-        Self {
-            indexer_sync_interval_secs: std::env::var("INDEXER_SYNC_INTERVAL_SECS")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(300),
-
-            yield_calc_interval_secs: std::env::var("YIELD_CALC_INTERVAL_SECS")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(1800),
-            enable_indexer_sync: std::env::var("ENABLE_INDEXER_SYNC")
-                .unwrap_or_else(|_| "true".to_string())
-                .parse()
-                .unwrap_or(true),
-            enable_yield_calc: std::env::var("ENABLE_YIELD_CALC")
-                .unwrap_or_else(|_| "true".to_string())
-                .parse()
-                .unwrap_or(true),
-        }
-    }
-}
+/// Part of `server.shutdown_timeout_secs` kept for putting unfinished jobs back in the queue.
+const JOB_RELEASE_MARGIN: Duration = Duration::from_secs(2);
 
 pub struct Scheduler {
     pool: PgPool,
     config: SchedulerConfig,
+    app_config: Arc<Config>,
+    election: LeaderElection,
+    queue: JobQueue,
+    shutdown: CancellationToken,
+    tasks: TaskTracker,
 }
 
 impl Scheduler {
-    pub fn new(pool: PgPool) -> Self {
-        let config = SchedulerConfig::default();
-        info!("Scheduler configuration: {:?}", config);
-        Self { pool, config }
-    }
-
-    #[allow(dead_code)]
-    pub fn new_with_config(pool: PgPool, config: SchedulerConfig) -> Self {
+    /// Creates a scheduler whose background tasks stop once `shutdown` is cancelled.
+    pub fn new(pool: PgPool, app_config: Arc<Config>, shutdown: CancellationToken) -> Self {
+        let config = app_config.scheduler.clone();
         info!("Scheduler configuration: {:?}", config);
-        Self { pool, config }
+        let instance_id = config
+            .instance_id
+            .clone()
+            .unwrap_or_else(default_instance_id);
+        let election = LeaderElection::new(
+            pool.clone(),
+            instance_id,
+            Duration::from_secs(config.leader_retry_interval_secs),
+        );
+        let queue = JobQueue::new(pool.clone(), &app_config.jobs);
+        Self {
+            pool,
+            config,
+            app_config,
+            election,
+            queue,
+            shutdown,
+            tasks: TaskTracker::new(),
+        }
     }
 
     pub async fn start(self: Arc<Self>) {
         info!("🚀 Starting scheduler with background jobs");
+        let schedules = match self.app_config.job_schedules() {
+            Ok(schedules) => schedules,
+            Err(e) => {
+                error!(
+                    "❌ Invalid job schedules, no recurring jobs will run: {}",
+                    e
+                );
+                Vec::new()
+            }
+        };
+        for kind in JobKind::ALL {
+            match schedules.iter().find(|(k, _)| *k == kind) {
+                Some((_, schedule)) => info!("   - {}: {}", kind, schedule),
+                None => info!("   - {}: disabled", kind),
+            }
+        }
         info!(
-            "   - Indexer sync: {} (interval: {}s)",
-            if self.config.enable_indexer_sync {
+            "   - Price sampling: {} (interval: {}s)",
+            if self.config.enable_price_sampling {
                 "enabled"
             } else {
                 "disabled"
             },
-            self.config.indexer_sync_interval_secs
+            self.config.price_sample_interval_secs
         );
         info!(
-            "   - Yield calculation: {} (interval: {}s)",
-            if self.config.enable_yield_calc {
+            "   - Transaction confirmation: {} (interval: {}s)",
+            if self.config.enable_tx_confirmation {
                 "enabled"
             } else {
                 "disabled"
             },
-            self.config.yield_calc_interval_secs
+            self.config.tx_confirm_interval_secs
         );
+        info!(
+            "   - Job workers: {} (poll: {}s)",
+            self.app_config.jobs.workers, self.app_config.jobs.poll_interval_secs
+        );
+        if self.config.enable_leader_election {
+            info!(
+                "   - Leader election: enabled as {} (retry: {}s)",
+                self.election.instance_id(),
+                self.config.leader_retry_interval_secs
+            );
+        } else {
+            info!("   - Leader election: disabled (every job runs on this instance)");
+        }
 
-        let sync_scheduler = Arc::clone(&self);
-        let yield_scheduler = Arc::clone(&self);
-        let db_event_scheduler = Arc::clone(&self);
-
-        tokio::spawn(async move {
-            let db_listener = DbEventListener::new(db_event_scheduler.pool.clone());
+        self.spawn_job(SchedulerJob::EventListener, |scheduler| async move {
+            let db_listener = DbEventListener::new(scheduler.pool.clone()).with_reconnect_backoff(
+                scheduler.config.listener_reconnect_base_secs,
+                scheduler.config.listener_reconnect_max_secs,
+            );
             info!("🎧 Starting database event listener for real-time event processing");
-            if let Err(e) = db_listener.start_listening().await {
+            if let Err(e) = db_listener
+                .start_listening(scheduler.shutdown.clone())
+                .await
+            {
                 error!("❌ Database event listener error: {}", e);
                 warn!("⚠️  Event listener failed, relying on periodic sync instead");
             }
         });
 
-        if self.config.enable_indexer_sync {
-            let interval_secs = self.config.indexer_sync_interval_secs;
-            tokio::spawn(async move {
+        if let Err(e) = self.queue.sync_schedules(&schedules).await {
+            error!("❌ Failed to store job schedules: {:#}", e);
+        }
+        let schedules = Arc::new(schedules);
+        let poll_interval = Duration::from_secs(self.app_config.jobs.poll_interval_secs);
+        self.spawn_job(SchedulerJob::JobScheduler, move |scheduler| {
+            let schedules = Arc::clone(&schedules);
+            async move {
+                let mut interval = time::interval(poll_interval);
+                let mut next_prune = Instant::now();
+
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {}
+                        _ = scheduler.shutdown.cancelled() => break,
+                    }
+                    scheduler.enqueue_due_jobs(&schedules).await;
+
+                    if Instant::now() >= next_prune {
+                        next_prune = Instant::now() + JOB_PRUNE_INTERVAL;
+                        match scheduler.queue.prune().await {
+                            Ok(0) => {}
+                            Ok(n) => info!("🧹 Pruned {} finished jobs", n),
+                            Err(e) => error!("❌ Failed to prune job history: {}", e),
+                        }
+                    }
+                }
+            }
+        });
+        info!(
+            "✅ Job scheduler started (every {}s)",
+            poll_interval.as_secs()
+        );
+
+        for worker in 1..=self.app_config.jobs.workers {
+            let scheduler = Arc::clone(&self);
+            let worker_id = format!("{}/{}", self.election.instance_id(), worker);
+            self.tasks
+                .spawn(async move { scheduler.run_worker(worker_id).await });
+        }
+        if self.app_config.jobs.workers == 0 {
+            warn!("⚠️  No job workers on this instance; queued jobs run on other instances");
+        }
+
+        if self.config.enable_price_sampling {
+            let interval_secs = self.config.price_sample_interval_secs;
+            self.spawn_job(SchedulerJob::PriceSampling, move |scheduler| async move {
+                let feed = match ChainlinkPriceFeed::new(&scheduler.app_config.price_feed) {
+                    Ok(feed) => feed.with_history(scheduler.pool.clone()),
+                    Err(e) => {
+                        error!("❌ Price sampling job could not start: {}", e);
+                        return;
+                    }
+                };
                 let mut interval = time::interval(Duration::from_secs(interval_secs));
-                let mut sync_count = 0u64;
+                let mut sample_count = 0u64;
 
                 loop {
-                    interval.tick().await;
-                    sync_count += 1;
-                    info!(
-                        "🔄 [Sync Job #{}] Running indexer database sync",
-                        sync_count
-                    );
+                    tokio::select! {
+                        _ = interval.tick() => {}
+                        _ = scheduler.shutdown.cancelled() => break,
+                    }
+                    sample_count += 1;
 
-                    let sync_service = BlockchainSyncService::new(sync_scheduler.pool.clone());
-                    match sync_service.run_full_sync().await {
-                        Ok(summary) => {
-                            if summary.total_processed > 0 {
-                                info!(
-                                    "✅ [Sync Job #{}] Completed: {} new events, {} errors, {}ms",
-                                    sync_count,
-                                    summary.total_processed,
-                                    summary.total_errors,
-                                    summary.duration_ms
-                                );
-
-                                for result in &summary.results {
-                                    if result.new_events > 0 {
-                                        info!(
-                                            "   └─ {}: {} new items",
-                                            result.event_type, result.new_events
-                                        );
-                                    }
-                                }
-                            } else {
-                                info!("✅ [Sync Job #{}] No new data to sync", sync_count);
+                    for (pair, result) in feed.sample_all().await {
+                        match result {
+                            Ok(price_data) => info!(
+                                "💹 [Price Job #{}] {} = ${:.4} ({} sources)",
+                                sample_count, pair, price_data.price, price_data.round_id
+                            ),
+                            Err(e) => {
+                                error!("❌ [Price Job #{}] {} failed: {}", sample_count, pair, e)
                             }
                         }
-                        Err(e) => {
-                            error!("❌ [Sync Job #{}] Failed: {}", sync_count, e);
-                        }
                     }
                 }
             });
-            info!("✅ Indexer sync job started (every {}s)", interval_secs);
+            info!("✅ Price sampling job started (every {}s)", interval_secs);
         } else {
-            warn!("⚠️  Indexer sync job is disabled");
+            warn!("⚠️  Price sampling job is disabled");
         }
 
-        if self.config.enable_yield_calc {
-            let interval_secs = self.config.yield_calc_interval_secs;
-            tokio::spawn(async move {
+        if self.config.enable_tx_confirmation {
+            let interval_secs = self.config.tx_confirm_interval_secs;
+            self.spawn_job(SchedulerJob::TxConfirmation, move |scheduler| async move {
+                let tx_service = ChainTransactionService::new(
+                    scheduler.pool.clone(),
+                    &scheduler.app_config.chain.node_url,
+                );
                 let mut interval = time::interval(Duration::from_secs(interval_secs));
-                let mut calc_count = 0u64;
+                let mut confirm_count = 0u64;
 
                 loop {
-                    interval.tick().await;
-                    calc_count += 1;
-                    info!("📊 [Yield Job #{}] Running yield calculation", calc_count);
+                    tokio::select! {
+                        _ = interval.tick() => {}
+                        _ = scheduler.shutdown.cancelled() => break,
+                    }
+                    confirm_count += 1;
 
-                    let yield_service = YieldService::new(yield_scheduler.pool.clone());
-                    match yield_service.calculate_all_market_yields().await {
-                        Ok(count) => {
+                    match tx_service.confirm_pending(TX_CONFIRM_BATCH_SIZE).await {
+                        Ok(summary) if summary.checked > 0 => {
                             info!(
-                                "✅ [Yield Job #{}] Calculated yields for {} markets",
-                                calc_count, count
+                                "🧾 [Tx Job #{}] Checked {}: {} committed, {} failed, {} expired, {} pending, {} errors",
+                                confirm_count,
+                                summary.checked,
+                                summary.committed,
+                                summary.failed,
+                                summary.expired,
+                                summary.still_pending,
+                                summary.errors
                             );
                         }
+                        Ok(_) => {}
                         Err(e) => {
-                            error!("❌ [Yield Job #{}] Failed: {}", calc_count, e);
+                            error!("❌ [Tx Job #{}] Failed: {}", confirm_count, e);
                         }
                     }
                 }
             });
             info!(
-                "✅ Yield calculation job started (every {}s)",
+                "✅ Transaction confirmation job started (every {}s)",
                 interval_secs
             );
         } else {
-            warn!("⚠️  Yield calculation job is disabled");
+            warn!("⚠️  Transaction confirmation job is disabled");
         }
 
         info!("✨ Scheduler started successfully - all background jobs running");
     }
 
-    pub async fn trigger_sync_now(&self) -> anyhow::Result<super::blockchain_sync::SyncSummary> {
+    async fn enqueue_due_jobs(&self, schedules: &[(JobKind, JobSchedule)]) {
+        match self.queue.enqueue_due(schedules).await {
+            Ok(kinds) => {
+                for kind in kinds {
+                    info!("🗓️  Enqueued scheduled {} job", kind);
+                }
+            }
+            Err(e) => error!("❌ Failed to enqueue scheduled jobs: {}", e),
+        }
+        match self.queue.requeue_stale().await {
+            Ok(0) => {}
+            Ok(n) => warn!("⚠️  Recovered {} jobs from stopped workers", n),
+            Err(e) => error!("❌ Failed to recover stale jobs: {}", e),
+        }
+    }
+
+    /// Claims and runs due jobs until the queue is empty, then waits for the next poll.
+    async fn run_worker(&self, worker_id: String) {
+        let poll_interval = Duration::from_secs(self.app_config.jobs.poll_interval_secs);
+
+        while !self.shutdown.is_cancelled() {
+            let job = match self.queue.claim(&worker_id).await {
+                Ok(Some(job)) => job,
+                Ok(None) => {
+                    self.sleep_unless_shutdown(poll_interval).await;
+                    continue;
+                }
+                Err(e) => {
+                    error!("❌ [Worker {}] Failed to claim a job: {}", worker_id, e);
+                    self.sleep_unless_shutdown(poll_interval).await;
+                    continue;
+                }
+            };
+
+            if !self
+                .supervise_job(&worker_id, &job, self.run_job(&job))
+                .await
+            {
+                break;
+            }
+        }
+    }
+
+    /// Drives a claimed job's `run` to the end, heartbeating while it runs, and records its
+    /// outcome. Returns false when shutdown interrupted the job.
+    ///
+    /// On shutdown a running job gets until shortly before the shutdown deadline to finish;
+    /// after that it is dropped, which rolls back its open transactions, and put back in the
+    /// queue for another worker.
+    async fn supervise_job<F>(&self, worker_id: &str, job: &Job, run: F) -> bool
+    where
+        F: Future<Output = anyhow::Result<Value>>,
+    {
+        // Heartbeat well inside the stale window so long jobs are not taken over.
+        let heartbeat_interval =
+            Duration::from_secs((self.app_config.jobs.stale_after_secs / 3).max(1));
+        let drain_timeout = Duration::from_secs(self.app_config.server.shutdown_timeout_secs)
+            .saturating_sub(JOB_RELEASE_MARGIN);
+
+        info!(
+            "⚙️  [Worker {}] Running {} job {} (attempt {}/{})",
+            worker_id, job.kind, job.id, job.attempts, job.max_attempts
+        );
+        let started = Instant::now();
+        let mut run = Box::pin(run);
+        let drain_deadline = time::sleep(drain_timeout);
+        tokio::pin!(drain_deadline);
+        let mut draining = false;
+        let outcome = loop {
+            tokio::select! {
+                outcome = &mut run => break Some(outcome),
+                _ = time::sleep(heartbeat_interval) => {
+                    if let Err(e) = self.queue.heartbeat(job).await {
+                        warn!("⚠️  [Worker {}] Job heartbeat failed: {}", worker_id, e);
+                    }
+                }
+                _ = self.shutdown.cancelled(), if !draining => {
+                    draining = true;
+                    drain_deadline.as_mut().reset(time::Instant::now() + drain_timeout);
+                    info!(
+                        "⏳ [Worker {}] Waiting up to {}s for {} job {} to finish",
+                        worker_id,
+                        drain_timeout.as_secs(),
+                        job.kind,
+                        job.id
+                    );
+                }
+                _ = &mut drain_deadline, if draining => break None,
+            }
+        };
+        drop(run);
+
+        let Some(outcome) = outcome else {
+            match self.queue.release(job).await {
+                Ok(true) => warn!(
+                    "⚠️  [Worker {}] {} job {} interrupted by shutdown; queued again",
+                    worker_id, job.kind, job.id
+                ),
+                Ok(false) => {}
+                Err(e) => error!(
+                    "❌ [Worker {}] Failed to requeue {} job {}: {}",
+                    worker_id, job.kind, job.id, e
+                ),
+            }
+            return false;
+        };
+
+        match outcome {
+            Ok(result) => match self.queue.complete(job, &result).await {
+                Ok(true) => info!(
+                    "✅ [Worker {}] {} job {} succeeded in {}ms",
+                    worker_id,
+                    job.kind,
+                    job.id,
+                    started.elapsed().as_millis()
+                ),
+                Ok(false) => warn!(
+                    "⚠️  [Worker {}] {} job {} was cancelled while running; result dropped",
+                    worker_id, job.kind, job.id
+                ),
+                Err(e) => error!("❌ [Worker {}] Failed to record success: {}", worker_id, e),
+            },
+            Err(job_error) => {
+                let message = format!("{:#}", job_error);
+                match self.queue.fail(job, &message).await {
+                    Ok(FailureOutcome::Retrying(delay)) => warn!(
+                        "⚠️  [Worker {}] {} job {} failed, retrying in {}s: {}",
+                        worker_id,
+                        job.kind,
+                        job.id,
+                        delay.as_secs(),
+                        message
+                    ),
+                    Ok(FailureOutcome::Failed) => error!(
+                        "❌ [Worker {}] {} job {} failed after {} attempts: {}",
+                        worker_id, job.kind, job.id, job.attempts, message
+                    ),
+                    Ok(FailureOutcome::Dropped) => warn!(
+                        "⚠️  [Worker {}] {} job {} was cancelled while running: {}",
+                        worker_id, job.kind, job.id, message
+                    ),
+                    Err(e) => {
+                        error!("❌ [Worker {}] Failed to record failure: {}", worker_id, e)
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    async fn sleep_unless_shutdown(&self, period: Duration) {
+        tokio::select! {
+            _ = time::sleep(period) => {}
+            _ = self.shutdown.cancelled() => {}
+        }
+    }
+
+    /// Runs one job and returns the summary stored as its result.
+    async fn run_job(&self, job: &Job) -> anyhow::Result<Value> {
+        let kind: JobKind = job.kind.parse()?;
+        match kind {
+            JobKind::IndexerSync => {
+                let summary = BlockchainSyncService::new(self.pool.clone())
+                    .run_full_sync()
+                    .await?;
+                for result in summary.results.iter().filter(|r| r.new_events > 0) {
+                    info!(
+                        "   └─ {}: {} new items",
+                        result.event_type, result.new_events
+                    );
+                }
+                Ok(serde_json::to_value(summary)?)
+            }
+            JobKind::YieldCalc => {
+                let count = YieldService::new(
+                    self.pool.clone(),
+                    &self.app_config.chain,
+                    &self.app_config.yield_allocation,
+                )
+                .calculate_all_market_yields()
+                .await?;
+                Ok(json!({ "marketsProcessed": count }))
+            }
+            JobKind::YieldAccrual => {
+                let service = YieldAccrualService::new(self.pool.clone(), &self.app_config);
+                match job.payload["marketId"].as_str() {
+                    Some(market_id) => Ok(serde_json::to_value(
+                        service.accrue_market(market_id).await?,
+                    )?),
+                    None => Ok(serde_json::to_value(service.accrue_all().await?)?),
+                }
+            }
+            JobKind::ApyRefresh => {
+                let updated = YieldService::new(
+                    self.pool.clone(),
+                    &self.app_config.chain,
+                    &self.app_config.yield_allocation,
+                )
+                .update_all_protocols_apy()
+                .await?;
+                Ok(json!(updated
+                    .iter()
+                    .map(|(protocol, apy)| json!({ "protocol": protocol, "apy": apy.to_string() }))
+                    .collect::<Vec<_>>()))
+            }
+            JobKind::Reconciliation => {
+                let repair = job.payload["repair"]
+                    .as_bool()
+                    .unwrap_or(self.config.reconciliation_auto_repair);
+                let report = ReconciliationService::new(self.pool.clone())
+                    .run(repair, Some(&job.id))
+                    .await?;
+                Ok(json!({
+                    "reportId": report.id,
+                    "marketsChecked": report.markets_checked,
+                    "discrepancyCount": report.discrepancy_count,
+                    "repairedCount": report.repaired_count
+                }))
+            }
+        }
+    }
+
+    /// Queues an indexer sync unless one is already queued or running, and returns that job.
+    pub async fn enqueue_sync_now(&self) -> anyhow::Result<(Job, bool)> {
         info!("🔄 Manual sync triggered");
-        let sync_service = BlockchainSyncService::new(self.pool.clone());
-        sync_service.run_full_sync().await
+        self.queue
+            .enqueue_unless_pending(JobKind::IndexerSync)
+            .await
+    }
+
+    /// Spawns a job loop, which must return once the shutdown token is cancelled. With leader
+    /// election on, the loop only runs while this instance holds the job's lock; otherwise
+    /// it runs unconditionally.
+    fn spawn_job<F, Fut>(self: &Arc<Self>, job: SchedulerJob, work: F)
+    where
+        F: Fn(Arc<Self>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let scheduler = Arc::clone(self);
+        self.tasks.spawn(async move {
+            if scheduler.config.enable_leader_election {
+                let election = scheduler.election.clone();
+                let shutdown = scheduler.shutdown.clone();
+                election
+                    .run(job, &shutdown, || work(Arc::clone(&scheduler)))
+                    .await;
+            } else {
+                work(scheduler).await;
+            }
+            info!("🛑 [{}] Stopped", job);
+        });
+    }
+
+    /// Waits for every background task to stop after the shutdown token is cancelled.
+    pub async fn stopped(&self) {
+        self.tasks.close();
+        self.tasks.wait().await;
     }
 
     pub fn get_status(&self) -> SchedulerStatus {
@@ -192,8 +525,31 @@ impl Scheduler {
             indexer_sync_interval_secs: self.config.indexer_sync_interval_secs,
             yield_calc_enabled: self.config.enable_yield_calc,
             yield_calc_interval_secs: self.config.yield_calc_interval_secs,
+            yield_accrual_enabled: self.config.enable_yield_accrual,
+            yield_accrual_interval_secs: self.config.yield_accrual_interval_secs,
+            apy_refresh_enabled: self.config.enable_apy_refresh,
+            apy_refresh_interval_secs: self.config.apy_refresh_interval_secs,
+            reconciliation_enabled: self.config.enable_reconciliation,
+            reconciliation_interval_secs: self.config.reconciliation_interval_secs,
+            reconciliation_auto_repair: self.config.reconciliation_auto_repair,
+            price_sampling_enabled: self.config.enable_price_sampling,
+            price_sample_interval_secs: self.config.price_sample_interval_secs,
+            tx_confirmation_enabled: self.config.enable_tx_confirmation,
+            tx_confirm_interval_secs: self.config.tx_confirm_interval_secs,
+            leader_election_enabled: self.config.enable_leader_election,
+            instance_id: self.election.instance_id().to_string(),
         }
     }
+
+    /// Which instance leads each job, from `scheduler_leaders`.
+    pub async fn get_leaders(&self) -> anyhow::Result<Vec<JobLeader>> {
+        self.election.leaders().await
+    }
+
+    /// Recurring job schedules with their pause state, from `job_schedules`.
+    pub async fn get_schedules(&self) -> anyhow::Result<Vec<ScheduledJob>> {
+        self.queue.schedules().await
+    }
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -202,4 +558,81 @@ pub struct SchedulerStatus {
     pub indexer_sync_interval_secs: u64,
     pub yield_calc_enabled: bool,
     pub yield_calc_interval_secs: u64,
+    pub yield_accrual_enabled: bool,
+    pub yield_accrual_interval_secs: u64,
+    pub apy_refresh_enabled: bool,
+    pub apy_refresh_interval_secs: u64,
+    pub reconciliation_enabled: bool,
+    pub reconciliation_interval_secs: u64,
+    pub reconciliation_auto_repair: bool,
+    pub price_sampling_enabled: bool,
+    pub price_sample_interval_secs: u64,
+    pub tx_confirmation_enabled: bool,
+    pub tx_confirm_interval_secs: u64,
+    pub leader_election_enabled: bool,
+    pub instance_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::job_queue::NewJob;
+
+    /// A scheduler whose workers get 1s to drain (3s shutdown timeout minus the release
+    /// margin), with shutdown already signalled, and a job it has claimed.
+    async fn shutting_down(pool: PgPool) -> (Scheduler, Job) {
+        let mut config = Config::default();
+        config.server.shutdown_timeout_secs = 3;
+        let scheduler = Scheduler::new(pool, Arc::new(config), CancellationToken::new());
+        scheduler
+            .queue
+            .enqueue(NewJob {
+                kind: JobKind::Reconciliation,
+                payload: None,
+                delay_secs: None,
+                max_attempts: None,
+            })
+            .await
+            .unwrap();
+        let job = scheduler.queue.claim("worker-1").await.unwrap().unwrap();
+        scheduler.shutdown.cancel();
+        (scheduler, job)
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_job_running_past_the_drain_deadline_is_released(pool: PgPool) {
+        let (scheduler, job) = shutting_down(pool).await;
+        assert_eq!(job.attempts, 1);
+
+        let run = async {
+            time::sleep(Duration::from_secs(30)).await;
+            Ok(json!({}))
+        };
+        assert!(!scheduler.supervise_job("worker-1", &job, run).await);
+
+        let released = scheduler.queue.get(&job.id).await.unwrap().unwrap();
+        assert_eq!(released.state, "queued");
+        assert_eq!(released.attempts, 0);
+        assert_eq!(
+            released.last_error.as_deref(),
+            Some("Interrupted by shutdown")
+        );
+        assert_eq!(released.locked_by, None);
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_job_finishing_inside_the_drain_deadline_is_completed(pool: PgPool) {
+        let (scheduler, job) = shutting_down(pool).await;
+
+        let run = async {
+            time::sleep(Duration::from_millis(100)).await;
+            Ok(json!({ "done": true }))
+        };
+        assert!(scheduler.supervise_job("worker-1", &job, run).await);
+
+        let finished = scheduler.queue.get(&job.id).await.unwrap().unwrap();
+        assert_eq!(finished.state, "succeeded");
+        assert_eq!(finished.attempts, 1);
+        assert_eq!(finished.result, Some(json!({ "done": true })));
+    }
 }
@@ -0,0 +1,928 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer as _, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sha3::{Digest, Sha3_256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+use crate::config::{ChainConfig, Config, SignerConfig};
+
+/// PBKDF2 rounds for new keystore files (written by `kizo-admin keystore new`).
+#[allow(dead_code)]
+pub const KEYSTORE_ITERATIONS: u32 = 600_000;
+const KEYSTORE_VERSION: u32 = 1;
+const KEYSTORE_CIPHER: &str = "aes-256-gcm";
+const KEYSTORE_KDF: &str = "pbkdf2-hmac-sha256";
+
+/// Domain separator Aptos prefixes to every raw transaction before signing.
+const RAW_TRANSACTION_SALT: &[u8] = b"APTOS::RawTransaction";
+/// Aptos authentication key scheme byte for single Ed25519 keys.
+const ED25519_SCHEME: u8 = 0x00;
+
+/// Where signing keys come from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerBackend {
+    /// Raw keys from `chain.private_key` / `chain.user_private_key`.
+    #[default]
+    Env,
+    /// Passphrase-encrypted keystore files, one account per file.
+    Keystore,
+    /// A separate signing process on a local Unix socket; keys never enter this process.
+    Socket,
+    /// Deterministic throwaway keys for tests and local development.
+    Test,
+}
+
+impl SignerBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignerBackend::Env => "env",
+            SignerBackend::Keystore => "keystore",
+            SignerBackend::Socket => "socket",
+            SignerBackend::Test => "test",
+        }
+    }
+}
+
+impl FromStr for SignerBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "env" => Ok(SignerBackend::Env),
+            "keystore" => Ok(SignerBackend::Keystore),
+            "socket" => Ok(SignerBackend::Socket),
+            "test" => Ok(SignerBackend::Test),
+            other => Err(anyhow!(
+                "unknown signer backend '{}' (expected env, keystore, socket or test)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SignerBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What an account signs. The `env` backend keeps `chain.private_key` for operator
+/// transactions and `chain.user_private_key` for bets and claims; accounts of the other
+/// backends sign both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerRole {
+    /// Market creation.
+    Admin,
+    /// Bets and claims.
+    User,
+}
+
+impl SignerRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignerRole::Admin => "admin",
+            SignerRole::User => "user",
+        }
+    }
+}
+
+impl fmt::Display for SignerRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Public side of a signing account.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignerAccount {
+    pub address: String,
+    pub public_key: String,
+    /// Where the key was loaded from, e.g. the keystore file.
+    pub source: String,
+}
+
+impl SignerAccount {
+    fn new(public_key: &VerifyingKey, source: String) -> Self {
+        Self {
+            address: account_address(public_key),
+            public_key: format!("0x{}", hex::encode(public_key.to_bytes())),
+            source,
+        }
+    }
+}
+
+/// An account that can sign transactions.
+#[async_trait]
+pub trait TransactionSigner: Send + Sync {
+    fn account(&self) -> &SignerAccount;
+
+    /// Ed25519 signature over `message`.
+    async fn sign(&self, message: &[u8]) -> Result<[u8; 64]>;
+}
+
+/// A key held in this process (env, keystore and test backends).
+struct LocalSigner {
+    account: SignerAccount,
+    key: SigningKey,
+}
+
+impl LocalSigner {
+    fn new(key: SigningKey, source: String) -> Self {
+        Self {
+            account: SignerAccount::new(&key.verifying_key(), source),
+            key,
+        }
+    }
+}
+
+#[async_trait]
+impl TransactionSigner for LocalSigner {
+    fn account(&self) -> &SignerAccount {
+        &self.account
+    }
+
+    async fn sign(&self, message: &[u8]) -> Result<[u8; 64]> {
+        Ok(self.key.sign(message).to_bytes())
+    }
+}
+
+/// An account whose key lives in an external signing process. Requests are one JSON object
+/// per line: `{"method":"accounts"}` answers `{"accounts":[{"address","publicKey"}]}` and
+/// `{"method":"sign","address","message"}` (hex) answers `{"signature"}` (hex) or `{"error"}`.
+struct SocketSigner {
+    account: SignerAccount,
+    public_key: VerifyingKey,
+    path: PathBuf,
+    timeout: Duration,
+}
+
+#[async_trait]
+impl TransactionSigner for SocketSigner {
+    fn account(&self) -> &SignerAccount {
+        &self.account
+    }
+
+    async fn sign(&self, message: &[u8]) -> Result<[u8; 64]> {
+        let response = socket_request(
+            &self.path,
+            self.timeout,
+            &json!({
+                "method": "sign",
+                "address": self.account.address,
+                "message": format!("0x{}", hex::encode(message)),
+            }),
+        )
+        .await?;
+
+        let signature: [u8; 64] = decode_hex(
+            response["signature"]
+                .as_str()
+                .ok_or_else(|| anyhow!("Signer response has no signature"))?,
+        )?
+        .try_into()
+        .map_err(|_| anyhow!("Signer returned a signature that is not 64 bytes"))?;
+
+        // The socket is trusted, but a wrong key or a buggy signer should fail here rather
+        // than on chain.
+        self.public_key
+            .verify(message, &Signature::from_bytes(&signature))
+            .map_err(|_| {
+                anyhow!(
+                    "Signer returned an invalid signature for {}",
+                    self.account.address
+                )
+            })?;
+
+        Ok(signature)
+    }
+}
+
+async fn socket_request(path: &Path, timeout: Duration, request: &Value) -> Result<Value> {
+    let exchange = async {
+        let mut stream = UnixStream::connect(path)
+            .await
+            .with_context(|| format!("Failed to connect to signer at {}", path.display()))?;
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        stream.write_all(&line).await?;
+
+        let mut reader = BufReader::new(stream);
+        let mut response = String::new();
+        reader.read_line(&mut response).await?;
+        let response: Value = serde_json::from_str(&response)
+            .map_err(|e| anyhow!("Invalid response from signer: {}", e))?;
+        if let Some(error) = response["error"].as_str() {
+            return Err(anyhow!("Signer refused request: {}", error));
+        }
+        Ok(response)
+    };
+
+    tokio::time::timeout(timeout, exchange)
+        .await
+        .map_err(|_| anyhow!("Signer at {} timed out", path.display()))?
+}
+
+/// Aptos account address of a single-key Ed25519 account: SHA3-256 of the public key
+/// followed by the scheme byte.
+pub fn account_address(public_key: &VerifyingKey) -> String {
+    let mut hasher = Sha3_256::new();
+    hasher.update(public_key.as_bytes());
+    hasher.update([ED25519_SCHEME]);
+    format!("0x{}", hex::encode(hasher.finalize()))
+}
+
+/// Bytes signed for a transaction: the Aptos raw-transaction domain separator followed by
+/// the encoded transaction.
+pub fn signing_message(transaction: &[u8]) -> Vec<u8> {
+    let mut message = Sha3_256::digest(RAW_TRANSACTION_SALT).to_vec();
+    message.extend_from_slice(transaction);
+    message
+}
+
+/// Parses an Ed25519 private key as hex, with or without `0x` or the `ed25519-priv-` prefix.
+pub fn parse_private_key(value: &str) -> Result<SigningKey> {
+    let value = value.trim();
+    let value = value.strip_prefix("ed25519-priv-").unwrap_or(value);
+    let bytes = Zeroizing::new(decode_hex(value)?);
+    let bytes: &[u8; 32] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow!("Ed25519 private key must be 32 bytes"))?;
+    Ok(SigningKey::from_bytes(bytes))
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .map_err(|e| anyhow!("Invalid hex: {}", e))
+}
+
+/// On-disk keystore: one Ed25519 key encrypted with AES-256-GCM under a key derived from a
+/// passphrase with PBKDF2-HMAC-SHA256.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeystoreFile {
+    pub version: u32,
+    pub address: String,
+    pub public_key: String,
+    pub crypto: KeystoreCrypto,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeystoreCrypto {
+    pub cipher: String,
+    pub kdf: String,
+    pub iterations: u32,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Zeroizing<[u8; 32]> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, key.as_mut());
+    key
+}
+
+impl KeystoreFile {
+    /// Encrypts `key` under `passphrase`.
+    #[allow(dead_code)]
+    pub fn encrypt(key: &SigningKey, passphrase: &str, iterations: u32) -> Result<Self> {
+        let salt: [u8; 16] = rand::random();
+        let nonce: [u8; 12] = rand::random();
+        let cipher = Aes256Gcm::new_from_slice(derive_key(passphrase, &salt, iterations).as_ref())
+            .map_err(|e| anyhow!("Invalid keystore key: {}", e))?;
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), key.to_bytes().as_ref())
+            .map_err(|_| anyhow!("Failed to encrypt key"))?;
+        let account = SignerAccount::new(&key.verifying_key(), String::new());
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            address: account.address,
+            public_key: account.public_key,
+            crypto: KeystoreCrypto {
+                cipher: KEYSTORE_CIPHER.to_string(),
+                kdf: KEYSTORE_KDF.to_string(),
+                iterations,
+                salt: hex::encode(salt),
+                nonce: hex::encode(nonce),
+                ciphertext: hex::encode(ciphertext),
+            },
+        })
+    }
+
+    /// Decrypts the key; a wrong passphrase fails the GCM tag check.
+    pub fn decrypt(&self, passphrase: &str) -> Result<SigningKey> {
+        if self.version != KEYSTORE_VERSION
+            || self.crypto.cipher != KEYSTORE_CIPHER
+            || self.crypto.kdf != KEYSTORE_KDF
+        {
+            return Err(anyhow!(
+                "Unsupported keystore (version {}, {}, {})",
+                self.version,
+                self.crypto.cipher,
+                self.crypto.kdf
+            ));
+        }
+
+        let salt = decode_hex(&self.crypto.salt)?;
+        let nonce = decode_hex(&self.crypto.nonce)?;
+        if nonce.len() != 12 {
+            return Err(anyhow!("Keystore nonce must be 12 bytes"));
+        }
+        let cipher = Aes256Gcm::new_from_slice(
+            derive_key(passphrase, &salt, self.crypto.iterations).as_ref(),
+        )
+        .map_err(|e| anyhow!("Invalid keystore key: {}", e))?;
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(
+                    Nonce::from_slice(&nonce),
+                    decode_hex(&self.crypto.ciphertext)?.as_ref(),
+                )
+                .map_err(|_| anyhow!("Wrong passphrase or corrupted keystore"))?,
+        );
+        let bytes: &[u8; 32] = plaintext
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow!("Keystore key must be 32 bytes"))?;
+        let key = SigningKey::from_bytes(bytes);
+
+        if account_address(&key.verifying_key()) != self.address {
+            return Err(anyhow!(
+                "Keystore key does not match its address {}",
+                self.address
+            ));
+        }
+        Ok(key)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read keystore {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid keystore {}", path.display()))
+    }
+}
+
+/// Deterministic key `index` of the test backend. Never use outside tests and development.
+pub fn test_signing_key(index: usize) -> SigningKey {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(b"kizo-test-signer");
+    hasher.update((index as u64).to_be_bytes());
+    SigningKey::from_bytes(&hasher.finalize().into())
+}
+
+/// A loaded account and the role it is limited to (`None` signs for every role).
+type RoleSigner = (Arc<dyn TransactionSigner>, Option<SignerRole>);
+
+/// Loads every account of the configured backend.
+async fn load_signers(signer: &SignerConfig, chain: &ChainConfig) -> Result<Vec<RoleSigner>> {
+    let mut signers: Vec<Arc<dyn TransactionSigner>> = Vec::new();
+
+    match signer.backend {
+        SignerBackend::Env => {
+            let keys = [
+                (
+                    "chain.private_key",
+                    chain.private_key.as_deref(),
+                    SignerRole::Admin,
+                ),
+                (
+                    "chain.user_private_key",
+                    chain.user_private_key.as_deref(),
+                    SignerRole::User,
+                ),
+            ];
+            let mut role_signers: Vec<RoleSigner> = Vec::new();
+            for (name, key, role) in keys {
+                if let Some(key) = key {
+                    let key =
+                        parse_private_key(key).with_context(|| format!("Invalid {}", name))?;
+                    let signer = LocalSigner::new(key, name.to_string());
+                    // One key configured for both roles is a single account signing both.
+                    match role_signers
+                        .iter_mut()
+                        .find(|(s, _)| s.account().address == signer.account.address)
+                    {
+                        Some(existing) => existing.1 = None,
+                        None => role_signers.push((Arc::new(signer), Some(role))),
+                    }
+                }
+            }
+            return Ok(role_signers);
+        }
+        SignerBackend::Keystore => {
+            let passphrase = signer.keystore_passphrase.as_deref().ok_or_else(|| {
+                anyhow!("signer.keystore_passphrase (KEYSTORE_PASSPHRASE) is required")
+            })?;
+            for path in &signer.keystore_paths {
+                let path = Path::new(path);
+                let key = KeystoreFile::read(path)?
+                    .decrypt(passphrase)
+                    .with_context(|| format!("Failed to unlock keystore {}", path.display()))?;
+                signers.push(Arc::new(LocalSigner::new(key, path.display().to_string())));
+            }
+        }
+        SignerBackend::Socket => {
+            let path =
+                PathBuf::from(signer.socket_path.as_deref().ok_or_else(|| {
+                    anyhow!("signer.socket_path (SIGNER_SOCKET_PATH) is required")
+                })?);
+            let timeout = Duration::from_secs(signer.socket_timeout_secs);
+            let response = socket_request(&path, timeout, &json!({ "method": "accounts" })).await?;
+            let accounts = response["accounts"]
+                .as_array()
+                .ok_or_else(|| anyhow!("Signer response has no accounts"))?;
+            for entry in accounts {
+                let public_key: [u8; 32] =
+                    decode_hex(entry["publicKey"].as_str().unwrap_or_default())?
+                        .try_into()
+                        .map_err(|_| anyhow!("Signer public key must be 32 bytes"))?;
+                let public_key = VerifyingKey::from_bytes(&public_key)
+                    .map_err(|e| anyhow!("Invalid signer public key: {}", e))?;
+                let account = SignerAccount::new(&public_key, format!("socket:{}", path.display()));
+                if let Some(address) = entry["address"].as_str() {
+                    if address != account.address {
+                        return Err(anyhow!(
+                            "Signer account {} does not match its public key",
+                            address
+                        ));
+                    }
+                }
+                signers.push(Arc::new(SocketSigner {
+                    account,
+                    public_key,
+                    path: path.clone(),
+                    timeout,
+                }));
+            }
+        }
+        SignerBackend::Test => {
+            for index in 0..signer.test_accounts {
+                signers.push(Arc::new(LocalSigner::new(
+                    test_signing_key(index),
+                    format!("test:{}", index),
+                )));
+            }
+        }
+    }
+
+    // An account listed twice would hand out its sequence numbers twice.
+    let mut seen = std::collections::HashSet::new();
+    signers.retain(|s| seen.insert(s.account().address.clone()));
+
+    Ok(signers.into_iter().map(|s| (s, None)).collect())
+}
+
+struct PoolEntry {
+    signer: Arc<dyn TransactionSigner>,
+    role: Option<SignerRole>,
+    sequence: Arc<AccountSequence>,
+    signed: AtomicU64,
+}
+
+/// An account's sequence numbers, shared by its entries across reloads.
+#[derive(Default)]
+struct AccountSequence {
+    /// Sequence number of the account's next transaction.
+    next: AtomicU64,
+    /// Held while the account signs, so a number is only used up once its transaction is
+    /// signed and a failed signature leaves no gap.
+    signing: tokio::sync::Mutex<()>,
+}
+
+impl PoolEntry {
+    fn signs_for(&self, role: SignerRole) -> bool {
+        self.role.is_none() || self.role == Some(role)
+    }
+}
+
+/// What an account signs for a transaction: the sender, its sequence number and an
+/// expiry around the payload, as in an Aptos raw transaction.
+#[derive(Serialize)]
+struct RawTransaction<'a> {
+    sender: &'a str,
+    sequence_number: String,
+    expiration_timestamp_secs: String,
+    payload: &'a Value,
+}
+
+/// A payload signed by one of the pool's accounts.
+#[derive(Debug, Clone)]
+pub struct SignedTransaction {
+    pub account: SignerAccount,
+    pub sequence_number: u64,
+    /// The signed raw transaction bytes.
+    pub raw: Vec<u8>,
+    pub signature: [u8; 64],
+}
+
+impl SignedTransaction {
+    pub fn hash(&self) -> String {
+        transaction_hash(&self.raw, &self.signature)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignerPoolStatus {
+    pub backend: SignerBackend,
+    pub loaded_at: DateTime<Utc>,
+    pub accounts: Vec<SignerAccountStatus>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignerAccountStatus {
+    #[serde(flatten)]
+    pub account: SignerAccount,
+    /// Role the account is limited to; `None` signs for every role.
+    pub role: Option<SignerRole>,
+    /// Transactions signed since the account was loaded.
+    pub signed: u64,
+    pub next_sequence_number: u64,
+}
+
+/// The operator accounts transactions are signed with. Each role's accounts are used
+/// round-robin so submissions spread over several accounts. `reload` swaps in a fresh set
+/// of keys (rotation) without a restart; signing already in flight finishes with the key it
+/// started with.
+///
+/// The signer and chain settings are the ones the pool was loaded with: a reload re-reads
+/// keystore files and asks the signing process again, but keys of the `env` backend
+/// (`chain.private_key` / `chain.user_private_key`) only change with a restart.
+pub struct SignerPool {
+    signer: SignerConfig,
+    chain: ChainConfig,
+    entries: RwLock<Arc<Vec<PoolEntry>>>,
+    loaded_at: RwLock<DateTime<Utc>>,
+    next_admin: AtomicUsize,
+    next_user: AtomicUsize,
+}
+
+impl SignerPool {
+    pub async fn load(config: &Config) -> Result<Self> {
+        let pool = Self {
+            signer: config.signer.clone(),
+            chain: config.chain.clone(),
+            entries: RwLock::new(Arc::new(Vec::new())),
+            loaded_at: RwLock::new(Utc::now()),
+            next_admin: AtomicUsize::new(0),
+            next_user: AtomicUsize::new(0),
+        };
+        let accounts = pool.reload().await?;
+        if accounts.is_empty() {
+            warn!(
+                "No signing accounts loaded ({} backend); transactions cannot be submitted",
+                pool.signer.backend
+            );
+        }
+        Ok(pool)
+    }
+
+    /// Loads the backend's accounts again and replaces the current ones. Accounts that stay
+    /// keep their sequence numbers. On error the current accounts stay in use. The `env`
+    /// backend reloads the keys the server started with.
+    pub async fn reload(&self) -> Result<Vec<SignerAccount>> {
+        let signers = load_signers(&self.signer, &self.chain).await?;
+        let accounts: Vec<SignerAccount> =
+            signers.iter().map(|(s, _)| s.account().clone()).collect();
+
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        let reloaded = signers
+            .into_iter()
+            .map(|(signer, role)| {
+                let sequence = entries
+                    .iter()
+                    .find(|e| e.signer.account().address == signer.account().address)
+                    .map(|e| e.sequence.clone())
+                    .unwrap_or_default();
+                PoolEntry {
+                    signer,
+                    role,
+                    sequence,
+                    signed: AtomicU64::new(0),
+                }
+            })
+            .collect();
+        *entries = Arc::new(reloaded);
+        drop(entries);
+        *self.loaded_at.write().unwrap_or_else(|e| e.into_inner()) = Utc::now();
+
+        info!(
+            "🔑 Loaded {} signing account(s) from {} backend: {}",
+            accounts.len(),
+            self.signer.backend,
+            accounts
+                .iter()
+                .map(|a| a.address.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        Ok(accounts)
+    }
+
+    /// Signs `payload` with the next account in rotation for `role`, under that account's
+    /// next sequence number. Signatures by one account are made one at a time; the number is
+    /// only taken when signing succeeds.
+    pub async fn sign_transaction(
+        &self,
+        role: SignerRole,
+        payload: &Value,
+    ) -> Result<SignedTransaction> {
+        let entries = self
+            .entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let candidates: Vec<&PoolEntry> = entries.iter().filter(|e| e.signs_for(role)).collect();
+        if candidates.is_empty() {
+            let key = match role {
+                SignerRole::Admin => "APTOS_PRIVATE_KEY",
+                SignerRole::User => "USER_PRIVATE_KEY",
+            };
+            return Err(anyhow!(
+                "No {} signing account configured (set {} or configure [signer])",
+                role,
+                key
+            ));
+        }
+        let next = match role {
+            SignerRole::Admin => &self.next_admin,
+            SignerRole::User => &self.next_user,
+        };
+        let entry = candidates[next.fetch_add(1, Ordering::Relaxed) % candidates.len()];
+
+        let account = entry.signer.account().clone();
+        let _signing = entry.sequence.signing.lock().await;
+        let sequence_number = entry.sequence.next.load(Ordering::Relaxed);
+        let expires_at = Utc::now().timestamp() + self.chain.tx_expiration_secs as i64;
+        let raw = serde_json::to_vec(&RawTransaction {
+            sender: &account.address,
+            sequence_number: sequence_number.to_string(),
+            expiration_timestamp_secs: expires_at.to_string(),
+            payload,
+        })?;
+
+        let signature = entry.signer.sign(&signing_message(&raw)).await?;
+        entry
+            .sequence
+            .next
+            .store(sequence_number + 1, Ordering::Relaxed);
+        entry.signed.fetch_add(1, Ordering::Relaxed);
+        Ok(SignedTransaction {
+            account,
+            sequence_number,
+            raw,
+            signature,
+        })
+    }
+
+    pub fn status(&self) -> SignerPoolStatus {
+        let entries = self
+            .entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        SignerPoolStatus {
+            backend: self.signer.backend,
+            loaded_at: *self.loaded_at.read().unwrap_or_else(|e| e.into_inner()),
+            accounts: entries
+                .iter()
+                .map(|e| SignerAccountStatus {
+                    account: e.signer.account().clone(),
+                    role: e.role,
+                    signed: e.signed.load(Ordering::Relaxed),
+                    next_sequence_number: e.sequence.next.load(Ordering::Relaxed),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Placeholder transaction hash derived from the signed bytes, used until submission goes
+/// through the node. Ed25519 signatures are deterministic, so it is only distinct because the
+/// raw transaction carries the sender's sequence number and expiry.
+fn transaction_hash(transaction: &[u8], signature: &[u8; 64]) -> String {
+    let mut hasher = Sha3_256::new();
+    hasher.update(transaction);
+    hasher.update(signature);
+    format!("0x{}", hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(accounts: usize) -> Config {
+        Config {
+            signer: SignerConfig {
+                backend: SignerBackend::Test,
+                test_accounts: accounts,
+                ..SignerConfig::default()
+            },
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_keystore_round_trip() {
+        let key = test_signing_key(7);
+        let keystore = KeystoreFile::encrypt(&key, "correct horse", 1_000).unwrap();
+
+        assert_eq!(keystore.address, account_address(&key.verifying_key()));
+        assert!(!keystore
+            .crypto
+            .ciphertext
+            .contains(&hex::encode(key.to_bytes())));
+        assert_eq!(
+            keystore.decrypt("correct horse").unwrap().to_bytes(),
+            key.to_bytes()
+        );
+        assert!(keystore.decrypt("wrong horse").is_err());
+
+        let mut tampered = keystore.clone();
+        tampered.address = account_address(&test_signing_key(8).verifying_key());
+        assert!(tampered.decrypt("correct horse").is_err());
+    }
+
+    #[test]
+    fn test_parse_private_key_accepts_prefixes() {
+        let key = test_signing_key(1);
+        let hex_key = hex::encode(key.to_bytes());
+
+        for value in [
+            hex_key.clone(),
+            format!("0x{}", hex_key),
+            format!("ed25519-priv-0x{}", hex_key),
+        ] {
+            assert_eq!(
+                parse_private_key(&value).unwrap().to_bytes(),
+                key.to_bytes()
+            );
+        }
+        assert!(parse_private_key("0xabc").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pool_signs_round_robin() {
+        let pool = SignerPool::load(&test_config(3)).await.unwrap();
+        let payload = json!({ "function": "0x1::kizo::place_bet" });
+        let mut senders = Vec::new();
+        for _ in 0..6 {
+            let signed = pool
+                .sign_transaction(SignerRole::User, &payload)
+                .await
+                .unwrap();
+            let public_key: [u8; 32] = decode_hex(&signed.account.public_key)
+                .unwrap()
+                .try_into()
+                .unwrap();
+            VerifyingKey::from_bytes(&public_key)
+                .unwrap()
+                .verify(
+                    &signing_message(&signed.raw),
+                    &Signature::from_bytes(&signed.signature),
+                )
+                .unwrap();
+            senders.push(signed.account.address);
+        }
+
+        assert_eq!(senders[0..3], senders[3..6]);
+        assert_ne!(senders[0], senders[1]);
+        assert_ne!(senders[1], senders[2]);
+        assert!(pool.status().accounts.iter().all(|a| a.signed == 2));
+    }
+
+    #[tokio::test]
+    async fn test_identical_payloads_get_distinct_hashes() {
+        let pool = SignerPool::load(&test_config(1)).await.unwrap();
+        let payload = json!({ "function": "0x1::kizo::place_bet", "arguments": ["1", true] });
+
+        let first = pool
+            .sign_transaction(SignerRole::User, &payload)
+            .await
+            .unwrap();
+        let second = pool
+            .sign_transaction(SignerRole::User, &payload)
+            .await
+            .unwrap();
+
+        assert_eq!(first.account, second.account);
+        assert_eq!((first.sequence_number, second.sequence_number), (0, 1));
+        assert_ne!(first.hash(), second.hash());
+
+        pool.reload().await.unwrap();
+        let third = pool
+            .sign_transaction(SignerRole::User, &payload)
+            .await
+            .unwrap();
+        assert_eq!(third.sequence_number, 2);
+    }
+
+    /// Fails its first signature, e.g. a signing process that timed out.
+    struct FlakySigner {
+        inner: LocalSigner,
+        failed: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait]
+    impl TransactionSigner for FlakySigner {
+        fn account(&self) -> &SignerAccount {
+            self.inner.account()
+        }
+
+        async fn sign(&self, message: &[u8]) -> Result<[u8; 64]> {
+            if !self.failed.swap(true, Ordering::Relaxed) {
+                return Err(anyhow!("signer timed out"));
+            }
+            self.inner.sign(message).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_signature_does_not_use_up_a_sequence_number() {
+        let pool = SignerPool::load(&test_config(0)).await.unwrap();
+        *pool.entries.write().unwrap() = Arc::new(vec![PoolEntry {
+            signer: Arc::new(FlakySigner {
+                inner: LocalSigner::new(test_signing_key(0), "test:0".to_string()),
+                failed: Default::default(),
+            }),
+            role: None,
+            sequence: Default::default(),
+            signed: AtomicU64::new(0),
+        }]);
+        let payload = json!({ "function": "0x1::kizo::place_bet" });
+
+        assert!(pool
+            .sign_transaction(SignerRole::User, &payload)
+            .await
+            .is_err());
+        let signed = pool
+            .sign_transaction(SignerRole::User, &payload)
+            .await
+            .unwrap();
+        assert_eq!(signed.sequence_number, 0);
+        assert_eq!(pool.status().accounts[0].next_sequence_number, 1);
+    }
+
+    #[tokio::test]
+    async fn test_env_backend_keeps_roles_apart() {
+        let mut config = Config::default();
+        config.chain.private_key = Some(hex::encode(test_signing_key(1).to_bytes()));
+        config.chain.user_private_key = Some(hex::encode(test_signing_key(2).to_bytes()));
+        let admin = account_address(&test_signing_key(1).verifying_key());
+        let user = account_address(&test_signing_key(2).verifying_key());
+
+        let pool = SignerPool::load(&config).await.unwrap();
+        let payload = json!({});
+        for _ in 0..3 {
+            let signed = pool
+                .sign_transaction(SignerRole::Admin, &payload)
+                .await
+                .unwrap();
+            assert_eq!(signed.account.address, admin);
+            let signed = pool
+                .sign_transaction(SignerRole::User, &payload)
+                .await
+                .unwrap();
+            assert_eq!(signed.account.address, user);
+        }
+
+        config.chain.user_private_key = None;
+        let pool = SignerPool::load(&config).await.unwrap();
+        assert!(pool
+            .sign_transaction(SignerRole::User, &payload)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_empty_pool_refuses_to_sign() {
+        let pool = SignerPool::load(&Config::default()).await.unwrap();
+        assert!(pool
+            .sign_transaction(SignerRole::Admin, &json!({}))
+            .await
+            .is_err());
+    }
+}
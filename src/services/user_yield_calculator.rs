@@ -3,8 +3,12 @@ use bigdecimal::{BigDecimal, ToPrimitive};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
 use tracing::{debug, info};
 
+use super::yield_allocation::YieldAllocator;
+use crate::config::YieldAllocationConfig;
+
 const OCTAS_PER_APT: f64 = 100_000_000.0;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,231 +28,234 @@ pub struct ProtocolYieldBreakdown {
     pub average_apy: f64,
 }
 
-#[derive(Debug)]
-struct ActiveBet {
-    amount: i64,
-    created_at: NaiveDateTime,
+/// A bet's share of its market's accrual ledger.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BetYield {
+    pub bet_id: i64,
+    pub market_id: Option<String>,
+    pub blockchain_market_id: Option<i64>,
+    pub amount: Option<BigDecimal>,
+    pub status: String,
+    pub yield_earned: BigDecimal,
+    pub yield_attributed_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug)]
-struct Protocol {
-    name: String,
-    base_apy: BigDecimal,
+struct ActiveBet {
+    amount: i64,
+    /// Share of the market's accrual ledger attributed to this bet, in octas.
+    yield_earned: f64,
+    /// `markets_extended.id`, when the market has been synced.
+    market_id: Option<String>,
 }
 
+/// Yield earned by unclaimed bets, as attributed from each market's accrual ledger.
 pub struct UserYieldCalculator {
     pool: PgPool,
+    allocator: YieldAllocator,
 }
 
 impl UserYieldCalculator {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, allocation: &YieldAllocationConfig) -> Self {
+        Self {
+            allocator: YieldAllocator::new(pool.clone(), allocation),
+            pool,
+        }
     }
 
     pub async fn calculate_user_yields(&self, user_address: &str) -> Result<UserYieldSummary> {
         info!("Calculating yields for user: {}", user_address);
 
-        let protocols = self.fetch_active_protocols().await?;
-
-        if protocols.is_empty() {
-            debug!("No active protocols found, returning zero yields");
-            return Ok(UserYieldSummary {
-                total_yield_earned: 0.0,
-                total_amount_staked: 0.0,
-                average_apy: 0.0,
-                active_pool_size: 0.0,
-                protocol_breakdown: Vec::new(),
-            });
-        }
+        let active_bets = self.fetch_user_active_bets(user_address).await?;
+        self.summarize(&active_bets).await
+    }
 
-        let best_protocol = protocols
-            .iter()
-            .max_by(|a, b| {
-                let apy_a = a.base_apy.to_f64().unwrap_or(0.0);
-                let apy_b = b.base_apy.to_f64().unwrap_or(0.0);
-                apy_a.partial_cmp(&apy_b).unwrap()
-            })
-            .unwrap();
+    pub async fn calculate_global_yields(&self) -> Result<UserYieldSummary> {
+        info!("Calculating global yields for all users");
 
-        let best_apy = best_protocol.base_apy.to_f64().unwrap_or(0.0);
+        let active_bets = self.fetch_all_active_bets().await?;
+        self.summarize(&active_bets).await
+    }
 
-        debug!(
-            "Using protocol '{}' with APY: {}",
-            best_protocol.name, best_apy
-        );
+    async fn summarize(&self, active_bets: &[ActiveBet]) -> Result<UserYieldSummary> {
+        let current_plan = self.allocator.current_plan().await?;
 
-        let active_bets = self.fetch_user_active_bets(user_address).await?;
+        let mut market_ids: Vec<String> = active_bets
+            .iter()
+            .filter_map(|bet| bet.market_id.clone())
+            .collect();
+        market_ids.sort();
+        market_ids.dedup();
+        let stored_plans = self.allocator.stored_plans(&market_ids).await?;
 
-        if active_bets.is_empty() {
-            debug!("No active bets found for user");
-            return Ok(self.create_empty_summary_with_protocols(&protocols));
-        }
+        // Every active protocol is listed, even when nothing is allocated to it.
+        let mut breakdown: BTreeMap<String, ProtocolYieldBreakdown> = current_plan
+            .slices
+            .iter()
+            .map(|slice| {
+                (
+                    slice.protocol.clone(),
+                    ProtocolYieldBreakdown {
+                        protocol: slice.protocol.clone(),
+                        total_amount: 0.0,
+                        total_yield: 0.0,
+                        average_apy: slice.apy,
+                    },
+                )
+            })
+            .collect();
+
+        let ledger_shares = self.ledger_protocol_shares(&market_ids).await?;
 
-        let now = chrono::Utc::now().naive_utc();
         let mut total_yield = 0.0;
         let mut total_amount = 0.0;
+        let mut weighted_apy = 0.0;
+
+        for bet in active_bets {
+            let plan = bet
+                .market_id
+                .as_ref()
+                .and_then(|id| stored_plans.get(id))
+                .unwrap_or(&current_plan);
 
-        for bet in &active_bets {
             let amount = bet.amount as f64 / OCTAS_PER_APT;
+            let bet_yield = bet.yield_earned / OCTAS_PER_APT;
             total_amount += amount;
+            total_yield += bet_yield;
+            weighted_apy += amount * plan.blended_apy();
+
+            for slice in &plan.slices {
+                let entry = breakdown.entry(slice.protocol.clone()).or_insert_with(|| {
+                    ProtocolYieldBreakdown {
+                        protocol: slice.protocol.clone(),
+                        total_amount: 0.0,
+                        total_yield: 0.0,
+                        average_apy: slice.apy,
+                    }
+                });
+                entry.total_amount += amount * slice.weight;
+            }
 
-            let elapsed_days = (now - bet.created_at).num_days() as f64;
-            let elapsed_hours = (now - bet.created_at).num_hours() as f64;
-
-            let time_factor = if elapsed_days < 1.0 {
-                elapsed_hours / 24.0
-            } else {
-                elapsed_days
-            };
-
-            let yield_amount = amount * (best_apy / 100.0) * (time_factor / 365.0);
-            total_yield += yield_amount;
+            // The bet's yield is split by where its market actually earned it.
+            if let Some(shares) = bet.market_id.as_ref().and_then(|id| ledger_shares.get(id)) {
+                for (protocol, share) in shares {
+                    let entry = breakdown.entry(protocol.clone()).or_insert_with(|| {
+                        ProtocolYieldBreakdown {
+                            protocol: protocol.clone(),
+                            total_amount: 0.0,
+                            total_yield: 0.0,
+                            average_apy: 0.0,
+                        }
+                    });
+                    entry.total_yield += bet_yield * share;
+                }
+            }
 
             debug!(
-                "Bet amount: {}, elapsed days: {:.2}, yield: {:.4}",
-                amount, time_factor, yield_amount
+                "Bet amount: {}, attributed yield: {:.8}, blended APY: {:.4}",
+                amount,
+                bet_yield,
+                plan.blended_apy()
             );
         }
 
-        let mut protocol_breakdown = Vec::new();
-        for protocol in &protocols {
-            let protocol_apy = protocol.base_apy.to_f64().unwrap_or(0.0);
-
-            if protocol.name == best_protocol.name {
-                protocol_breakdown.push(ProtocolYieldBreakdown {
-                    protocol: protocol.name.clone(),
-                    total_amount,
-                    total_yield,
-                    average_apy: protocol_apy,
-                });
-            } else {
-                protocol_breakdown.push(ProtocolYieldBreakdown {
-                    protocol: protocol.name.clone(),
-                    total_amount: 0.0,
-                    total_yield: 0.0,
-                    average_apy: protocol_apy,
-                });
-            }
-        }
+        let average_apy = if total_amount > 0.0 {
+            weighted_apy / total_amount
+        } else {
+            0.0
+        };
 
         info!(
             "Calculated yields - Total: {:.4}, Amount: {:.4}, APY: {:.2}",
-            total_yield, total_amount, best_apy
+            total_yield, total_amount, average_apy
         );
 
         Ok(UserYieldSummary {
             total_yield_earned: total_yield,
             total_amount_staked: total_amount,
-            average_apy: best_apy,
+            average_apy,
             active_pool_size: total_amount,
-            protocol_breakdown,
+            protocol_breakdown: breakdown.into_values().collect(),
         })
     }
 
-    pub async fn calculate_global_yields(&self) -> Result<UserYieldSummary> {
-        info!("Calculating global yields for all users");
-
-        let protocols = self.fetch_active_protocols().await?;
-
-        if protocols.is_empty() {
-            return Ok(UserYieldSummary {
-                total_yield_earned: 0.0,
-                total_amount_staked: 0.0,
-                average_apy: 0.0,
-                active_pool_size: 0.0,
-                protocol_breakdown: Vec::new(),
-            });
-        }
-
-        let best_protocol = protocols
-            .iter()
-            .max_by(|a, b| {
-                let apy_a = a.base_apy.to_f64().unwrap_or(0.0);
-                let apy_b = b.base_apy.to_f64().unwrap_or(0.0);
-                apy_a.partial_cmp(&apy_b).unwrap()
-            })
-            .unwrap();
-
-        let best_apy = best_protocol.base_apy.to_f64().unwrap_or(0.0);
-
-        let active_bets = self.fetch_all_active_bets().await?;
-
-        if active_bets.is_empty() {
-            return Ok(self.create_empty_summary_with_protocols(&protocols));
-        }
-
-        let now = chrono::Utc::now().naive_utc();
-        let mut total_yield = 0.0;
-        let mut total_amount = 0.0;
-
-        for bet in &active_bets {
-            let amount = bet.amount as f64 / OCTAS_PER_APT;
-            total_amount += amount;
-
-            let elapsed_days = (now - bet.created_at).num_days() as f64;
-            let elapsed_hours = (now - bet.created_at).num_hours() as f64;
-
-            let time_factor = if elapsed_days < 1.0 {
-                elapsed_hours / 24.0
-            } else {
-                elapsed_days
-            };
-
-            let yield_amount = amount * (best_apy / 100.0) * (time_factor / 365.0);
-            total_yield += yield_amount;
-        }
+    /// Attributed yield of one bet, by blockchain bet id.
+    pub async fn bet_yield(&self, bet_id: i64) -> Result<Option<BetYield>> {
+        let bet = sqlx::query_as!(
+            BetYield,
+            r#"
+            SELECT be."blockchainBetId" as bet_id, be."marketId" as market_id,
+                   me."blockchainMarketId" as "blockchain_market_id?", be.amount, be.status,
+                   be."yieldEarned" as yield_earned, be."yieldAttributedAt" as yield_attributed_at
+            FROM bets_extended be
+            LEFT JOIN markets_extended me ON me.id = be."marketId"
+            WHERE be."blockchainBetId" = $1
+            "#,
+            bet_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
 
-        let mut protocol_breakdown = Vec::new();
-        for protocol in &protocols {
-            let protocol_apy = protocol.base_apy.to_f64().unwrap_or(0.0);
+        Ok(bet)
+    }
 
-            if protocol.name == best_protocol.name {
-                protocol_breakdown.push(ProtocolYieldBreakdown {
-                    protocol: protocol.name.clone(),
-                    total_amount,
-                    total_yield,
-                    average_apy: protocol_apy,
-                });
-            } else {
-                protocol_breakdown.push(ProtocolYieldBreakdown {
-                    protocol: protocol.name.clone(),
-                    total_amount: 0.0,
-                    total_yield: 0.0,
-                    average_apy: protocol_apy,
-                });
-            }
-        }
+    /// Attributed yield of every bet placed by `user_address`, newest first.
+    pub async fn user_bet_yields(&self, user_address: &str) -> Result<Vec<BetYield>> {
+        let bets = sqlx::query_as!(
+            BetYield,
+            r#"
+            SELECT be."blockchainBetId" as bet_id, be."marketId" as market_id,
+                   me."blockchainMarketId" as "blockchain_market_id?", be.amount, be.status,
+                   be."yieldEarned" as yield_earned, be."yieldAttributedAt" as yield_attributed_at
+            FROM bets_extended be
+            JOIN users u ON u.id = be."userId"
+            LEFT JOIN markets_extended me ON me.id = be."marketId"
+            WHERE u.address = $1
+            ORDER BY be."createdAt" DESC
+            "#,
+            user_address
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(UserYieldSummary {
-            total_yield_earned: total_yield,
-            total_amount_staked: total_amount,
-            average_apy: best_apy,
-            active_pool_size: total_amount,
-            protocol_breakdown,
-        })
+        Ok(bets)
     }
 
-    async fn fetch_active_protocols(&self) -> Result<Vec<Protocol>> {
+    /// Fraction of each market's ledger yield earned in each protocol, keyed by protocol name.
+    async fn ledger_protocol_shares(
+        &self,
+        market_ids: &[String],
+    ) -> Result<HashMap<String, Vec<(String, f64)>>> {
         let rows = sqlx::query!(
             r#"
-            SELECT name, "baseApy" as base_apy
-            FROM protocols
-            WHERE "isActive" = true
-            ORDER BY "baseApy" DESC
-            "#
+            SELECT yr."marketId" as market_id, p.name, COALESCE(SUM(yr.yield), 0) as "total!"
+            FROM yield_records yr
+            JOIN protocols p ON p.id = yr."protocolId"
+            WHERE yr."marketId" = ANY($1)
+            GROUP BY yr."marketId", p.name
+            "#,
+            market_ids
         )
         .fetch_all(&self.pool)
         .await?;
 
-        let protocols = rows
-            .into_iter()
-            .map(|row| Protocol {
-                name: row.name,
-                base_apy: row.base_apy,
-            })
-            .collect();
+        let mut totals: HashMap<String, Vec<(String, f64)>> = HashMap::new();
+        for row in rows {
+            totals
+                .entry(row.market_id)
+                .or_default()
+                .push((row.name, row.total.to_f64().unwrap_or(0.0)));
+        }
+
+        for shares in totals.values_mut() {
+            let sum: f64 = shares.iter().map(|(_, total)| total).sum();
+            for (_, share) in shares.iter_mut() {
+                *share = if sum > 0.0 { *share / sum } else { 0.0 };
+            }
+        }
 
-        Ok(protocols)
+        Ok(totals)
     }
 
     async fn fetch_user_active_bets(&self, user_address: &str) -> Result<Vec<ActiveBet>> {
@@ -256,9 +263,12 @@ impl UserYieldCalculator {
             r#"
             SELECT
                 b.amount,
-                b.inserted_at
+                be."yieldEarned" as "yield_earned?",
+                me.id as "market_id?"
             FROM bets b
             INNER JOIN markets m ON b.market_id = m.market_id
+            LEFT JOIN markets_extended me ON me."blockchainMarketId" = b.market_id
+            LEFT JOIN bets_extended be ON be."blockchainBetId" = b.bet_id
             WHERE b.user_addr = $1
               AND (b.claimed IS NULL OR b.claimed = false)
             ORDER BY b.inserted_at ASC
@@ -272,7 +282,8 @@ impl UserYieldCalculator {
             .into_iter()
             .map(|row| ActiveBet {
                 amount: row.amount.to_i64().unwrap_or(0),
-                created_at: row.inserted_at,
+                yield_earned: row.yield_earned.and_then(|y| y.to_f64()).unwrap_or(0.0),
+                market_id: row.market_id,
             })
             .collect();
 
@@ -284,9 +295,12 @@ impl UserYieldCalculator {
             r#"
             SELECT
                 b.amount,
-                b.inserted_at
+                be."yieldEarned" as "yield_earned?",
+                me.id as "market_id?"
             FROM bets b
             INNER JOIN markets m ON b.market_id = m.market_id
+            LEFT JOIN markets_extended me ON me."blockchainMarketId" = b.market_id
+            LEFT JOIN bets_extended be ON be."blockchainBetId" = b.bet_id
             WHERE (b.claimed IS NULL OR b.claimed = false)
             ORDER BY b.inserted_at ASC
             "#
@@ -298,33 +312,11 @@ impl UserYieldCalculator {
             .into_iter()
             .map(|row| ActiveBet {
                 amount: row.amount.to_i64().unwrap_or(0),
-                created_at: row.inserted_at,
+                yield_earned: row.yield_earned.and_then(|y| y.to_f64()).unwrap_or(0.0),
+                market_id: row.market_id,
             })
             .collect();
 
         Ok(bets)
     }
-
-    fn create_empty_summary_with_protocols(&self, protocols: &[Protocol]) -> UserYieldSummary {
-        let protocol_breakdown = protocols
-            .iter()
-            .map(|p| {
-                let apy = p.base_apy.to_f64().unwrap_or(0.0);
-                ProtocolYieldBreakdown {
-                    protocol: p.name.clone(),
-                    total_amount: 0.0,
-                    total_yield: 0.0,
-                    average_apy: apy,
-                }
-            })
-            .collect();
-
-        UserYieldSummary {
-            total_yield_earned: 0.0,
-            total_amount_staked: 0.0,
-            average_apy: 0.0,
-            active_pool_size: 0.0,
-            protocol_breakdown,
-        }
-    }
 }
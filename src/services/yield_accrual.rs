@@ -0,0 +1,686 @@
+use anyhow::{anyhow, Result};
+use bigdecimal::{BigDecimal, ToPrimitive, Zero};
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing::{error, info};
+
+use crate::config::Config;
+use crate::utils::time_series::time_weighted_average;
+
+use super::yield_allocation::YieldAllocator;
+use super::yield_service::YieldService;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const MARKET_BATCH_SIZE: i64 = 100;
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccrualSummary {
+    pub markets_processed: usize,
+    pub markets_failed: usize,
+    pub periods_accrued: usize,
+    pub records_written: usize,
+    /// Records that already existed for their period and were left untouched.
+    pub records_skipped: usize,
+    pub yield_accrued: f64,
+    pub bets_attributed: usize,
+    /// Markets whose per-bet yields were re-split to match `totalYieldEarned` again.
+    pub markets_reconciled: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketAccrual {
+    pub market_id: String,
+    pub periods_accrued: usize,
+    pub records_written: usize,
+    pub records_skipped: usize,
+    pub yield_accrued: f64,
+    pub total_yield_earned: BigDecimal,
+    pub accrued_until: Option<NaiveDateTime>,
+    pub attribution: BetAttribution,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BetAttribution {
+    pub bets_attributed: usize,
+    pub attributed_yield: BigDecimal,
+    /// Ledger yield from days with no synced bets in the pool, which no bet can claim.
+    pub unattributed_yield: BigDecimal,
+}
+
+struct AccrualMarket {
+    id: String,
+    created_at: NaiveDateTime,
+    close_at: NaiveDateTime,
+    total_pool_size: BigDecimal,
+    accrued_until: Option<NaiveDateTime>,
+}
+
+/// Writes one `yield_records` entry per market, protocol and completed day.
+///
+/// Days are accrued from `yieldAccruedUntil` (or the market's creation) up to the start of
+/// the current day, so missed runs are backfilled and re-runs never double count. The
+/// market's `totalYieldEarned` is always the sum of its ledger.
+pub struct YieldAccrualService {
+    pool: PgPool,
+    yield_service: YieldService,
+    allocator: YieldAllocator,
+    compounding: bool,
+    max_backfill_days: i64,
+}
+
+#[allow(dead_code)]
+impl YieldAccrualService {
+    pub fn new(pool: PgPool, config: &Config) -> Self {
+        Self {
+            yield_service: YieldService::new(pool.clone(), &config.chain, &config.yield_allocation),
+            allocator: YieldAllocator::new(pool.clone(), &config.yield_allocation),
+            pool,
+            compounding: config.yield_accrual.compounding,
+            max_backfill_days: config.yield_accrual.max_backfill_days,
+        }
+    }
+
+    /// Accrues every completed day for all active and resolved markets.
+    pub async fn accrue_all(&self) -> Result<AccrualSummary> {
+        self.accrue_all_until(chrono::Utc::now().naive_utc()).await
+    }
+
+    pub async fn accrue_all_until(&self, now: NaiveDateTime) -> Result<AccrualSummary> {
+        let today = start_of_day(now);
+        let mut summary = AccrualSummary::default();
+        let mut last_id = String::new();
+
+        loop {
+            let markets = sqlx::query!(
+                r#"
+                SELECT id
+                FROM markets_extended
+                WHERE status IN ('active', 'resolved')
+                  AND ("yieldAccruedUntil" IS NULL
+                       OR "yieldAccruedUntil" < LEAST($1, COALESCE("resolutionDate", "endDate"), "endDate"))
+                  AND id > $2
+                ORDER BY id
+                LIMIT $3
+                "#,
+                today,
+                last_id,
+                MARKET_BATCH_SIZE
+            )
+            .fetch_all(&self.pool)
+            .await?;
+
+            let Some(last) = markets.last() else {
+                break;
+            };
+            last_id = last.id.clone();
+            let batch_len = markets.len() as i64;
+
+            for market in markets {
+                match self.accrue_market_until(&market.id, now).await {
+                    Ok(result) => {
+                        summary.markets_processed += 1;
+                        summary.periods_accrued += result.periods_accrued;
+                        summary.records_written += result.records_written;
+                        summary.records_skipped += result.records_skipped;
+                        summary.yield_accrued += result.yield_accrued;
+                        summary.bets_attributed += result.attribution.bets_attributed;
+                    }
+                    Err(e) => {
+                        error!("Failed to accrue yield for market {}: {}", market.id, e);
+                        summary.markets_failed += 1;
+                    }
+                }
+            }
+
+            if batch_len < MARKET_BATCH_SIZE {
+                break;
+            }
+        }
+
+        summary.markets_reconciled = self.reconcile_bet_yields().await?;
+
+        info!(
+            "Accrued {} periods for {} markets ({} records written, {} already present)",
+            summary.periods_accrued,
+            summary.markets_processed,
+            summary.records_written,
+            summary.records_skipped
+        );
+
+        Ok(summary)
+    }
+
+    pub async fn accrue_market(&self, market_id: &str) -> Result<MarketAccrual> {
+        self.accrue_market_until(market_id, chrono::Utc::now().naive_utc())
+            .await
+    }
+
+    async fn accrue_market_until(
+        &self,
+        market_id: &str,
+        now: NaiveDateTime,
+    ) -> Result<MarketAccrual> {
+        let market = self.fetch_market(market_id).await?;
+
+        let today = start_of_day(now);
+        let end = today.min(end_of_day(market.close_at));
+        let start = market
+            .accrued_until
+            .unwrap_or_else(|| start_of_day(market.created_at))
+            .max(today - Duration::days(self.max_backfill_days));
+
+        let mut result = MarketAccrual {
+            market_id: market.id.clone(),
+            periods_accrued: 0,
+            records_written: 0,
+            records_skipped: 0,
+            yield_accrued: 0.0,
+            total_yield_earned: BigDecimal::from(0),
+            accrued_until: market.accrued_until,
+            attribution: BetAttribution::default(),
+        };
+
+        // Without an allocation there is nothing to price the days at yet; leave them to be
+        // backfilled once the yield job has allocated the market.
+        let allocated = start >= end || self.accrue_days(&market, start, end, &mut result).await?;
+
+        let accrued_until = if allocated {
+            market.accrued_until.max(Some(end))
+        } else {
+            market.accrued_until
+        };
+        let total_yield_earned = sqlx::query_scalar!(
+            r#"
+            UPDATE markets_extended
+            SET "totalYieldEarned" = (
+                    SELECT COALESCE(SUM(yield), 0) FROM yield_records WHERE "marketId" = $1
+                ),
+                "yieldAccruedUntil" = $2
+            WHERE id = $1
+            RETURNING "totalYieldEarned"
+            "#,
+            market.id,
+            accrued_until
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        result.total_yield_earned = total_yield_earned;
+        result.accrued_until = accrued_until;
+        result.attribution = self.attribute_market(&market).await?;
+        Ok(result)
+    }
+
+    /// Re-splits the ledger of every market whose per-bet yields and unattributed yield no
+    /// longer add up to its `totalYieldEarned`, e.g. after bets were synced or cancelled late.
+    /// Returns how many markets were re-attributed in full.
+    pub async fn reconcile_bet_yields(&self) -> Result<usize> {
+        let market_ids = sqlx::query_scalar!(
+            r#"
+            SELECT me.id
+            FROM markets_extended me
+            JOIN bets_extended b ON b."marketId" = me.id
+            GROUP BY me.id, me."totalYieldEarned", me."unattributedYield"
+            HAVING COALESCE(SUM(b."yieldEarned"), 0) + me."unattributedYield"
+                != me."totalYieldEarned"
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut reconciled = 0;
+        for market_id in market_ids {
+            let market = self.fetch_market(&market_id).await?;
+            let attribution = self.attribute_market(&market).await?;
+            if attribution.unattributed_yield.is_zero() {
+                reconciled += 1;
+            }
+        }
+
+        Ok(reconciled)
+    }
+
+    async fn fetch_market(&self, market_id: &str) -> Result<AccrualMarket> {
+        let market = sqlx::query_as!(
+            AccrualMarket,
+            r#"
+            SELECT id, "createdAt" as created_at,
+                   LEAST(COALESCE("resolutionDate", "endDate"), "endDate") as "close_at!",
+                   "totalPoolSize" as total_pool_size,
+                   "yieldAccruedUntil" as accrued_until
+            FROM markets_extended
+            WHERE id = $1
+            "#,
+            market_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| anyhow!("Market not found: {}", market_id))?;
+
+        Ok(market)
+    }
+
+    /// Splits each ledger day of the market across its bets by amount and time in pool that
+    /// day, and stores the result in `bets_extended."yieldEarned"`. Yield from days no bet
+    /// was in the pool is kept in `markets_extended."unattributedYield"`.
+    async fn attribute_market(&self, market: &AccrualMarket) -> Result<BetAttribution> {
+        let periods: Vec<(i64, BigDecimal)> = sqlx::query!(
+            r#"
+            SELECT period, COALESCE(SUM(yield), 0) as "total!"
+            FROM yield_records
+            WHERE "marketId" = $1
+            GROUP BY period
+            ORDER BY period
+            "#,
+            market.id
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.period.and_utc().timestamp(), row.total))
+        .collect();
+
+        let bets = sqlx::query!(
+            r#"
+            SELECT id, amount, status, "createdAt" as created_at
+            FROM bets_extended
+            WHERE "marketId" = $1
+            "#,
+            market.id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let stakes: Vec<(i64, f64)> = bets
+            .iter()
+            .map(|bet| match &bet.amount {
+                Some(amount) if bet.status != "cancelled" && bet.status != "failed" => (
+                    bet.created_at.max(market.created_at).and_utc().timestamp(),
+                    amount.to_f64().unwrap_or(0.0),
+                ),
+                _ => (i64::MAX, 0.0),
+            })
+            .collect();
+
+        let (shares, unattributed_yield) =
+            attribute_periods(&periods, &stakes, market.close_at.and_utc().timestamp());
+
+        let ids: Vec<String> = bets.into_iter().map(|bet| bet.id).collect();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            r#"
+            UPDATE bets_extended b
+            SET "yieldEarned" = a.yield,
+                "yieldAttributedAt" = NOW()
+            FROM UNNEST($1::text[], $2::numeric[]) AS a(id, yield)
+            WHERE b.id = a.id
+            "#,
+            &ids,
+            &shares
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"UPDATE markets_extended SET "unattributedYield" = $2 WHERE id = $1"#,
+            market.id,
+            unattributed_yield
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(BetAttribution {
+            bets_attributed: ids.len(),
+            attributed_yield: shares.iter().sum(),
+            unattributed_yield,
+        })
+    }
+
+    async fn accrue_days(
+        &self,
+        market: &AccrualMarket,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        result: &mut MarketAccrual,
+    ) -> Result<bool> {
+        let plan = self.allocator.plan_for_market(&market.id).await?;
+        if plan.slices.is_empty() {
+            return Ok(false);
+        }
+
+        let bets: Vec<(i64, f64)> = sqlx::query!(
+            r#"
+            SELECT amount as "amount!", "createdAt" as created_at
+            FROM bets_extended
+            WHERE "marketId" = $1 AND status NOT IN ('cancelled', 'failed') AND amount IS NOT NULL
+            "#,
+            market.id
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            (
+                row.created_at.max(market.created_at).and_utc().timestamp(),
+                row.amount.to_f64().unwrap_or(0.0),
+            )
+        })
+        .collect();
+
+        // Markets without synced bets fall back to their pool size for the whole open period.
+        let deposits = if bets.is_empty() {
+            vec![(
+                market.created_at.and_utc().timestamp(),
+                market.total_pool_size.to_f64().unwrap_or(0.0),
+            )]
+        } else {
+            bets
+        };
+
+        let paths = self.yield_service.apy_paths_since(start).await?;
+
+        let mut prior_yield: HashMap<String, f64> = HashMap::new();
+        if self.compounding {
+            let rows = sqlx::query!(
+                r#"
+                SELECT "protocolId" as protocol_id, COALESCE(SUM(yield), 0) as "total!"
+                FROM yield_records
+                WHERE "marketId" = $1 AND period < $2
+                GROUP BY "protocolId"
+                "#,
+                market.id,
+                start
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            for row in rows {
+                prior_yield.insert(row.protocol_id, row.total.to_f64().unwrap_or(0.0));
+            }
+        }
+
+        let open_ts = market.created_at.and_utc().timestamp();
+        let close_ts = market.close_at.and_utc().timestamp();
+
+        let mut day = start;
+        while day < end {
+            let day_ts = day.and_utc().timestamp();
+            let open_fraction = day_fraction(day_ts, open_ts, close_ts);
+            if open_fraction <= 0.0 {
+                day += Duration::days(1);
+                continue;
+            }
+
+            let pool_days: f64 = deposits
+                .iter()
+                .map(|(from, amount)| amount * day_fraction(day_ts, *from, close_ts))
+                .sum();
+
+            for slice in &plan.slices {
+                let compounded = prior_yield.get(&slice.protocol_id).copied().unwrap_or(0.0);
+                let principal = pool_days * slice.weight + compounded * open_fraction;
+                if principal <= 0.0 {
+                    continue;
+                }
+
+                let path = paths
+                    .get(&slice.protocol_id)
+                    .map(Vec::as_slice)
+                    .unwrap_or(&[]);
+                let apy = time_weighted_average(path, day_ts, day_ts + SECONDS_PER_DAY)
+                    .unwrap_or(slice.apy);
+                let yield_amount = daily_yield(principal, apy, self.compounding);
+
+                let inserted = self
+                    .yield_service
+                    .record_yield(
+                        &market.id,
+                        &slice.protocol_id,
+                        BigDecimal::try_from(principal)?.round(18),
+                        BigDecimal::try_from(apy)?.round(6),
+                        BigDecimal::try_from(yield_amount)?.round(18),
+                        day,
+                    )
+                    .await?;
+
+                if inserted {
+                    result.records_written += 1;
+                    result.yield_accrued += yield_amount;
+                } else {
+                    result.records_skipped += 1;
+                }
+                if self.compounding {
+                    *prior_yield.entry(slice.protocol_id.clone()).or_default() += yield_amount;
+                }
+            }
+
+            result.periods_accrued += 1;
+            day += Duration::days(1);
+        }
+
+        Ok(true)
+    }
+}
+
+fn start_of_day(at: NaiveDateTime) -> NaiveDateTime {
+    at.date().and_time(NaiveTime::MIN)
+}
+
+/// Start of the day after the one containing `at`, or `at` itself on a day boundary.
+fn end_of_day(at: NaiveDateTime) -> NaiveDateTime {
+    let start = start_of_day(at);
+    if start == at {
+        at
+    } else {
+        start + Duration::days(1)
+    }
+}
+
+/// Portion of the day starting at `day_start` that lies inside `[from, to)`, in days.
+fn day_fraction(day_start: i64, from: i64, to: i64) -> f64 {
+    let overlap = to.min(day_start + SECONDS_PER_DAY) - from.max(day_start);
+    overlap.max(0) as f64 / SECONDS_PER_DAY as f64
+}
+
+/// Splits each `(day_start, yield)` period across `(in_pool_from, amount)` stakes in
+/// proportion to amount times the part of the day they were in the pool, until `close`.
+/// Rounding remainders go to the period's largest stake so each period sums exactly.
+/// Returns the per-stake totals and the yield of periods with nothing in the pool.
+fn attribute_periods(
+    periods: &[(i64, BigDecimal)],
+    stakes: &[(i64, f64)],
+    close: i64,
+) -> (Vec<BigDecimal>, BigDecimal) {
+    let mut shares = vec![BigDecimal::from(0); stakes.len()];
+    let mut unattributed = BigDecimal::from(0);
+
+    for (day_start, total) in periods {
+        let weights: Vec<f64> = stakes
+            .iter()
+            .map(|(from, amount)| amount * day_fraction(*day_start, *from, close))
+            .collect();
+        let weight_sum: f64 = weights.iter().sum();
+        if weight_sum <= 0.0 {
+            unattributed += total;
+            continue;
+        }
+
+        let mut distributed = BigDecimal::from(0);
+        let mut largest = 0;
+        for (i, weight) in weights.iter().enumerate() {
+            if *weight > weights[largest] {
+                largest = i;
+            }
+            if *weight <= 0.0 {
+                continue;
+            }
+            let ratio = BigDecimal::try_from(weight / weight_sum).unwrap_or_default();
+            let share = (total * ratio).round(18);
+            distributed += &share;
+            shares[i] += share;
+        }
+        shares[largest] += total - distributed;
+    }
+
+    (shares, unattributed)
+}
+
+/// One day of yield on `principal` at `apy_pct`. With compounding the APY is treated as an
+/// effective annual rate; otherwise it accrues as simple interest.
+fn daily_yield(principal: f64, apy_pct: f64, compounding: bool) -> f64 {
+    let apy = apy_pct / 100.0;
+    if compounding {
+        principal * ((1.0 + apy).powf(1.0 / 365.0) - 1.0)
+    } else {
+        principal * apy / 365.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_day_fraction_clips_to_open_period() {
+        let day = 10 * SECONDS_PER_DAY;
+        assert_eq!(day_fraction(day, 0, i64::MAX), 1.0);
+        assert_eq!(day_fraction(day, day + SECONDS_PER_DAY / 4, i64::MAX), 0.75);
+        assert_eq!(day_fraction(day, 0, day + SECONDS_PER_DAY / 2), 0.5);
+        assert_eq!(day_fraction(day, day + SECONDS_PER_DAY, i64::MAX), 0.0);
+    }
+
+    #[test]
+    fn test_daily_yield_compounds_to_apy() {
+        assert!((daily_yield(365.0, 10.0, false) - 0.1).abs() < 1e-12);
+
+        let mut principal = 1_000.0;
+        for _ in 0..365 {
+            principal += daily_yield(principal, 10.0, true);
+        }
+        assert!((principal - 1_100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_attribute_periods_sums_to_ledger() {
+        let day = 10 * SECONDS_PER_DAY;
+        let periods = vec![
+            (day, BigDecimal::from_str("1.000000000000000001").unwrap()),
+            (day + SECONDS_PER_DAY, BigDecimal::from(3)),
+            (day + 2 * SECONDS_PER_DAY, BigDecimal::from(5)),
+        ];
+        // The second stake joins half way through the first day; the third was cancelled.
+        let stakes = vec![
+            (day, 100.0),
+            (day + SECONDS_PER_DAY / 2, 200.0),
+            (i64::MAX, 0.0),
+        ];
+
+        let (shares, unattributed) =
+            attribute_periods(&periods, &stakes, day + 2 * SECONDS_PER_DAY);
+
+        // Both bets were in the pool 50/50 on day one and 1/3 vs 2/3 on day two; the market
+        // was closed on day three, so that yield cannot be attributed.
+        assert_eq!(unattributed, BigDecimal::from(5));
+        assert_eq!(
+            &shares[0] + &shares[1],
+            BigDecimal::from_str("4.000000000000000001").unwrap()
+        );
+        assert!((shares[0].to_f64().unwrap() - 1.5).abs() < 1e-12);
+        assert_eq!(shares[2], BigDecimal::from(0));
+    }
+
+    #[test]
+    fn test_day_bounds() {
+        let at = chrono::NaiveDate::from_ymd_opt(2025, 3, 1)
+            .unwrap()
+            .and_hms_opt(15, 30, 0)
+            .unwrap();
+        let midnight = chrono::NaiveDate::from_ymd_opt(2025, 3, 1)
+            .unwrap()
+            .and_time(NaiveTime::MIN);
+        assert_eq!(start_of_day(at), midnight);
+        assert_eq!(end_of_day(at), midnight + Duration::days(1));
+        assert_eq!(end_of_day(midnight), midnight);
+    }
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_reconciled_market_with_unattributed_yield_is_left_alone(pool: PgPool) {
+        let today = start_of_day(chrono::Utc::now().naive_utc());
+        sqlx::query!("INSERT INTO users (id, address) VALUES ('u-1', '0x1')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query!(
+            r#"INSERT INTO protocols (id, name, "displayName") VALUES ('p-1', 'amnis', 'Amnis')"#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO markets_extended (id, "endDate", "totalPoolSize", "totalYieldEarned", "createdAt")
+            VALUES ('m-1', $1, 100, 10, $2)
+            "#,
+            today + Duration::days(30),
+            today - Duration::days(6)
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO bets_extended (id, "blockchainBetId", "userId", "marketId", position, amount, odds, "createdAt")
+            VALUES ('b-1', 1, 'u-1', 'm-1', true, 100, 1, $1)
+            "#,
+            today - Duration::days(2)
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        // The first day predates every bet; the second is the bet's alone.
+        for (days_ago, amount) in [(5, 7), (1, 3)] {
+            sqlx::query!(
+                r#"
+                INSERT INTO yield_records (id, "marketId", "protocolId", amount, apy, yield, period)
+                VALUES ($1, 'm-1', 'p-1', 100, 5, $2, $3)
+                "#,
+                format!("y-{}", days_ago),
+                BigDecimal::from(amount),
+                today - Duration::days(days_ago)
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let service = YieldAccrualService::new(pool.clone(), &Config::default());
+        assert_eq!(service.reconcile_bet_yields().await.unwrap(), 0);
+
+        let attributed = sqlx::query!(
+            r#"
+            SELECT b."yieldEarned" as yield_earned, b."yieldAttributedAt" as attributed_at,
+                   me."unattributedYield" as unattributed
+            FROM bets_extended b JOIN markets_extended me ON me.id = b."marketId"
+            "#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(attributed.yield_earned, BigDecimal::from(3));
+        assert_eq!(attributed.unattributed, BigDecimal::from(7));
+
+        service.reconcile_bet_yields().await.unwrap();
+        let attributed_at = sqlx::query_scalar!(
+            r#"SELECT "yieldAttributedAt" FROM bets_extended WHERE id = 'b-1'"#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(attributed_at, attributed.attributed_at);
+    }
+}
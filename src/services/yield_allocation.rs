@@ -0,0 +1,424 @@
+use anyhow::{anyhow, Result};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::config::YieldAllocationConfig;
+
+/// Weights smaller than this are treated as zero.
+const WEIGHT_EPSILON: f64 = 1e-9;
+
+/// How a market pool is split across the active yield protocols.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AllocationStrategy {
+    /// The same share in every active protocol.
+    #[default]
+    EqualWeight,
+    /// Everything in the protocol with the highest APY.
+    MaxApy,
+    /// Shares proportional to APY divided by risk score, capped per protocol, with part
+    /// of the pool held back as a liquidity reserve.
+    RiskWeighted,
+}
+
+impl AllocationStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AllocationStrategy::EqualWeight => "equal_weight",
+            AllocationStrategy::MaxApy => "max_apy",
+            AllocationStrategy::RiskWeighted => "risk_weighted",
+        }
+    }
+}
+
+impl FromStr for AllocationStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "equal_weight" => Ok(AllocationStrategy::EqualWeight),
+            "max_apy" => Ok(AllocationStrategy::MaxApy),
+            "risk_weighted" => Ok(AllocationStrategy::RiskWeighted),
+            other => Err(anyhow!(
+                "unknown allocation strategy '{}' (expected equal_weight, max_apy or risk_weighted)",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProtocolCandidate {
+    pub id: String,
+    pub name: String,
+    /// APY in percent.
+    pub apy: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllocationSlice {
+    pub protocol_id: String,
+    pub protocol: String,
+    pub apy: f64,
+    /// Share of the whole pool, 0..=1.
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllocationPlan {
+    pub strategy: AllocationStrategy,
+    pub slices: Vec<AllocationSlice>,
+    /// When the plan was persisted for a market; `None` for a freshly computed plan.
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl AllocationPlan {
+    /// Share of the pool that is not deployed to any protocol.
+    pub fn idle_weight(&self) -> f64 {
+        (1.0 - self.slices.iter().map(|s| s.weight).sum::<f64>()).max(0.0)
+    }
+
+    /// APY of the whole pool, counting the idle share as earning nothing.
+    pub fn blended_apy(&self) -> f64 {
+        self.slices.iter().map(|s| s.weight * s.apy).sum()
+    }
+
+    /// The slice holding the largest share of the pool.
+    pub fn primary(&self) -> Option<&AllocationSlice> {
+        self.slices
+            .iter()
+            .max_by(|a, b| a.weight.total_cmp(&b.weight))
+    }
+}
+
+/// Splits a pool across `candidates` with the given strategy.
+///
+/// Weights are fractions of the whole pool, so the same plan applies to any pool size.
+pub fn plan_allocation(
+    strategy: AllocationStrategy,
+    candidates: &[ProtocolCandidate],
+    config: &YieldAllocationConfig,
+) -> AllocationPlan {
+    let weights: Vec<f64> = match strategy {
+        AllocationStrategy::EqualWeight => {
+            let share = if candidates.is_empty() {
+                0.0
+            } else {
+                1.0 / candidates.len() as f64
+            };
+            vec![share; candidates.len()]
+        }
+        AllocationStrategy::MaxApy => {
+            let best = candidates
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.apy.total_cmp(&b.apy))
+                .map(|(i, _)| i);
+            (0..candidates.len())
+                .map(|i| if Some(i) == best { 1.0 } else { 0.0 })
+                .collect()
+        }
+        AllocationStrategy::RiskWeighted => risk_weighted(candidates, config),
+    };
+
+    AllocationPlan {
+        strategy,
+        slices: candidates
+            .iter()
+            .zip(weights)
+            .filter(|(_, weight)| *weight > WEIGHT_EPSILON)
+            .map(|(c, weight)| AllocationSlice {
+                protocol_id: c.id.clone(),
+                protocol: c.name.clone(),
+                apy: c.apy,
+                weight,
+            })
+            .collect(),
+        updated_at: None,
+    }
+}
+
+/// Fills protocols in proportion to `apy / risk_score`; a protocol that would exceed its cap
+/// is pinned at the cap and the remainder is redistributed over the others. Whatever cannot
+/// be placed under the caps stays idle alongside the liquidity reserve.
+fn risk_weighted(candidates: &[ProtocolCandidate], config: &YieldAllocationConfig) -> Vec<f64> {
+    let mut weights = vec![0.0; candidates.len()];
+    let mut remaining = (1.0 - config.min_liquidity_pct / 100.0).clamp(0.0, 1.0);
+
+    let mut open: Vec<(usize, f64, f64)> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| {
+            let risk = config.risk_score(&c.name);
+            let score = c.apy / risk;
+            (c.apy > 0.0 && risk > 0.0).then(|| (i, score, config.cap_pct(&c.name) / 100.0))
+        })
+        .collect();
+
+    while remaining > WEIGHT_EPSILON && !open.is_empty() {
+        let total_score: f64 = open.iter().map(|(_, score, _)| score).sum();
+        if total_score <= 0.0 {
+            break;
+        }
+
+        let (capped, uncapped): (Vec<_>, Vec<_>) = open
+            .iter()
+            .partition(|(_, score, cap)| remaining * score / total_score > *cap);
+
+        if capped.is_empty() {
+            for (i, score, _) in &open {
+                weights[*i] = remaining * score / total_score;
+            }
+            break;
+        }
+
+        for (i, _, cap) in &capped {
+            weights[*i] = *cap;
+            remaining -= cap;
+        }
+        open = uncapped;
+    }
+
+    weights
+}
+
+/// Computes, persists and loads per-market allocations. All yield figures in the API use
+/// these plans so the market, user and protocol views agree.
+pub struct YieldAllocator {
+    pool: PgPool,
+    config: YieldAllocationConfig,
+}
+
+#[allow(dead_code)]
+impl YieldAllocator {
+    pub fn new(pool: PgPool, config: &YieldAllocationConfig) -> Self {
+        Self {
+            pool,
+            config: config.clone(),
+        }
+    }
+
+    pub fn strategy(&self) -> AllocationStrategy {
+        self.config.strategy
+    }
+
+    async fn candidates(&self) -> Result<Vec<ProtocolCandidate>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, name, "baseApy" as base_apy
+            FROM protocols
+            WHERE "isActive" = true
+            ORDER BY name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ProtocolCandidate {
+                id: row.id,
+                name: row.name,
+                apy: row.base_apy.to_f64().unwrap_or(0.0),
+            })
+            .collect())
+    }
+
+    /// Allocation the configured strategy would choose right now.
+    pub async fn current_plan(&self) -> Result<AllocationPlan> {
+        let candidates = self.candidates().await?;
+        Ok(plan_allocation(
+            self.config.strategy,
+            &candidates,
+            &self.config,
+        ))
+    }
+
+    /// Recomputes the market's allocation from current APYs and replaces the stored one.
+    pub async fn rebalance_market(
+        &self,
+        market_id: &str,
+        pool_size: &BigDecimal,
+    ) -> Result<AllocationPlan> {
+        let mut plan = self.current_plan().await?;
+        let pool_amount = pool_size.to_f64().unwrap_or(0.0);
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"DELETE FROM market_allocations WHERE "marketId" = $1"#,
+            market_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for slice in &plan.slices {
+            let weight = BigDecimal::try_from(slice.weight)?.round(8);
+            let amount = BigDecimal::try_from(pool_amount * slice.weight)?.round(18);
+            let apy = BigDecimal::try_from(slice.apy)?.round(6);
+
+            sqlx::query!(
+                r#"
+                INSERT INTO market_allocations
+                    (id, "marketId", "protocolId", strategy, weight, amount, apy, "createdAt", "updatedAt")
+                VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                "#,
+                Uuid::new_v4().to_string(),
+                market_id,
+                slice.protocol_id,
+                plan.strategy.as_str(),
+                weight,
+                amount,
+                apy
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        plan.updated_at = Some(chrono::Utc::now().naive_utc());
+        info!(
+            "Allocated market {} with {} across {} protocols ({:.2}% idle)",
+            market_id,
+            plan.strategy.as_str(),
+            plan.slices.len(),
+            plan.idle_weight() * 100.0
+        );
+
+        Ok(plan)
+    }
+
+    /// Stored allocations for the given markets. Markets that were never allocated are absent.
+    pub async fn stored_plans(
+        &self,
+        market_ids: &[String],
+    ) -> Result<HashMap<String, AllocationPlan>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT ma."marketId" as market_id, ma."protocolId" as protocol_id, p.name as protocol,
+                   ma.strategy, ma.weight, ma.apy, ma."updatedAt" as updated_at
+            FROM market_allocations ma
+            JOIN protocols p ON p.id = ma."protocolId"
+            WHERE ma."marketId" = ANY($1)
+            ORDER BY ma."marketId", p.name
+            "#,
+            market_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut plans: HashMap<String, AllocationPlan> = HashMap::new();
+        for row in rows {
+            let strategy = row.strategy.parse().unwrap_or(self.config.strategy);
+            let plan = plans
+                .entry(row.market_id)
+                .or_insert_with(|| AllocationPlan {
+                    strategy,
+                    slices: Vec::new(),
+                    updated_at: Some(row.updated_at),
+                });
+            plan.updated_at = plan.updated_at.max(Some(row.updated_at));
+            plan.slices.push(AllocationSlice {
+                protocol_id: row.protocol_id,
+                protocol: row.protocol,
+                apy: row.apy.to_f64().unwrap_or(0.0),
+                weight: row.weight.to_f64().unwrap_or(0.0),
+            });
+        }
+
+        Ok(plans)
+    }
+
+    /// Stored allocation for the market, or the current plan if it has not been allocated yet.
+    pub async fn plan_for_market(&self, market_id: &str) -> Result<AllocationPlan> {
+        let mut stored = self.stored_plans(&[market_id.to_string()]).await?;
+        match stored.remove(market_id) {
+            Some(plan) => Ok(plan),
+            None => self.current_plan().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<ProtocolCandidate> {
+        [("amnis", 6.0), ("kiln", 4.0), ("kofi", 10.0)]
+            .iter()
+            .map(|(name, apy)| ProtocolCandidate {
+                id: format!("{}-id", name),
+                name: name.to_string(),
+                apy: *apy,
+            })
+            .collect()
+    }
+
+    fn weight_of(plan: &AllocationPlan, protocol: &str) -> f64 {
+        plan.slices
+            .iter()
+            .find(|s| s.protocol == protocol)
+            .map(|s| s.weight)
+            .unwrap_or(0.0)
+    }
+
+    #[test]
+    fn test_equal_weight_and_max_apy() {
+        let config = YieldAllocationConfig::default();
+
+        let equal = plan_allocation(AllocationStrategy::EqualWeight, &candidates(), &config);
+        assert_eq!(equal.slices.len(), 3);
+        assert!((equal.blended_apy() - 20.0 / 3.0).abs() < 1e-9);
+        assert!(equal.idle_weight() < 1e-9);
+
+        let max = plan_allocation(AllocationStrategy::MaxApy, &candidates(), &config);
+        assert_eq!(max.slices.len(), 1);
+        assert_eq!(max.primary().unwrap().protocol, "kofi");
+        assert_eq!(max.blended_apy(), 10.0);
+    }
+
+    #[test]
+    fn test_risk_weighted_respects_caps_and_reserve() {
+        let config = YieldAllocationConfig {
+            strategy: AllocationStrategy::RiskWeighted,
+            max_protocol_share_pct: 40.0,
+            min_liquidity_pct: 10.0,
+            risk_scores: [("kofi".to_string(), 2.0)].into_iter().collect(),
+            ..Default::default()
+        };
+
+        // Scores: amnis 6, kiln 4, kofi 10 / 2 = 5. Uncapped amnis would get 0.9 * 6/15 = 0.36.
+        let plan = plan_allocation(AllocationStrategy::RiskWeighted, &candidates(), &config);
+        assert!((weight_of(&plan, "amnis") - 0.36).abs() < 1e-9);
+        assert!((weight_of(&plan, "kiln") - 0.24).abs() < 1e-9);
+        assert!((weight_of(&plan, "kofi") - 0.30).abs() < 1e-9);
+        assert!((plan.idle_weight() - 0.10).abs() < 1e-9);
+
+        // A tight cap on amnis pushes its excess onto the others.
+        let capped = YieldAllocationConfig {
+            protocol_caps_pct: [("amnis".to_string(), 20.0)].into_iter().collect(),
+            ..config.clone()
+        };
+        let plan = plan_allocation(AllocationStrategy::RiskWeighted, &candidates(), &capped);
+        assert!((weight_of(&plan, "amnis") - 0.20).abs() < 1e-9);
+        assert!((weight_of(&plan, "kiln") - 0.70 * 4.0 / 9.0).abs() < 1e-9);
+        assert!((weight_of(&plan, "kofi") - 0.70 * 5.0 / 9.0).abs() < 1e-9);
+
+        // When every protocol is capped the rest stays idle.
+        let all_capped = YieldAllocationConfig {
+            max_protocol_share_pct: 20.0,
+            ..config
+        };
+        let plan = plan_allocation(AllocationStrategy::RiskWeighted, &candidates(), &all_capped);
+        assert!((plan.idle_weight() - 0.40).abs() < 1e-9);
+    }
+}
@@ -1,46 +1,37 @@
-use anyhow::Result;
 use chrono::NaiveDateTime;
 use sqlx::types::BigDecimal;
-use sqlx::PgPool;
 use std::str::FromStr;
 
+use super::yield_allocation::{AllocationPlan, AllocationStrategy};
+
 #[derive(Debug, Clone)]
 pub struct YieldData {
     pub daily_yield: f64,
     pub total_yield_until_end: f64,
     pub days_remaining: i64,
-    pub best_protocol_apy: f64,
-    pub best_protocol_name: String,
+    /// APY of the whole pool under its allocation.
+    pub apy: f64,
+    pub strategy: AllocationStrategy,
+    /// APY and name of the protocol holding the largest share of the pool.
+    pub primary_protocol_apy: f64,
+    pub primary_protocol_name: String,
 }
 
-pub async fn calculate_market_yield_data(
-    pool: &PgPool,
+/// Projects a market's yield from its allocation plan.
+pub fn calculate_market_yield_data(
+    plan: &AllocationPlan,
     total_pool_size: &BigDecimal,
     volume: &BigDecimal,
     end_date: &NaiveDateTime,
-) -> Result<YieldData> {
+) -> YieldData {
     let now = chrono::Utc::now().naive_utc();
     let days_remaining = (*end_date - now).num_days().max(0);
 
-    let best_protocol = sqlx::query!(
-        r#"
-        SELECT name, "baseApy" as base_apy
-        FROM protocols
-        WHERE "isActive" = true
-        ORDER BY "baseApy" DESC
-        LIMIT 1
-        "#
-    )
-    .fetch_optional(pool)
-    .await?;
-
-    let (best_apy, best_name) = if let Some(protocol) = best_protocol {
-        let apy_str = protocol.base_apy.to_string();
-        let apy = f64::from_str(&apy_str).unwrap_or(5.0);
-        (apy, protocol.name)
-    } else {
-        (5.0, "default".to_string())
-    };
+    let apy = plan.blended_apy();
+    let (primary_apy, primary_name) = plan
+        .primary()
+        .map(|slice| (slice.apy, slice.protocol.clone()))
+        .unwrap_or((0.0, "none".to_string()));
 
     let pool_str = total_pool_size.to_string();
     let mut pool_amount = f64::from_str(&pool_str).unwrap_or(0.0);
@@ -56,17 +47,19 @@ pub async fn calculate_market_yield_data(
     let (daily_yield, total_yield_until_end) = if pool_amount <= 0.0 || days_remaining <= 0 {
         (0.0, 0.0)
     } else {
-        let daily_rate = best_apy / 365.0 / 100.0;
+        let daily_rate = apy / 365.0 / 100.0;
         let daily = pool_amount * daily_rate;
         let total = daily * (days_remaining as f64);
         (daily, total)
     };
 
-    Ok(YieldData {
+    YieldData {
         daily_yield,
         total_yield_until_end,
         days_remaining,
-        best_protocol_apy: best_apy,
-        best_protocol_name: best_name,
-    })
+        apy,
+        strategy: plan.strategy,
+        primary_protocol_apy: primary_apy,
+        primary_protocol_name: primary_name,
+    }
 }
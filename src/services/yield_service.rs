@@ -1,19 +1,37 @@
 use anyhow::Result;
+use bigdecimal::ToPrimitive;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
 use sqlx::types::BigDecimal;
 use sqlx::PgPool;
-use tracing::{error, info};
+use std::collections::HashMap;
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+use crate::config::{ChainConfig, YieldAllocationConfig};
 use crate::models::Protocol;
+use crate::services::yield_allocation::{AllocationStrategy, YieldAllocator};
+use crate::utils::time_series::time_weighted_average;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const SECONDS_PER_YEAR: f64 = 365.0 * SECONDS_PER_DAY as f64;
+/// Markets are processed in pages of this size so every active market is covered.
+const MARKET_BATCH_SIZE: i64 = 100;
 
 pub struct YieldService {
     pool: PgPool,
+    chain: ChainConfig,
+    allocation: YieldAllocationConfig,
 }
 
 #[allow(dead_code)]
 #[derive(Debug)]
 pub struct YieldCalculation {
+    pub strategy: AllocationStrategy,
+    /// Daily yield at the time-weighted APY of the last 24 hours.
     pub current_yield: BigDecimal,
+    /// Yield accrued since the market opened, following the recorded APY path.
+    pub accrued_yield: BigDecimal,
     pub protocol_breakdown: Vec<ProtocolYield>,
 }
 
@@ -21,30 +39,86 @@ pub struct YieldCalculation {
 #[derive(Debug)]
 pub struct ProtocolYield {
     pub protocol: String,
+    /// Share of the pool allocated to the protocol.
+    pub weight: f64,
     pub amount: BigDecimal,
     pub apy: BigDecimal,
     pub yield_amount: BigDecimal,
+    /// Time-weighted APY over the market lifetime.
+    pub average_apy: BigDecimal,
+    pub accrued_yield: BigDecimal,
+}
+
+/// Where an `apy_history` reading came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApySource {
+    /// Read from the protocol adapter's `get_current_apy` view function.
+    Contract,
+    /// The contract call failed and the stored `baseApy` was kept.
+    Default,
+}
+
+impl ApySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApySource::Contract => "contract",
+            ApySource::Default => "default",
+        }
+    }
+}
+
+/// Bucket size for APY time series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApyInterval {
+    Raw,
+    Hour,
+    Day,
+    Week,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApyPoint {
+    pub timestamp: NaiveDateTime,
+    /// Average of the readings in the bucket (the reading itself for `raw`).
+    pub apy: BigDecimal,
+    pub min: BigDecimal,
+    pub max: BigDecimal,
+    /// Last reading in the bucket.
+    pub close: BigDecimal,
+    pub samples: i64,
+    /// Only set for `raw` points.
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApySeries {
+    pub protocol_id: String,
+    pub protocol: String,
+    pub display_name: String,
+    pub points: Vec<ApyPoint>,
 }
 
 #[allow(dead_code)]
 impl YieldService {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, chain: &ChainConfig, allocation: &YieldAllocationConfig) -> Self {
+        Self {
+            pool,
+            chain: chain.clone(),
+            allocation: allocation.clone(),
+        }
     }
 
     pub async fn initialize_protocols(&self) -> Result<()> {
         info!("Initializing yield protocols");
 
-        let module_address = std::env::var("APTOS_MODULE_ADDRESS").unwrap_or_else(|_| {
-            "0xaab1ca043fb6cd7b2f264f3ce32f301427e7f67b0e816b30d4e95f1e2bcbabfa".to_string()
-        });
+        let module_address = self.chain.module_address_or_default().to_string();
 
-        let amnis_addr =
-            std::env::var("AMNIS_PROTOCOL_ADDRESS").unwrap_or_else(|_| module_address.clone());
-        let kiln_addr =
-            std::env::var("KILN_PROTOCOL_ADDRESS").unwrap_or_else(|_| module_address.clone());
-        let kofi_addr =
-            std::env::var("KOFI_PROTOCOL_ADDRESS").unwrap_or_else(|_| module_address.clone());
+        let amnis_addr = self.chain.protocol_address("amnis").to_string();
+        let kiln_addr = self.chain.protocol_address("kiln").to_string();
+        let kofi_addr = self.chain.protocol_address("kofi").to_string();
 
         let protocols = vec![
             (
@@ -71,14 +145,18 @@ impl YieldService {
         ];
 
         for (name, display_name, description, protocol_addr, adapter_name) in protocols {
-            let apy = match self
+            let (apy, source, fetch_error) = match self
                 .fetch_protocol_apy(&module_address, adapter_name, &protocol_addr)
                 .await
             {
                 Ok(contract_apy) => {
                     let apy_percent = contract_apy as f64 / 100.0;
                     info!("Fetched APY from {} contract: {}%", name, apy_percent);
-                    BigDecimal::try_from(apy_percent)?
+                    (
+                        BigDecimal::try_from(apy_percent)?,
+                        ApySource::Contract,
+                        None,
+                    )
                 }
                 Err(e) => {
                     error!(
@@ -86,7 +164,7 @@ impl YieldService {
                         name, e
                     );
 
-                    match sqlx::query_scalar::<_, BigDecimal>(
+                    let apy = match sqlx::query_scalar::<_, BigDecimal>(
                         r#"SELECT "baseApy" FROM protocols WHERE name = $1"#,
                     )
                     .bind(name)
@@ -103,7 +181,8 @@ impl YieldService {
                             };
                             BigDecimal::try_from(default.parse::<f64>()?).unwrap()
                         }
-                    }
+                    };
+                    (apy, ApySource::Default, Some(e.to_string()))
                 }
             };
 
@@ -128,6 +207,9 @@ impl YieldService {
             .execute(&self.pool)
             .await?;
 
+            self.record_apy(name, Some(&apy), source, fetch_error.as_deref())
+                .await?;
+
             info!("Updated protocol {}: {}% APY", name, apy);
         }
 
@@ -139,13 +221,9 @@ impl YieldService {
         &self,
         protocol_name: &str,
     ) -> Result<BigDecimal> {
-        let module_address = std::env::var("APTOS_MODULE_ADDRESS").unwrap_or_else(|_| {
-            "0xaab1ca043fb6cd7b2f264f3ce32f301427e7f67b0e816b30d4e95f1e2bcbabfa".to_string()
-        });
+        let module_address = self.chain.module_address_or_default().to_string();
 
-        let protocol_addr =
-            std::env::var(format!("{}_PROTOCOL_ADDRESS", protocol_name.to_uppercase()))
-                .unwrap_or_else(|_| module_address.clone());
+        let protocol_addr = self.chain.protocol_address(protocol_name).to_string();
 
         let adapter_name = format!("{}_adapter", protocol_name);
 
@@ -163,6 +241,9 @@ impl YieldService {
         .execute(&self.pool)
         .await?;
 
+        self.record_apy(protocol_name, Some(&apy), ApySource::Contract, None)
+            .await?;
+
         info!(
             "Updated protocol {} APY from blockchain: {}%",
             protocol_name, apy_percent
@@ -185,6 +266,15 @@ impl YieldService {
                         "Failed to update APY for {}: {}. Using default value.",
                         protocol, e
                     );
+                    if let Err(record_err) = self
+                        .record_apy(protocol, None, ApySource::Default, Some(&e.to_string()))
+                        .await
+                    {
+                        warn!(
+                            "Failed to record default APY for {}: {}",
+                            protocol, record_err
+                        );
+                    }
                     failed_protocols.push(protocol.to_string());
                 }
             }
@@ -215,8 +305,7 @@ impl YieldService {
         adapter_name: &str,
         protocol_address: &str,
     ) -> Result<u64> {
-        let node_url = std::env::var("APTOS_NODE_URL")
-            .unwrap_or_else(|_| "https://fullnode.testnet.aptoslabs.com/v1".to_string());
+        let node_url = &self.chain.node_url;
 
         let function_id = format!("{}::{}::get_current_apy", module_address, adapter_name);
 
@@ -253,6 +342,176 @@ impl YieldService {
         Err(anyhow::anyhow!("Invalid APY response format"))
     }
 
+    /// Appends a reading to `apy_history`. With `apy = None` the protocol's current `baseApy`
+    /// is recorded, which is what the yield calculations keep using after a failed refresh.
+    pub async fn record_apy(
+        &self,
+        protocol_name: &str,
+        apy: Option<&BigDecimal>,
+        source: ApySource,
+        error_message: Option<&str>,
+    ) -> Result<()> {
+        let id = Uuid::new_v4().to_string();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO apy_history (id, "protocolId", apy, source, error, "recordedAt")
+            SELECT $1, p.id, COALESCE($3, p."baseApy"), $4, $5, CURRENT_TIMESTAMP
+            FROM protocols p
+            WHERE p.name = $2
+            "#,
+            id,
+            protocol_name,
+            apy,
+            source.as_str(),
+            error_message
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// APY series per protocol between `from` and `to`, optionally bucketed.
+    ///
+    /// `protocol` matches the protocol id or name; `None` returns every active protocol.
+    pub async fn get_apy_time_series(
+        &self,
+        protocol: Option<&str>,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        interval: ApyInterval,
+    ) -> Result<Vec<ApySeries>> {
+        let protocols = sqlx::query!(
+            r#"
+            SELECT id, name, "displayName" as display_name
+            FROM protocols
+            WHERE ($1::text IS NULL AND "isActive" = true) OR id = $1 OR name = $1
+            ORDER BY name
+            "#,
+            protocol
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut series = Vec::new();
+        for p in protocols {
+            let points = match interval {
+                ApyInterval::Raw => sqlx::query!(
+                    r#"
+                    SELECT apy, source, "recordedAt" as recorded_at
+                    FROM apy_history
+                    WHERE "protocolId" = $1 AND "recordedAt" >= $2 AND "recordedAt" <= $3
+                    ORDER BY "recordedAt" ASC
+                    "#,
+                    p.id,
+                    from,
+                    to
+                )
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|r| ApyPoint {
+                    timestamp: r.recorded_at,
+                    apy: r.apy.clone(),
+                    min: r.apy.clone(),
+                    max: r.apy.clone(),
+                    close: r.apy,
+                    samples: 1,
+                    source: Some(r.source),
+                })
+                .collect(),
+                bucketed => {
+                    let unit = match bucketed {
+                        ApyInterval::Hour => "hour",
+                        ApyInterval::Day => "day",
+                        _ => "week",
+                    };
+                    sqlx::query!(
+                        r#"
+                        SELECT
+                            date_trunc($4, "recordedAt") as "bucket!",
+                            AVG(apy) as "avg!",
+                            MIN(apy) as "min!",
+                            MAX(apy) as "max!",
+                            (array_agg(apy ORDER BY "recordedAt" DESC))[1] as "close!",
+                            COUNT(*) as "samples!"
+                        FROM apy_history
+                        WHERE "protocolId" = $1 AND "recordedAt" >= $2 AND "recordedAt" <= $3
+                        GROUP BY 1
+                        ORDER BY 1 ASC
+                        "#,
+                        p.id,
+                        from,
+                        to,
+                        unit
+                    )
+                    .fetch_all(&self.pool)
+                    .await?
+                    .into_iter()
+                    .map(|r| ApyPoint {
+                        timestamp: r.bucket,
+                        apy: r.avg.round(6),
+                        min: r.min,
+                        max: r.max,
+                        close: r.close,
+                        samples: r.samples,
+                        source: None,
+                    })
+                    .collect()
+                }
+            };
+
+            series.push(ApySeries {
+                protocol_id: p.id,
+                protocol: p.name,
+                display_name: p.display_name,
+                points,
+            });
+        }
+
+        Ok(series)
+    }
+
+    /// APY readings per protocol since `since`, plus the last reading before it so the
+    /// series has a value at `since`. Values are `(unix_secs, apy_percent)`.
+    pub async fn apy_paths_since(
+        &self,
+        since: NaiveDateTime,
+    ) -> Result<HashMap<String, Vec<(i64, f64)>>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT "protocolId" as "protocol_id!", apy as "apy!", "recordedAt" as "recorded_at!"
+            FROM apy_history
+            WHERE "recordedAt" >= $1
+            UNION ALL
+            SELECT "protocolId", apy, "recordedAt"
+            FROM (
+                SELECT DISTINCT ON ("protocolId") "protocolId", apy, "recordedAt"
+                FROM apy_history
+                WHERE "recordedAt" < $1
+                ORDER BY "protocolId", "recordedAt" DESC
+            ) latest
+            "#,
+            since
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut paths: HashMap<String, Vec<(i64, f64)>> = HashMap::new();
+        for row in rows {
+            paths.entry(row.protocol_id).or_default().push((
+                row.recorded_at.and_utc().timestamp(),
+                row.apy.to_f64().unwrap_or(0.0),
+            ));
+        }
+        for path in paths.values_mut() {
+            path.sort_by_key(|(ts, _)| *ts);
+        }
+
+        Ok(paths)
+    }
+
     pub async fn get_protocols(&self) -> Result<Vec<Protocol>> {
         let protocols = sqlx::query_as!(
             Protocol,
@@ -269,50 +528,92 @@ impl YieldService {
         Ok(protocols)
     }
 
+    /// Re-allocates the market's pool with the configured strategy and prices each slice
+    /// along its protocol's recorded APY path since `opened_at`.
+    ///
+    /// `current_yield` is the daily yield at the time-weighted APY of the last 24 hours;
+    /// `accrued_yield` integrates the APY over the whole period the market has been open.
+    /// Protocols without history fall back to the APY the allocation was made with.
     pub async fn calculate_market_yield(
         &self,
-        _market_id: i64,
+        market_id: &str,
+        opened_at: NaiveDateTime,
         pool_size: BigDecimal,
     ) -> Result<YieldCalculation> {
-        let protocols = self.get_protocols().await?;
+        let plan = YieldAllocator::new(self.pool.clone(), &self.allocation)
+            .rebalance_market(market_id, &pool_size)
+            .await?;
 
-        if protocols.is_empty() {
+        if plan.slices.is_empty() {
             return Ok(YieldCalculation {
+                strategy: plan.strategy,
                 current_yield: BigDecimal::from(0),
+                accrued_yield: BigDecimal::from(0),
                 protocol_breakdown: Vec::new(),
             });
         }
 
-        let protocol_count = BigDecimal::from(protocols.len() as i64);
-        let amount_per_protocol = &pool_size / &protocol_count;
+        let now = chrono::Utc::now().naive_utc();
+        let opened_at = opened_at.min(now);
+        let paths = self
+            .apy_paths_since(opened_at.min(now - chrono::Duration::days(1)))
+            .await?;
+
+        let now_ts = now.and_utc().timestamp();
+        let opened_ts = opened_at.and_utc().timestamp();
+        let elapsed_years = (now_ts - opened_ts) as f64 / SECONDS_PER_YEAR;
+        let pool_amount = pool_size.to_f64().unwrap_or(0.0);
 
         let mut protocol_breakdown = Vec::new();
         let mut total_yield = BigDecimal::from(0);
-
-        for protocol in protocols {
-            let market_multiplier = BigDecimal::from(1);
-            let effective_apy = &protocol.base_apy * &market_multiplier;
-
-            let yield_amount = (&amount_per_protocol * &effective_apy)
-                / BigDecimal::from(100)
-                / BigDecimal::from(365);
+        let mut total_accrued = BigDecimal::from(0);
+
+        for slice in plan.slices {
+            let path = paths
+                .get(&slice.protocol_id)
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+
+            let recent_apy =
+                time_weighted_average(path, now_ts - SECONDS_PER_DAY, now_ts).unwrap_or(slice.apy);
+            let average_apy = time_weighted_average(path, opened_ts, now_ts).unwrap_or(slice.apy);
+
+            let amount = pool_amount * slice.weight;
+            let amount_decimal = BigDecimal::try_from(amount)?.round(18);
+            let effective_apy = BigDecimal::try_from(recent_apy)?.round(6);
+            let average_apy = BigDecimal::try_from(average_apy)?.round(6);
+
+            let yield_amount =
+                (&amount_decimal * &effective_apy) / BigDecimal::from(100) / BigDecimal::from(365);
+            let accrued_yield = BigDecimal::try_from(
+                amount * average_apy.to_f64().unwrap_or(0.0) / 100.0 * elapsed_years,
+            )?
+            .round(8);
 
             protocol_breakdown.push(ProtocolYield {
-                protocol: protocol.name,
-                amount: amount_per_protocol.clone(),
+                protocol: slice.protocol,
+                weight: slice.weight,
+                amount: amount_decimal,
                 apy: effective_apy,
                 yield_amount: yield_amount.clone(),
+                average_apy,
+                accrued_yield: accrued_yield.clone(),
             });
 
             total_yield += yield_amount;
+            total_accrued += accrued_yield;
         }
 
         Ok(YieldCalculation {
+            strategy: plan.strategy,
             current_yield: total_yield,
+            accrued_yield: total_accrued,
             protocol_breakdown,
         })
     }
 
+    /// Writes one ledger entry. Periods are idempotent: a second write for the same
+    /// (market, protocol, period) is ignored and returns `false`.
     pub async fn record_yield(
         &self,
         market_id: &str,
@@ -321,13 +622,14 @@ impl YieldService {
         apy: BigDecimal,
         yield_amount: BigDecimal,
         period: chrono::NaiveDateTime,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         let id = Uuid::new_v4().to_string();
 
-        sqlx::query!(
+        let result = sqlx::query!(
             r#"
             INSERT INTO yield_records (id, "marketId", "protocolId", amount, apy, yield, period, "createdAt")
             VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP)
+            ON CONFLICT ("marketId", "protocolId", period) DO NOTHING
             "#,
             id,
             market_id,
@@ -340,61 +642,82 @@ impl YieldService {
         .execute(&self.pool)
         .await?;
 
-        info!(
-            "Recorded yield for market {} with protocol {}",
-            market_id, protocol_id
-        );
-        Ok(())
+        let inserted = result.rows_affected() > 0;
+        if inserted {
+            debug!(
+                "Recorded yield for market {} with protocol {} for {}",
+                market_id, protocol_id, period
+            );
+        }
+        Ok(inserted)
     }
 
     pub async fn calculate_all_market_yields(&self) -> Result<i64> {
         info!("Calculating yields for all active markets");
 
-        let markets = sqlx::query!(
-            r#"
-            SELECT me.id, me."blockchainMarketId" as blockchain_market_id, me."totalPoolSize" as total_pool_size
-            FROM markets_extended me
-            WHERE me.status = 'active'
-              AND me."totalPoolSize" > 0
-            LIMIT 100
-            "#
-        )
-        .fetch_all(&self.pool)
-        .await?;
-
         let mut processed = 0;
+        let mut last_id = String::new();
 
-        for market in markets {
-            match self
-                .calculate_market_yield(
-                    market.blockchain_market_id.unwrap_or(0),
-                    market.total_pool_size.clone(),
-                )
-                .await
-            {
-                Ok(yield_calc) => {
-                    if let Err(e) = sqlx::query!(
-                        r#"
-                        UPDATE markets_extended
-                        SET "currentYield" = $1, "updatedAt" = CURRENT_TIMESTAMP
-                        WHERE id = $2
-                        "#,
-                        yield_calc.current_yield,
-                        market.id
+        loop {
+            let markets = sqlx::query!(
+                r#"
+                SELECT me.id, me."createdAt" as created_at, me."totalPoolSize" as total_pool_size
+                FROM markets_extended me
+                WHERE me.status = 'active'
+                  AND me."totalPoolSize" > 0
+                  AND me.id > $1
+                ORDER BY me.id
+                LIMIT $2
+                "#,
+                last_id,
+                MARKET_BATCH_SIZE
+            )
+            .fetch_all(&self.pool)
+            .await?;
+
+            let Some(last) = markets.last() else {
+                break;
+            };
+            last_id = last.id.clone();
+            let batch_len = markets.len() as i64;
+
+            for market in markets {
+                match self
+                    .calculate_market_yield(
+                        &market.id,
+                        market.created_at,
+                        market.total_pool_size.clone(),
                     )
-                    .execute(&self.pool)
                     .await
-                    {
-                        error!("Failed to update market {} yield: {}", market.id, e);
-                        continue;
-                    }
+                {
+                    Ok(yield_calc) => {
+                        if let Err(e) = sqlx::query!(
+                            r#"
+                            UPDATE markets_extended
+                            SET "currentYield" = $1, "updatedAt" = CURRENT_TIMESTAMP
+                            WHERE id = $2
+                            "#,
+                            yield_calc.current_yield,
+                            market.id
+                        )
+                        .execute(&self.pool)
+                        .await
+                        {
+                            error!("Failed to update market {} yield: {}", market.id, e);
+                            continue;
+                        }
 
-                    processed += 1;
-                }
-                Err(e) => {
-                    error!("Failed to calculate yield for market {}: {}", market.id, e);
+                        processed += 1;
+                    }
+                    Err(e) => {
+                        error!("Failed to calculate yield for market {}: {}", market.id, e);
+                    }
                 }
             }
+
+            if batch_len < MARKET_BATCH_SIZE {
+                break;
+            }
         }
 
         info!("Calculated yields for {} markets", processed);
@@ -459,3 +782,47 @@ pub struct ProtocolSummary {
     pub total_yield: BigDecimal,
     pub average_apy: BigDecimal,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[sqlx::test(migrator = "crate::migrate::MIGRATOR")]
+    async fn test_apy_paths_start_from_the_latest_earlier_reading(pool: PgPool) {
+        sqlx::query!(
+            r#"
+            INSERT INTO protocols (id, name, "displayName", "baseApy")
+            VALUES ('p-amnis', 'amnis', 'Amnis', 3.0)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let since = chrono::Utc::now().naive_utc() - chrono::Duration::days(1);
+        for (offset_hours, apy) in [(-48, 1.0), (-24, 2.0), (-1, 3.0), (1, 4.0)] {
+            sqlx::query!(
+                r#"
+                INSERT INTO apy_history (id, "protocolId", apy, source, "recordedAt")
+                VALUES ($1, 'p-amnis', $2, 'contract', $3)
+                "#,
+                Uuid::new_v4().to_string(),
+                BigDecimal::try_from(apy).unwrap(),
+                since + chrono::Duration::hours(offset_hours)
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let config = Config::default();
+        let paths = YieldService::new(pool, &config.chain, &config.yield_allocation)
+            .apy_paths_since(since)
+            .await
+            .unwrap();
+
+        let apys: Vec<f64> = paths["p-amnis"].iter().map(|(_, apy)| *apy).collect();
+        assert_eq!(apys, vec![3.0, 4.0]);
+    }
+}
@@ -0,0 +1,54 @@
+use axum::extract::FromRef;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::services::response_cache::ResponseCache;
+use crate::services::signer::SignerPool;
+
+/// Shared state handed to every router.
+///
+/// Handlers that only need the database keep extracting `State<Database>`;
+/// the `FromRef` impls below let them do so unchanged.
+#[derive(Clone)]
+pub struct AppState {
+    pub db: Database,
+    pub config: Arc<Config>,
+    pub signers: Arc<SignerPool>,
+    pub cache: Arc<ResponseCache>,
+}
+
+impl AppState {
+    pub fn new(db: Database, config: Config, signers: SignerPool) -> Self {
+        Self {
+            db,
+            cache: Arc::new(ResponseCache::new(&config.cache)),
+            config: Arc::new(config),
+            signers: Arc::new(signers),
+        }
+    }
+}
+
+impl FromRef<AppState> for Database {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Config> {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<SignerPool> {
+    fn from_ref(state: &AppState) -> Self {
+        state.signers.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<ResponseCache> {
+    fn from_ref(state: &AppState) -> Self {
+        state.cache.clone()
+    }
+}
@@ -2,6 +2,8 @@ use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header,
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::config::{AuthConfig, DEFAULT_JWT_SECRET};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
@@ -30,24 +32,25 @@ impl Claims {
 
 pub struct JwtService {
     secret: String,
+    expires_in_hours: i64,
 }
 
 impl JwtService {
-    pub fn new() -> Self {
-        let secret = std::env::var("JWT_SECRET")
-            .unwrap_or_else(|_| "your-secret-key-change-in-production".to_string());
-
-        if secret == "your-secret-key-change-in-production" {
+    pub fn new(config: &AuthConfig) -> Self {
+        if config.jwt_secret == DEFAULT_JWT_SECRET {
             tracing::warn!(
                 "⚠️  Using default JWT_SECRET! Please set JWT_SECRET environment variable in production"
             );
         }
 
-        Self { secret }
+        Self {
+            secret: config.jwt_secret.clone(),
+            expires_in_hours: config.jwt_expiry_hours,
+        }
     }
 
     pub fn generate_token(&self, user_id: String, address: String) -> Result<String, String> {
-        let claims = Claims::new(user_id, address, 24 * 7);
+        let claims = Claims::new(user_id, address, self.expires_in_hours);
 
         encode(
             &Header::new(Algorithm::HS256),
@@ -77,9 +80,3 @@ impl JwtService {
         }
     }
 }
-
-impl Default for JwtService {
-    fn default() -> Self {
-        Self::new()
-    }
-}
@@ -0,0 +1,55 @@
+//! Helpers for step series such as price samples and APY readings, where each sample
+//! holds until the next one.
+
+/// Average of a step series where each sample holds until the next one (or `end`).
+///
+/// `samples` are `(unix_secs, value)` sorted by time. A sample before `start` provides the
+/// opening value; with no elapsed time in the window the latest sample is returned.
+pub fn time_weighted_average(samples: &[(i64, f64)], start: i64, end: i64) -> Option<f64> {
+    let samples: Vec<&(i64, f64)> = samples.iter().filter(|(ts, _)| *ts <= end).collect();
+    if samples.is_empty() || end < start {
+        return None;
+    }
+
+    let mut weighted = 0.0;
+    let mut total = 0i64;
+    for (i, (ts, value)) in samples.iter().enumerate() {
+        let segment_start = (*ts).max(start);
+        let segment_end = samples.get(i + 1).map(|(next, _)| *next).unwrap_or(end);
+        let segment_end = segment_end.min(end);
+        if segment_end > segment_start {
+            weighted += value * (segment_end - segment_start) as f64;
+            total += segment_end - segment_start;
+        }
+    }
+
+    if total == 0 {
+        return samples.last().map(|(_, value)| *value);
+    }
+
+    Some(weighted / total as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_weighted_average_weights_by_duration() {
+        // 10.0 for 30s (carried in from before the window), then 20.0 for 10s.
+        let samples = [(50, 10.0), (130, 20.0)];
+        assert_eq!(time_weighted_average(&samples, 100, 140), Some(12.5));
+
+        // A single sample holds for the whole window.
+        assert_eq!(time_weighted_average(&[(120, 8.0)], 100, 200), Some(8.0));
+
+        // Samples after the window end are ignored.
+        assert_eq!(
+            time_weighted_average(&[(100, 5.0), (300, 50.0)], 100, 200),
+            Some(5.0)
+        );
+
+        assert_eq!(time_weighted_average(&[], 100, 200), None);
+        assert_eq!(time_weighted_average(&[(300, 1.0)], 100, 200), None);
+    }
+}